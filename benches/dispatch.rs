@@ -0,0 +1,144 @@
+//! Latency of the operations dispatched by the driver, measured through a mounted `MemFs`.
+//!
+//! Run with `cargo bench --features parallel --bench dispatch` (or `--features serial`), which needs
+//! `fusermount` and access to `/dev/fuse` like the integration tests. Each benchmark reports the time
+//! of one round trip through the kernel, the driver and the handler:
+//! - `lookup`: `stat` of a file, with attribute caching disabled so that every call reaches the handler.
+//! - `read_64k`: open, read of 64 KiB and close, as the page cache is dropped on each open.
+//! - `write_4k`: open, write of 4 KiB and close.
+//! - `readdir_1000`: complete listing of a directory of 1000 entries, which spans several `readdir` replies.
+//!
+//! Outside of the serial mode, `threads/lookup` compares the `lookup` latency with `num_threads` set to 1,
+//! where operations run inline on the session thread, and set to 2, where they go through the thread pool.
+//!
+//! Compare the results of a change against a baseline with `--save-baseline` and `--baseline`.
+//! The absolute numbers depend heavily on the kernel and the machine; only their evolution is meaningful.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mem_fs::MemFs, DefaultFuseHandler};
+
+use std::fs::{self, File, OpenOptions};
+use std::os::unix::fs::FileExt;
+use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// Disables the kernel caching of attributes and entries, so that every operation reaches the handler
+struct UncachedFs {
+    inner: MemFs<PathBuf>,
+}
+
+impl FuseHandler<PathBuf> for UncachedFs {
+    fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+        &self.inner
+    }
+
+    fn get_default_ttl(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+fn mem_fs() -> UncachedFs {
+    let inner = MemFs::new(DefaultFuseHandler::new());
+    inner.insert_file("file.bin", vec![0u8; 1 << 20]).unwrap();
+    for i in 0..1000 {
+        inner
+            .insert_file(format!("dir/entry_{:04}", i), Vec::new())
+            .unwrap();
+    }
+    UncachedFs { inner }
+}
+
+fn mount_mem_fs(mntpoint: &Path) -> JoinHandle<()> {
+    let mntpoint = mntpoint.to_path_buf();
+    let handle = thread::spawn(move || {
+        #[cfg(feature = "serial")]
+        mount(mem_fs(), &mntpoint, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(mem_fs(), &mntpoint, &[], 4).unwrap();
+    });
+    thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+    handle
+}
+
+fn unmount_mem_fs(mntpoint: &Path, handle: JoinHandle<()>) {
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
+
+fn bench_dispatch(c: &mut Criterion) {
+    let mount_dir = TempDir::new().unwrap();
+    let mntpoint = mount_dir.path().to_path_buf();
+    let handle = mount_mem_fs(&mntpoint);
+
+    let file_path = mntpoint.join("file.bin");
+    let dir_path = mntpoint.join("dir");
+    let mut group = c.benchmark_group("dispatch");
+
+    group.bench_function("lookup", |b| {
+        b.iter(|| fs::metadata(&file_path).unwrap());
+    });
+
+    let mut buffer = vec![0u8; 64 * 1024];
+    group.bench_function("read_64k", |b| {
+        b.iter(|| {
+            let file = File::open(&file_path).unwrap();
+            file.read_exact_at(&mut buffer, 4096).unwrap();
+        });
+    });
+
+    let data = vec![1u8; 4096];
+    group.bench_function("write_4k", |b| {
+        b.iter(|| {
+            let file = OpenOptions::new().write(true).open(&file_path).unwrap();
+            file.write_all_at(&data, 8192).unwrap();
+        });
+    });
+
+    group.bench_function("readdir_1000", |b| {
+        b.iter(|| assert_eq!(fs::read_dir(&dir_path).unwrap().count(), 1000));
+    });
+
+    group.finish();
+    unmount_mem_fs(&mntpoint, handle);
+}
+
+#[cfg(not(feature = "serial"))]
+fn bench_threads(c: &mut Criterion) {
+    let mut group = c.benchmark_group("threads");
+    for num_threads in [1, 2] {
+        let mount_dir = TempDir::new().unwrap();
+        let mntpoint = mount_dir.path().to_path_buf();
+        let mntpoint_clone = mntpoint.clone();
+        let handle = thread::spawn(move || {
+            mount(mem_fs(), &mntpoint_clone, &[], num_threads).unwrap();
+        });
+        thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+        let file_path = mntpoint.join("file.bin");
+        group.bench_function(format!("lookup/{}", num_threads), |b| {
+            b.iter(|| fs::metadata(&file_path).unwrap());
+        });
+        unmount_mem_fs(&mntpoint, handle);
+    }
+    group.finish();
+}
+
+#[cfg(feature = "serial")]
+fn bench_threads(_c: &mut Criterion) {}
+
+criterion_group! {
+    name = benches;
+    // Keeps the suite within a few minutes
+    config = Criterion::default()
+        .sample_size(20)
+        .measurement_time(Duration::from_secs(3));
+    targets = bench_dispatch, bench_threads
+}
+criterion_main!(benches);
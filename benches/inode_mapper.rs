@@ -0,0 +1,116 @@
+//! Micro-benchmarks of `InodeMapper`, which backs the resolution of inodes to paths.
+//!
+//! Run with `cargo bench --features parallel --bench inode_mapper`. No mount is needed.
+//! - `resolve_depth_16`: resolution of an inode 16 directories below the root, done by every operation
+//!   of a `PathBuf` or `Vec<OsString>` filesystem.
+//! - `lookup_10000`: lookup of a child in a directory of 10 000 entries.
+//! - `insert_1000`: insertion of the entries of a listing of 1000 files, as done by `readdirplus`.
+//! - `shared_resolve/{threads}`: 10 000 resolutions per thread through the shared lock taken by the
+//!   resolvers of the driver, with concurrent threads.
+//! - `rebuild_10000`: building a mapping of 10 000 entries, the order of the copy a snapshot-based
+//!   read path would make on each mutation.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use easy_fuser::inode_mapper::InodeMapper;
+use easy_fuser::types::Inode;
+
+use std::ffi::{OsStr, OsString};
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn deep_mapper(depth: usize) -> (InodeMapper<()>, Inode) {
+    let mut mapper = InodeMapper::new(());
+    let mut inode = mapper.get_root_inode();
+    for i in 0..depth {
+        inode = mapper
+            .insert_child(&inode, OsString::from(format!("dir_{}", i)), |_| ())
+            .unwrap();
+    }
+    (mapper, inode)
+}
+
+fn wide_mapper(width: usize) -> InodeMapper<()> {
+    let mut mapper = InodeMapper::new(());
+    let root = mapper.get_root_inode();
+    for i in 0..width {
+        mapper
+            .insert_child(&root, OsString::from(format!("file_{}", i)), |_| ())
+            .unwrap();
+    }
+    mapper
+}
+
+fn bench_inode_mapper(c: &mut Criterion) {
+    let mut group = c.benchmark_group("inode_mapper");
+
+    let (mapper, inode) = deep_mapper(16);
+    group.bench_function("resolve_depth_16", |b| {
+        b.iter(|| mapper.resolve(&inode).unwrap().len());
+    });
+
+    let mapper = wide_mapper(10_000);
+    let root = mapper.get_root_inode();
+    group.bench_function("lookup_10000", |b| {
+        b.iter(|| mapper.lookup(&root, OsStr::new("file_5000")).is_some());
+    });
+
+    let names: Vec<OsString> = (0..1000)
+        .map(|i| OsString::from(format!("file_{}", i)))
+        .collect();
+    group.bench_function("insert_1000", |b| {
+        b.iter_batched(
+            || InodeMapper::new(()),
+            |mut mapper| {
+                let root = mapper.get_root_inode();
+                for name in &names {
+                    mapper.insert_child(&root, name.clone(), |_| ()).unwrap();
+                }
+                mapper
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("rebuild_10000", |b| {
+        b.iter(|| wide_mapper(10_000));
+    });
+
+    group.finish();
+}
+
+fn bench_shared_resolve(c: &mut Criterion) {
+    let mut group = c.benchmark_group("shared_resolve");
+    let (mapper, inode) = deep_mapper(16);
+    let mapper = Arc::new(RwLock::new(mapper));
+
+    for threads in [1, 2, 4, 8] {
+        group.bench_function(threads.to_string(), |b| {
+            b.iter_custom(|iters| {
+                let start = Instant::now();
+                for _ in 0..iters {
+                    thread::scope(|scope| {
+                        for _ in 0..threads {
+                            scope.spawn(|| {
+                                for _ in 0..10_000 {
+                                    let mapper = mapper.read().unwrap();
+                                    mapper.resolve(&inode).unwrap();
+                                }
+                            });
+                        }
+                    });
+                }
+                start.elapsed()
+            });
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().measurement_time(Duration::from_secs(3));
+    targets = bench_inode_mapper, bench_shared_resolve
+}
+criterion_main!(benches);
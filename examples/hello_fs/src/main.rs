@@ -110,7 +110,7 @@ impl FuseHandler<Inode> for HelloFS {
         _file_handle: BorrowedFileHandle,
         seek: SeekFrom,
         size: u32,
-        _flags: FUSEOpenFlags,
+        _flags: OpenFlags,
         _lock_owner: Option<u64>,
     ) -> FuseResult<Vec<u8>> {
         if file_id == HELLO_TXT_ATTR.0 {
@@ -166,7 +166,12 @@ fn main() {
         .try_init();
 
     let mountpoint = std::env::args().nth(1).expect("Usage: hello <MOUNTPOINT>");
-    let options = vec![MountOption::RO, MountOption::FSName("hello".to_string())];
+    // Shown as "hello on <MOUNTPOINT> type fuse.hello" by mount
+    let options = MountOptionsBuilder::new()
+        .fs_name("hello")
+        .subtype("hello")
+        .option(MountOption::RO)
+        .build();
 
     println!("Mounting FTP filesystem...");
     easy_fuser::mount(HelloFS::new(), Path::new(&mountpoint), &options).unwrap();
@@ -1,49 +1,23 @@
 #![doc = include_str!("../README.md")]
 
 use easy_fuser::prelude::*;
-use std::ffi::OsStr;
 use std::path::Path;
 
 const README_CONTENT: &[u8] = include_bytes!("../README.md") as &[u8];
 
 mod filesystem;
+mod tree;
 pub use filesystem::InMemoryFS;
+pub use tree::InMemoryFSBuilder;
 
-fn create_memory_fs() -> InMemoryFS {
-    let memoryfs = InMemoryFS::new();
-    #[cfg(feature = "readme")]
-    {
-        // An example of interacting directly with the filesystem
-        let request_info = RequestInfo {
-            id: 0,
-            uid: 0,
-            gid: 0,
-            pid: 0,
-        }; // dummy RequestInfo
-        let (fd, (inode, _), _) = memoryfs
-            .create(
-                &request_info,
-                ROOT_INODE,
-                OsStr::new("README.md"),
-                0o755,
-                0,
-                OpenFlags::empty(),
-            )
-            .unwrap();
-        let _ = memoryfs
-            .write(
-                &request_info,
-                inode,
-                fd.borrow(),
-                SeekFrom::Start(0),
-                README_CONTENT.to_vec(),
-                FUSEWriteFlags::empty(),
-                OpenFlags::empty(),
-                None,
-            )
-            .unwrap();
+fn create_memory_fs(source: Option<&Path>) -> InMemoryFS {
+    if let Some(source) = source {
+        return InMemoryFS::from_tree(source).unwrap();
     }
-    memoryfs
+    let builder = InMemoryFS::builder();
+    #[cfg(feature = "readme")]
+    let builder = builder.file("README.md", README_CONTENT);
+    builder.build().unwrap()
 }
 
 fn main() {
@@ -57,13 +31,15 @@ fn main() {
 
     let mountpoint = std::env::args()
         .nth(1)
-        .expect("Usage: in_memory_fs <MOUNTPOINT>");
+        .expect("Usage: in_memory_fs <MOUNTPOINT> [SOURCE_DIR]");
+    // The filesystem is seeded with a copy of the source directory, if any
+    let source = std::env::args().nth(2);
     let options = vec![
         MountOption::RW,
         MountOption::FSName("in_memory_fs".to_string()),
     ];
 
-    let memoryfs = create_memory_fs();
+    let memoryfs = create_memory_fs(source.as_deref().map(Path::new));
 
     println!("Mounting filesystem...");
     easy_fuser::mount(memoryfs, Path::new(&mountpoint), &options, 1).unwrap();
@@ -0,0 +1,182 @@
+use easy_fuser::prelude::*;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs;
+use std::path::Path;
+
+use crate::InMemoryFS;
+
+enum TreeEntry {
+    File(Vec<u8>),
+    Directory(BTreeMap<OsString, TreeEntry>),
+}
+
+/// Declarative description of the initial content of an `InMemoryFS`
+///
+/// ```rust,ignore
+/// let memoryfs = InMemoryFS::builder()
+///     .file("README.md", "Hello")
+///     .dir("docs", InMemoryFS::builder().file("guide.txt", "Welcome"))
+///     .build()?;
+/// ```
+#[derive(Default)]
+pub struct InMemoryFSBuilder {
+    entries: BTreeMap<OsString, TreeEntry>,
+}
+
+impl InMemoryFSBuilder {
+    /// Adds a regular file with the given content
+    pub fn file<N: Into<OsString>, C: Into<Vec<u8>>>(mut self, name: N, content: C) -> Self {
+        self.entries
+            .insert(name.into(), TreeEntry::File(content.into()));
+        self
+    }
+
+    /// Adds a directory holding the entries of `children`
+    pub fn dir<N: Into<OsString>>(mut self, name: N, children: InMemoryFSBuilder) -> Self {
+        self.entries
+            .insert(name.into(), TreeEntry::Directory(children.entries));
+        self
+    }
+
+    /// Creates the filesystem, through the same `mkdir`, `create` and `write` operations as the kernel
+    ///
+    /// Entries are owned by root.
+    pub fn build(self) -> FuseResult<InMemoryFS> {
+        let memoryfs = InMemoryFS::new();
+        let request_info = RequestInfo {
+            id: 0,
+            uid: 0,
+            gid: 0,
+            pid: 0,
+            deadline: None,
+        }; // dummy RequestInfo
+        import(&memoryfs, &request_info, ROOT_INODE, self.entries)?;
+        Ok(memoryfs)
+    }
+}
+
+fn import(
+    memoryfs: &InMemoryFS,
+    req: &RequestInfo,
+    parent: Inode,
+    entries: BTreeMap<OsString, TreeEntry>,
+) -> FuseResult<()> {
+    for (name, entry) in entries {
+        match entry {
+            TreeEntry::File(content) => {
+                let (fd, (inode, _), _) =
+                    memoryfs.create(req, parent.clone(), &name, 0o644, 0, OpenFlags::empty())?;
+                memoryfs.write(
+                    req,
+                    inode,
+                    fd.borrow(),
+                    SeekFrom::Start(0),
+                    content,
+                    FUSEWriteFlags::empty(),
+                    OpenFlags::empty(),
+                    None,
+                )?;
+            }
+            TreeEntry::Directory(children) => {
+                let (inode, _) = memoryfs.mkdir(req, parent.clone(), &name, 0o755, 0)?;
+                import(memoryfs, req, inode, children)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reads the files and directories below `path`, other kinds of files are skipped
+fn read_tree(path: &Path) -> FuseResult<InMemoryFSBuilder> {
+    let mut builder = InMemoryFS::builder();
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            builder = builder.dir(entry.file_name(), read_tree(&entry.path())?);
+        } else if file_type.is_file() {
+            builder = builder.file(entry.file_name(), fs::read(entry.path())?);
+        }
+    }
+    Ok(builder)
+}
+
+impl InMemoryFS {
+    pub fn builder() -> InMemoryFSBuilder {
+        InMemoryFSBuilder::default()
+    }
+
+    /// Creates a filesystem holding a copy of the files and directories below `root`
+    ///
+    /// Symbolic links and special files are not imported.
+    pub fn from_tree(root: &Path) -> FuseResult<Self> {
+        read_tree(root)?.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+
+    fn request() -> RequestInfo {
+        RequestInfo {
+            id: 0,
+            uid: 0,
+            gid: 0,
+            pid: 0,
+            deadline: None,
+        }
+    }
+
+    fn read_path(memoryfs: &InMemoryFS, path: &[&str]) -> Vec<u8> {
+        let req = request();
+        let mut inode = ROOT_INODE;
+        for name in path {
+            inode = memoryfs.lookup(&req, inode, OsStr::new(name)).unwrap().0;
+        }
+        memoryfs
+            .read(
+                &req,
+                inode,
+                unsafe { BorrowedFileHandle::from_raw(0) },
+                SeekFrom::Start(0),
+                4096,
+                OpenFlags::empty(),
+                None,
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_builder() {
+        let memoryfs = InMemoryFS::builder()
+            .file("top.txt", "top")
+            .dir(
+                "docs",
+                InMemoryFS::builder()
+                    .file("guide.txt", "nested")
+                    .dir("empty", InMemoryFS::builder()),
+            )
+            .build()
+            .unwrap();
+
+        assert_eq!(read_path(&memoryfs, &["top.txt"]), b"top");
+        assert_eq!(read_path(&memoryfs, &["docs", "guide.txt"]), b"nested");
+        let (_, attr) = memoryfs
+            .lookup(&request(), ROOT_INODE, OsStr::new("docs"))
+            .unwrap();
+        assert_eq!(attr.kind, FileKind::Directory);
+    }
+
+    #[test]
+    fn test_from_tree() {
+        let source = tempfile::TempDir::new().unwrap();
+        fs::create_dir(source.path().join("dir")).unwrap();
+        fs::write(source.path().join("dir/file.txt"), "content").unwrap();
+
+        let memoryfs = InMemoryFS::from_tree(source.path()).unwrap();
+        assert_eq!(read_path(&memoryfs, &["dir", "file.txt"]), b"content");
+    }
+}
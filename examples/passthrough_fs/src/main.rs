@@ -7,7 +7,6 @@ use easy_fuser::templates::mirror_fs::*;
 use easy_fuser::templates::DefaultFuseHandler;
 use std::path::PathBuf;
 use std::process::exit;
-use std::process::Command;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
@@ -23,6 +22,10 @@ struct Args {
     #[arg(short, long)]
     source_dir: Option<PathBuf>,
 
+    /// Dereference the symlinks of the source directory, exposing their targets even outside of it
+    #[arg(long)]
+    follow_symlinks: bool,
+
     /// Positional arguments: [SOURCE_DIR] [MOUNT_POINT]
     #[arg(required = false)]
     args: Vec<PathBuf>,
@@ -56,7 +59,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             return;
         }
         println!("Unmounting filesystem...");
-        let _ = Command::new("fusermount").arg("-u").arg(mntpoint).status();
+        let _ = unmount(mntpoint);
     };
 
     // Set up Ctrl+C handler
@@ -68,7 +71,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         exit(1);
     })?;
 
-    let fs = MirrorFs::new(source_dir, DefaultFuseHandler::new());
+    let fs =
+        MirrorFs::new(source_dir, DefaultFuseHandler::new()).follow_symlinks(args.follow_symlinks);
 
     println!("Mounting mirror filesystem...");
     println!("Mount point: {:?}", &mntpoint);
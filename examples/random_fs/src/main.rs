@@ -1,7 +1,7 @@
 #![doc = include_str!("../README.md")]
 
 use easy_fuser::prelude::*;
-use easy_fuser::templates::DefaultFuseHandler;
+use easy_fuser::templates::{DefaultFuseHandler, HandleTable};
 use rand::rngs::ThreadRng;
 use rand::Rng;
 use std::ffi::{OsStr, OsString};
@@ -10,6 +10,7 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 pub struct RandomFS {
     inner: DefaultFuseHandler,
+    handles: HandleTable<()>,
 }
 
 const ROOT_ATTR: (Inode, FileAttribute) = (
@@ -38,6 +39,7 @@ impl RandomFS {
     pub fn new() -> Self {
         Self {
             inner: DefaultFuseHandler::new(),
+            handles: HandleTable::new(),
         }
     }
 
@@ -65,6 +67,11 @@ impl FuseHandler<Inode> for RandomFS {
         &self.inner
     }
 
+    // Every lookup and readdir invents new inodes, the kernel must not cache them
+    fn volatile_inodes(&self) -> bool {
+        true
+    }
+
     fn access(&self, _req: &RequestInfo, _file_id: Inode, _mask: AccessMask) -> FuseResult<()> {
         Ok(())
     }
@@ -89,8 +96,7 @@ impl FuseHandler<Inode> for RandomFS {
         let ino = Self::random_inode(&mut rng);
         let attr = self.getattr(_req, ino.clone(), None)?;
         Ok((
-            // Safe because we won't release it
-            unsafe { OwnedFileHandle::from_raw(0) },
+            self.handles.allocate(()),
             (ino, attr),
             FUSEOpenResponseFlags::empty(),
         ))
@@ -171,7 +177,7 @@ impl FuseHandler<Inode> for RandomFS {
         _fh: BorrowedFileHandle,
         offset: SeekFrom,
         size: u32,
-        _flags: FUSEOpenFlags,
+        _flags: OpenFlags,
         _lock_owner: Option<u64>,
     ) -> FuseResult<Vec<u8>> {
         let mut rng = rand::thread_rng();
@@ -216,6 +222,19 @@ impl FuseHandler<Inode> for RandomFS {
         Ok(entries)
     }
 
+    fn release(
+        &self,
+        _req: &RequestInfo,
+        _ino: Inode,
+        file_handle: OwnedFileHandle,
+        _flags: OpenFlags,
+        _lock_owner: Option<u64>,
+        _flush: bool,
+    ) -> FuseResult<()> {
+        self.handles.release(file_handle);
+        Ok(())
+    }
+
     fn rmdir(&self, _req: &RequestInfo, _parent_id: Inode, _name: &OsStr) -> FuseResult<()> {
         Ok(())
     }
@@ -9,6 +9,7 @@ use std::fs::File;
 use std::io::{Read, Seek};
 use std::path::Path;
 use std::sync::{Mutex, RwLock};
+use std::time::Duration;
 
 use crate::helpers::*;
 
@@ -121,7 +122,10 @@ impl FuseHandler<Inode> for ZipFs {
             data: &(idx, is_dir),
         } = binding
             .lookup(&parent_id, name)
-            .ok_or_else(|| ErrorKind::FileNotFound.to_error("File not found"))?;
+            // The archive is read-only: a missing name will stay missing
+            .ok_or_else(|| {
+                PosixError::not_found_cached(Duration::from_secs(3600), "File not found")
+            })?;
 
         let mut archive = self.archive.lock().unwrap();
         let file_attr = create_file_attribute(&archive.by_index(idx)?, is_dir);
@@ -135,7 +139,7 @@ impl FuseHandler<Inode> for ZipFs {
         _file_handle: BorrowedFileHandle,
         seek: SeekFrom,
         size: u32,
-        _flags: FUSEOpenFlags,
+        _flags: OpenFlags,
         _lock_owner: Option<u64>,
     ) -> FuseResult<Vec<u8>> {
         let InodeInfo {
@@ -57,10 +57,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             return;
         }
         println!("Unmounting filesystem...");
-        let _ = std::process::Command::new("fusermount")
-            .arg("-u")
-            .arg(mount_point)
-            .status();
+        let _ = easy_fuser::unmount(mount_point);
     };
 
     // Set up Ctrl+C handler
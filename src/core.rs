@@ -1,3 +1,5 @@
+#[cfg(debug_assertions)]
+mod consistency;
 mod fuse_driver;
 mod fuse_driver_types;
 mod inode_mapping;
@@ -5,4 +7,4 @@ mod macros;
 mod thread_mode;
 
 pub(crate) use fuse_driver_types::FuseDriver;
-pub(crate) use inode_mapping::{InodeResolvable, ROOT_INO};
+pub(crate) use inode_mapping::{FileIdResolver, InodeResolvable, ROOT_INO};
@@ -0,0 +1,144 @@
+//! Cross-validation of the resolver and the handler, to track down inode bugs in debug builds.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use super::{fuse_driver_types::FuseDriver, inode_mapping::FileIdResolver, ROOT_INO};
+use crate::{fuse_handler::FuseHandler, types::*};
+
+/// Discrepancy found by `FuseDriver::check_consistency`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Inconsistency {
+    /// The id the inode resolves to is not recognized by the handler
+    UnknownId { ino: u64 },
+    /// The parents of the inode don't lead to the root
+    Unreachable { ino: u64 },
+    /// The parents of the inode loop back to it
+    Cycle { ino: u64 },
+}
+
+impl fmt::Display for Inconsistency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Inconsistency::UnknownId { ino } => {
+                write!(f, "ino {:x?} resolves to an id unknown to the handler", ino)
+            }
+            Inconsistency::Unreachable { ino } => {
+                write!(f, "ino {:x?} is not attached to the root", ino)
+            }
+            Inconsistency::Cycle { ino } => write!(f, "ino {:x?} is its own ancestor", ino),
+        }
+    }
+}
+
+impl<TId, THandler> FuseDriver<TId, THandler>
+where
+    TId: FileIdType,
+    THandler: FuseHandler<TId>,
+{
+    /// Checks that every inode tracked by the resolver is attached to the root and resolves to an id the handler recognizes (see `FuseHandler::id_exists`)
+    pub(crate) fn check_consistency(&self) -> Vec<Inconsistency> {
+        let handler = self.get_handler();
+        let resolver = self.get_resolver();
+        let parents: HashMap<u64, u64> = resolver
+            .known_entries()
+            .into_iter()
+            .map(|(ino, parent, _)| (ino, parent))
+            .collect();
+
+        let mut inconsistencies = Vec::new();
+        let mut inos: Vec<u64> = parents.keys().copied().collect();
+        inos.sort_unstable();
+        for ino in inos {
+            // Walk up to the root, ids of detached inodes can't be resolved
+            let mut visited = HashSet::from([ino]);
+            let mut current = ino;
+            let reachable = loop {
+                match parents.get(&current) {
+                    Some(&ROOT_INO) => break true,
+                    Some(&parent) if !visited.insert(parent) => {
+                        inconsistencies.push(Inconsistency::Cycle { ino });
+                        break false;
+                    }
+                    Some(&parent) => current = parent,
+                    None => {
+                        inconsistencies.push(Inconsistency::Unreachable { ino });
+                        break false;
+                    }
+                }
+            };
+            if reachable && !handler.id_exists(resolver.resolve_id(ino)) {
+                inconsistencies.push(Inconsistency::UnknownId { ino });
+            }
+        }
+        inconsistencies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::{
+        mirror_fs::{MirrorFs, MirrorFsTrait},
+        DefaultFuseHandler,
+    };
+    use std::ffi::OsStr;
+    use std::fs;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    fn mirror_driver(source: &TempDir) -> FuseDriver<PathBuf, MirrorFs> {
+        let handler = MirrorFs::new(source.path().to_path_buf(), DefaultFuseHandler::new());
+        FuseDriver::new(handler, 1)
+    }
+
+    #[test]
+    fn test_consistent_state() {
+        let source = TempDir::new().unwrap();
+        fs::create_dir_all(source.path().join("dir/subdir")).unwrap();
+        fs::write(source.path().join("dir/subdir/file"), "content").unwrap();
+        let driver = mirror_driver(&source);
+        let resolver = driver.get_resolver();
+
+        let dir = resolver.lookup(ROOT_INO, OsStr::new("dir"), (), true);
+        let subdir = resolver.lookup(dir, OsStr::new("subdir"), (), true);
+        let file = resolver.lookup(subdir, OsStr::new("file"), (), true);
+        resolver.lookup(subdir, OsStr::new("file"), (), true);
+        resolver.rename(
+            subdir,
+            OsStr::new("file"),
+            dir,
+            OsStr::new("moved"),
+            RenameFlags::empty(),
+        );
+        fs::rename(
+            source.path().join("dir/subdir/file"),
+            source.path().join("dir/moved"),
+        )
+        .unwrap();
+        resolver.forget(file, 1);
+        assert_eq!(driver.check_consistency(), vec![]);
+    }
+
+    #[test]
+    fn test_detects_inconsistencies() {
+        let source = TempDir::new().unwrap();
+        fs::write(source.path().join("removed"), "content").unwrap();
+        fs::write(source.path().join("forgotten"), "content").unwrap();
+        let driver = mirror_driver(&source);
+        let resolver = driver.get_resolver();
+
+        // Removed behind the back of the resolver
+        let removed = resolver.lookup(ROOT_INO, OsStr::new("removed"), (), true);
+        fs::remove_file(source.path().join("removed")).unwrap();
+        // Forgotten more times than looked up, the inode is dropped
+        let forgotten = resolver.lookup(ROOT_INO, OsStr::new("forgotten"), (), true);
+        resolver.forget(forgotten, 2);
+        assert_eq!(resolver.try_resolve_id(forgotten), None);
+
+        assert_eq!(
+            driver.check_consistency(),
+            vec![Inconsistency::UnknownId { ino: removed }]
+        );
+    }
+}
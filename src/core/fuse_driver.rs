@@ -1,38 +1,188 @@
+#[cfg(feature = "xattr")]
+use std::os::unix::ffi::OsStrExt;
 use std::{
-    ffi::OsStr,
+    cell::RefCell,
+    collections::VecDeque,
+    ffi::{OsStr, OsString},
     path::Path,
-    time::{Instant, SystemTime},
+    time::{Duration, Instant, SystemTime},
 };
 
 use libc::c_int;
 use log::{error, info, warn};
 
+#[cfg(feature = "ioctl")]
+use fuser::ReplyIoctl;
+#[cfg(feature = "locking")]
+use fuser::ReplyLock;
+#[cfg(feature = "xattr")]
+use fuser::ReplyXattr;
 use fuser::{
-    self, KernelConfig, ReplyAttr, ReplyBmap, ReplyCreate, ReplyData, ReplyDirectory,
-    ReplyDirectoryPlus, ReplyEmpty, ReplyEntry, ReplyIoctl, ReplyLock, ReplyLseek, ReplyOpen,
-    ReplyStatfs, ReplyWrite, ReplyXattr, Request, TimeOrNow,
+    self, fuse_forget_one, KernelConfig, PollHandle, ReplyAttr, ReplyBmap, ReplyCreate, ReplyData,
+    ReplyDirectory, ReplyDirectoryPlus, ReplyEmpty, ReplyEntry, ReplyLseek, ReplyOpen, ReplyPoll,
+    ReplyStatfs, ReplyWrite, Request, TimeOrNow,
 };
 
 use super::{
-    fuse_driver_types::{execute_task, FuseDriver},
-    inode_mapping::FileIdResolver,
+    fuse_driver_types::{execute_task, save_dir_stream, DirStream, FuseDriver, DIR_BATCH_SIZE},
+    inode_mapping::{FileIdResolver, ROOT_INO},
     macros::*,
     thread_mode::*,
 };
+#[cfg(feature = "ioctl")]
+use crate::notifier::DROP_CACHES_IOCTL;
 use crate::{fuse_handler::FuseHandler, types::*};
 
 fn get_random_generation() -> u64 {
     Instant::now().elapsed().as_nanos() as u64
 }
 
+/// Fails with `EFBIG` if a file reaching `end` bytes would exceed the maximum size of the handler
+fn check_max_file_size(max_file_size: Option<u64>, end: u64) -> FuseResult<()> {
+    match max_file_size {
+        Some(max_file_size) if end > max_file_size => Err(ErrorKind::FileTooLarge.to_error(
+            format!("File size {} exceeds the maximum of {}", end, max_file_size),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Fails with `ENAMETOOLONG` if `name` exceeds the maximum name length of the handler
+fn check_max_name_length(max_name_length: Option<u32>, name: &OsStr) -> FuseResult<()> {
+    match max_name_length {
+        Some(max_name_length) if name.len() > max_name_length as usize => {
+            Err(ErrorKind::FileNameTooLong.to_error(format!(
+                "{:?}: name exceeds the maximum length of {}",
+                name, max_name_length
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+thread_local! {
+    /// Buffer passed to `read_into`, reused by the requests served on the thread
+    static READ_BUFFER: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Calls `f` with the read buffer of the thread, resized to `size` bytes
+fn with_read_buffer<R>(size: usize, f: impl FnOnce(&mut [u8]) -> R) -> R {
+    READ_BUFFER.with(|buffer| {
+        let mut buffer = buffer.borrow_mut();
+        buffer.resize(size, 0);
+        f(&mut buffer)
+    })
+}
+
+/// Joins extended attribute names in the wire format of `listxattr`, each followed by a NUL byte
+#[cfg(feature = "xattr")]
+fn serialize_xattr_names(names: &[OsString]) -> FuseResult<Vec<u8>> {
+    let mut xattr_data = Vec::with_capacity(names.iter().map(|name| name.len() + 1).sum());
+    for name in names {
+        if name.is_empty() || name.as_bytes().contains(&0) {
+            return Err(ErrorKind::InvalidArgument
+                .to_error(format!("{:?}: invalid extended attribute name", name)));
+        }
+        xattr_data.extend_from_slice(name.as_bytes());
+        xattr_data.push(0);
+    }
+    Ok(xattr_data)
+}
+
+/// TTL of an entry or attributes reply, zero for handlers with volatile inodes
+fn reply_ttl<TId: FileIdType, THandler: FuseHandler<TId> + ?Sized>(
+    handler: &THandler,
+    ttl: Option<Duration>,
+) -> Duration {
+    if handler.volatile_inodes() {
+        return Duration::ZERO;
+    }
+    ttl.unwrap_or_else(|| handler.get_default_ttl())
+}
+
+/// Number of directory reads in progress to keep, none for handlers with volatile inodes
+fn dir_streams_capacity<TId: FileIdType, THandler: FuseHandler<TId> + ?Sized>(
+    handler: &THandler,
+) -> usize {
+    if handler.volatile_inodes() {
+        return 0;
+    }
+    handler.max_dir_streams()
+}
+
+/// Attributes of a negative entry, the kernel only considers the zero inode and the entry TTL
+fn negative_entry_attr() -> fuser::FileAttr {
+    fuser::FileAttr {
+        ino: 0,
+        size: 0,
+        blocks: 0,
+        atime: SystemTime::UNIX_EPOCH,
+        mtime: SystemTime::UNIX_EPOCH,
+        ctime: SystemTime::UNIX_EPOCH,
+        crtime: SystemTime::UNIX_EPOCH,
+        kind: fuser::FileType::RegularFile,
+        perm: 0,
+        nlink: 0,
+        uid: 0,
+        gid: 0,
+        rdev: 0,
+        blksize: 0,
+        flags: 0,
+    }
+}
+
+impl<TId, THandler> FuseDriver<TId, THandler>
+where
+    TId: FileIdType,
+    THandler: FuseHandler<TId>,
+{
+    /// Information of a request, with the deadline configured by the handler
+    fn request_info(&self, req: &Request) -> RequestInfo {
+        RequestInfo::from(req).with_timeout(self.get_handler().request_timeout())
+    }
+
+    /// Resolves the file ids of forgotten `(ino, nlookup)` pairs, skipping the root and unknown inodes
+    ///
+    /// The kernel never forgets the root, and a zero or stale inode has no file id to forget.
+    fn forgotten_ids(&self, nodes: &[(u64, u64)]) -> Vec<(u64, u64, TId)> {
+        let resolver = self.get_resolver();
+        nodes
+            .iter()
+            .filter_map(|&(ino, nlookup)| {
+                if ino == ROOT_INO || ino == 0 {
+                    warn!("Ignoring forget of inode {} with nlookup {}", ino, nlookup);
+                    return None;
+                }
+                let file_id = resolver.try_resolve_id(ino);
+                if file_id.is_none() {
+                    warn!(
+                        "Ignoring forget of unknown inode {} with nlookup {}",
+                        ino, nlookup
+                    );
+                }
+                file_id.map(|file_id| (ino, nlookup, file_id))
+            })
+            .collect()
+    }
+}
+
 impl<TId, THandler> fuser::Filesystem for FuseDriver<TId, THandler>
 where
     TId: FileIdType,
     THandler: FuseHandler<TId>,
 {
     fn init(&mut self, req: &Request, config: &mut KernelConfig) -> Result<(), c_int> {
-        let req = RequestInfo::from(req);
-        match self.get_handler().init(&req, config) {
+        let req = self.request_info(req);
+        let handler = self.get_handler();
+        if let Err(nearest) = config.set_max_write(handler.max_write()) {
+            info!(
+                "init: max_write {} out of range, using {}",
+                handler.max_write(),
+                nearest
+            );
+            let _ = config.set_max_write(nearest);
+        }
+        match handler.init(&req, config) {
             Ok(()) => Ok(()),
             Err(e) => {
                 warn!("[{}] init {:?}", e, req);
@@ -42,11 +192,19 @@ where
     }
 
     fn destroy(&mut self) {
-        self.get_handler().destroy();
+        #[cfg(debug_assertions)]
+        for inconsistency in self.check_consistency() {
+            warn!("Inconsistent resolver state: {}", inconsistency);
+        }
+        let handler = self.get_handler();
+        if let Err(e) = handler.pre_unmount() {
+            error!("pre_unmount failed, data may have been lost: {}", e);
+        }
+        handler.destroy();
     }
 
     fn access(&mut self, req: &Request, ino: u64, mask: i32, reply: ReplyEmpty) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
         let resolver = self.get_resolver();
         execute_task!(self, {
@@ -64,8 +222,25 @@ where
         });
     }
 
+    fn batch_forget(&mut self, req: &Request, nodes: &[fuse_forget_one]) {
+        let req = self.request_info(req);
+        let handler = self.get_handler();
+        let resolver = self.get_resolver();
+        let nodes: Vec<(u64, u64)> = nodes
+            .iter()
+            .map(|node| (node.nodeid, node.nlookup))
+            .collect();
+        let (inodes, file_ids) = self
+            .forgotten_ids(&nodes)
+            .into_iter()
+            .map(|(ino, nlookup, file_id)| ((ino, nlookup), (file_id, nlookup)))
+            .unzip::<_, _, Vec<_>, Vec<_>>();
+        handler.batch_forget(&req, file_ids);
+        resolver.batch_forget(&inodes);
+    }
+
     fn bmap(&mut self, req: &Request<'_>, ino: u64, blocksize: u32, idx: u64, reply: ReplyBmap) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
         let resolver = self.get_resolver();
         execute_task!(self, {
@@ -92,9 +267,10 @@ where
         flags: u32,
         reply: ReplyWrite,
     ) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
         let resolver = self.get_resolver();
+        let clean_handles = self.get_clean_handles();
         execute_task!(self, {
             match handler.copy_file_range(
                 &req,
@@ -107,7 +283,10 @@ where
                 len,
                 flags,
             ) {
-                Ok(bytes_written) => reply.written(bytes_written),
+                Ok(bytes_written) => {
+                    clean_handles.safe_borrow_mut().written(fh_out);
+                    reply.written(bytes_written)
+                }
                 Err(e) => {
                     warn!("copy_file_range: ino {:x?}, [{}], {:?}", ino_in, e, req);
                     reply.error(e.raw_error())
@@ -126,9 +305,15 @@ where
         flags: i32,
         reply: ReplyCreate,
     ) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
+        if let Err(e) = check_max_name_length(handler.max_name_length(), name) {
+            warn!("create: parent {:x?}, [{}], {:?}", parent, e, req);
+            reply.error(e.raw_error());
+            return;
+        }
         let resolver = self.get_resolver();
+        let clean_handles = self.get_clean_handles();
         let name = name.to_owned();
         execute_task!(self, {
             match handler.create(
@@ -140,12 +325,16 @@ where
                 OpenFlags::from_bits_retain(flags),
             ) {
                 Ok((file_handle, metadata, response_flags)) => {
-                    let default_ttl = handler.get_default_ttl();
                     let (id, file_attr) = TId::extract_metadata(metadata);
+                    // The kernel references the created entry until it sends a forget for it
                     let ino = resolver.lookup(parent, &name, id, true);
-                    let (fuse_attr, ttl, generation) = file_attr.to_fuse(ino);
+                    let (fuse_attr, ttl, generation) = file_attr
+                        .clear_unavailable(handler.available_attributes())
+                        .with_default_blksize(handler.preferred_blksize())
+                        .to_fuse(ino);
+                    clean_handles.safe_borrow_mut().opened(file_handle.as_raw());
                     reply.created(
-                        &ttl.unwrap_or(default_ttl),
+                        &reply_ttl(&*handler, ttl),
                         &fuse_attr,
                         generation.unwrap_or(get_random_generation()),
                         file_handle.as_raw(),
@@ -170,9 +359,22 @@ where
         mode: i32,
         reply: ReplyEmpty,
     ) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
         let resolver = self.get_resolver();
+        #[cfg(target_os = "linux")]
+        let keep_size = FallocateFlags::from_bits_retain(mode).contains(FallocateFlags::KEEP_SIZE);
+        #[cfg(not(target_os = "linux"))]
+        let keep_size = false;
+        if !keep_size {
+            let end = (offset.max(0) as u64).saturating_add(length.max(0) as u64);
+            if let Err(e) = check_max_file_size(handler.max_file_size(), end) {
+                warn!("fallocate: ino {:x?}, [{}], {:?}", ino, e, req);
+                reply.error(e.raw_error());
+                return;
+            }
+        }
+        let clean_handles = self.get_clean_handles();
         execute_task!(self, {
             match handler.fallocate(
                 &req,
@@ -182,7 +384,10 @@ where
                 length,
                 FallocateFlags::from_bits_retain(mode),
             ) {
-                Ok(()) => reply.ok(),
+                Ok(()) => {
+                    clean_handles.safe_borrow_mut().written(fh);
+                    reply.ok()
+                }
                 Err(e) => {
                     warn!("fallocate: ino {:x?}, [{}], {:?}", ino, e, req);
                     reply.error(e.raw_error())
@@ -192,17 +397,30 @@ where
     }
 
     fn flush(&mut self, req: &Request, ino: u64, fh: u64, lock_owner: u64, reply: ReplyEmpty) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
         let resolver = self.get_resolver();
+        let clean_handles = self.get_clean_handles();
         execute_task!(self, {
+            let skip_clean = handler.skip_clean_flushes();
+            let dirty_writes = clean_handles.safe_borrow_mut().dirty_writes(fh);
+            if skip_clean && dirty_writes.is_none() {
+                // Nothing was written since the last flush, eg: close() of a dup'd descriptor
+                reply.ok();
+                return;
+            }
             match handler.flush(
                 &req,
                 resolver.resolve_id(ino),
                 unsafe { BorrowedFileHandle::from_raw(fh) },
                 lock_owner,
             ) {
-                Ok(()) => reply.ok(),
+                Ok(()) => {
+                    if let Some(writes) = dirty_writes {
+                        clean_handles.safe_borrow_mut().flushed(fh, writes);
+                    }
+                    reply.ok()
+                }
                 Err(e) => {
                     warn!("flush: ino {:x?}, [{}], {:?}", ino, e, req);
                     reply.error(e.raw_error())
@@ -212,15 +430,17 @@ where
     }
 
     fn forget(&mut self, req: &Request, ino: u64, nlookup: u64) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
         let resolver = self.get_resolver();
-        handler.forget(&req, resolver.resolve_id(ino), nlookup);
-        resolver.forget(ino, nlookup);
+        for (ino, nlookup, file_id) in self.forgotten_ids(&[(ino, nlookup)]) {
+            handler.forget(&req, file_id, nlookup);
+            resolver.forget(ino, nlookup);
+        }
     }
 
     fn fsync(&mut self, req: &Request, ino: u64, fh: u64, datasync: bool, reply: ReplyEmpty) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
         let resolver = self.get_resolver();
         execute_task!(self, {
@@ -240,7 +460,7 @@ where
     }
 
     fn fsyncdir(&mut self, req: &Request, ino: u64, fh: u64, datasync: bool, reply: ReplyEmpty) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
         let resolver = self.get_resolver();
         execute_task!(self, {
@@ -260,7 +480,7 @@ where
     }
 
     fn getattr(&mut self, req: &Request, ino: u64, fh: Option<u64>, reply: ReplyAttr) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
         let resolver = self.get_resolver();
         execute_task!(self, {
@@ -280,6 +500,7 @@ where
         });
     }
 
+    #[cfg(feature = "locking")]
     fn getlk(
         &mut self,
         req: &Request<'_>,
@@ -292,7 +513,7 @@ where
         pid: u32,
         reply: ReplyLock,
     ) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
         let resolver = self.get_resolver();
         execute_task!(self, {
@@ -323,8 +544,9 @@ where
         });
     }
 
+    #[cfg(feature = "xattr")]
     fn getxattr(&mut self, req: &Request, ino: u64, name: &OsStr, size: u32, reply: ReplyXattr) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
         let resolver = self.get_resolver();
         let name = name.to_owned();
@@ -347,6 +569,7 @@ where
         });
     }
 
+    #[cfg(feature = "ioctl")]
     fn ioctl(
         &mut self,
         req: &Request<'_>,
@@ -358,8 +581,31 @@ where
         out_size: u32,
         reply: ReplyIoctl,
     ) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
+        if cmd == DROP_CACHES_IOCTL {
+            let notifier = self.get_notifier();
+            execute_task!(self, {
+                match handler.drop_caches(&req) {
+                    Ok(()) => {
+                        reply.ioctl(0, &[]);
+                        // The kernel may wait on requests being served to complete invalidations
+                        if let Some(notifier) = notifier.get().cloned() {
+                            std::thread::spawn(move || {
+                                if let Err(e) = notifier.invalidate_all() {
+                                    warn!("drop_caches: [{}] kernel invalidation failed", e);
+                                }
+                            });
+                        }
+                    }
+                    Err(e) => {
+                        warn!("drop_caches: ino {:x?}, [{}], {:?}", ino, e, req);
+                        reply.error(e.raw_error())
+                    }
+                };
+            });
+            return;
+        }
         let resolver = self.get_resolver();
         let in_data = in_data.to_owned();
         execute_task!(self, {
@@ -389,35 +635,67 @@ where
         newname: &OsStr,
         reply: ReplyEntry,
     ) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
+        if let Err(e) = check_max_name_length(handler.max_name_length(), newname) {
+            warn!("link: parent {:x?}, [{}], {:?}", newparent, e, req);
+            reply.error(e.raw_error());
+            return;
+        }
         let resolver = self.get_resolver();
+        let notifier = self.get_notifier();
         let newname = newname.to_owned();
         execute_task!(self, {
-            handle_fuse_reply_entry!(
-                handler,
-                resolver,
+            match handler.link(
                 &req,
-                newparent,
+                resolver.resolve_id(ino),
+                resolver.resolve_id(newparent),
                 &newname,
-                reply,
-                link,
-                (
-                    &req,
-                    resolver.resolve_id(ino),
-                    resolver.resolve_id(newparent),
-                    &newname
-                )
-            );
+            ) {
+                Ok(metadata) => {
+                    let (id, file_attr) = TId::extract_metadata(metadata);
+                    let new_ino = resolver.lookup(newparent, &newname, id, true);
+                    let (fuse_attr, ttl, generation) = file_attr
+                        .clear_unavailable(handler.available_attributes())
+                        .with_default_blksize(handler.preferred_blksize())
+                        .to_fuse(new_ino);
+                    // Resolvers giving each path its own inode leave the link count of the source stale.
+                    // Invalidating before the reply ensures it is done once the link syscall returns, it doesn't
+                    // wait on the source inode locked by the kernel as only the attributes are invalidated.
+                    if new_ino != ino {
+                        if let Some(notifier) = notifier.get() {
+                            if let Err(e) = notifier.invalidate_inode_attr(ino) {
+                                warn!("link: ino {:x?}, [{}] kernel invalidation failed", ino, e);
+                            }
+                        }
+                    }
+                    reply.entry(
+                        &reply_ttl(&*handler, ttl),
+                        &fuse_attr,
+                        generation.unwrap_or(get_random_generation()),
+                    );
+                }
+                Err(e) => {
+                    warn!("link: parent_ino {:x?}, [{}], {:?}", newparent, e, req);
+                    reply.error(e.raw_error())
+                }
+            };
         });
     }
 
+    #[cfg(feature = "xattr")]
     fn listxattr(&mut self, req: &Request, ino: u64, size: u32, reply: ReplyXattr) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
         let resolver = self.get_resolver();
         execute_task!(self, {
-            match handler.listxattr(&req, resolver.resolve_id(ino), size) {
+            let xattr_data = match handler.listxattr_names(&req, resolver.resolve_id(ino)) {
+                Err(e) if e.kind() == ErrorKind::FunctionNotImplemented => {
+                    handler.listxattr(&req, resolver.resolve_id(ino), size)
+                }
+                result => result.and_then(|names| serialize_xattr_names(&names)),
+            };
+            match xattr_data {
                 Ok(xattr_data) => {
                     if size == 0 {
                         reply.size(xattr_data.len() as u32);
@@ -436,8 +714,13 @@ where
     }
 
     fn lookup(&mut self, req: &Request, parent: u64, name: &OsStr, reply: ReplyEntry) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
+        if let Err(e) = check_max_name_length(handler.max_name_length(), name) {
+            warn!("lookup: parent {:x?}, [{}], {:?}", parent, e, req);
+            reply.error(e.raw_error());
+            return;
+        }
         let resolver = self.get_resolver();
         let name = name.to_owned();
         execute_task!(self, {
@@ -463,16 +746,23 @@ where
         whence: i32,
         reply: ReplyLseek,
     ) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
         let resolver = self.get_resolver();
         execute_task!(self, {
-            match handler.lseek(
-                &req,
-                resolver.resolve_id(ino),
-                unsafe { BorrowedFileHandle::from_raw(fh) },
-                seek_from_raw(Some(whence), offset),
-            ) {
+            let file_id = resolver.resolve_id(ino);
+            let file_handle = unsafe { BorrowedFileHandle::from_raw(fh) };
+            // SEEK_DATA and SEEK_HOLE have no SeekFrom equivalent
+            let result = match whence {
+                libc::SEEK_SET | libc::SEEK_CUR | libc::SEEK_END => handler.lseek(
+                    &req,
+                    file_id,
+                    file_handle,
+                    seek_from_raw(Some(whence), offset),
+                ),
+                _ => handler.lseek_raw(&req, file_id, file_handle, offset, whence),
+            };
+            match result {
                 Ok(new_offset) => reply.offset(new_offset),
                 Err(e) => {
                     warn!("lseek: ino {:x?}, [{}], {:?}", ino, e, req);
@@ -491,8 +781,13 @@ where
         umask: u32,
         reply: ReplyEntry,
     ) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
+        if let Err(e) = check_max_name_length(handler.max_name_length(), name) {
+            warn!("mkdir: parent {:x?}, [{}], {:?}", parent, e, req);
+            reply.error(e.raw_error());
+            return;
+        }
         let resolver = self.get_resolver();
         let name = name.to_owned();
         execute_task!(self, {
@@ -519,8 +814,13 @@ where
         rdev: u32,
         reply: ReplyEntry,
     ) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
+        if let Err(e) = check_max_name_length(handler.max_name_length(), name) {
+            warn!("mknod: parent {:x?}, [{}], {:?}", parent, e, req);
+            reply.error(e.raw_error());
+            return;
+        }
         let resolver = self.get_resolver();
         let name = name.to_owned();
         execute_task!(self, {
@@ -545,9 +845,10 @@ where
     }
 
     fn open(&mut self, req: &Request, ino: u64, _flags: i32, reply: ReplyOpen) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
         let resolver = self.get_resolver();
+        let clean_handles = self.get_clean_handles();
         execute_task!(self, {
             match handler.open(
                 &req,
@@ -555,6 +856,7 @@ where
                 OpenFlags::from_bits_retain(_flags),
             ) {
                 Ok((file_handle, response_flags)) => {
+                    clean_handles.safe_borrow_mut().opened(file_handle.as_raw());
                     reply.opened(file_handle.as_raw(), response_flags.bits())
                 }
                 Err(e) => {
@@ -566,7 +868,7 @@ where
     }
 
     fn opendir(&mut self, req: &Request, ino: u64, _flags: i32, reply: ReplyOpen) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
         let resolver = self.get_resolver();
         execute_task!(self, {
@@ -586,39 +888,75 @@ where
         });
     }
 
-    fn read(
+    fn poll(
         &mut self,
         req: &Request,
         ino: u64,
         fh: u64,
-        offset: i64,
-        size: u32,
-        flags: i32,
-        lock_owner: Option<u64>,
-        reply: ReplyData,
+        ph: PollHandle,
+        events: u32,
+        flags: u32,
+        reply: ReplyPoll,
     ) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
         let resolver = self.get_resolver();
         execute_task!(self, {
-            match handler.read(
+            match handler.poll(
                 &req,
                 resolver.resolve_id(ino),
                 unsafe { BorrowedFileHandle::from_raw(fh) },
-                seek_from_raw(None, offset),
-                size,
-                FUSEOpenFlags::from_bits_retain(flags),
-                lock_owner,
+                ph,
+                events,
+                flags,
             ) {
-                Ok(data_reply) => reply.data(&data_reply),
+                Ok(revents) => reply.poll(revents),
                 Err(e) => {
-                    warn!("read: ino {:x?}, [{}], {:?}", ino, e, req);
+                    // Not implemented is the common case, the kernel stops polling after it
+                    if e.kind() != ErrorKind::FunctionNotImplemented {
+                        warn!("poll: ino {:x?}, [{}], {:?}", ino, e, req);
+                    }
                     reply.error(e.raw_error())
                 }
             };
         });
     }
 
+    fn read(
+        &mut self,
+        req: &Request,
+        ino: u64,
+        fh: u64,
+        offset: i64,
+        size: u32,
+        flags: i32,
+        lock_owner: Option<u64>,
+        reply: ReplyData,
+    ) {
+        let req = self.request_info(req);
+        let handler = self.get_handler();
+        let resolver = self.get_resolver();
+        execute_task!(self, {
+            with_read_buffer(size as usize, |buffer| {
+                match handler.read_into(
+                    &req,
+                    resolver.resolve_id(ino),
+                    unsafe { BorrowedFileHandle::from_raw(fh) },
+                    seek_from_raw(None, offset),
+                    buffer,
+                    OpenFlags::from_bits_retain(flags),
+                    lock_owner,
+                ) {
+                    Ok(len) => reply.data(&buffer[..len.min(buffer.len())]),
+                    Err(e) => {
+                        warn!("read: ino {:x?}, [{}], {:?}", ino, e, req);
+                        reply.error(e.raw_error())
+                    }
+                };
+            });
+        });
+    }
+
     fn readdir(
         &mut self,
         req: &Request,
@@ -662,14 +1000,14 @@ where
     }
 
     fn readlink(&mut self, req: &Request, ino: u64, reply: ReplyData) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
         let resolver = self.get_resolver();
         execute_task!(self, {
             match handler.readlink(&req, resolver.resolve_id(ino)) {
                 Ok(link) => reply.data(&link),
                 Err(e) => {
-                    warn!("[{}] readlink, ino: {:x?}, {:?}", ino, e, req);
+                    warn!("readlink: ino {:x?}, [{}], {:?}", ino, e, req);
                     reply.error(e.raw_error())
                 }
             };
@@ -686,10 +1024,12 @@ where
         _flush: bool,
         reply: ReplyEmpty,
     ) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
         let resolver = self.get_resolver();
+        let clean_handles = self.get_clean_handles();
         execute_task!(self, {
+            clean_handles.safe_borrow_mut().released(fh);
             match handler.release(
                 &req,
                 resolver.resolve_id(ino),
@@ -708,10 +1048,19 @@ where
     }
 
     fn releasedir(&mut self, req: &Request, ino: u64, fh: u64, flags: i32, reply: ReplyEmpty) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
         let resolver = self.get_resolver();
+        let dirmap_iter = self.get_dirmap_iter();
+        let dirmapplus_iter = self.get_dirmapplus_iter();
         execute_task!(self, {
+            // Drop the continuation of reads that were not run to completion
+            dirmap_iter
+                .safe_borrow_mut()
+                .retain(|&(dir_ino, dir_fh, _), _| (dir_ino, dir_fh) != (ino, fh));
+            dirmapplus_iter
+                .safe_borrow_mut()
+                .retain(|&(dir_ino, dir_fh, _), _| (dir_ino, dir_fh) != (ino, fh));
             match handler.releasedir(
                 &req,
                 resolver.resolve_id(ino),
@@ -727,8 +1076,9 @@ where
         });
     }
 
+    #[cfg(feature = "xattr")]
     fn removexattr(&mut self, req: &Request, ino: u64, name: &OsStr, reply: ReplyEmpty) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
         let resolver = self.get_resolver();
         let name = name.to_owned();
@@ -753,11 +1103,17 @@ where
         flags: u32,
         reply: ReplyEmpty,
     ) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
+        if let Err(e) = check_max_name_length(handler.max_name_length(), newname) {
+            warn!("rename: parent {:x?}, [{}], {:?}", newparent, e, req);
+            reply.error(e.raw_error());
+            return;
+        }
         let resolver = self.get_resolver();
         let name = name.to_owned();
         let newname = newname.to_owned();
+        let flags = RenameFlags::from_bits_retain(flags);
         execute_task!(self, {
             match handler.rename(
                 &req,
@@ -765,10 +1121,10 @@ where
                 &name,
                 resolver.resolve_id(newparent),
                 &newname,
-                RenameFlags::from_bits_retain(flags),
+                flags,
             ) {
                 Ok(()) => {
-                    resolver.rename(parent, &name, newparent, &newname);
+                    resolver.rename(parent, &name, newparent, &newname, flags);
                     reply.ok()
                 }
                 Err(e) => {
@@ -780,7 +1136,7 @@ where
     }
 
     fn rmdir(&mut self, req: &Request, parent: u64, name: &OsStr, reply: ReplyEmpty) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
         let resolver = self.get_resolver();
         let name = name.to_owned();
@@ -810,12 +1166,19 @@ where
         crtime: Option<SystemTime>,
         chgtime: Option<SystemTime>,
         bkuptime: Option<SystemTime>,
-        _flags: Option<u32>,
+        flags: Option<u32>,
         reply: ReplyAttr,
     ) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
         let resolver = self.get_resolver();
+        if let Some(size) = size {
+            if let Err(e) = check_max_file_size(handler.max_file_size(), size) {
+                warn!("setattr: ino {:x?}, [{}], {:?}", ino, e, req);
+                reply.error(e.raw_error());
+                return;
+            }
+        }
         let attrs = SetAttrRequest {
             mode,
             uid,
@@ -827,7 +1190,7 @@ where
             crtime: crtime,
             chgtime: chgtime,
             bkuptime: bkuptime,
-            flags: None,
+            flags,
             file_handle: fh.map(|fh| unsafe { BorrowedFileHandle::from_raw(fh) }),
         };
         execute_task!(self, {
@@ -843,6 +1206,7 @@ where
         });
     }
 
+    #[cfg(feature = "locking")]
     fn setlk(
         &mut self,
         req: &Request<'_>,
@@ -856,7 +1220,7 @@ where
         sleep: bool,
         reply: ReplyEmpty,
     ) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
         let resolver = self.get_resolver();
         execute_task!(self, {
@@ -883,6 +1247,7 @@ where
         });
     }
 
+    #[cfg(feature = "xattr")]
     fn setxattr(
         &mut self,
         req: &Request,
@@ -893,7 +1258,7 @@ where
         position: u32,
         reply: ReplyEmpty,
     ) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
         let resolver = self.get_resolver();
         let name = name.to_owned();
@@ -917,21 +1282,27 @@ where
     }
 
     fn statfs(&mut self, req: &Request, ino: u64, reply: ReplyStatfs) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
         let resolver = self.get_resolver();
         execute_task!(self, {
             match handler.statfs(&req, resolver.resolve_id(ino)) {
-                Ok(statfs) => reply.statfs(
-                    statfs.total_blocks,
-                    statfs.free_blocks,
-                    statfs.available_blocks,
-                    statfs.total_files,
-                    statfs.free_files,
-                    statfs.block_size,
-                    statfs.max_filename_length,
-                    statfs.fragment_size,
-                ),
+                Ok(mut statfs) => {
+                    // Advertise the enforced limit, which applications read through `pathconf`
+                    if let Some(max_name_length) = handler.max_name_length() {
+                        statfs.max_filename_length = max_name_length;
+                    }
+                    reply.statfs(
+                        statfs.total_blocks,
+                        statfs.free_blocks,
+                        statfs.available_blocks,
+                        statfs.total_files,
+                        statfs.free_files,
+                        statfs.block_size,
+                        statfs.max_filename_length,
+                        statfs.fragment_size,
+                    )
+                }
                 Err(e) => {
                     warn!("statfs: ino {:x?}, [{}], {:?}", ino, e, req);
                     reply.error(e.raw_error())
@@ -948,8 +1319,13 @@ where
         target: &Path,
         reply: ReplyEntry,
     ) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
+        if let Err(e) = check_max_name_length(handler.max_name_length(), link_name) {
+            warn!("symlink: parent {:x?}, [{}], {:?}", parent, e, req);
+            reply.error(e.raw_error());
+            return;
+        }
         let resolver = self.get_resolver();
         let link_name = link_name.to_owned();
         let target = target.to_owned();
@@ -979,9 +1355,16 @@ where
         lock_owner: Option<u64>,
         reply: ReplyWrite,
     ) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
         let resolver = self.get_resolver();
+        let end = (offset.max(0) as u64).saturating_add(data.len() as u64);
+        if let Err(e) = check_max_file_size(handler.max_file_size(), end) {
+            warn!("write: ino {:x?}, [{}], {:?}", ino, e, req);
+            reply.error(e.raw_error());
+            return;
+        }
+        let clean_handles = self.get_clean_handles();
         let data = data.to_owned();
         execute_task!(self, {
             match handler.write(
@@ -994,7 +1377,10 @@ where
                 OpenFlags::from_bits_retain(flags),
                 lock_owner,
             ) {
-                Ok(bytes_written) => reply.written(bytes_written),
+                Ok(bytes_written) => {
+                    clean_handles.safe_borrow_mut().written(fh);
+                    reply.written(bytes_written)
+                }
                 Err(e) => {
                     warn!("write: ino {:x?}, [{}], {:?}", ino, e, req);
                     reply.error(e.raw_error())
@@ -1004,7 +1390,7 @@ where
     }
 
     fn unlink(&mut self, req: &Request, parent: u64, name: &OsStr, reply: ReplyEmpty) {
-        let req = RequestInfo::from(req);
+        let req = self.request_info(req);
         let handler = self.get_handler();
         let resolver = self.get_resolver();
         let name = name.to_owned();
@@ -1019,3 +1405,36 @@ where
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::DefaultFuseHandler;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_forget_skips_root_and_unknown_inodes() {
+        let driver = FuseDriver::<PathBuf, _>::new(DefaultFuseHandler::new(), 1);
+        let resolver = driver.get_resolver();
+        let ino = resolver.lookup(ROOT_INO, OsStr::new("file"), (), true);
+
+        let forgotten = driver.forgotten_ids(&[(0, 1), (ROOT_INO, 1), (ino + 1, 1), (ino, 1)]);
+        assert_eq!(forgotten, [(ino, 1, PathBuf::from("file"))]);
+        resolver.batch_forget(&[(ino, 1)]);
+        // The forgotten inode is now stale
+        assert!(driver.forgotten_ids(&[(ino, 1)]).is_empty());
+    }
+
+    #[cfg(feature = "xattr")]
+    #[test]
+    fn test_serialize_xattr_names() {
+        let names = [OsString::from("user.foo"), OsString::from("user.bar")];
+        let xattr_data = serialize_xattr_names(&names).unwrap();
+        assert_eq!(xattr_data, b"user.foo\0user.bar\0");
+        assert_eq!(xattr_data.len(), 18);
+
+        assert!(serialize_xattr_names(&[]).unwrap().is_empty());
+        let error = serialize_xattr_names(&[OsString::from("user.\0foo")]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidArgument);
+    }
+}
@@ -1,21 +1,120 @@
 #![allow(unused_imports)]
 
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     ffi::{OsStr, OsString},
+    sync::OnceLock,
+    time::Instant,
 };
 
 use super::inode_mapping::FileIdResolver;
 use crate::fuse_handler::FuseHandler;
+use crate::notifier::Notifier;
 use crate::types::*;
 
-type DirIter<TAttr> = HashMap<(u64, i64), VecDeque<(OsString, u64, TAttr)>>;
+/// Directory read in progress
+pub(crate) struct DirStream<TMetadata, TAttr> {
+    /// Entries already registered in the resolver, without lookup count, not sent yet
+    pub pending: VecDeque<(OsString, u64, TAttr)>,
+    /// Remaining entries, pulled from the handler on demand
+    pub entries: Box<dyn Iterator<Item = FuseResult<(OsString, TMetadata)>> + Send>,
+    /// Time at which the stream was saved, to evict the oldest one
+    pub saved_at: Instant,
+}
+
+/// Directory reads in progress, keyed by (directory ino, file handle, offset)
+type DirIter<TMetadata, TAttr> = HashMap<(u64, u64, i64), DirStream<TMetadata, TAttr>>;
+
+/// Number of entries pulled from a directory stream and registered in the resolver at once
+pub(crate) const DIR_BATCH_SIZE: usize = 64;
+
+/// Saves a directory read to be continued, evicting the oldest one if `capacity` is reached
+pub(crate) fn save_dir_stream<TMetadata, TAttr>(
+    dir_streams: &mut DirIter<TMetadata, TAttr>,
+    key: (u64, u64, i64),
+    mut dir_stream: DirStream<TMetadata, TAttr>,
+    capacity: usize,
+) {
+    if capacity == 0 {
+        return;
+    }
+    while dir_streams.len() >= capacity {
+        let oldest = dir_streams
+            .iter()
+            .min_by_key(|(_, dir_stream)| dir_stream.saved_at)
+            .map(|(key, _)| *key);
+        match oldest {
+            Some(oldest) => dir_streams.remove(&oldest),
+            None => break,
+        };
+    }
+    dir_stream.saved_at = Instant::now();
+    dir_streams.insert(key, dir_stream);
+}
+
+/// Write state of the open file handles, to skip the flushes of clean handles
+///
+/// Handlers may return the same file handle for several opens (eg: a constant handle), so the opens of each
+/// handle are counted, and a new open of a handle written to doesn't mark it clean.
+#[derive(Default)]
+pub(crate) struct CleanHandles {
+    handles: HashMap<u64, HandleWrites>,
+}
+
+#[derive(Default)]
+struct HandleWrites {
+    opens: usize,
+    /// Number of writes, and number of writes covered by the last successful flush
+    writes: u64,
+    flushed: u64,
+}
+
+impl CleanHandles {
+    pub fn opened(&mut self, fh: u64) {
+        self.handles.entry(fh).or_default().opens += 1;
+    }
+
+    pub fn written(&mut self, fh: u64) {
+        self.handles.entry(fh).or_default().writes += 1;
+    }
+
+    /// Returns `None` if nothing was written to the handle since it was opened or last flushed,
+    /// otherwise the number of writes to pass to `flushed` once the flush succeeded
+    pub fn dirty_writes(&self, fh: u64) -> Option<u64> {
+        match self.handles.get(&fh) {
+            Some(state) if state.writes == state.flushed => None,
+            Some(state) => Some(state.writes),
+            // Unknown handles are never skipped
+            None => Some(0),
+        }
+    }
+
+    /// Marks the `writes` first writes as flushed, writes completed during the flush stay dirty
+    pub fn flushed(&mut self, fh: u64, writes: u64) {
+        if let Some(state) = self.handles.get_mut(&fh) {
+            state.flushed = state.flushed.max(writes);
+        }
+    }
+
+    pub fn released(&mut self, fh: u64) {
+        if let Some(state) = self.handles.get_mut(&fh) {
+            state.opens = state.opens.saturating_sub(1);
+            if state.opens == 0 {
+                self.handles.remove(&fh);
+            }
+        }
+    }
+}
+
+/// Notifier of the session, only available once the session is created
+pub(crate) type NotifierSlot<TId> = OnceLock<Notifier<TId>>;
 
 #[cfg(feature = "serial")]
 mod serial {
     use super::*;
 
     use std::cell::RefCell;
+    use std::sync::Arc;
 
     pub(crate) struct FuseDriver<TId, THandler>
     where
@@ -23,9 +122,11 @@ mod serial {
         THandler: FuseHandler<TId>,
     {
         handler: THandler,
-        resolver: TId::Resolver,
-        dirmap_iter: RefCell<DirIter<FileKind>>,
-        dirmapplus_iter: RefCell<DirIter<FileAttribute>>,
+        resolver: Arc<TId::Resolver>,
+        dirmap_iter: RefCell<DirIter<TId::MinimalMetadata, FileKind>>,
+        dirmapplus_iter: RefCell<DirIter<TId::Metadata, FileAttribute>>,
+        clean_handles: RefCell<CleanHandles>,
+        notifier: Arc<NotifierSlot<TId>>,
     }
 
     impl<TId, THandler> FuseDriver<TId, THandler>
@@ -37,9 +138,11 @@ mod serial {
         pub fn new(handler: THandler, _num_threads: usize) -> FuseDriver<TId, THandler> {
             FuseDriver {
                 handler,
-                resolver: TId::Resolver::new(),
+                resolver: Arc::new(TId::Resolver::new()),
                 dirmap_iter: RefCell::new(HashMap::new()),
                 dirmapplus_iter: RefCell::new(HashMap::new()),
+                clean_handles: RefCell::new(CleanHandles::default()),
+                notifier: Arc::new(OnceLock::new()),
             }
         }
 
@@ -51,13 +154,25 @@ mod serial {
             &self.resolver
         }
 
-        pub fn get_dirmap_iter(&self) -> &RefCell<DirIter<FileKind>> {
+        pub fn get_shared_resolver(&self) -> Arc<TId::Resolver> {
+            self.resolver.clone()
+        }
+
+        pub fn get_dirmap_iter(&self) -> &RefCell<DirIter<TId::MinimalMetadata, FileKind>> {
             &self.dirmap_iter
         }
 
-        pub fn get_dirmapplus_iter(&self) -> &RefCell<DirIter<FileAttribute>> {
+        pub fn get_dirmapplus_iter(&self) -> &RefCell<DirIter<TId::Metadata, FileAttribute>> {
             &self.dirmapplus_iter
         }
+
+        pub fn get_clean_handles(&self) -> &RefCell<CleanHandles> {
+            &self.clean_handles
+        }
+
+        pub fn get_notifier(&self) -> Arc<NotifierSlot<TId>> {
+            self.notifier.clone()
+        }
     }
 
     macro_rules! execute_task {
@@ -89,9 +204,12 @@ mod parallel {
     {
         handler: Arc<THandler>,
         resolver: Arc<TId::Resolver>,
-        dirmap_iter: Arc<Mutex<DirIter<FileKind>>>,
-        dirmapplus_iter: Arc<Mutex<DirIter<FileAttribute>>>,
-        pub threadpool: ThreadPool,
+        dirmap_iter: Arc<Mutex<DirIter<TId::MinimalMetadata, FileKind>>>,
+        dirmapplus_iter: Arc<Mutex<DirIter<TId::Metadata, FileAttribute>>>,
+        clean_handles: Arc<Mutex<CleanHandles>>,
+        notifier: Arc<NotifierSlot<TId>>,
+        /// None when running with a single thread: tasks then run inline on the session thread
+        pub threadpool: Option<ThreadPool>,
     }
 
     impl<TId, THandler> FuseDriver<TId, THandler>
@@ -107,7 +225,10 @@ mod parallel {
                 resolver: Arc::new(TId::create_resolver()),
                 dirmap_iter: Arc::new(Mutex::new(HashMap::new())),
                 dirmapplus_iter: Arc::new(Mutex::new(HashMap::new())),
-                threadpool: ThreadPool::new(num_threads),
+                clean_handles: Arc::new(Mutex::new(CleanHandles::default())),
+                notifier: Arc::new(OnceLock::new()),
+                // A pool of one thread only adds a handoff to each request
+                threadpool: (num_threads > 1).then(|| ThreadPool::new(num_threads)),
             }
         }
 
@@ -119,18 +240,37 @@ mod parallel {
             self.resolver.clone()
         }
 
-        pub fn get_dirmap_iter(&self) -> Arc<Mutex<DirIter<FileKind>>> {
+        pub fn get_shared_resolver(&self) -> Arc<TId::Resolver> {
+            self.resolver.clone()
+        }
+
+        pub fn get_dirmap_iter(&self) -> Arc<Mutex<DirIter<TId::MinimalMetadata, FileKind>>> {
             self.dirmap_iter.clone()
         }
 
-        pub fn get_dirmapplus_iter(&self) -> Arc<Mutex<DirIter<FileAttribute>>> {
+        pub fn get_dirmapplus_iter(&self) -> Arc<Mutex<DirIter<TId::Metadata, FileAttribute>>> {
             self.dirmapplus_iter.clone()
         }
+
+        pub fn get_clean_handles(&self) -> Arc<Mutex<CleanHandles>> {
+            self.clean_handles.clone()
+        }
+
+        pub fn get_notifier(&self) -> Arc<NotifierSlot<TId>> {
+            self.notifier.clone()
+        }
     }
 
     macro_rules! execute_task {
         ($self:expr, $block:block) => {
-            $self.threadpool.execute(move || $block);
+            match &$self.threadpool {
+                Some(threadpool) => threadpool.execute(move || $block),
+                None => {
+                    // Still a closure, so that `return` in the block behaves the same in both modes
+                    let task = move || $block;
+                    task()
+                }
+            }
         };
     }
 
@@ -152,8 +292,10 @@ mod async_task {
     {
         handler: Arc<THandler>,
         resolver: Arc<TId::Resolver>,
-        dirmap_iter: Arc<Mutex<DirIter<FileKind>>>,
-        dirmapplus_iter: Arc<Mutex<DirIter<FileAttribute>>>,
+        dirmap_iter: Arc<Mutex<DirIter<TId::MinimalMetadata, FileKind>>>,
+        dirmapplus_iter: Arc<Mutex<DirIter<TId::Metadata, FileAttribute>>>,
+        clean_handles: Arc<Mutex<CleanHandles>>,
+        notifier: Arc<NotifierSlot<TId>>,
         pub runtime: Runtime,
     }
 
@@ -170,6 +312,8 @@ mod async_task {
                 resolver: Arc::new(TId::create_resolver()),
                 dirmap_iter: Arc::new(Mutex::new(HashMap::new())),
                 dirmapplus_iter: Arc::new(Mutex::new(HashMap::new())),
+                clean_handles: Arc::new(Mutex::new(CleanHandles::default())),
+                notifier: Arc::new(OnceLock::new()),
                 runtime: Runtime::new().unwrap(),
             }
         }
@@ -182,13 +326,25 @@ mod async_task {
             self.resolver.clone()
         }
 
-        pub fn get_dirmap_iter(&self) -> Arc<Mutex<DirIter<FileKind>>> {
+        pub fn get_shared_resolver(&self) -> Arc<TId::Resolver> {
+            self.resolver.clone()
+        }
+
+        pub fn get_dirmap_iter(&self) -> Arc<Mutex<DirIter<TId::MinimalMetadata, FileKind>>> {
             self.dirmap_iter.clone()
         }
 
-        pub fn get_dirmapplus_iter(&self) -> Arc<Mutex<DirIter<FileAttribute>>> {
+        pub fn get_dirmapplus_iter(&self) -> Arc<Mutex<DirIter<TId::Metadata, FileAttribute>>> {
             self.dirmapplus_iter.clone()
         }
+
+        pub fn get_clean_handles(&self) -> Arc<Mutex<CleanHandles>> {
+            self.clean_handles.clone()
+        }
+
+        pub fn get_notifier(&self) -> Arc<NotifierSlot<TId>> {
+            self.notifier.clone()
+        }
     }
 
     macro_rules! execute_task {
@@ -234,3 +390,56 @@ pub use parallel::*;
 
 #[cfg(feature = "async")]
 pub use async_task::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dir_stream() -> DirStream<FileKind, FileKind> {
+        DirStream {
+            pending: VecDeque::new(),
+            entries: Box::new(std::iter::empty()),
+            saved_at: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn test_save_dir_stream_evicts_oldest() {
+        let mut dir_streams = HashMap::new();
+        for offset in 1..=3 {
+            save_dir_stream(&mut dir_streams, (2, 0, offset), dir_stream(), 2);
+            // Distinct save times
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        assert_eq!(dir_streams.len(), 2);
+        assert!(!dir_streams.contains_key(&(2, 0, 1)));
+        assert!(dir_streams.contains_key(&(2, 0, 3)));
+
+        save_dir_stream(&mut dir_streams, (2, 0, 4), dir_stream(), 0);
+        assert!(!dir_streams.contains_key(&(2, 0, 4)));
+    }
+
+    #[test]
+    fn test_clean_handles_shared_by_opens() {
+        // Both opens get the same constant handle
+        let mut clean_handles = CleanHandles::default();
+        clean_handles.opened(0);
+        assert_eq!(clean_handles.dirty_writes(0), None);
+        clean_handles.written(0);
+        clean_handles.opened(0);
+        // The data written through the first open must still be flushed on its close
+        assert_eq!(clean_handles.dirty_writes(0), Some(1));
+        // A write completed during the flush isn't covered by it
+        clean_handles.written(0);
+        clean_handles.flushed(0, 1);
+        assert_eq!(clean_handles.dirty_writes(0), Some(2));
+        clean_handles.flushed(0, 2);
+        assert_eq!(clean_handles.dirty_writes(0), None);
+
+        // The handle stays tracked until both opens are released
+        clean_handles.released(0);
+        assert_eq!(clean_handles.dirty_writes(0), None);
+        clean_handles.released(0);
+        assert_eq!(clean_handles.dirty_writes(0), Some(0));
+    }
+}
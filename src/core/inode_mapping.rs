@@ -1,11 +1,13 @@
 use std::{
     ffi::{OsStr, OsString},
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::atomic::Ordering,
 };
 
 use std::sync::{atomic::AtomicU64, RwLock};
 
+use log::warn;
+
 use crate::inode_mapper::*;
 use crate::types::*;
 
@@ -44,11 +46,24 @@ impl InodeResolvable for Vec<OsString> {
 
 /// FileIdResolver
 /// FileIdResolver handles its data behind Locks if needed and should not be nested inside a Mutex
+///
+/// `resolve_id` is on the path of every operation, so it only takes shared locks: concurrent resolutions
+/// don't wait for each other, only for mutations (`lookup` of a new entry, the last `forget`, `rename`).
+/// Publishing immutable snapshots instead would copy the whole mapping on each of these mutations,
+/// which are as frequent as resolutions on lookup-heavy workloads: copying 10 000 entries costs thousands
+/// of resolutions (`rebuild_10000` and `shared_resolve` benchmarks of `benches/inode_mapper.rs`).
 pub trait FileIdResolver: Send + Sync + 'static {
     type ResolvedType: FileIdType;
 
+    /// Creates a resolver which already resolves the root inode, to the root id of the file id type
+    ///
+    /// The kernel may request the root (eg: `getattr`) before any `lookup`.
     fn new() -> Self;
     fn resolve_id(&self, ino: u64) -> Self::ResolvedType;
+    /// Same as `resolve_id`, but returns `None` instead of panicking for an inode the resolver doesn't know
+    fn try_resolve_id(&self, ino: u64) -> Option<Self::ResolvedType> {
+        Some(self.resolve_id(ino))
+    }
     fn lookup(
         &self,
         parent: u64,
@@ -63,7 +78,31 @@ pub trait FileIdResolver: Send + Sync + 'static {
         increment: bool,
     ) -> Vec<(OsString, u64)>;
     fn forget(&self, ino: u64, nlookup: u64);
-    fn rename(&self, parent: u64, name: &OsStr, newparent: u64, newname: &OsStr);
+    /// Same as `forget` for several `(ino, nlookup)` pairs, resolvers with a lock take it only once
+    fn batch_forget(&self, nodes: &[(u64, u64)]) {
+        for &(ino, nlookup) in nodes {
+            self.forget(ino, nlookup);
+        }
+    }
+    /// Moves the entry `name` of `parent` to `newname` of `newparent`, after a successful rename
+    ///
+    /// With `RenameFlags::EXCHANGE`, both entries exist and swap their locations.
+    fn rename(
+        &self,
+        parent: u64,
+        name: &OsStr,
+        newparent: u64,
+        newname: &OsStr,
+        flags: RenameFlags,
+    );
+    /// Returns the inode currently associated to the id, without registering it
+    fn find_ino(&self, id: &Self::ResolvedType) -> Option<u64>;
+    /// Returns the parent inode and name under which the id is known, if the resolver tracks it
+    fn find_entry(&self, id: &Self::ResolvedType) -> Option<(u64, OsString)>;
+    /// Returns the inode, parent inode and name of every entry tracked by the resolver, except the root
+    fn known_entries(&self) -> Vec<(u64, u64, OsString)>;
+    /// Increments the lookup count of already known inodes, once the kernel is sent their entry
+    fn add_lookups(&self, _inos: &[u64]) {}
 }
 
 pub struct InodeResolver {}
@@ -98,7 +137,44 @@ impl FileIdResolver for InodeResolver {
 
     fn forget(&self, _ino: u64, _nlookup: u64) {}
 
-    fn rename(&self, _parent: u64, _name: &OsStr, _newparent: u64, _newname: &OsStr) {}
+    fn rename(
+        &self,
+        _parent: u64,
+        _name: &OsStr,
+        _newparent: u64,
+        _newname: &OsStr,
+        _flags: RenameFlags,
+    ) {
+    }
+
+    fn find_ino(&self, id: &Inode) -> Option<u64> {
+        Some(u64::from(id.clone()))
+    }
+
+    // Parents are not tracked, user provides its own inodes
+    fn find_entry(&self, _id: &Inode) -> Option<(u64, OsString)> {
+        None
+    }
+
+    fn known_entries(&self) -> Vec<(u64, u64, OsString)> {
+        Vec::new()
+    }
+}
+
+/// Subtracts `nlookup` from the lookup count of an inode, returns whether no lookup remains
+fn release_lookups(count: &AtomicU64, nlookup: u64) -> bool {
+    let previous = count
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+            Some(count.saturating_sub(nlookup))
+        })
+        .unwrap();
+    if previous < nlookup {
+        warn!(
+            "Forgetting {} lookups of an inode looked up {} times",
+            nlookup, previous
+        );
+    }
+    previous <= nlookup
 }
 
 pub struct ComponentsResolver {
@@ -115,14 +191,19 @@ impl FileIdResolver for ComponentsResolver {
     }
 
     fn resolve_id(&self, ino: u64) -> Self::ResolvedType {
-        self.mapper
-            .read()
-            .unwrap()
-            .resolve(&Inode::from(ino))
-            .expect("Failed to resolve inode")
-            .iter()
-            .map(|inode_info| (**inode_info.name).clone())
-            .collect()
+        self.try_resolve_id(ino).expect("Failed to resolve inode")
+    }
+
+    fn try_resolve_id(&self, ino: u64) -> Option<Self::ResolvedType> {
+        Some(
+            self.mapper
+                .read()
+                .unwrap()
+                .resolve(&Inode::from(ino))?
+                .iter()
+                .map(|inode_info| (**inode_info.name).clone())
+                .collect(),
+        )
     }
 
     fn lookup(&self, parent: u64, child: &OsStr, _id: (), increment: bool) -> u64 {
@@ -186,31 +267,123 @@ impl FileIdResolver for ComponentsResolver {
     }
 
     fn forget(&self, ino: u64, nlookup: u64) {
+        // The kernel never forgets the root, dropping it would make every request fail
+        if ino == ROOT_INO || ino == 0 {
+            warn!("Ignoring forget of inode {} with nlookup {}", ino, nlookup);
+            return;
+        }
         let inode = Inode::from(ino);
         {
             // Optimistically assume we don't have to remove yet
             let guard = self.mapper.read().expect("Failed to acquire read lock");
-            let inode_info = guard.get(&inode).expect("Failed to find inode");
-            if inode_info.data.fetch_sub(nlookup, Ordering::SeqCst) > 0 {
+            let Some(inode_info) = guard.get(&inode) else {
+                warn!(
+                    "Ignoring forget of unknown inode {} with nlookup {}",
+                    ino, nlookup
+                );
                 return;
+            };
+            if !release_lookups(inode_info.data, nlookup) {
+                return;
+            }
+        }
+        let mut mapper = self.mapper.write().expect("Failed to acquire write lock");
+        // A lookup may have referenced the inode again before the write lock was acquired
+        if mapper
+            .get(&inode)
+            .is_some_and(|inode_info| inode_info.data.load(Ordering::SeqCst) == 0)
+        {
+            mapper.remove(&inode);
+        }
+    }
+
+    fn batch_forget(&self, nodes: &[(u64, u64)]) {
+        let mut mapper = self.mapper.write().expect("Failed to acquire write lock");
+        for &(ino, nlookup) in nodes {
+            if ino == ROOT_INO || ino == 0 {
+                warn!("Ignoring forget of inode {} with nlookup {}", ino, nlookup);
+                continue;
+            }
+            let inode = Inode::from(ino);
+            let Some(inode_info) = mapper.get(&inode) else {
+                warn!(
+                    "Ignoring forget of unknown inode {} with nlookup {}",
+                    ino, nlookup
+                );
+                continue;
+            };
+            if release_lookups(inode_info.data, nlookup) {
+                mapper.remove(&inode);
             }
         }
-        self.mapper.write().unwrap().remove(&inode).unwrap();
     }
 
-    fn rename(&self, parent: u64, name: &OsStr, newparent: u64, newname: &OsStr) {
+    fn rename(
+        &self,
+        parent: u64,
+        name: &OsStr,
+        newparent: u64,
+        newname: &OsStr,
+        flags: RenameFlags,
+    ) {
         let parent_inode = Inode::from(parent);
         let newparent_inode = Inode::from(newparent);
-        self.mapper
-            .write()
-            .expect("Failed to acquire write lock")
-            .rename(
-                &parent_inode,
-                name,
-                &newparent_inode,
-                newname.to_os_string(),
-            )
-            .expect("Failed to rename inode");
+        let mut mapper = self.mapper.write().expect("Failed to acquire write lock");
+        if flags.contains(RenameFlags::EXCHANGE) {
+            mapper
+                .exchange(&parent_inode, name, &newparent_inode, newname)
+                .expect("Failed to exchange inodes");
+        } else {
+            mapper
+                .rename(
+                    &parent_inode,
+                    name,
+                    &newparent_inode,
+                    newname.to_os_string(),
+                )
+                .expect("Failed to rename inode");
+        }
+    }
+
+    fn find_ino(&self, id: &Vec<OsString>) -> Option<u64> {
+        let mapper = self.mapper.read().expect("Failed to acquire read lock");
+        let mut inode = mapper.get_root_inode();
+        // Components are stored from leaf to root
+        for component in id.iter().rev() {
+            inode = mapper.lookup(&inode, component)?.inode.clone();
+        }
+        Some(u64::from(inode))
+    }
+
+    fn find_entry(&self, id: &Vec<OsString>) -> Option<(u64, OsString)> {
+        let (name, parent) = id.split_first()?;
+        let parent_ino = self.find_ino(&parent.to_vec())?;
+        Some((parent_ino, name.clone()))
+    }
+
+    fn known_entries(&self) -> Vec<(u64, u64, OsString)> {
+        let mapper = self.mapper.read().expect("Failed to acquire read lock");
+        let root = mapper.get_root_inode();
+        mapper
+            .iter()
+            .filter(|(inode, _)| **inode != root)
+            .map(|(inode, info)| {
+                (
+                    u64::from(inode.clone()),
+                    u64::from(info.parent.clone()),
+                    (**info.name).clone(),
+                )
+            })
+            .collect()
+    }
+
+    fn add_lookups(&self, inos: &[u64]) {
+        let mapper = self.mapper.read().expect("Failed to acquire read lock");
+        for &ino in inos {
+            if let Some(inode_info) = mapper.get(&Inode::from(ino)) {
+                inode_info.data.fetch_add(1, Ordering::SeqCst);
+            }
+        }
     }
 }
 
@@ -218,6 +391,16 @@ pub struct PathResolver {
     resolver: ComponentsResolver,
 }
 
+impl PathResolver {
+    /// Returns the inode currently allocated to `path`, relative to the root of the filesystem.
+    ///
+    /// The mapping is walked under its read lock, which is only held for the duration of the call:
+    /// the kernel may forget the inode right after it is returned.
+    pub fn resolve_path(&self, path: &Path) -> Option<u64> {
+        self.resolver.find_ino(&path_to_components(path))
+    }
+}
+
 impl FileIdResolver for PathResolver {
     type ResolvedType = PathBuf;
 
@@ -235,6 +418,16 @@ impl FileIdResolver for PathResolver {
             .collect::<PathBuf>()
     }
 
+    fn try_resolve_id(&self, ino: u64) -> Option<Self::ResolvedType> {
+        Some(
+            self.resolver
+                .try_resolve_id(ino)?
+                .iter()
+                .rev()
+                .collect::<PathBuf>(),
+        )
+    }
+
     fn lookup(
         &self,
         parent: u64,
@@ -258,9 +451,45 @@ impl FileIdResolver for PathResolver {
         self.resolver.forget(ino, nlookup);
     }
 
-    fn rename(&self, parent: u64, name: &OsStr, newparent: u64, newname: &OsStr) {
-        self.resolver.rename(parent, name, newparent, newname);
+    fn batch_forget(&self, nodes: &[(u64, u64)]) {
+        self.resolver.batch_forget(nodes);
+    }
+
+    fn rename(
+        &self,
+        parent: u64,
+        name: &OsStr,
+        newparent: u64,
+        newname: &OsStr,
+        flags: RenameFlags,
+    ) {
+        self.resolver
+            .rename(parent, name, newparent, newname, flags);
+    }
+
+    fn find_ino(&self, id: &PathBuf) -> Option<u64> {
+        self.resolve_path(id)
+    }
+
+    fn find_entry(&self, id: &PathBuf) -> Option<(u64, OsString)> {
+        self.resolver.find_entry(&path_to_components(id))
+    }
+
+    fn known_entries(&self) -> Vec<(u64, u64, OsString)> {
+        self.resolver.known_entries()
     }
+
+    fn add_lookups(&self, inos: &[u64]) {
+        self.resolver.add_lookups(inos);
+    }
+}
+
+/// Converts a path to the component order used by `ComponentsResolver` (from leaf to root)
+fn path_to_components(path: &Path) -> Vec<OsString> {
+    path.iter()
+        .rev()
+        .map(|component| component.to_os_string())
+        .collect()
 }
 
 #[cfg(test)]
@@ -289,7 +518,8 @@ mod tests {
 
         assert_eq!(added_children.len(), 2);
 
-        // Test forget
+        // Test forget, the remaining lookup keeps the child known
+        resolver.lookup(parent_ino, OsStr::new("child"), (), true);
         resolver.forget(child_ino, 1);
 
         // Test rename
@@ -298,6 +528,7 @@ mod tests {
             OsStr::new("child"),
             parent_ino,
             OsStr::new("renamed_child"),
+            RenameFlags::empty(),
         );
 
         let renamed_path = resolver.resolve_id(child_ino);
@@ -339,7 +570,8 @@ mod tests {
             );
         }
 
-        // Test forget
+        // Test forget, the remaining lookup keeps the file known
+        resolver.lookup(dir2_ino, OsStr::new("file.txt"), (), true);
         resolver.forget(file_ino, 1);
 
         // Test rename within the same directory
@@ -348,6 +580,7 @@ mod tests {
             OsStr::new("file.txt"),
             dir2_ino,
             OsStr::new("renamed_file.txt"),
+            RenameFlags::empty(),
         );
 
         let renamed_file_path = resolver.resolve_id(file_ino);
@@ -363,6 +596,7 @@ mod tests {
             OsStr::new("renamed_file.txt"),
             dir3_ino,
             OsStr::new("moved_file.txt"),
+            RenameFlags::empty(),
         );
 
         let moved_file_path = resolver.resolve_id(file_ino);
@@ -374,4 +608,164 @@ mod tests {
         let non_existent_path = resolver.resolve_id(non_existent_ino);
         assert_eq!(non_existent_path, PathBuf::from("non_existent"));
     }
+
+    #[test]
+    fn test_path_resolver_exchange() {
+        let resolver = PathResolver::new();
+        let root_ino = ROOT_INODE.into();
+        let dir_ino = resolver.lookup(root_ino, OsStr::new("dir"), (), true);
+        let file1_ino = resolver.lookup(root_ino, OsStr::new("file1"), (), true);
+        let file2_ino = resolver.lookup(dir_ino, OsStr::new("file2"), (), true);
+
+        resolver.rename(
+            root_ino,
+            OsStr::new("file1"),
+            dir_ino,
+            OsStr::new("file2"),
+            RenameFlags::EXCHANGE,
+        );
+
+        // The inodes follow their file to its new name
+        assert_eq!(resolver.resolve_id(file1_ino), PathBuf::from("dir/file2"));
+        assert_eq!(resolver.resolve_id(file2_ino), PathBuf::from("file1"));
+        assert_eq!(resolver.find_ino(&PathBuf::from("file1")), Some(file2_ino));
+        assert_eq!(
+            resolver.find_ino(&PathBuf::from("dir/file2")),
+            Some(file1_ino)
+        );
+    }
+
+    #[test]
+    fn test_path_resolver_find() {
+        let resolver = PathResolver::new();
+        let root_ino = ROOT_INODE.into();
+        let dir_ino = resolver.lookup(root_ino, OsStr::new("dir"), (), true);
+        let file_ino = resolver.lookup(dir_ino, OsStr::new("file.txt"), (), true);
+
+        assert_eq!(resolver.find_ino(&PathBuf::from("")), Some(root_ino));
+        assert_eq!(
+            resolver.find_ino(&PathBuf::from("dir/file.txt")),
+            Some(file_ino)
+        );
+        assert_eq!(
+            resolver.find_entry(&PathBuf::from("dir/file.txt")),
+            Some((dir_ino, OsString::from("file.txt")))
+        );
+        assert_eq!(resolver.find_entry(&PathBuf::from("")), None);
+
+        assert_eq!(resolver.resolve_path(Path::new("dir")), Some(dir_ino));
+        let mut entries = resolver.known_entries();
+        entries.sort();
+        assert_eq!(
+            entries,
+            vec![
+                (dir_ino, root_ino, OsString::from("dir")),
+                (file_ino, dir_ino, OsString::from("file.txt"))
+            ]
+        );
+
+        // Unknown ids are not registered
+        assert_eq!(resolver.find_ino(&PathBuf::from("dir/unknown")), None);
+        assert_eq!(
+            resolver.find_entry(&PathBuf::from("unknown/file.txt")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_fresh_resolvers_resolve_root() {
+        let root_ino = ROOT_INODE.into();
+        assert_eq!(InodeResolver::new().resolve_id(root_ino), ROOT_INODE);
+        assert_eq!(PathResolver::new().resolve_id(root_ino), PathBuf::from(""));
+        assert!(ComponentsResolver::new().resolve_id(root_ino).is_empty());
+        assert_eq!(
+            PathResolver::new().find_ino(&PathBuf::new()),
+            Some(root_ino)
+        );
+    }
+
+    #[test]
+    fn test_forget_root_is_ignored() {
+        let resolver = PathResolver::new();
+        let root_ino = ROOT_INODE.into();
+        let file_ino = resolver.lookup(root_ino, OsStr::new("file.txt"), (), true);
+
+        resolver.forget(root_ino, 1);
+        resolver.forget(0, 1);
+
+        assert_eq!(resolver.resolve_id(root_ino), PathBuf::from(""));
+        assert_eq!(resolver.resolve_id(file_ino), PathBuf::from("file.txt"));
+        let dir_ino = resolver.lookup(root_ino, OsStr::new("dir"), (), true);
+        assert_eq!(resolver.resolve_id(dir_ino), PathBuf::from("dir"));
+    }
+
+    #[test]
+    fn test_concurrent_resolution() {
+        let resolver = std::sync::Arc::new(PathResolver::new());
+        let root_ino = ROOT_INODE.into();
+        let file_ino = resolver.lookup(root_ino, OsStr::new("file.txt"), (), true);
+
+        // Resolutions proceed while other threads register new entries
+        let threads: Vec<_> = (0..4)
+            .map(|thread| {
+                let resolver = resolver.clone();
+                std::thread::spawn(move || {
+                    for i in 0..1000 {
+                        assert_eq!(resolver.resolve_id(file_ino), PathBuf::from("file.txt"));
+                        let name = format!("{}_{}", thread, i);
+                        let ino = resolver.lookup(root_ino, OsStr::new(&name), (), true);
+                        assert_eq!(resolver.resolve_id(ino), PathBuf::from(&name));
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_batch_forget() {
+        let resolver = PathResolver::new();
+        let root_ino = ROOT_INODE.into();
+        let file_ino = resolver.lookup(root_ino, OsStr::new("file.txt"), (), true);
+        let dir_ino = resolver.lookup(root_ino, OsStr::new("dir"), (), true);
+        resolver.lookup(root_ino, OsStr::new("dir"), (), true);
+
+        resolver.batch_forget(&[(root_ino, 1), (file_ino, 1), (dir_ino, 1), (dir_ino + 1, 1)]);
+
+        assert_eq!(resolver.resolve_id(root_ino), PathBuf::from(""));
+        // The file has no lookup left, it is removed
+        assert_eq!(resolver.try_resolve_id(file_ino), None);
+        assert_eq!(resolver.find_ino(&PathBuf::from("file.txt")), None);
+        // The remaining lookup of the directory keeps it known
+        assert_eq!(resolver.resolve_id(dir_ino), PathBuf::from("dir"));
+        assert_eq!(resolver.find_ino(&PathBuf::from("dir")), Some(dir_ino));
+    }
+
+    #[test]
+    fn test_create_lookup_count() {
+        let resolver = ComponentsResolver::new();
+        let root_ino = ROOT_INODE.into();
+        let nlookup = |ino: u64| {
+            resolver
+                .mapper
+                .read()
+                .unwrap()
+                .get(&Inode::from(ino))
+                .map(|info| info.data.load(Ordering::SeqCst))
+        };
+
+        // As done by the driver on a successful create
+        let file_ino = resolver.lookup(root_ino, OsStr::new("file.txt"), (), true);
+        assert_eq!(nlookup(file_ino), Some(1));
+
+        // A plain readdir listing the file doesn't reference it
+        resolver.add_children(root_ino, vec![(OsString::from("file.txt"), ())], false);
+        assert_eq!(nlookup(file_ino), Some(1));
+
+        // The forget sent by the kernel balances the create
+        resolver.forget(file_ino, 1);
+        assert!(nlookup(file_ino).is_none());
+    }
 }
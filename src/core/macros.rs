@@ -13,12 +13,14 @@ macro_rules! handle_fuse_reply_entry {
         let handler = $handler;
         match handler.$function($($args),*) {
             Ok(metadata) => {
-                let default_ttl = handler.get_default_ttl();
                 let (id, file_attr) = TId::extract_metadata(metadata);
                 let ino = $resolver.lookup($parent, $name, id, true);
-                let (fuse_attr, ttl, generation) = file_attr.to_fuse(ino);
+                let (fuse_attr, ttl, generation) = file_attr
+                    .clear_unavailable(handler.available_attributes())
+                    .with_default_blksize(handler.preferred_blksize())
+                    .to_fuse(ino);
                 $reply.entry(
-                    &ttl.unwrap_or(default_ttl),
+                    &reply_ttl(&*handler, ttl),
                     &fuse_attr,
                     generation.unwrap_or(get_random_generation()),
                 );
@@ -35,7 +37,18 @@ macro_rules! handle_fuse_reply_entry {
                 }, {
                     warn!("{}: parent_ino {:x?}, [{}], {:?}", stringify!($function), $parent, e, $req);
                 });
-                $reply.error(e.raw_error())
+                let negative_ttl = if_lookup!($function, {
+                    e.negative_ttl().or_else(|| {
+                        (e.kind() == ErrorKind::FileNotFound)
+                            .then(|| handler.negative_lookup_ttl())
+                            .flatten()
+                    })
+                }, { None });
+                match negative_ttl {
+                    // Let the kernel cache the absence of the entry
+                    Some(negative_ttl) => $reply.entry(&negative_ttl, &negative_entry_attr(), 0),
+                    None => $reply.error(e.raw_error()),
+                }
             }
         }
     };
@@ -46,9 +59,11 @@ macro_rules! handle_fuse_reply_attr {
         $function:ident, ($($args:expr),*)) => {
         match $handler.$function($($args),*) {
             Ok(file_attr) => {
-                let default_ttl = $handler.get_default_ttl();
-                let (fuse_attr, ttl, _) = file_attr.to_fuse($ino);
-                $reply.attr(&ttl.unwrap_or(default_ttl), &fuse_attr);
+                let (fuse_attr, ttl, _) = file_attr
+                    .clear_unavailable($handler.available_attributes())
+                    .with_default_blksize($handler.preferred_blksize())
+                    .to_fuse($ino);
+                $reply.attr(&reply_ttl(&*$handler, ttl), &fuse_attr);
             }
             Err(e) => {
                 warn!("{}: ino {:x?}, [{}], {:?}", stringify!($function), $ino, e, $req);
@@ -93,7 +108,7 @@ macro_rules! handle_dir_read {
             };
         }
 
-        let req_info = RequestInfo::from($req);
+        let req_info = $self.request_info($req);
         let handler = $self.get_handler();
         let resolver = $self.get_resolver();
         let dirmap_iter = $self.$get_iter_method();
@@ -106,39 +121,51 @@ macro_rules! handle_dir_read {
                 return;
             }
 
-            // ### Initialize directory iterator
-            let mut dir_iter = match $offset {
-                // First read: fetch children from handler
-                0 => match handler.$handler_method(&req_info, resolver.resolve_id($ino), unsafe {
-                    BorrowedFileHandle::from_raw($fh)
-                }) {
-                    Ok(children) => {
-                        // Unpack and process children
-                        let (child_list, attr_list): (Vec<_>, Vec<_>) = children
-                            .into_iter()
-                            .map(|item| {
-                                let (child_id, child_attr) = if_readdir!(
-                                    $handler_method,
-                                    { TId::extract_minimal_metadata(item.1) },
-                                    { TId::extract_metadata(item.1) }
-                                );
-                                ((item.0, child_id), child_attr)
+            // ### Initialize directory stream
+            // The offset of an entry is the number of entries preceding it plus one,
+            // so a read at a given offset resumes right after the entry which returned it
+            let saved_stream = match $offset {
+                0 => None,
+                // Subsequent reads: retrieve saved stream
+                _ => dirmap_iter.safe_borrow_mut().remove(&($ino, $fh, $offset)),
+            };
+            let mut dir_stream = match saved_stream {
+                Some(dir_stream) => dir_stream,
+                // First read, or seek to an offset whose stream is gone (seekdir after a
+                // complete listing): fetch children from handler and skip the preceding entries
+                None => match if_readdir!(
+                    $handler_method,
+                    {
+                        handler.readdir_stream(&req_info, resolver.resolve_id($ino), unsafe {
+                            BorrowedFileHandle::from_raw($fh)
+                        })
+                    },
+                    {
+                        handler
+                            .readdirplus(&req_info, resolver.resolve_id($ino), unsafe {
+                                BorrowedFileHandle::from_raw($fh)
                             })
-                            .unzip();
-
-                        // Add children to resolver and create iterator
-                        resolver
-                            .add_children(
-                                $ino,
-                                child_list,
-                                if_readdir!($handler_method, false, true),
-                            )
-                            .into_iter()
-                            .zip(attr_list.into_iter())
-                            .map(|((file_name, file_ino), file_attr)| {
-                                (file_name, file_ino, file_attr)
+                            .map(|children| {
+                                Box::new(children.into_iter().map(Ok::<_, PosixError>))
+                                    as Box<dyn Iterator<Item = FuseResult<(OsString, TId::Metadata)>> + Send>
                             })
-                            .collect()
+                    }
+                ) {
+                    Ok(mut entries) => {
+                        if let Some(Err(e)) = entries
+                            .by_ref()
+                            .take($offset as usize)
+                            .find(|entry| entry.is_err())
+                        {
+                            warn!("readdir {:?}: {:?}", req_info, e);
+                            $reply.error(e.raw_error());
+                            return;
+                        }
+                        DirStream {
+                            pending: VecDeque::new(),
+                            entries,
+                            saved_at: Instant::now(),
+                        }
                     }
                     Err(e) => {
                         warn!("readdir {:?}: {:?}", req_info, e);
@@ -146,60 +173,126 @@ macro_rules! handle_dir_read {
                         return;
                     }
                 },
-                // Subsequent reads: retrieve saved iterator
-                _ => match { dirmap_iter.safe_borrow_mut().remove(&($ino, $offset)) } {
-                    Some(dirmap_iter) => dirmap_iter,
-                    None => {
-                        // Case when fuse tries to read again after the final item
-                        $reply.ok();
-                        return;
-                    }
-                },
             };
 
             let mut new_offset = $offset;
+            // Entries sent by readdirplus, which the kernel counts as looked up
+            let mut sent_inos = Vec::new();
 
-            // ### Process directory entries
-            if_readdir!(
-                $handler_method,
-                {
-                    // readdir: Add entries until buffer is full
-                    while let Some((name, ino, kind)) = dir_iter.pop_front() {
-                        if $reply.add(ino, new_offset, kind, &name) {
-                            dir_iter.push_front((name, ino, kind));
-                            dirmap_iter
-                                .safe_borrow_mut()
-                                .insert(($ino, new_offset - 1), dir_iter);
-                            break;
+            // ### Process directory entries until the buffer is full
+            loop {
+                if dir_stream.pending.is_empty() {
+                    // Register the next entries with a single resolver lock. The lookup count is only
+                    // incremented for the entries actually sent, the others may never be.
+                    let mut children = Vec::new();
+                    let mut attrs = Vec::new();
+                    let mut error = None;
+                    for entry in dir_stream.entries.by_ref().take(DIR_BATCH_SIZE) {
+                        match entry {
+                            Ok((name, metadata)) => {
+                                let (child_id, child_attr) = if_readdir!(
+                                    $handler_method,
+                                    { TId::extract_minimal_metadata(metadata) },
+                                    { TId::extract_metadata(metadata) }
+                                );
+                                children.push((name, child_id));
+                                attrs.push(child_attr);
+                            }
+                            Err(e) => {
+                                error = Some(e);
+                                break;
+                            }
                         }
-                        new_offset += 1;
                     }
-                    $reply.ok();
-                },
-                {
-                    // readdirplus: Add entries with extended attributes
-                    let default_ttl = handler.get_default_ttl();
-                    while let Some((name, ino, file_attr)) = dir_iter.pop_front() {
-                        let (fuse_attr, ttl, generation) = file_attr.clone().to_fuse(ino);
-                        if $reply.add(
-                            ino,
-                            new_offset,
+                    dir_stream.pending = resolver
+                        .add_children($ino, children, false)
+                        .into_iter()
+                        .zip(attrs)
+                        .map(|((name, child_ino), child_attr)| (name, child_ino, child_attr))
+                        .collect();
+                    if let Some(e) = error {
+                        // Returned once the entries preceding it are sent
+                        dir_stream.entries = Box::new(std::iter::once(Err(e)));
+                    }
+                }
+                let (name, child_ino, child_attr) = match dir_stream.pending.pop_front() {
+                    Some(entry) => entry,
+                    None => match dir_stream.entries.next() {
+                        // Only for iterators yielding entries after their end, register it on the next turn
+                        Some(Ok(entry)) => {
+                            let entries = std::mem::replace(
+                                &mut dir_stream.entries,
+                                Box::new(std::iter::empty()),
+                            );
+                            dir_stream.entries = Box::new(std::iter::once(Ok(entry)).chain(entries));
+                            continue;
+                        }
+                        Some(Err(e)) => {
+                            if new_offset == $offset {
+                                warn!("readdir {:?}: {:?}", req_info, e);
+                                $reply.error(e.raw_error());
+                                return;
+                            }
+                            // Send the entries already gathered, the error is returned by the next call
+                            dir_stream.entries = Box::new(std::iter::once(Err(e)));
+                            save_dir_stream(
+                                &mut dirmap_iter.safe_borrow_mut(),
+                                ($ino, $fh, new_offset),
+                                dir_stream,
+                                dir_streams_capacity(&*handler),
+                            );
+                            break;
+                        }
+                        None => {
+                            // Keep the exhausted stream, so that the final read
+                            // doesn't fetch the children again
+                            save_dir_stream(
+                                &mut dirmap_iter.safe_borrow_mut(),
+                                ($ino, $fh, new_offset),
+                                dir_stream,
+                                dir_streams_capacity(&*handler),
+                            );
+                            break;
+                        }
+                    },
+                };
+                let buffer_full = if_readdir!(
+                    $handler_method,
+                    { $reply.add(child_ino, new_offset + 1, child_attr.into(), &name) },
+                    {
+                        let (fuse_attr, ttl, generation) = child_attr
+                            .clone()
+                            .clear_unavailable(handler.available_attributes())
+                            .with_default_blksize(handler.preferred_blksize())
+                            .to_fuse(child_ino);
+                        $reply.add(
+                            child_ino,
+                            new_offset + 1,
                             &name,
-                            &ttl.unwrap_or(default_ttl),
+                            &reply_ttl(&*handler, ttl),
                             &fuse_attr,
                             generation.unwrap_or(get_random_generation()),
-                        ) {
-                            dir_iter.push_front((name, ino, file_attr.clone()));
-                            dirmap_iter
-                                .safe_borrow_mut()
-                                .insert((ino, new_offset - 1), dir_iter);
-                            break;
-                        }
-                        new_offset += 1;
+                        )
                     }
-                    $reply.ok();
+                );
+                if buffer_full {
+                    dir_stream.pending.push_front((name, child_ino, child_attr));
+                    save_dir_stream(
+                        &mut dirmap_iter.safe_borrow_mut(),
+                        ($ino, $fh, new_offset),
+                        dir_stream,
+                        dir_streams_capacity(&*handler),
+                    );
+                    break;
+                }
+                // The kernel doesn't take a reference on the dot entries
+                if name != "." && name != ".." {
+                    sent_inos.push(child_ino);
                 }
-            );
+                new_offset += 1;
+            }
+            if_readdir!($handler_method, {}, { resolver.add_lookups(&sent_inos) });
+            $reply.ok();
         });
     }};
 }
@@ -89,8 +89,11 @@
 /// Documentation is inspired by the original fuser documentation
 use std::ffi::{OsStr, OsString};
 use std::path::Path;
+use std::sync::Arc;
 use std::time::Duration;
 
+use log::warn;
+
 use crate::types::*;
 
 mod private {
@@ -116,16 +119,172 @@ pub trait FuseHandler<TId: FileIdType>: OptionalSendSync + 'static {
         Duration::from_secs(1)
     }
 
+    /// Skip `flush` for file handles that were not written to since their last successful flush
+    ///
+    /// `flush` is called on each close(), including the ones of duplicated file descriptors (dup, fork...),
+    /// and the kernel doesn't tell how many descriptors still reference the open file. The last close can
+    /// only be known when `release` is called. When this returns true, the driver tracks writes for each
+    /// file handle and only forwards `flush` if data was written since the previous one, so a write-back
+    /// handler flushes once even if the file is closed through several descriptors. Opens sharing a file handle
+    /// (eg: a handler returning a constant handle) are tracked together: a write through any of them makes the
+    /// next flush of the handle reach the handler.
+    ///
+    /// Note: handlers relying on `flush` to release the locks of `lock_owner` should keep it disabled.
+    fn skip_clean_flushes(&self) -> bool {
+        self.get_inner().skip_clean_flushes()
+    }
+
+    /// Attributes the filesystem is able to provide
+    ///
+    /// Attributes missing from the mask are not computed by the handler and reported to the kernel
+    /// as "not provided" (see `FileAttribute::clear_unavailable`) rather than with fabricated values.
+    fn available_attributes(&self) -> AttrMask {
+        self.get_inner().available_attributes()
+    }
+
+    /// Maximum size of a file, if any
+    ///
+    /// When set, the driver rejects writes, truncates and allocations that would grow a file past it with
+    /// `ErrorKind::FileTooLarge` (`EFBIG`) before calling the handler, as with `RLIMIT_FSIZE`.
+    fn max_file_size(&self) -> Option<u64> {
+        self.get_inner().max_file_size()
+    }
+
+    /// Duration during which the kernel may cache a `FileNotFound` error returned by `lookup`, if any
+    ///
+    /// Applies to every failed lookup, unless the error carries its own duration (see `PosixError::not_found_cached`).
+    /// This cuts the repeated probes of shells and compilers for files like `./config`, but entries created
+    /// behind the back of the kernel stay invisible until the duration expires.
+    fn negative_lookup_ttl(&self) -> Option<Duration> {
+        self.get_inner().negative_lookup_ttl()
+    }
+
+    /// Maximum length of a file name in bytes, if any
+    ///
+    /// When set, the driver rejects longer names with `ErrorKind::FileNameTooLong` (`ENAMETOOLONG`)
+    /// before calling the handler, and reports it as `max_filename_length` in `statfs` replies, which
+    /// applications read through `pathconf(_PC_NAME_MAX)`.
+    fn max_name_length(&self) -> Option<u32> {
+        self.get_inner().max_name_length()
+    }
+
+    /// Block size reported for files whose attributes leave `blksize` to zero
+    ///
+    /// The block size is the IO size advertised to applications through `st_blksize`, it doesn't limit
+    /// the size of requests. Raising it (eg: to 1 MiB) lets tools use larger buffers, which pays off on
+    /// network backends. Zero leaves the choice to the kernel, which uses the page size.
+    fn preferred_blksize(&self) -> u32 {
+        self.get_inner().preferred_blksize()
+    }
+
+    /// Maximum size of the data of a `write` request, in bytes
+    ///
+    /// The driver proposes it to the kernel before calling `init`, which can still override it with
+    /// `KernelConfig::set_max_write`. The kernel splits larger writes into several requests, so raising it
+    /// reduces the number of `write` calls for large sequential writes. The kernel caps it to its own limit
+    /// (128 KiB on most kernels for this protocol version).
+    fn max_write(&self) -> u32 {
+        self.get_inner().max_write()
+    }
+
+    /// Maximum number of directory reads in progress kept between two `readdir` calls
+    ///
+    /// A listing which doesn't fit in a single reply is kept to be continued by the next call, until the
+    /// directory is released. Beyond this number, the oldest one is dropped: its next read lists
+    /// the directory again and skips the entries already returned. Zero disables the continuations.
+    fn max_dir_streams(&self) -> usize {
+        self.get_inner().max_dir_streams()
+    }
+
+    /// Whether a same entry may be reported with a different inode by each `lookup` or `readdir`
+    ///
+    /// The kernel assumes inodes to be stable: cached entries and attributes of such filesystems go out of
+    /// sync with the ones of new lookups. When enabled, the driver replies with zero TTLs whatever the
+    /// attributes request, so the kernel asks again instead of caching, and doesn't keep directory reads
+    /// in progress between two `readdir` calls, as if `max_dir_streams` returned zero. Navigation is
+    /// slower, but stays coherent.
+    fn volatile_inodes(&self) -> bool {
+        self.get_inner().volatile_inodes()
+    }
+
+    /// Soft deadline of every request, counted from its reception by the driver, if any
+    ///
+    /// It is carried by `RequestInfo::deadline` for handlers to check, for example with
+    /// `RequestInfo::check_deadline`, and fail early with `ErrorKind::TimedOut` when they know they can't
+    /// reply in time. The driver doesn't interrupt handlers past the deadline.
+    fn request_timeout(&self) -> Option<Duration> {
+        self.get_inner().request_timeout()
+    }
+
+    /// Whether `file_id` still designates an existing file of the filesystem
+    ///
+    /// In debug builds, the driver checks on unmount that every id tracked by the resolver still exists,
+    /// and logs the ones that don't. Handlers which can't tell should return `true`.
+    fn id_exists(&self, file_id: TId) -> bool {
+        self.get_inner().id_exists(file_id)
+    }
+
     /// Initialize the filesystem and configure kernel connection
+    ///
+    /// This is the place to spawn background maintenance tasks tied to the mount (cache expiry, write-back
+    /// flushing...). They should be stopped in `destroy`, see `templates::BackgroundTask`.
+    ///
+    /// `config` holds the parameters negotiated with the kernel, which can be tuned before they are sent
+    /// back: capabilities from `FUSEInitFlags` with `add_capabilities`, `set_max_write`, `set_max_readahead`,
+    /// `set_max_background`... Values the kernel doesn't accept are rejected, the previous value is kept.
+    /// Returning an error aborts the mount.
+    ///
+    /// ```rust, no_run
+    /// use easy_fuser::prelude::*;
+    /// use std::path::PathBuf;
+    ///
+    /// struct TunedFs {
+    ///     inner: Box<dyn FuseHandler<PathBuf>>,
+    /// }
+    ///
+    /// impl FuseHandler<PathBuf> for TunedFs {
+    ///     fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+    ///         self.inner.as_ref()
+    ///     }
+    ///
+    ///     fn init(&self, req: &RequestInfo, config: &mut KernelConfig) -> FuseResult<()> {
+    ///         // Capabilities not offered by the kernel are returned as an error and left out
+    ///         let _ = config.add_capabilities(FUSEInitFlags::WRITEBACK_CACHE.bits().into());
+    ///         let _ = config.set_max_write(1 << 20);
+    ///         let _ = config.set_max_readahead(1 << 20);
+    ///         self.inner.init(req, config)
+    ///     }
+    /// }
+    /// ```
     fn init(&self, req: &RequestInfo, config: &mut KernelConfig) -> FuseResult<()> {
         self.get_inner().init(req, config)
     }
 
     /// Perform cleanup operations on filesystem exit
+    ///
+    /// Called when the filesystem is unmounted, background tasks started in `init` should be stopped here.
     fn destroy(&self) {
         self.get_inner().destroy();
     }
 
+    /// Last chance to persist data before the filesystem goes away
+    ///
+    /// Called when the filesystem is unmounted, right before `destroy`. Contrary to `destroy`, it can report
+    /// a failure, for example when pending data couldn't be flushed. FUSE doesn't allow to refuse an unmount,
+    /// so the error is only logged prominently, and `destroy` is called anyway.
+    fn pre_unmount(&self) -> FuseResult<()> {
+        self.get_inner().pre_unmount()
+    }
+
+    /// Drop the caches kept by the handler
+    ///
+    /// Called when `notifier::DROP_CACHES_IOCTL` is issued on the mount. Pending data should be written out
+    /// and cached state discarded, so the next requests reach the backend. The kernel caches are invalidated
+    /// by the driver once this returns successfully.
+    fn drop_caches(&self, req: &RequestInfo) -> FuseResult<()> {
+        self.get_inner().drop_caches(req)
+    }
+
     /// Check file access permissions
     ///
     /// This method is called for the access() system call. If the 'default_permissions'
@@ -135,6 +294,15 @@ pub trait FuseHandler<TId: FileIdType>: OptionalSendSync + 'static {
         self.get_inner().access(req, file_id, mask)
     }
 
+    /// Release references to several inodes at once, as sent by the kernel when evicting many of them
+    ///
+    /// Default implementation calls `forget` for each inode.
+    fn batch_forget(&self, req: &RequestInfo, nodes: Vec<(TId, u64)>) {
+        for (file_id, nlookup) in nodes {
+            self.forget(req, file_id, nlookup);
+        }
+    }
+
     /// Map block index within file to block index within device
     ///
     /// Note: This makes sense only for block device backed filesystems mounted
@@ -175,6 +343,10 @@ pub trait FuseHandler<TId: FileIdType>: OptionalSendSync + 'static {
     /// open it. Open flags (with the exception of O_NOCTTY) are available in flags.
     /// If this method is not implemented or under Linux kernel versions earlier than
     /// 2.6.15, the mknod() and open() methods will be called instead.
+    ///
+    /// Like `lookup`, a successful `create` increments the lookup count of the returned entry by one:
+    /// the kernel references the entry it receives and balances it with a later `forget`, even if the file
+    /// is unlinked and closed right away. This can't be opted out of without leaking or dropping inodes.
     fn create(
         &self,
         req: &RequestInfo,
@@ -206,6 +378,8 @@ pub trait FuseHandler<TId: FileIdType>: OptionalSendSync + 'static {
     ///
     /// Called on each close() of the opened file. Not guaranteed to be called after writes or at all.
     /// Used for returning write errors or removing file locks.
+    ///
+    /// See `skip_clean_flushes` to avoid redundant flushes when a descriptor has been duplicated.
     fn flush(
         &self,
         req: &RequestInfo,
@@ -252,7 +426,11 @@ pub trait FuseHandler<TId: FileIdType>: OptionalSendSync + 'static {
             .fsyncdir(req, file_id, file_handle, datasync)
     }
 
-    /// Modify file attributes
+    /// Get file attributes
+    ///
+    /// `file_handle` is provided when the attributes are requested through an open file, like with `fstat`.
+    /// The attributes must then include the changes made through that handle which are not committed yet,
+    /// like buffered writes growing the file, as the kernel trusts them over its own view of the file.
     fn getattr(
         &self,
         req: &RequestInfo,
@@ -262,6 +440,7 @@ pub trait FuseHandler<TId: FileIdType>: OptionalSendSync + 'static {
         self.get_inner().getattr(req, file_id, file_handle)
     }
 
+    #[cfg(feature = "locking")]
     /// Test for a POSIX file lock.
     fn getlk(
         &self,
@@ -275,6 +454,7 @@ pub trait FuseHandler<TId: FileIdType>: OptionalSendSync + 'static {
             .getlk(req, file_id, file_handle, lock_owner, lock_info)
     }
 
+    #[cfg(feature = "xattr")]
     /// Get an extended attribute
     fn getxattr(
         &self,
@@ -286,6 +466,7 @@ pub trait FuseHandler<TId: FileIdType>: OptionalSendSync + 'static {
         self.get_inner().getxattr(req, file_id, name, size)
     }
 
+    #[cfg(feature = "ioctl")]
     /// control device
     fn ioctl(
         &self,
@@ -312,11 +493,24 @@ pub trait FuseHandler<TId: FileIdType>: OptionalSendSync + 'static {
         self.get_inner().link(req, file_id, newparent, newname)
     }
 
-    /// List extended attribute names
+    #[cfg(feature = "xattr")]
+    /// List extended attribute names, in the wire format
+    ///
+    /// The names must each be followed by a NUL byte, including the last one. Prefer `listxattr_names`,
+    /// which is called first and leaves the serialization to the driver.
     fn listxattr(&self, req: &RequestInfo, file_id: TId, size: u32) -> FuseResult<Vec<u8>> {
         self.get_inner().listxattr(req, file_id, size)
     }
 
+    #[cfg(feature = "xattr")]
+    /// List extended attribute names
+    ///
+    /// The driver serializes the names and handles the size requests of the kernel. Returning
+    /// `FunctionNotImplemented` makes the driver call `listxattr` instead.
+    fn listxattr_names(&self, req: &RequestInfo, file_id: TId) -> FuseResult<Vec<OsString>> {
+        self.get_inner().listxattr_names(req, file_id)
+    }
+
     /// Retrieve file attributes for a directory entry by name and increment the lookup count associated with the inode.
     fn lookup(&self, req: &RequestInfo, parent_id: TId, name: &OsStr) -> FuseResult<TId::Metadata> {
         self.get_inner().lookup(req, parent_id, name)
@@ -333,6 +527,23 @@ pub trait FuseHandler<TId: FileIdType>: OptionalSendSync + 'static {
         self.get_inner().lseek(req, file_id, file_handle, seek)
     }
 
+    /// Reposition the file offset for a `whence` that `SeekFrom` can't represent
+    ///
+    /// The driver calls it for `SEEK_DATA` and `SEEK_HOLE` (Linux), used by sparse-aware tools like
+    /// `cp --sparse` or `tar`. Returning `FunctionNotImplemented` lets the kernel treat the whole
+    /// file as data, and seeking past the last data region should fail with `NoSuchDeviceOrAddress`.
+    fn lseek_raw(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        file_handle: BorrowedFileHandle,
+        offset: i64,
+        whence: i32,
+    ) -> FuseResult<i64> {
+        self.get_inner()
+            .lseek_raw(req, file_id, file_handle, offset, whence)
+    }
+
     /// Create a new directory
     fn mkdir(
         &self,
@@ -362,6 +573,14 @@ pub trait FuseHandler<TId: FileIdType>: OptionalSendSync + 'static {
     /// Open a file and return a file handle.
     ///
     /// Open flags (with the exception of O_CREAT, O_EXCL, O_NOCTTY and O_TRUNC) are available in flags. You may store an arbitrary file handle (pointer, index, etc) in file_handle response, and use this in other all other file operations (read, write, flush, release, fsync). Filesystem may also implement stateless file I/O and not store anything in fh. There are also some flags (direct_io, keep_cache) which the filesystem may set, to change the way the file is opened. See fuse_file_info structure in <fuse_common.h> for more details.
+    ///
+    /// An `O_PATH` open (see `OpenFlags::is_path`) only grants metadata operations: it should succeed, and subsequent reads or writes return `EBADF`.
+    ///
+    /// The kernel chooses between `open` and `opendir` from the kind of the inode, so a directory opened with `open(2)` is received by `opendir`. This method is never called on directories.
+    ///
+    /// When `FUSEInitFlags::WRITEBACK_CACHE` is enabled in `init`, the kernel reads through the handles of files opened write-only,
+    /// to complete the pages partially written before writing them back. `read` must then succeed whatever the open mode,
+    /// see `OpenFlags::for_writeback_cache` for handles backed by file descriptors.
     fn open(
         &self,
         req: &RequestInfo,
@@ -374,6 +593,7 @@ pub trait FuseHandler<TId: FileIdType>: OptionalSendSync + 'static {
     /// Open a directory
     ///
     /// Allows storing a file handle for use in subsequent directory operations.
+    /// Called for every open of a directory, whether by `opendir(3)` or by `open(2)`.
     fn opendir(
         &self,
         req: &RequestInfo,
@@ -383,11 +603,41 @@ pub trait FuseHandler<TId: FileIdType>: OptionalSendSync + 'static {
         self.get_inner().opendir(req, file_id, flags)
     }
 
+    /// Poll for IO readiness events
+    ///
+    /// Returns the events of `events` (`POLLIN`, `POLLOUT`...) which are ready on the file.
+    /// `events` is only filled by kernels speaking FUSE 7.21 or later, older ones send 0.
+    /// If none is ready and `flags` contains `FUSE_POLL_SCHEDULE_NOTIFY`, the `PollHandle` can be kept
+    /// and its `notify` method called once an event occurs, so that the kernel polls again.
+    ///
+    /// Default implementation (DefaultFuseHandler) returns a `FunctionNotImplemented` error, after which
+    /// the kernel stops polling the filesystem and reports its files as always ready.
+    fn poll(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        file_handle: BorrowedFileHandle,
+        poll_handle: PollHandle,
+        events: u32,
+        flags: u32,
+    ) -> FuseResult<u32> {
+        self.get_inner()
+            .poll(req, file_id, file_handle, poll_handle, events, flags)
+    }
+
     /// Read data from a file
     ///
     /// Read should send exactly the number of bytes requested except on EOF or error, otherwise the rest of the data will be substituted with zeroes. An exception to this is when the file has been opened in ‘direct_io’ mode, in which case the return value of the read system call will reflect the return value of this operation. fh will contain the value set by the open method, or will be undefined if the open method didn’t set any value.
     ///
     /// flags: these are the file flags, such as O_SYNC. Only supported with ABI >= 7.9 lock_owner: only supported with ABI >= 7.9
+    ///
+    /// There is no way to signal a partial read: the kernel takes any read shorter than `size` for the end of the file.
+    /// Data available in chunks (a stream, a network backend) must be gathered until `size` bytes or the end of the file
+    /// are reached.
+    ///
+    /// If the file was opened with `OpenFlags::NON_BLOCKING_MODE` (a FIFO, a network stream) and no data is available yet,
+    /// return `ErrorKind::ResourceUnavailableTryAgain` (EAGAIN) instead of blocking the worker thread.
+    /// The file should then be opened in direct_io mode, so the error reaches the caller instead of failing a page cache fill.
     fn read(
         &self,
         req: &RequestInfo,
@@ -395,17 +645,54 @@ pub trait FuseHandler<TId: FileIdType>: OptionalSendSync + 'static {
         file_handle: BorrowedFileHandle,
         seek: SeekFrom,
         size: u32,
-        flags: FUSEOpenFlags,
+        flags: OpenFlags,
         lock_owner: Option<u64>,
     ) -> FuseResult<Vec<u8>> {
         self.get_inner()
             .read(req, file_id, file_handle, seek, size, flags, lock_owner)
     }
 
+    /// Read data into a buffer provided by the driver
+    ///
+    /// Alternative to `read` avoiding an allocation per request: the driver passes a buffer of the requested size,
+    /// reused across the requests of its thread, and sends the number of bytes returned. The same rules as `read`
+    /// apply for short reads.
+    ///
+    /// The default implementation calls `read` and copies its data, so handlers overriding only `read` keep working.
+    /// Override both when delegating to an inner handler implementing `read_into`, like `FdHandlerHelper`.
+    #[allow(clippy::too_many_arguments)]
+    fn read_into(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        buf: &mut [u8],
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+    ) -> FuseResult<usize> {
+        let data = self.read(
+            req,
+            file_id,
+            file_handle,
+            seek,
+            buf.len() as u32,
+            flags,
+            lock_owner,
+        )?;
+        let len = data.len().min(buf.len());
+        buf[..len].copy_from_slice(&data[..len]);
+        Ok(len)
+    }
+
     /// Read directory contents
     ///
     /// Returns a list of directory entries with minimal metadata.
     ///
+    /// To support `seekdir` to an offset returned earlier by `telldir`, the directory may be listed again
+    /// and the entries before that offset skipped. Listings of an unchanged directory should therefore
+    /// keep the same order.
+    ///
     /// Important: The returned file names (OsString) must not contain any slashes ('/').
     /// Including slashes in the file names will result in undefined behavior.
     fn readdir(
@@ -417,10 +704,64 @@ pub trait FuseHandler<TId: FileIdType>: OptionalSendSync + 'static {
         self.get_inner().readdir(req, file_id, file_handle)
     }
 
+    /// Read directory contents, keeping the entries gathered before a failure
+    ///
+    /// Returns the entries listed so far along with the error which interrupted the listing, if any.
+    /// This suits slow or remote listings, where a transient error shouldn't discard the entries
+    /// already received. The gathered entries are sent and the error is only returned if there are none,
+    /// otherwise it is logged and the listing ends.
+    ///
+    /// Default implementation returns the entries of `readdir`.
+    ///
+    /// Important: The returned file names (OsString) must not contain any slashes ('/').
+    /// Including slashes in the file names will result in undefined behavior.
+    fn readdir_partial(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        file_handle: BorrowedFileHandle,
+    ) -> (Vec<(OsString, TId::MinimalMetadata)>, Option<PosixError>) {
+        match self.readdir(req, file_id, file_handle) {
+            Ok(entries) => (entries, None),
+            Err(e) => (Vec::new(), Some(e)),
+        }
+    }
+
+    /// Read directory contents lazily
+    ///
+    /// Entries are pulled from the returned iterator only as the kernel asks for them, so a large or remote
+    /// directory doesn't have to be listed entirely before its first entries are sent.
+    /// If the iterator yields an error, the entries gathered so far are sent and the error ends the listing.
+    ///
+    /// Default implementation returns the entries of `readdir_partial`.
+    ///
+    /// Important: The returned file names (OsString) must not contain any slashes ('/').
+    /// Including slashes in the file names will result in undefined behavior.
+    fn readdir_stream(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        file_handle: BorrowedFileHandle,
+    ) -> FuseResult<DirEntryStream<TId>> {
+        let (entries, error) = self.readdir_partial(req, file_id, file_handle);
+        match error {
+            Some(e) if entries.is_empty() => return Err(e),
+            Some(e) => warn!("readdir {:?}: listing is partial: {:?}", req, e),
+            None => {}
+        }
+        Ok(Box::new(entries.into_iter().map(Ok)))
+    }
+
     /// Read directory contents with full file attributes
     ///
     /// Default implementation combines readdir and lookup operations.
     ///
+    /// Only called when `DO_READDIRPLUS` (or `READDIRPLUS_AUTO`) was requested in `init` and granted by the kernel.
+    ///
+    /// Each entry is cached by the kernel for the `ttl` of its own `FileAttribute`, falling back to
+    /// `get_default_ttl` when unset. A listing can therefore mix volatile entries, with a short or zero `ttl`,
+    /// and stable ones cached for longer.
+    ///
     /// Important: The returned file names (OsString) must not contain any slashes ('/').
     /// Including slashes in the file names will result in undefined behavior.
     fn readdirplus(
@@ -476,6 +817,7 @@ pub trait FuseHandler<TId: FileIdType>: OptionalSendSync + 'static {
             .releasedir(req, file_id, file_handle, flags)
     }
 
+    #[cfg(feature = "xattr")]
     /// Remove an extended attribute.
     fn removexattr(&self, req: &RequestInfo, file_id: TId, name: &OsStr) -> FuseResult<()> {
         self.get_inner().removexattr(req, file_id, name)
@@ -510,6 +852,7 @@ pub trait FuseHandler<TId: FileIdType>: OptionalSendSync + 'static {
         self.get_inner().setattr(req, file_id, attrs)
     }
 
+    #[cfg(feature = "locking")]
     /// Acquire, modify or release a POSIX file lock
     ///
     /// For POSIX threads (NPTL) there's a 1-1 relation between pid and owner, but
@@ -529,6 +872,7 @@ pub trait FuseHandler<TId: FileIdType>: OptionalSendSync + 'static {
             .setlk(req, file_id, file_handle, lock_owner, lock_info, sleep)
     }
 
+    #[cfg(feature = "xattr")]
     /// Set an extended attribute
     fn setxattr(
         &self,
@@ -544,6 +888,10 @@ pub trait FuseHandler<TId: FileIdType>: OptionalSendSync + 'static {
     }
 
     /// Get file system statistics
+    ///
+    /// `file_id` is the file on which `statfs(2)` was called, not necessarily the root. A filesystem
+    /// composed of several backends (submounts, union of directories) can report the statistics of
+    /// the backend holding that file, so that `df` on a subdirectory shows the space available there.
     fn statfs(&self, req: &RequestInfo, file_id: TId) -> FuseResult<StatFs> {
         self.get_inner().statfs(req, file_id)
     }
@@ -564,6 +912,10 @@ pub trait FuseHandler<TId: FileIdType>: OptionalSendSync + 'static {
     /// Write should return exactly the number of bytes requested except on error. An exception to this is when the file has been opened in ‘direct_io’ mode, in which case the return value of the write system call will reflect the return value of this operation. fh will contain the value set by the open method, or will be undefined if the open method didn’t set any value.
     ///
     /// write_flags: will contain FUSE_WRITE_CACHE, if this write is from the page cache. If set, the pid, uid, gid, and fh may not match the value that would have been sent if write cachin is disabled flags: these are the file flags, such as O_SYNC. Only supported with ABI >= 7.9 lock_owner: only supported with ABI >= 7.9
+    ///
+    /// As for read, a write on a non-blocking file that cannot make progress should return `ErrorKind::ResourceUnavailableTryAgain`.
+    ///
+    /// A write interrupted after part of the data was persisted should return `Ok` with the number of bytes actually written, rather than an error leaving the application unaware of the partial write. In `direct_io` mode, the application sees a short write and retries with the rest of the data, like `write_all` does. `unix_fs::write` reports this progress when interrupted by `EINTR`.
     fn write(
         &self,
         req: &RequestInfo,
@@ -592,3 +944,501 @@ pub trait FuseHandler<TId: FileIdType>: OptionalSendSync + 'static {
         self.get_inner().unlink(req, parent_id, name)
     }
 }
+
+/// Shares a handler with the driver, so the caller keeps a reference to it once mounted
+///
+/// Every operation is forwarded to the shared handler, including the ones it overrides without delegating.
+impl<TId: FileIdType, H: FuseHandler<TId>> FuseHandler<TId> for Arc<H> {
+    fn get_inner(&self) -> &dyn FuseHandler<TId> {
+        self.as_ref()
+    }
+
+    fn get_default_ttl(&self) -> Duration {
+        (**self).get_default_ttl()
+    }
+
+    fn skip_clean_flushes(&self) -> bool {
+        (**self).skip_clean_flushes()
+    }
+
+    fn available_attributes(&self) -> AttrMask {
+        (**self).available_attributes()
+    }
+
+    fn max_file_size(&self) -> Option<u64> {
+        (**self).max_file_size()
+    }
+
+    fn negative_lookup_ttl(&self) -> Option<Duration> {
+        (**self).negative_lookup_ttl()
+    }
+
+    fn max_name_length(&self) -> Option<u32> {
+        (**self).max_name_length()
+    }
+
+    fn preferred_blksize(&self) -> u32 {
+        (**self).preferred_blksize()
+    }
+
+    fn max_write(&self) -> u32 {
+        (**self).max_write()
+    }
+
+    fn max_dir_streams(&self) -> usize {
+        (**self).max_dir_streams()
+    }
+
+    fn volatile_inodes(&self) -> bool {
+        (**self).volatile_inodes()
+    }
+
+    fn request_timeout(&self) -> Option<Duration> {
+        (**self).request_timeout()
+    }
+
+    fn id_exists(&self, file_id: TId) -> bool {
+        (**self).id_exists(file_id)
+    }
+
+    fn init(&self, req: &RequestInfo, config: &mut KernelConfig) -> FuseResult<()> {
+        (**self).init(req, config)
+    }
+
+    fn destroy(&self) {
+        (**self).destroy()
+    }
+
+    fn pre_unmount(&self) -> FuseResult<()> {
+        (**self).pre_unmount()
+    }
+
+    fn drop_caches(&self, req: &RequestInfo) -> FuseResult<()> {
+        (**self).drop_caches(req)
+    }
+
+    fn access(&self, req: &RequestInfo, file_id: TId, mask: AccessMask) -> FuseResult<()> {
+        (**self).access(req, file_id, mask)
+    }
+
+    fn batch_forget(&self, req: &RequestInfo, nodes: Vec<(TId, u64)>) {
+        (**self).batch_forget(req, nodes)
+    }
+
+    fn bmap(&self, req: &RequestInfo, file_id: TId, blocksize: u32, idx: u64) -> FuseResult<u64> {
+        (**self).bmap(req, file_id, blocksize, idx)
+    }
+
+    fn copy_file_range(
+        &self,
+        req: &RequestInfo,
+        file_in: TId,
+        file_handle_in: BorrowedFileHandle,
+        offset_in: i64,
+        file_out: TId,
+        file_handle_out: BorrowedFileHandle,
+        offset_out: i64,
+        len: u64,
+        flags: u32,
+    ) -> FuseResult<u32> {
+        (**self).copy_file_range(
+            req,
+            file_in,
+            file_handle_in,
+            offset_in,
+            file_out,
+            file_handle_out,
+            offset_out,
+            len,
+            flags,
+        )
+    }
+
+    fn create(
+        &self,
+        req: &RequestInfo,
+        parent_id: TId,
+        name: &OsStr,
+        mode: u32,
+        umask: u32,
+        flags: OpenFlags,
+    ) -> FuseResult<(OwnedFileHandle, TId::Metadata, FUSEOpenResponseFlags)> {
+        (**self).create(req, parent_id, name, mode, umask, flags)
+    }
+
+    fn fallocate(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        file_handle: BorrowedFileHandle,
+        offset: i64,
+        length: i64,
+        mode: FallocateFlags,
+    ) -> FuseResult<()> {
+        (**self).fallocate(req, file_id, file_handle, offset, length, mode)
+    }
+
+    fn flush(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        file_handle: BorrowedFileHandle,
+        lock_owner: u64,
+    ) -> FuseResult<()> {
+        (**self).flush(req, file_id, file_handle, lock_owner)
+    }
+
+    fn forget(&self, req: &RequestInfo, file_id: TId, nlookup: u64) {
+        (**self).forget(req, file_id, nlookup)
+    }
+
+    fn fsync(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        file_handle: BorrowedFileHandle,
+        datasync: bool,
+    ) -> FuseResult<()> {
+        (**self).fsync(req, file_id, file_handle, datasync)
+    }
+
+    fn fsyncdir(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        file_handle: BorrowedFileHandle,
+        datasync: bool,
+    ) -> FuseResult<()> {
+        (**self).fsyncdir(req, file_id, file_handle, datasync)
+    }
+
+    fn getattr(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        file_handle: Option<BorrowedFileHandle>,
+    ) -> FuseResult<FileAttribute> {
+        (**self).getattr(req, file_id, file_handle)
+    }
+
+    #[cfg(feature = "locking")]
+    fn getlk(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        file_handle: BorrowedFileHandle,
+        lock_owner: u64,
+        lock_info: LockInfo,
+    ) -> FuseResult<LockInfo> {
+        (**self).getlk(req, file_id, file_handle, lock_owner, lock_info)
+    }
+
+    #[cfg(feature = "xattr")]
+    fn getxattr(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        name: &OsStr,
+        size: u32,
+    ) -> FuseResult<Vec<u8>> {
+        (**self).getxattr(req, file_id, name, size)
+    }
+
+    #[cfg(feature = "ioctl")]
+    fn ioctl(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        file_handle: BorrowedFileHandle,
+        flags: IOCtlFlags,
+        cmd: u32,
+        in_data: Vec<u8>,
+        out_size: u32,
+    ) -> FuseResult<(i32, Vec<u8>)> {
+        (**self).ioctl(req, file_id, file_handle, flags, cmd, in_data, out_size)
+    }
+
+    fn link(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        newparent: TId,
+        newname: &OsStr,
+    ) -> FuseResult<TId::Metadata> {
+        (**self).link(req, file_id, newparent, newname)
+    }
+
+    #[cfg(feature = "xattr")]
+    fn listxattr(&self, req: &RequestInfo, file_id: TId, size: u32) -> FuseResult<Vec<u8>> {
+        (**self).listxattr(req, file_id, size)
+    }
+
+    #[cfg(feature = "xattr")]
+    fn listxattr_names(&self, req: &RequestInfo, file_id: TId) -> FuseResult<Vec<OsString>> {
+        (**self).listxattr_names(req, file_id)
+    }
+
+    fn lookup(&self, req: &RequestInfo, parent_id: TId, name: &OsStr) -> FuseResult<TId::Metadata> {
+        (**self).lookup(req, parent_id, name)
+    }
+
+    fn lseek(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+    ) -> FuseResult<i64> {
+        (**self).lseek(req, file_id, file_handle, seek)
+    }
+
+    fn lseek_raw(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        file_handle: BorrowedFileHandle,
+        offset: i64,
+        whence: i32,
+    ) -> FuseResult<i64> {
+        (**self).lseek_raw(req, file_id, file_handle, offset, whence)
+    }
+
+    fn mkdir(
+        &self,
+        req: &RequestInfo,
+        parent_id: TId,
+        name: &OsStr,
+        mode: u32,
+        umask: u32,
+    ) -> FuseResult<TId::Metadata> {
+        (**self).mkdir(req, parent_id, name, mode, umask)
+    }
+
+    fn mknod(
+        &self,
+        req: &RequestInfo,
+        parent_id: TId,
+        name: &OsStr,
+        mode: u32,
+        umask: u32,
+        rdev: DeviceType,
+    ) -> FuseResult<TId::Metadata> {
+        (**self).mknod(req, parent_id, name, mode, umask, rdev)
+    }
+
+    fn open(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        flags: OpenFlags,
+    ) -> FuseResult<(OwnedFileHandle, FUSEOpenResponseFlags)> {
+        (**self).open(req, file_id, flags)
+    }
+
+    fn opendir(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        flags: OpenFlags,
+    ) -> FuseResult<(OwnedFileHandle, FUSEOpenResponseFlags)> {
+        (**self).opendir(req, file_id, flags)
+    }
+
+    fn poll(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        file_handle: BorrowedFileHandle,
+        poll_handle: PollHandle,
+        events: u32,
+        flags: u32,
+    ) -> FuseResult<u32> {
+        (**self).poll(req, file_id, file_handle, poll_handle, events, flags)
+    }
+
+    fn read(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        size: u32,
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+    ) -> FuseResult<Vec<u8>> {
+        (**self).read(req, file_id, file_handle, seek, size, flags, lock_owner)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn read_into(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        buf: &mut [u8],
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+    ) -> FuseResult<usize> {
+        (**self).read_into(req, file_id, file_handle, seek, buf, flags, lock_owner)
+    }
+
+    fn readdir(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        file_handle: BorrowedFileHandle,
+    ) -> FuseResult<Vec<(OsString, TId::MinimalMetadata)>> {
+        (**self).readdir(req, file_id, file_handle)
+    }
+
+    fn readdir_partial(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        file_handle: BorrowedFileHandle,
+    ) -> (Vec<(OsString, TId::MinimalMetadata)>, Option<PosixError>) {
+        (**self).readdir_partial(req, file_id, file_handle)
+    }
+
+    fn readdir_stream(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        file_handle: BorrowedFileHandle,
+    ) -> FuseResult<DirEntryStream<TId>> {
+        (**self).readdir_stream(req, file_id, file_handle)
+    }
+
+    fn readdirplus(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        file_handle: BorrowedFileHandle,
+    ) -> FuseResult<Vec<(OsString, TId::Metadata)>> {
+        (**self).readdirplus(req, file_id, file_handle)
+    }
+
+    fn readlink(&self, req: &RequestInfo, file_id: TId) -> FuseResult<Vec<u8>> {
+        (**self).readlink(req, file_id)
+    }
+
+    fn release(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        file_handle: OwnedFileHandle,
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+        flush: bool,
+    ) -> FuseResult<()> {
+        (**self).release(req, file_id, file_handle, flags, lock_owner, flush)
+    }
+
+    fn releasedir(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        file_handle: OwnedFileHandle,
+        flags: OpenFlags,
+    ) -> FuseResult<()> {
+        (**self).releasedir(req, file_id, file_handle, flags)
+    }
+
+    #[cfg(feature = "xattr")]
+    fn removexattr(&self, req: &RequestInfo, file_id: TId, name: &OsStr) -> FuseResult<()> {
+        (**self).removexattr(req, file_id, name)
+    }
+
+    fn rename(
+        &self,
+        req: &RequestInfo,
+        parent_id: TId,
+        name: &OsStr,
+        newparent: TId,
+        newname: &OsStr,
+        flags: RenameFlags,
+    ) -> FuseResult<()> {
+        (**self).rename(req, parent_id, name, newparent, newname, flags)
+    }
+
+    fn rmdir(&self, req: &RequestInfo, parent_id: TId, name: &OsStr) -> FuseResult<()> {
+        (**self).rmdir(req, parent_id, name)
+    }
+
+    fn setattr(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        attrs: SetAttrRequest,
+    ) -> FuseResult<FileAttribute> {
+        (**self).setattr(req, file_id, attrs)
+    }
+
+    #[cfg(feature = "locking")]
+    fn setlk(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        file_handle: BorrowedFileHandle,
+        lock_owner: u64,
+        lock_info: LockInfo,
+        sleep: bool,
+    ) -> FuseResult<()> {
+        (**self).setlk(req, file_id, file_handle, lock_owner, lock_info, sleep)
+    }
+
+    #[cfg(feature = "xattr")]
+    fn setxattr(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        name: &OsStr,
+        value: Vec<u8>,
+        flags: FUSESetXAttrFlags,
+        position: u32,
+    ) -> FuseResult<()> {
+        (**self).setxattr(req, file_id, name, value, flags, position)
+    }
+
+    fn statfs(&self, req: &RequestInfo, file_id: TId) -> FuseResult<StatFs> {
+        (**self).statfs(req, file_id)
+    }
+
+    fn symlink(
+        &self,
+        req: &RequestInfo,
+        parent_id: TId,
+        link_name: &OsStr,
+        target: &Path,
+    ) -> FuseResult<TId::Metadata> {
+        (**self).symlink(req, parent_id, link_name, target)
+    }
+
+    fn write(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        data: Vec<u8>,
+        write_flags: FUSEWriteFlags,
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+    ) -> FuseResult<u32> {
+        (**self).write(
+            req,
+            file_id,
+            file_handle,
+            seek,
+            data,
+            write_flags,
+            flags,
+            lock_owner,
+        )
+    }
+
+    fn unlink(&self, req: &RequestInfo, parent_id: TId, name: &OsStr) -> FuseResult<()> {
+        (**self).unlink(req, parent_id, name)
+    }
+}
@@ -380,6 +380,22 @@ impl<T: Send + Sync + 'static> InodeMapper<T> {
         })
     }
 
+    /// Iterates over all inodes with their information, in no particular order.
+    ///
+    /// The root inode is included.
+    pub fn iter(&self) -> impl Iterator<Item = (&Inode, InodeInfo<'_, T>)> {
+        self.data.inodes.iter().map(|(inode, inode_value)| {
+            (
+                inode,
+                InodeInfo {
+                    parent: &inode_value.parent,
+                    name: inode_value.name.as_ref(),
+                    data: &inode_value.data,
+                },
+            )
+        })
+    }
+
     pub fn get_mut(&mut self, inode: &Inode) -> Option<InodeInfoMut<'_, T>> {
         self.data
             .inodes
@@ -489,6 +505,57 @@ impl<T: Send + Sync + 'static> InodeMapper<T> {
         }
     }
 
+    /// Swaps the children `name` of `parent` and `newname` of `newparent`, as done by `RENAME_EXCHANGE`
+    ///
+    /// Both children must exist. Each inode keeps its data and takes the location of the other.
+    pub fn exchange(
+        &mut self,
+        parent: &Inode,
+        name: &OsStr,
+        newparent: &Inode,
+        newname: &OsStr,
+    ) -> Result<(), RenameError> {
+        if !self.data.inodes.contains_key(parent) {
+            return Err(RenameError::ParentNotFound);
+        }
+        if !self.data.inodes.contains_key(newparent) {
+            return Err(RenameError::NewParentNotFound);
+        }
+        let find_child = |parent: &Inode, name: &OsStr| {
+            self.data
+                .children
+                .get(parent)
+                .and_then(|children| children.get(name))
+                .cloned()
+                .ok_or(RenameError::NotFound)
+        };
+        let inode = find_child(parent, name)?;
+        let newinode = find_child(newparent, newname)?;
+        let name = self.data.inodes[&inode].name.clone();
+        let newname = self.data.inodes[&newinode].name.clone();
+
+        // Swap the children entries
+        self.data
+            .children
+            .get_mut(parent)
+            .unwrap()
+            .insert(name.clone(), newinode.clone());
+        self.data
+            .children
+            .get_mut(newparent)
+            .unwrap()
+            .insert(newname.clone(), inode.clone());
+
+        // Update the inode values with their new parent and name
+        let inode_value = self.data.inodes.get_mut(&inode).unwrap();
+        inode_value.parent = newparent.clone();
+        inode_value.name = newname;
+        let inode_value = self.data.inodes.get_mut(&newinode).unwrap();
+        inode_value.parent = parent.clone();
+        inode_value.name = name;
+        Ok(())
+    }
+
     /// Removes an inode and its associated data from the `InodeMapper`.
     ///
     /// This function removes the specified inode from both the `inodes` and `children` maps.
@@ -770,6 +837,42 @@ mod tests {
         assert!(matches!(result, Err(RenameError::NotFound)));
     }
 
+    #[test]
+    fn test_exchange_children() {
+        let mut mapper = InodeMapper::new(0);
+        let root = mapper.get_root_inode();
+        let dir = mapper
+            .insert_child(&root, OsString::from("dir"), |_| 1)
+            .unwrap();
+        let file1 = mapper
+            .insert_child(&root, OsString::from("file1"), |_| 2)
+            .unwrap();
+        let file2 = mapper
+            .insert_child(&dir, OsString::from("file2"), |_| 3)
+            .unwrap();
+
+        mapper
+            .exchange(&root, OsStr::new("file1"), &dir, OsStr::new("file2"))
+            .unwrap();
+
+        // Each inode keeps its data at the location of the other
+        let lookup = mapper.lookup(&root, OsStr::new("file1")).unwrap();
+        assert_eq!((lookup.inode, *lookup.data), (&file2, 3));
+        let lookup = mapper.lookup(&dir, OsStr::new("file2")).unwrap();
+        assert_eq!((lookup.inode, *lookup.data), (&file1, 2));
+        let inode_value = mapper.get(&file1).unwrap();
+        assert_eq!(inode_value.parent, &dir);
+        assert_eq!(inode_value.name.as_os_str(), OsStr::new("file2"));
+        let inode_value = mapper.get(&file2).unwrap();
+        assert_eq!(inode_value.parent, &root);
+        assert_eq!(inode_value.name.as_os_str(), OsStr::new("file1"));
+
+        assert_eq!(
+            mapper.exchange(&root, OsStr::new("file1"), &dir, OsStr::new("unknown")),
+            Err(RenameError::NotFound)
+        );
+    }
+
     #[test]
     fn test_remove_cascading() {
         let mut mapper = InodeMapper::new(());
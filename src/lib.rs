@@ -21,32 +21,89 @@ compile_error!("Feature 'async' cannot be used with feature serial or parallel")
 
 mod core;
 mod fuse_handler;
+mod threaded_session;
 
 pub mod inode_mapper;
+pub mod notifier;
 pub mod templates;
 pub mod types;
 pub mod unix_fs;
 
 pub use fuse_handler::FuseHandler;
 use fuser::{BackgroundSession, MountOption};
+pub use notifier::Notifier;
+pub use threaded_session::{ThreadConfig, ThreadedSession};
 
 pub mod prelude {
     //! Re-exports the necessary types and functions from the `easy_fuser` crate.
     pub use super::fuse_handler::FuseHandler;
+    pub use super::notifier::Notifier;
+    pub use super::threaded_session::{ThreadConfig, ThreadedSession};
     pub use super::types::*;
-    pub use super::{mount, spawn_mount};
+    pub use super::{
+        mount, mount_on_fd, spawn_mount, spawn_mount_with_notifier, spawn_mount_with_thread_config,
+        unmount,
+    };
 
     pub use fuser::{BackgroundSession, MountOption, Session, SessionUnmounter};
 }
 
 // Implentation of the high-level functions
+use std::ffi::CString;
 use std::io;
+use std::os::fd::OwnedFd;
+use std::os::unix::ffi::OsStrExt;
 use std::path::Path;
 
 use core::FuseDriver;
-use fuser::{mount2, spawn_mount2};
+use fuser::{Session, SessionACL};
 use prelude::*;
 
+/// Session of a driver, with the notifier bound to it
+type BoundSession<T, FS> = (Session<FuseDriver<T, FS>>, Notifier<T>);
+
+/// Creates the session of `driver` with `new_session`, making its notifier available to the driver
+fn bind_session<T, FS, F>(
+    driver: FuseDriver<T, FS>,
+    new_session: F,
+) -> io::Result<BoundSession<T, FS>>
+where
+    T: FileIdType,
+    FS: FuseHandler<T>,
+    F: FnOnce(FuseDriver<T, FS>) -> io::Result<Session<FuseDriver<T, FS>>>,
+{
+    let resolver = driver.get_shared_resolver();
+    let notifier_slot = driver.get_notifier();
+    let session = new_session(driver)?;
+    let notifier = Notifier::new(session.notifier(), resolver);
+    let _ = notifier_slot.set(notifier.clone());
+    Ok((session, notifier))
+}
+
+/// Mounts `driver` on `mountpoint` and creates its session
+fn create_session<T, FS>(
+    driver: FuseDriver<T, FS>,
+    mountpoint: &Path,
+    options: &[MountOption],
+) -> io::Result<BoundSession<T, FS>>
+where
+    T: FileIdType,
+    FS: FuseHandler<T>,
+{
+    bind_session(driver, |driver| Session::new(driver, mountpoint, options))
+}
+
+/// Access control of a session on an already mounted device, where only `AllowOther` and `AllowRoot` still apply
+fn session_acl(options: &[MountOption]) -> SessionACL {
+    if options.contains(&MountOption::AllowOther) {
+        SessionACL::All
+    } else if options.contains(&MountOption::AllowRoot) {
+        SessionACL::RootAndOwner
+    } else {
+        SessionACL::Owner
+    }
+}
+
 #[doc = include_str!("../docs/mount.md")]
 #[cfg(not(feature = "serial"))]
 pub fn mount<T, FS, P>(
@@ -61,7 +118,8 @@ where
     P: AsRef<Path>,
 {
     let driver = FuseDriver::new(filesystem, num_threads);
-    mount2(driver, mountpoint, options)
+    let (mut session, _) = create_session(driver, mountpoint.as_ref(), options)?;
+    session.run()
 }
 #[doc = include_str!("../docs/mount.md")]
 #[cfg(feature = "serial")]
@@ -73,7 +131,39 @@ where
 {
     // num_thread argument will not be taken into account in this function due to feature serial
     let driver = FuseDriver::new(filesystem, 1);
-    mount2(driver, mountpoint, options)
+    let (mut session, _) = create_session(driver, mountpoint.as_ref(), options)?;
+    session.run()
+}
+
+#[doc = include_str!("../docs/mount_on_fd.md")]
+#[cfg(not(feature = "serial"))]
+pub fn mount_on_fd<T, FS>(
+    filesystem: FS,
+    fd: OwnedFd,
+    options: &[MountOption],
+    num_threads: usize,
+) -> io::Result<()>
+where
+    T: FileIdType,
+    FS: FuseHandler<T>,
+{
+    let driver = FuseDriver::new(filesystem, num_threads);
+    let acl = session_acl(options);
+    let (mut session, _) = bind_session(driver, |driver| Ok(Session::from_fd(driver, fd, acl)))?;
+    session.run()
+}
+#[doc = include_str!("../docs/mount_on_fd.md")]
+#[cfg(feature = "serial")]
+pub fn mount_on_fd<T, FS>(filesystem: FS, fd: OwnedFd, options: &[MountOption]) -> io::Result<()>
+where
+    T: FileIdType,
+    FS: FuseHandler<T>,
+{
+    // num_thread argument will not be taken into account in this function due to feature serial
+    let driver = FuseDriver::new(filesystem, 1);
+    let acl = session_acl(options);
+    let (mut session, _) = bind_session(driver, |driver| Ok(Session::from_fd(driver, fd, acl)))?;
+    session.run()
 }
 
 #[doc = include_str!("../docs/spawn_mount.md")]
@@ -90,7 +180,8 @@ where
     P: AsRef<Path>,
 {
     let driver = FuseDriver::new(filesystem, num_threads);
-    spawn_mount2(driver, mountpoint, options)
+    let (session, _) = create_session(driver, mountpoint.as_ref(), options)?;
+    session.spawn()
 }
 
 #[doc = include_str!("../docs/spawn_mount.md")]
@@ -107,5 +198,111 @@ where
 {
     // num_thread argument will not be taken into account in this function due to feature serial
     let driver = FuseDriver::new(filesystem, 1);
-    spawn_mount2(driver, mountpoint, options)
+    let (session, _) = create_session(driver, mountpoint.as_ref(), options)?;
+    session.spawn()
+}
+
+#[doc = include_str!("../docs/spawn_mount_with_notifier.md")]
+#[cfg(not(feature = "serial"))]
+pub fn spawn_mount_with_notifier<T, FS, P>(
+    filesystem: FS,
+    mountpoint: P,
+    options: &[MountOption],
+    num_threads: usize,
+) -> io::Result<(BackgroundSession, Notifier<T>)>
+where
+    T: FileIdType,
+    FS: FuseHandler<T> + Send,
+    P: AsRef<Path>,
+{
+    let driver = FuseDriver::new(filesystem, num_threads);
+    let (session, notifier) = create_session(driver, mountpoint.as_ref(), options)?;
+    Ok((session.spawn()?, notifier))
+}
+
+#[doc = include_str!("../docs/spawn_mount_with_notifier.md")]
+#[cfg(feature = "serial")]
+pub fn spawn_mount_with_notifier<T, FS, P>(
+    filesystem: FS,
+    mountpoint: P,
+    options: &[MountOption],
+) -> io::Result<(BackgroundSession, Notifier<T>)>
+where
+    T: FileIdType,
+    FS: FuseHandler<T> + Send,
+    P: AsRef<Path>,
+{
+    // num_thread argument will not be taken into account in this function due to feature serial
+    let driver = FuseDriver::new(filesystem, 1);
+    let (session, notifier) = create_session(driver, mountpoint.as_ref(), options)?;
+    Ok((session.spawn()?, notifier))
+}
+
+#[doc = include_str!("../docs/spawn_mount_with_thread_config.md")]
+#[cfg(not(feature = "serial"))]
+pub fn spawn_mount_with_thread_config<T, FS, P>(
+    filesystem: FS,
+    mountpoint: P,
+    options: &[MountOption],
+    num_threads: usize,
+    thread_config: ThreadConfig,
+) -> io::Result<ThreadedSession>
+where
+    T: FileIdType,
+    FS: FuseHandler<T> + Send,
+    P: AsRef<Path>,
+{
+    let driver = FuseDriver::new(filesystem, num_threads);
+    let (session, _) = create_session(driver, mountpoint.as_ref(), options)?;
+    ThreadedSession::spawn(session, thread_config)
+}
+
+#[doc = include_str!("../docs/spawn_mount_with_thread_config.md")]
+#[cfg(feature = "serial")]
+pub fn spawn_mount_with_thread_config<T, FS, P>(
+    filesystem: FS,
+    mountpoint: P,
+    options: &[MountOption],
+    thread_config: ThreadConfig,
+) -> io::Result<ThreadedSession>
+where
+    T: FileIdType,
+    FS: FuseHandler<T> + Send,
+    P: AsRef<Path>,
+{
+    // num_thread argument will not be taken into account in this function due to feature serial
+    let driver = FuseDriver::new(filesystem, 1);
+    let (session, _) = create_session(driver, mountpoint.as_ref(), options)?;
+    ThreadedSession::spawn(session, thread_config)
+}
+
+#[doc = include_str!("../docs/unmount.md")]
+pub fn unmount<P: AsRef<Path>>(mountpoint: P) -> io::Result<()> {
+    let mountpoint = mountpoint.as_ref();
+    let c_path = CString::new(mountpoint.as_os_str().as_bytes())?;
+    #[cfg(target_os = "linux")]
+    let result = unsafe { libc::umount2(c_path.as_ptr(), 0) };
+    #[cfg(not(target_os = "linux"))]
+    let result = unsafe { libc::unmount(c_path.as_ptr(), 0) };
+    if result == 0 {
+        return Ok(());
+    }
+    let error = io::Error::last_os_error();
+    // Unprivileged users can only unmount through the setuid fusermount helper
+    #[cfg(target_os = "linux")]
+    if error.raw_os_error() == Some(libc::EPERM) {
+        let status = std::process::Command::new("fusermount")
+            .arg("-u")
+            .arg(mountpoint)
+            .status()?;
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "fusermount -u {} failed: {}",
+                mountpoint.display(),
+                status
+            )));
+        }
+        return Ok(());
+    }
+    Err(error)
 }
@@ -0,0 +1,159 @@
+//! Kernel cache invalidation for a mounted filesystem.
+//!
+//! The kernel caches attributes, directory entries and file data for the duration given by the TTLs
+//! returned to it. When the backend changes out-of-band (a remote sync completes, another process edits
+//! the source), a [`Notifier`] tells the kernel to drop those caches so the next access reaches the
+//! filesystem again.
+//!
+//! A `Notifier` is obtained from [`spawn_mount_with_notifier`](crate::spawn_mount_with_notifier) and
+//! translates the crate's file ids to the inodes known by the kernel.
+//!
+//! Invalidations must not be issued from inside a `FuseHandler` method, as the kernel may wait on the
+//! request being served to complete the invalidation. Call them from another thread instead.
+//!
+//! ## Dropping all caches
+//!
+//! With the `ioctl` feature (enabled by default), any mounted filesystem also answers the [`DROP_CACHES_IOCTL`]
+//! ioctl, issued to its root or any of its files:
+//! [`FuseHandler::drop_caches`](crate::FuseHandler::drop_caches) is called, then the kernel caches of every inode known by
+//! the mount are invalidated. [`drop_caches`] issues it from Rust. This is meant for tests and for debugging
+//! stale caches.
+
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io;
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::core::{FileIdResolver, ROOT_INO};
+use crate::types::*;
+
+/// Ioctl command dropping all the caches of a mounted filesystem, equivalent to `_IO(0xEF, 0x01)`.
+///
+/// It takes no argument. See the module documentation.
+pub const DROP_CACHES_IOCTL: u32 = 0xEF01;
+
+/// Issues `DROP_CACHES_IOCTL` on `path`, which must be inside a filesystem mounted by this crate.
+///
+/// Kernel invalidations are pushed in the background once the ioctl returns.
+pub fn drop_caches<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    let file = File::open(path)?;
+    let result = unsafe { libc::ioctl(file.as_raw_fd(), DROP_CACHES_IOCTL as _) };
+    if result == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Handle to push cache invalidations to the kernel for a mounted filesystem.
+pub struct Notifier<T: FileIdType> {
+    inner: fuser::Notifier,
+    resolver: Arc<T::Resolver>,
+}
+
+// Derived Clone would require T: Clone
+impl<T: FileIdType> Clone for Notifier<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            resolver: self.resolver.clone(),
+        }
+    }
+}
+
+impl<T: FileIdType> Notifier<T> {
+    pub(crate) fn new(inner: fuser::Notifier, resolver: Arc<T::Resolver>) -> Self {
+        Self { inner, resolver }
+    }
+
+    /// Invalidates the cached attributes of a file, keeping its cached data.
+    ///
+    /// Nothing is done if the kernel never looked up the file.
+    pub fn invalidate_attr(&self, id: &T) -> FuseResult<()> {
+        match self.resolver.find_ino(id) {
+            Some(ino) => self.invalidate_inode_attr(ino),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn invalidate_inode_attr(&self, ino: u64) -> FuseResult<()> {
+        // A negative offset restricts the invalidation to the attributes
+        ignore_uncached(self.inner.inval_inode(ino, -1, 0))
+    }
+
+    /// Invalidates the cached directory entry `name` of `parent`, so its next access triggers a new `lookup`.
+    ///
+    /// Nothing is done if the kernel never looked up the parent.
+    pub fn invalidate_entry(&self, parent: &T, name: &OsStr) -> FuseResult<()> {
+        match self.resolver.find_ino(parent) {
+            Some(parent_ino) => ignore_uncached(self.inner.inval_entry(parent_ino, name)),
+            None => Ok(()),
+        }
+    }
+
+    /// Invalidates the cached attributes, data and directory entries of several files at once.
+    ///
+    /// Ids are translated to inodes in a single pass and duplicates are removed, so each inode
+    /// and each directory entry is invalidated only once. Ids never looked up by the kernel are skipped,
+    /// as nothing is cached for them. The next access to an invalidated entry triggers a new `lookup`.
+    ///
+    /// With `Inode` ids, the parent of a file is not known, so only its attributes and data are invalidated.
+    pub fn invalidate_batch(&self, ids: &[T]) -> FuseResult<()> {
+        let mut entries = HashSet::new();
+        let mut inodes = HashSet::new();
+        for id in ids {
+            if let Some(entry) = self.resolver.find_entry(id) {
+                entries.insert(entry);
+            }
+            if let Some(ino) = self.resolver.find_ino(id) {
+                inodes.insert(ino);
+            }
+        }
+        for (parent, name) in &entries {
+            ignore_uncached(self.inner.inval_entry(*parent, name))?;
+        }
+        for ino in inodes {
+            // A zero length invalidates the attributes along with all cached data
+            ignore_uncached(self.inner.inval_inode(ino, 0, 0))?;
+        }
+        Ok(())
+    }
+
+    /// Invalidates the cached attributes, data and directory entries of every file known by the kernel.
+    ///
+    /// With `Inode` ids, the files looked up are not tracked, so only the root is invalidated.
+    pub fn invalidate_all(&self) -> FuseResult<()> {
+        let entries = self.resolver.known_entries();
+        for (_, parent, name) in &entries {
+            ignore_uncached(self.inner.inval_entry(*parent, name))?;
+        }
+        ignore_uncached(self.inner.inval_inode(ROOT_INO, 0, 0))?;
+        for (ino, _, _) in entries {
+            ignore_uncached(self.inner.inval_inode(ino, 0, 0))?;
+        }
+        Ok(())
+    }
+}
+
+impl Notifier<PathBuf> {
+    /// Returns the inode the kernel currently knows `path` by, relative to the mountpoint.
+    ///
+    /// `None` means the kernel holds no cache for this path, so there is nothing to invalidate.
+    ///
+    /// The inode mapping is shared with the mount session. It is read-locked only for the duration of
+    /// the call, so lookups served concurrently are not blocked, but the kernel may forget the inode
+    /// right after it is returned.
+    pub fn resolve_path(&self, path: &Path) -> Option<u64> {
+        self.resolver.resolve_path(path)
+    }
+}
+
+/// The kernel answers ENOENT when it holds nothing to invalidate
+fn ignore_uncached(result: io::Result<()>) -> FuseResult<()> {
+    match result {
+        Err(e) if e.raw_os_error() == Some(libc::ENOENT) => Ok(()),
+        result => Ok(result?),
+    }
+}
@@ -25,14 +25,76 @@
 //! ## Available Templates:
 //!
 //! - `DefaultFuseHandler`: A complete implementation of basic FUSE operations.
+//! - `archive`: Read-only filesystems exposing the content of an archive, like `TarFs` (feature `tar`).
+//! - `BackgroundTask`: A maintenance thread started in `init` and stopped in `destroy`.
+//! - `BlockOn`: A dedicated tokio runtime to call async backends from a handler (feature `block_on`).
+//! - `DefaultPermissions`: A wrapper implementing `access` with the POSIX permission checks.
+//! - `ErrorMapHandler`: A wrapper remapping the errors returned by an inner handler.
 //! - `fd_handler_helper`: Utilities for handling file descriptors in FUSE operations.
+//! - `GenFs`: A read-only filesystem of files generated on demand by closures, like `/proc`.
+//! - `HandleTable`: Unique file handles mapped to a per-open state, for filesystems without file descriptors.
+//! - `LoggingHandler`: A wrapper logging every call to an inner handler and its outcome.
+//! - `mem_fs`: A filesystem stored in memory, which can be pre-seeded with files.
 //! - `mirror_fs`: Templates for creating mirror filesystems.
+//! - `net`: Filesystems backed by a remote server, like `SftpFs` (feature `ssh`).
+//! - `OverlayFs`: A writable upper handler stacked over a read-only lower handler, with copy-up and whiteouts.
+//! - `OwnershipHandler`: A wrapper presenting every file as owned by a single user and group.
+//! - `ReadOnly`: A wrapper rejecting every modification of an inner handler with `EROFS`.
+//! - `seekable_compression`: Random access reads into files compressed in independent blocks.
+//! - `union_readdir`: Merging of directory listings from several backends, for union filesystems.
+//! - `WithTimeout`: A wrapper failing the read-only metadata operations of an inner handler which exceed a deadline.
 //!
 //! For detailed information on each template, refer to their respective documentation.
 
+pub mod archive;
+
+mod background_task;
+pub use background_task::BackgroundTask;
+
+#[cfg(feature = "block_on")]
+mod block_on;
+#[cfg(feature = "block_on")]
+pub use block_on::BlockOn;
+
 mod default_fuse_handler;
-pub use default_fuse_handler::DefaultFuseHandler;
+pub use default_fuse_handler::{DefaultFuseHandler, ObservedOperation};
+
+mod default_permissions;
+pub use default_permissions::DefaultPermissions;
+
+mod error_map;
+pub use error_map::{ErrorMapHandler, Operation};
 
 pub mod fd_handler_helper;
 
+mod gen_fs;
+pub use gen_fs::GenFs;
+
+mod handle_table;
+pub use handle_table::HandleTable;
+
+mod logging;
+pub use logging::LoggingHandler;
+
+pub mod mem_fs;
+
 pub mod mirror_fs;
+
+#[cfg(feature = "ssh")]
+pub mod net;
+
+mod overlay_fs;
+pub use overlay_fs::OverlayFs;
+
+mod ownership;
+pub use ownership::OwnershipHandler;
+
+mod read_only;
+pub use read_only::ReadOnly;
+
+pub mod seekable_compression;
+
+pub mod union_readdir;
+
+mod with_timeout;
+pub use with_timeout::WithTimeout;
@@ -0,0 +1,137 @@
+//! # Archive backends
+//!
+//! Templates exposing the content of an archive file as a read-only filesystem. They are compiled in
+//! with the feature of their format.
+//!
+//! - `TarFs` (feature `tar`): A `.tar` or gzip compressed `.tar.gz` archive.
+//!
+//! The archive is scanned once when opened, to build an [`ArchiveIndex`] of its entries. File ids are
+//! paths relative to the root of the archive, like for `MirrorFs`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsString;
+use std::path::{Component, Path, PathBuf};
+
+use crate::prelude::*;
+
+#[cfg(feature = "tar")]
+mod tar_fs;
+#[cfg(feature = "tar")]
+pub use tar_fs::TarFs;
+
+/// Metadata and location of an entry of an archive
+#[derive(Debug, Clone)]
+pub struct ArchiveEntry {
+    pub attr: FileAttribute,
+    /// Offset of the content in the archive, once decompressed
+    pub data_offset: u64,
+    /// Target of a symbolic link, as stored in the archive
+    pub link_target: Option<PathBuf>,
+}
+
+/// Directory tree of an archive, keyed by the paths of the entries relative to the root
+///
+/// Parent directories missing from the archive are created with the attributes of the root.
+#[derive(Debug, Clone)]
+pub struct ArchiveIndex {
+    entries: HashMap<PathBuf, ArchiveEntry>,
+    children: HashMap<PathBuf, BTreeMap<OsString, FileKind>>,
+}
+
+impl ArchiveIndex {
+    pub fn new(root_attr: FileAttribute) -> Self {
+        let root = ArchiveEntry {
+            attr: root_attr,
+            data_offset: 0,
+            link_target: None,
+        };
+        Self {
+            entries: HashMap::from([(PathBuf::new(), root)]),
+            children: HashMap::from([(PathBuf::new(), BTreeMap::new())]),
+        }
+    }
+
+    /// Adds the entry at `path`, replacing any previous one like an extraction would
+    ///
+    /// Fails if a parent of `path` is not a directory.
+    pub fn insert(&mut self, path: &Path, entry: ArchiveEntry) -> FuseResult<()> {
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            return Err(ErrorKind::InvalidArgument.to_error("Path has no file name"));
+        };
+        self.create_dir_all(parent)?;
+        let kind = entry.attr.kind;
+        let previous = self.entries.insert(path.to_path_buf(), entry);
+        match previous.map(|previous| previous.attr.kind) {
+            Some(FileKind::Directory) if kind == FileKind::Directory => {}
+            Some(FileKind::Directory) => {
+                self.entries
+                    .retain(|entry_path, _| !entry_path.starts_with(path) || entry_path == path);
+                self.children
+                    .retain(|entry_path, _| !entry_path.starts_with(path));
+            }
+            _ if kind == FileKind::Directory => {
+                self.children.insert(path.to_path_buf(), BTreeMap::new());
+            }
+            _ => {}
+        }
+        self.children
+            .get_mut(parent)
+            .expect("Parent is a directory")
+            .insert(name.to_os_string(), kind);
+        Ok(())
+    }
+
+    fn create_dir_all(&mut self, path: &Path) -> FuseResult<()> {
+        match self.entries.get(path) {
+            Some(entry) if entry.attr.kind == FileKind::Directory => return Ok(()),
+            Some(_) => {
+                return Err(ErrorKind::NotADirectory
+                    .to_error(format!("{}: Not a directory", path.display())))
+            }
+            None => {}
+        }
+        let mut attr = self.entries[Path::new("")].attr.clone();
+        attr.nlink = 2;
+        let entry = ArchiveEntry {
+            attr,
+            data_offset: 0,
+            link_target: None,
+        };
+        self.insert(path, entry)
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&ArchiveEntry> {
+        self.entries.get(path)
+    }
+
+    /// Names and kinds of the entries of the directory at `path`
+    pub fn children(&self, path: &Path) -> Option<&BTreeMap<OsString, FileKind>> {
+        self.children.get(path)
+    }
+
+    /// Number of entries, including the root
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Sum of the blocks of the entries, for `statfs`
+    pub fn total_blocks(&self) -> u64 {
+        self.entries.values().map(|entry| entry.attr.blocks).sum()
+    }
+}
+
+/// Returns the path of an archive entry relative to the root
+///
+/// Leading `/` and `./` are removed, as well as `..` components which could escape the root.
+pub fn entry_path(path: &Path) -> PathBuf {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name),
+            _ => None,
+        })
+        .collect()
+}
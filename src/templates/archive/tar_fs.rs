@@ -0,0 +1,421 @@
+/*!
+# TarFs
+
+A FUSE handler exposing the content of a tar archive, in read-only mode.
+
+## Overview
+
+`TarFs` implements `FuseHandler<PathBuf>` over a `.tar` or gzip compressed `.tar.gz` archive. The compression is
+detected from the content of the file, whatever its extension.
+
+- **Index**: Tar has no central directory, so the whole archive is scanned once by [`TarFs::open`] to build an
+  [`ArchiveIndex`] of the entries and the offsets of their content. Parent directories missing from the archive
+  are created with the attributes of the root.
+- **Plain archives**: Reads are served with positioned reads into the archive, and can be concurrent.
+- **Compressed archives**: A gzip stream can't be decompressed from an arbitrary offset. The decompressor is kept
+  between reads, so that reads moving forward only decompress the data in between, while reads moving backward
+  restart the decompression from the start of the archive. Files are opened with
+  `FUSEOpenResponseFlags::KEEP_CACHE` to limit the restarts, the archive being immutable.
+
+Hard links are served as copies of their target, symbolic links keep the target stored in the archive.
+
+## Usage
+
+```text
+let fs = TarFs::open("backup.tar.gz", DefaultFuseHandler::new())?;
+mount(fs, mountpoint, &[MountOption::RO], 4)
+```
+
+## Note
+Modifications are delegated to the inner handler. Mount with `MountOption::RO`, or wrap the filesystem in
+`ReadOnly`, to reject them with `EROFS`.
+*/
+
+use std::ffi::{OsStr, OsString};
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::FileExt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, UNIX_EPOCH};
+
+use flate2::read::MultiGzDecoder;
+use log::warn;
+use tar::{Archive, Entries, EntryType};
+
+use super::{entry_path, ArchiveEntry, ArchiveIndex};
+use crate::prelude::*;
+use crate::unix_fs;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Decompressed stream of a compressed archive, with its current position
+struct GzipStream {
+    decoder: MultiGzDecoder<BufReader<File>>,
+    position: u64,
+}
+
+enum Source {
+    Plain(File),
+    Gzip {
+        path: PathBuf,
+        stream: Mutex<Option<GzipStream>>,
+    },
+}
+
+impl Source {
+    fn read_at(&self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        match self {
+            Source::Plain(file) => {
+                let mut data = vec![0; len];
+                let mut filled = 0;
+                while filled < len {
+                    let read = file.read_at(&mut data[filled..], offset + filled as u64)?;
+                    if read == 0 {
+                        break;
+                    }
+                    filled += read;
+                }
+                data.truncate(filled);
+                Ok(data)
+            }
+            Source::Gzip { path, stream } => {
+                let mut stream = stream.lock().unwrap();
+                if stream
+                    .as_ref()
+                    .map_or(true, |stream| stream.position > offset)
+                {
+                    *stream = Some(GzipStream {
+                        decoder: MultiGzDecoder::new(BufReader::new(File::open(path)?)),
+                        position: 0,
+                    });
+                }
+                let stream = stream.as_mut().unwrap();
+                stream.position += io::copy(
+                    &mut (&mut stream.decoder).take(offset - stream.position),
+                    &mut io::sink(),
+                )?;
+                let mut data = Vec::with_capacity(len);
+                (&mut stream.decoder)
+                    .take(len as u64)
+                    .read_to_end(&mut data)?;
+                stream.position += data.len() as u64;
+                Ok(data)
+            }
+        }
+    }
+}
+
+fn build_index<R: Read>(
+    entries: Entries<'_, R>,
+    root_attr: FileAttribute,
+) -> io::Result<ArchiveIndex> {
+    let mut index = ArchiveIndex::new(root_attr);
+    for entry in entries {
+        let entry = entry?;
+        let header = entry.header();
+        let path = entry_path(&entry.path()?);
+        if path.as_os_str().is_empty() {
+            continue;
+        }
+        let kind = match header.entry_type() {
+            EntryType::Regular | EntryType::Continuous => FileKind::RegularFile,
+            EntryType::Directory => FileKind::Directory,
+            EntryType::Symlink => FileKind::Symlink,
+            EntryType::Char => FileKind::CharDevice,
+            EntryType::Block => FileKind::BlockDevice,
+            EntryType::Fifo => FileKind::NamedPipe,
+            EntryType::Link => {
+                let target = entry.link_name()?.map(|target| entry_path(&target));
+                match target.and_then(|target| index.get(&target).cloned()) {
+                    Some(target) => {
+                        if let Err(e) = index.insert(&path, target) {
+                            warn!("TarFs: skipping {}: {}", path.display(), e);
+                        }
+                    }
+                    None => warn!(
+                        "TarFs: skipping {}: hard link to an unknown entry",
+                        path.display()
+                    ),
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        let link_target = match kind {
+            FileKind::Symlink => entry.link_name()?.map(|target| target.into_owned()),
+            _ => None,
+        };
+        let size = match kind {
+            FileKind::RegularFile => entry.size(),
+            FileKind::Symlink => link_target
+                .as_ref()
+                .map_or(0, |target| target.as_os_str().len() as u64),
+            _ => 0,
+        };
+        let rdev = match kind {
+            FileKind::CharDevice | FileKind::BlockDevice => DeviceType::from_parts(
+                header.device_major()?.unwrap_or(0),
+                header.device_minor()?.unwrap_or(0),
+                kind,
+            )
+            .to_rdev() as u32,
+            _ => 0,
+        };
+        let mtime = UNIX_EPOCH + Duration::from_secs(header.mtime().unwrap_or(0));
+        let attr = FileAttribute {
+            size,
+            blocks: size.div_ceil(512),
+            atime: mtime,
+            mtime,
+            ctime: mtime,
+            crtime: mtime,
+            kind,
+            perm: (header.mode().unwrap_or(0o644) & 0o7777) as u16,
+            nlink: if kind == FileKind::Directory { 2 } else { 1 },
+            uid: header.uid().unwrap_or(0) as u32,
+            gid: header.gid().unwrap_or(0) as u32,
+            rdev,
+            blksize: 4096,
+            flags: 0,
+            ttl: None,
+            generation: None,
+        };
+        let archive_entry = ArchiveEntry {
+            attr,
+            data_offset: entry.raw_file_position(),
+            link_target,
+        };
+        if let Err(e) = index.insert(&path, archive_entry) {
+            warn!("TarFs: skipping {}: {}", path.display(), e);
+        }
+    }
+    Ok(index)
+}
+
+fn not_found() -> PosixError {
+    ErrorKind::FileNotFound.to_error("No such file or directory")
+}
+
+/// Specific documentation is located in parent module documentation.
+pub struct TarFs {
+    inner: Box<dyn FuseHandler<PathBuf>>,
+    index: ArchiveIndex,
+    source: Source,
+}
+
+impl TarFs {
+    /// Scans the archive at `path` to build its index, delegating the operations it doesn't implement to `inner`
+    ///
+    /// The root takes the ownership and times of the archive file.
+    pub fn open<P: AsRef<Path>, U: FuseHandler<PathBuf>>(path: P, inner: U) -> io::Result<Self> {
+        let path = path.as_ref();
+        let mut file = File::open(path)?;
+        let mut root_attr = unix_fs::convert_fileattribute(file.metadata()?);
+        root_attr.kind = FileKind::Directory;
+        root_attr.perm = 0o755;
+        root_attr.size = 0;
+        root_attr.blocks = 0;
+        root_attr.nlink = 2;
+
+        let mut magic = [0; 2];
+        let is_gzip = file.read_exact_at(&mut magic, 0).is_ok() && magic == GZIP_MAGIC;
+        let (index, source) = if is_gzip {
+            let decoder = MultiGzDecoder::new(BufReader::new(file));
+            let index = build_index(Archive::new(decoder).entries()?, root_attr)?;
+            let source = Source::Gzip {
+                path: path.to_path_buf(),
+                stream: Mutex::new(None),
+            };
+            (index, source)
+        } else {
+            let index = {
+                let mut archive = Archive::new(&mut file);
+                build_index(archive.entries_with_seek()?, root_attr)?
+            };
+            (index, Source::Plain(file))
+        };
+        Ok(Self {
+            inner: Box::new(inner),
+            index,
+            source,
+        })
+    }
+
+    pub fn index(&self) -> &ArchiveIndex {
+        &self.index
+    }
+
+    fn entry(&self, path: &Path) -> FuseResult<&ArchiveEntry> {
+        self.index.get(path).ok_or_else(not_found)
+    }
+}
+
+impl FuseHandler<PathBuf> for TarFs {
+    fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+        self.inner.as_ref()
+    }
+
+    fn getattr(
+        &self,
+        _req: &RequestInfo,
+        file_id: PathBuf,
+        _file_handle: Option<BorrowedFileHandle>,
+    ) -> FuseResult<FileAttribute> {
+        Ok(self.entry(&file_id)?.attr.clone())
+    }
+
+    fn lookup(
+        &self,
+        _req: &RequestInfo,
+        parent_id: PathBuf,
+        name: &OsStr,
+    ) -> FuseResult<FileAttribute> {
+        Ok(self.entry(&parent_id.join(name))?.attr.clone())
+    }
+
+    fn open(
+        &self,
+        _req: &RequestInfo,
+        file_id: PathBuf,
+        flags: OpenFlags,
+    ) -> FuseResult<(OwnedFileHandle, FUSEOpenResponseFlags)> {
+        self.entry(&file_id)?;
+        if flags.intersects(OpenFlags::WRITE_ONLY | OpenFlags::READ_WRITE) {
+            return Err(ErrorKind::ReadOnlyFileSystem.to_error("Archives are read-only"));
+        }
+        // Safe because file handles are not used
+        Ok((
+            unsafe { OwnedFileHandle::from_raw(0) },
+            FUSEOpenResponseFlags::KEEP_CACHE,
+        ))
+    }
+
+    fn read(
+        &self,
+        _req: &RequestInfo,
+        file_id: PathBuf,
+        _file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        size: u32,
+        _flags: OpenFlags,
+        _lock_owner: Option<u64>,
+    ) -> FuseResult<Vec<u8>> {
+        let entry = self.entry(&file_id)?;
+        if entry.attr.kind == FileKind::Directory {
+            return Err(ErrorKind::IsADirectory.to_error("Is a directory"));
+        }
+        let offset = match seek {
+            SeekFrom::Start(offset) => offset,
+            _ => return Err(ErrorKind::InvalidArgument.to_error("Invalid offset")),
+        };
+        let len = entry.attr.size.saturating_sub(offset).min(size as u64);
+        if len == 0 {
+            return Ok(Vec::new());
+        }
+        Ok(self
+            .source
+            .read_at(entry.data_offset + offset, len as usize)?)
+    }
+
+    fn readdir(
+        &self,
+        _req: &RequestInfo,
+        file_id: PathBuf,
+        _file_handle: BorrowedFileHandle,
+    ) -> FuseResult<Vec<(OsString, FileKind)>> {
+        let children = match self.index.children(&file_id) {
+            Some(children) => children,
+            None => {
+                self.entry(&file_id)?;
+                return Err(ErrorKind::NotADirectory.to_error("Not a directory"));
+            }
+        };
+        let mut entries = vec![
+            (OsString::from("."), FileKind::Directory),
+            (OsString::from(".."), FileKind::Directory),
+        ];
+        entries.extend(children.iter().map(|(name, kind)| (name.clone(), *kind)));
+        Ok(entries)
+    }
+
+    fn readlink(&self, _req: &RequestInfo, file_id: PathBuf) -> FuseResult<Vec<u8>> {
+        match &self.entry(&file_id)?.link_target {
+            Some(target) => Ok(target.as_os_str().as_bytes().to_vec()),
+            None => Err(ErrorKind::InvalidArgument.to_error("Not a symbolic link")),
+        }
+    }
+
+    fn statfs(&self, _req: &RequestInfo, _file_id: PathBuf) -> FuseResult<StatFs> {
+        Ok(StatFs::builder()
+            .block_size(512)
+            .fragment_size(512)
+            .total_blocks(self.index.total_blocks())
+            .total_files(self.index.len() as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::DefaultFuseHandler;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_compressed_archive() {
+        let archive = NamedTempFile::new().unwrap();
+        let encoder = GzEncoder::new(archive.reopen().unwrap(), Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        for (path, content) in [("a/first", "0123456789"), ("a/b/second", "abcdef")] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o640);
+            builder
+                .append_data(&mut header, path, content.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let fs = TarFs::open(archive.path(), DefaultFuseHandler::new()).unwrap();
+        let req = RequestInfo::default();
+        let attr = fs
+            .lookup(&req, PathBuf::from("a/b"), OsStr::new("second"))
+            .unwrap();
+        assert_eq!(
+            (attr.kind, attr.size, attr.perm),
+            (FileKind::RegularFile, 6, 0o640)
+        );
+        // Implicit parent directory
+        let attr = fs.lookup(&req, PathBuf::new(), OsStr::new("a")).unwrap();
+        assert_eq!(attr.kind, FileKind::Directory);
+
+        let handle = unsafe { OwnedFileHandle::from_raw(0) };
+        let read = |path: &str, offset: u64, size: u32| {
+            fs.read(
+                &req,
+                PathBuf::from(path),
+                handle.borrow(),
+                SeekFrom::Start(offset),
+                size,
+                OpenFlags::empty(),
+                None,
+            )
+            .unwrap()
+        };
+        // Forward, then backward to restart the decompression
+        assert_eq!(read("a/b/second", 2, 100), b"cdef");
+        assert_eq!(read("a/first", 8, 100), b"89");
+        assert_eq!(read("a/first", 10, 100), b"");
+
+        let names: Vec<_> = fs
+            .readdir(&req, PathBuf::from("a"), handle.borrow())
+            .unwrap()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, vec![".", "..", "b", "first"]);
+    }
+}
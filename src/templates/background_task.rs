@@ -0,0 +1,149 @@
+/*!
+# BackgroundTask
+
+A helper to run a maintenance thread tied to the lifetime of a mount, such as cache expiry sweeps or
+flushing write-back buffers after an idle timeout.
+
+The thread is meant to be spawned from `FuseHandler::init` and stopped from `FuseHandler::destroy`, which the
+driver calls when the filesystem is unmounted. Stopping signals the shutdown channel given to the task and waits
+for the thread to exit, so no maintenance work outlives the mount.
+
+## Example
+
+```rust, no_run
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{BackgroundTask, DefaultFuseHandler};
+use std::time::Duration;
+
+struct MyFs {
+    inner: DefaultFuseHandler,
+    maintenance: BackgroundTask,
+}
+
+impl FuseHandler<Inode> for MyFs {
+    fn get_inner(&self) -> &dyn FuseHandler<Inode> {
+        &self.inner
+    }
+
+    fn init(&self, req: &RequestInfo, config: &mut KernelConfig) -> FuseResult<()> {
+        self.maintenance
+            .start_periodic(Duration::from_secs(30), || { /* expire cache entries */ })?;
+        self.get_inner().init(req, config)
+    }
+
+    fn destroy(&self) {
+        self.maintenance.stop();
+        self.get_inner().destroy();
+    }
+}
+```
+*/
+
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use log::error;
+
+use crate::types::*;
+
+/// Owns a background thread and the channel used to ask it to shut down.
+#[derive(Default)]
+pub struct BackgroundTask {
+    running: Mutex<Option<(Sender<()>, JoinHandle<()>)>>,
+}
+
+impl BackgroundTask {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spawns `task` in a new thread, giving it the receiving end of the shutdown channel.
+    ///
+    /// The task must return once a message is received or the channel is disconnected.
+    /// A previously started task is stopped first.
+    pub fn start<F>(&self, task: F) -> FuseResult<()>
+    where
+        F: FnOnce(Receiver<()>) + Send + 'static,
+    {
+        self.stop();
+        let (sender, receiver) = mpsc::channel();
+        let handle = thread::Builder::new()
+            .name("easy_fuser-background".to_string())
+            .spawn(move || task(receiver))
+            .map_err(|e| {
+                PosixError::new(
+                    ErrorKind::ResourceUnavailableTryAgain,
+                    format!("Failed to spawn background task: {}", e),
+                )
+            })?;
+        *self.running.lock().unwrap() = Some((sender, handle));
+        Ok(())
+    }
+
+    /// Spawns a thread calling `job` every `interval` until the task is stopped.
+    pub fn start_periodic<F>(&self, interval: Duration, mut job: F) -> FuseResult<()>
+    where
+        F: FnMut() + Send + 'static,
+    {
+        self.start(move |shutdown| {
+            // Stops on a message or once the task is dropped
+            while let Err(RecvTimeoutError::Timeout) = shutdown.recv_timeout(interval) {
+                job();
+            }
+        })
+    }
+
+    /// Returns true if a task has been started and not stopped yet.
+    pub fn is_running(&self) -> bool {
+        self.running.lock().unwrap().is_some()
+    }
+
+    /// Signals the shutdown channel and waits for the thread to exit.
+    ///
+    /// Does nothing if no task is running.
+    pub fn stop(&self) {
+        let running = self.running.lock().unwrap().take();
+        if let Some((sender, handle)) = running {
+            let _ = sender.send(());
+            if handle.join().is_err() {
+                error!("Background task panicked");
+            }
+        }
+    }
+}
+
+impl Drop for BackgroundTask {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_periodic_task_stops() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let task = BackgroundTask::new();
+        let counter_clone = counter.clone();
+        task.start_periodic(Duration::from_millis(5), move || {
+            counter_clone.fetch_add(1, Ordering::SeqCst);
+        })
+        .unwrap();
+        assert!(task.is_running());
+
+        thread::sleep(Duration::from_millis(50));
+        task.stop();
+        assert!(!task.is_running());
+
+        let count = counter.load(Ordering::SeqCst);
+        assert!(count > 0);
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(counter.load(Ordering::SeqCst), count);
+    }
+}
@@ -0,0 +1,80 @@
+use std::future::Future;
+use std::io;
+
+use tokio::runtime::{Builder, Handle, Runtime};
+
+/// Drives async work from the synchronous methods of a `FuseHandler`.
+///
+/// Owns a dedicated multi-threaded tokio runtime, created once and shared by every call, so that
+/// handlers backed by async clients (FTP, HTTP, ...) don't build a runtime per operation.
+/// `block_on` takes `&self` and may be called concurrently by the worker threads of the parallel driver.
+///
+/// This is a stopgap until the `async` feature is available.
+///
+/// ## Restrictions
+///
+/// `block_on` panics when called from within an async context, like a task of this or another runtime.
+/// It is meant to be called from the driver threads only.
+///
+/// ```text
+/// struct HttpFs {
+///     inner: DefaultFuseHandler,
+///     runtime: BlockOn,
+///     client: reqwest::Client,
+/// }
+///
+/// // In a FuseHandler method
+/// let body = self.runtime.block_on(async { self.client.get(url).send().await?.bytes().await })?;
+/// ```
+pub struct BlockOn {
+    runtime: Runtime,
+}
+
+impl BlockOn {
+    /// Creates a runtime with the default number of worker threads and both IO and time drivers enabled
+    pub fn new() -> io::Result<Self> {
+        Ok(Self::from_runtime(
+            Builder::new_multi_thread().enable_all().build()?,
+        ))
+    }
+
+    /// Uses a runtime configured by the caller
+    pub fn from_runtime(runtime: Runtime) -> Self {
+        Self { runtime }
+    }
+
+    /// Runs `future` to completion on the runtime, blocking the current thread
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    /// Handle to the runtime, for example to spawn background tasks from `init`
+    pub fn handle(&self) -> &Handle {
+        self.runtime.handle()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_block_on_from_several_threads() {
+        let runtime = Arc::new(BlockOn::new().unwrap());
+        let threads: Vec<_> = (0..4)
+            .map(|i| {
+                let runtime = runtime.clone();
+                std::thread::spawn(move || {
+                    runtime.block_on(async move {
+                        tokio::time::sleep(Duration::from_millis(10)).await;
+                        i * 2
+                    })
+                })
+            })
+            .collect();
+        let results: Vec<_> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+        assert_eq!(results, vec![0, 2, 4, 6]);
+    }
+}
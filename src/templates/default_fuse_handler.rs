@@ -1,6 +1,8 @@
 use std::{
     ffi::{OsStr, OsString},
+    fmt::Display,
     path::Path,
+    sync::mpsc::Sender,
     time::Duration,
 };
 
@@ -22,9 +24,11 @@ The `DefaultFuseHandler` implements the `FuseHandler` trait, providing default i
 The following functions are implemented with default responses, so they don't need to be explicitly implemented in derived handlers:
 
 - `init`: Returns `Ok(())`.
+- `pre_unmount`: Returns `Ok(())`.
 - `opendir`: Returns a `OwnedFileHandle` with value 0 and empty `FUSEOpenResponseFlags`. Only safe because releasedir don't use the file handle
 - `releasedir`: Returns `Ok(())`.
 - `fsyncdir`: Returns `Ok(())`.
+- `poll`: Returns a `FunctionNotImplemented` error whatever the configuration, so the kernel reports files as always ready.
 - `statfs`: Returns `StatFs::default()`.
 
 ## Usage
@@ -41,12 +45,41 @@ The `DefaultFuseHandler` can be configured to either return errors or panic when
 - `DefaultFuseHandler::new()`: Creates a handler that returns "Not Implemented" errors.
 - `DefaultFuseHandler::new_with_panic()`: Creates a handler that panics on unimplemented methods.
 
+## Observing the requests
+
+`DefaultFuseHandler::with_observer(sender)` additionally sends an [`ObservedOperation`] to the channel
+for each operation the handler receives, to see which requests the kernel makes for a given command:
+
+```text
+let (sender, receiver) = std::sync::mpsc::channel();
+let fs = DefaultFuseHandler::new().with_observer(sender);
+// Mount fs, then run `ls mountpoint/dir`
+for operation in receiver.try_iter() {
+    println!("{} {:?} {:?}", operation.operation, operation.file_id, operation.name);
+}
+```
+
+Operations implemented by an outer handler never reach `DefaultFuseHandler`, so they are not observed.
+
 ## Note
 
 This is a basic skeleton. For more complete implementations, refer to the templates provided in the library.
 */
 pub struct DefaultFuseHandler {
     handling: HandlingMethod,
+    observer: Option<Sender<ObservedOperation>>,
+}
+
+/// Operation received by a `DefaultFuseHandler`, see `DefaultFuseHandler::with_observer`
+#[derive(Debug, Clone)]
+pub struct ObservedOperation {
+    /// Name of the `FuseHandler` method, eg: `lookup`
+    pub operation: &'static str,
+    pub req: RequestInfo,
+    /// Displayed id of the file, or of the parent directory for operations on a directory entry
+    pub file_id: Option<String>,
+    /// Name of the directory entry, for operations taking one
+    pub name: Option<OsString>,
 }
 
 enum HandlingMethod {
@@ -62,6 +95,7 @@ impl DefaultFuseHandler {
     pub fn new() -> Self {
         DefaultFuseHandler {
             handling: HandlingMethod::Error(ErrorKind::FunctionNotImplemented),
+            observer: None,
         }
     }
 
@@ -72,6 +106,7 @@ impl DefaultFuseHandler {
     pub fn new_with_panic() -> Self {
         DefaultFuseHandler {
             handling: HandlingMethod::Panic,
+            observer: None,
         }
     }
 
@@ -81,6 +116,32 @@ impl DefaultFuseHandler {
     pub fn new_with_custom_error(error_kind: ErrorKind) -> Self {
         DefaultFuseHandler {
             handling: HandlingMethod::Error(error_kind),
+            observer: None,
+        }
+    }
+
+    /// Sends an `ObservedOperation` to `sender` for each operation received, in addition to the default behavior.
+    ///
+    /// Meant for tests and diagnostics. Records are dropped once the receiver is gone.
+    pub fn with_observer(mut self, sender: Sender<ObservedOperation>) -> Self {
+        self.observer = Some(sender);
+        self
+    }
+
+    fn observe(
+        &self,
+        req: &RequestInfo,
+        operation: &'static str,
+        file_id: Option<&dyn Display>,
+        name: Option<&OsStr>,
+    ) {
+        if let Some(observer) = &self.observer {
+            let _ = observer.send(ObservedOperation {
+                operation,
+                req: req.clone(),
+                file_id: file_id.map(|file_id| file_id.to_string()),
+                name: name.map(OsStr::to_os_string),
+            });
         }
     }
 }
@@ -94,13 +155,68 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
         Duration::from_secs(1)
     }
 
-    fn init(&self, _req: &RequestInfo, _config: &mut KernelConfig) -> FuseResult<()> {
+    fn skip_clean_flushes(&self) -> bool {
+        false
+    }
+
+    fn available_attributes(&self) -> AttrMask {
+        AttrMask::all()
+    }
+
+    fn max_file_size(&self) -> Option<u64> {
+        None
+    }
+
+    fn negative_lookup_ttl(&self) -> Option<Duration> {
+        None
+    }
+
+    fn max_name_length(&self) -> Option<u32> {
+        None
+    }
+
+    fn preferred_blksize(&self) -> u32 {
+        0
+    }
+
+    fn max_write(&self) -> u32 {
+        128 * 1024
+    }
+
+    fn max_dir_streams(&self) -> usize {
+        1024
+    }
+
+    fn volatile_inodes(&self) -> bool {
+        false
+    }
+
+    fn request_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    fn id_exists(&self, _file_id: TId) -> bool {
+        true
+    }
+
+    fn init(&self, req: &RequestInfo, _config: &mut KernelConfig) -> FuseResult<()> {
+        self.observe(req, "init", None, None);
         Ok(())
     }
 
     fn destroy(&self) {}
 
-    fn access(&self, _req: &RequestInfo, file_id: TId, mask: AccessMask) -> FuseResult<()> {
+    fn pre_unmount(&self) -> FuseResult<()> {
+        Ok(())
+    }
+
+    fn drop_caches(&self, req: &RequestInfo) -> FuseResult<()> {
+        self.observe(req, "drop_caches", None, None);
+        Ok(())
+    }
+
+    fn access(&self, req: &RequestInfo, file_id: TId, mask: AccessMask) -> FuseResult<()> {
+        self.observe(req, "access", Some(&file_id.display()), None);
         match self.handling {
             HandlingMethod::Error(kind) => Err(PosixError::new(
                 kind,
@@ -118,7 +234,8 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
         }
     }
 
-    fn bmap(&self, _req: &RequestInfo, file_id: TId, blocksize: u32, idx: u64) -> FuseResult<u64> {
+    fn bmap(&self, req: &RequestInfo, file_id: TId, blocksize: u32, idx: u64) -> FuseResult<u64> {
+        self.observe(req, "bmap", Some(&file_id.display()), None);
         match self.handling {
             HandlingMethod::Error(kind) => Err(PosixError::new(
                 kind,
@@ -144,7 +261,7 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
 
     fn copy_file_range(
         &self,
-        _req: &RequestInfo,
+        req: &RequestInfo,
         file_in: TId,
         file_handle_in: BorrowedFileHandle,
         offset_in: i64,
@@ -154,6 +271,7 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
         len: u64,
         flags: u32, // Not implemented yet in standard
     ) -> FuseResult<u32> {
+        self.observe(req, "copy_file_range", Some(&file_in.display()), None);
         match self.handling {
             HandlingMethod::Error(kind) => Err(
                 PosixError::new(kind, if cfg!(debug_assertions) {
@@ -188,13 +306,14 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
 
     fn create(
         &self,
-        _req: &RequestInfo,
+        req: &RequestInfo,
         parent_id: TId,
         name: &OsStr,
         mode: u32,
         umask: u32,
         flags: OpenFlags,
     ) -> FuseResult<(OwnedFileHandle, TId::Metadata, FUSEOpenResponseFlags)> {
+        self.observe(req, "create", Some(&parent_id.display()), Some(name));
         match self.handling {
             HandlingMethod::Error(kind) => Err(PosixError::new(
                 kind,
@@ -224,13 +343,14 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
 
     fn fallocate(
         &self,
-        _req: &RequestInfo,
+        req: &RequestInfo,
         file_id: TId,
         file_handle: BorrowedFileHandle,
         offset: i64,
         length: i64,
         mode: FallocateFlags,
     ) -> FuseResult<()> {
+        self.observe(req, "fallocate", Some(&file_id.display()), None);
         match self.handling {
             HandlingMethod::Error(kind) => Err(
                 PosixError::new(kind, if cfg!(debug_assertions) {
@@ -259,11 +379,12 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
 
     fn flush(
         &self,
-        _req: &RequestInfo,
+        req: &RequestInfo,
         file_id: TId,
         file_handle: BorrowedFileHandle,
         lock_owner: u64,
     ) -> FuseResult<()> {
+        self.observe(req, "flush", Some(&file_id.display()), None);
         match self.handling {
             HandlingMethod::Error(kind) => Err(PosixError::new(
                 kind,
@@ -287,15 +408,18 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
         }
     }
 
-    fn forget(&self, _req: &RequestInfo, _file_id: TId, _nlookup: u64) {}
+    fn forget(&self, req: &RequestInfo, file_id: TId, _nlookup: u64) {
+        self.observe(req, "forget", Some(&file_id.display()), None);
+    }
 
     fn fsync(
         &self,
-        _req: &RequestInfo,
+        req: &RequestInfo,
         file_id: TId,
         file_handle: BorrowedFileHandle,
         datasync: bool,
     ) -> FuseResult<()> {
+        self.observe(req, "fsync", Some(&file_id.display()), None);
         match self.handling {
             HandlingMethod::Error(kind) => Err(PosixError::new(
                 kind,
@@ -321,20 +445,22 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
 
     fn fsyncdir(
         &self,
-        _req: &RequestInfo,
-        _file_id: TId,
+        req: &RequestInfo,
+        file_id: TId,
         _file_handle: BorrowedFileHandle,
         _datasync: bool,
     ) -> FuseResult<()> {
+        self.observe(req, "fsyncdir", Some(&file_id.display()), None);
         Ok(())
     }
 
     fn getattr(
         &self,
-        _req: &RequestInfo,
+        req: &RequestInfo,
         file_id: TId,
         file_handle: Option<BorrowedFileHandle>,
     ) -> FuseResult<FileAttribute> {
+        self.observe(req, "getattr", Some(&file_id.display()), None);
         match self.handling {
             HandlingMethod::Error(kind) => Err(PosixError::new(
                 kind,
@@ -356,14 +482,16 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
         }
     }
 
+    #[cfg(feature = "locking")]
     fn getlk(
         &self,
-        _req: &RequestInfo,
+        req: &RequestInfo,
         file_id: TId,
         file_handle: BorrowedFileHandle,
         lock_owner: u64,
         lock_info: LockInfo,
     ) -> FuseResult<LockInfo> {
+        self.observe(req, "getlk", Some(&file_id.display()), None);
         match self.handling {
             HandlingMethod::Error(kind) => Err(
                 PosixError::new(kind, if cfg!(debug_assertions) {
@@ -388,13 +516,15 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
         }
     }
 
+    #[cfg(feature = "xattr")]
     fn getxattr(
         &self,
-        _req: &RequestInfo,
+        req: &RequestInfo,
         file_id: TId,
         name: &OsStr,
         size: u32,
     ) -> FuseResult<Vec<u8>> {
+        self.observe(req, "getxattr", Some(&file_id.display()), Some(name));
         match self.handling {
             HandlingMethod::Error(kind) => Err(PosixError::new(
                 kind,
@@ -418,9 +548,10 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
         }
     }
 
+    #[cfg(feature = "ioctl")]
     fn ioctl(
         &self,
-        _req: &RequestInfo,
+        req: &RequestInfo,
         file_id: TId,
         file_handle: BorrowedFileHandle,
         flags: IOCtlFlags,
@@ -428,6 +559,7 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
         in_data: Vec<u8>,
         out_size: u32,
     ) -> FuseResult<(i32, Vec<u8>)> {
+        self.observe(req, "ioctl", Some(&file_id.display()), None);
         match self.handling {
             HandlingMethod::Error(kind) => Err(PosixError::new(
                 kind,
@@ -459,11 +591,12 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
 
     fn link(
         &self,
-        _req: &RequestInfo,
+        req: &RequestInfo,
         file_id: TId,
         newparent: TId,
         newname: &OsStr,
     ) -> FuseResult<TId::Metadata> {
+        self.observe(req, "link", Some(&file_id.display()), Some(newname));
         match self.handling {
             HandlingMethod::Error(kind) => Err(PosixError::new(
                 kind,
@@ -487,7 +620,9 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
         }
     }
 
-    fn listxattr(&self, _req: &RequestInfo, file_id: TId, size: u32) -> FuseResult<Vec<u8>> {
+    #[cfg(feature = "xattr")]
+    fn listxattr(&self, req: &RequestInfo, file_id: TId, size: u32) -> FuseResult<Vec<u8>> {
+        self.observe(req, "listxattr", Some(&file_id.display()), None);
         match self.handling {
             HandlingMethod::Error(kind) => Err(PosixError::new(
                 kind,
@@ -505,12 +640,14 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
         }
     }
 
-    fn lookup(
-        &self,
-        _req: &RequestInfo,
-        parent_id: TId,
-        name: &OsStr,
-    ) -> FuseResult<TId::Metadata> {
+    #[cfg(feature = "xattr")]
+    fn listxattr_names(&self, req: &RequestInfo, file_id: TId) -> FuseResult<Vec<OsString>> {
+        self.observe(req, "listxattr_names", Some(&file_id.display()), None);
+        // Makes the driver fall back to listxattr, which most handlers implement
+        Err(ErrorKind::FunctionNotImplemented.to_error("listxattr_names"))
+    }
+    fn lookup(&self, req: &RequestInfo, parent_id: TId, name: &OsStr) -> FuseResult<TId::Metadata> {
+        self.observe(req, "lookup", Some(&parent_id.display()), Some(name));
         match self.handling {
             HandlingMethod::Error(kind) => Err(PosixError::new(
                 kind,
@@ -530,11 +667,12 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
 
     fn lseek(
         &self,
-        _req: &RequestInfo,
+        req: &RequestInfo,
         file_id: TId,
         file_handle: BorrowedFileHandle,
         seek: SeekFrom,
     ) -> FuseResult<i64> {
+        self.observe(req, "lseek", Some(&file_id.display()), None);
         match self.handling {
             HandlingMethod::Error(kind) => Err(PosixError::new(
                 kind,
@@ -558,14 +696,28 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
         }
     }
 
+    fn lseek_raw(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        _file_handle: BorrowedFileHandle,
+        _offset: i64,
+        _whence: i32,
+    ) -> FuseResult<i64> {
+        self.observe(req, "lseek_raw", Some(&file_id.display()), None);
+        // ENOSYS lets the kernel treat the whole file as data, any other error would be reported to lseek
+        Err(ErrorKind::FunctionNotImplemented.to_error("lseek_raw"))
+    }
+
     fn mkdir(
         &self,
-        _req: &RequestInfo,
+        req: &RequestInfo,
         parent_id: TId,
         name: &OsStr,
         mode: u32,
         umask: u32,
     ) -> FuseResult<TId::Metadata> {
+        self.observe(req, "mkdir", Some(&parent_id.display()), Some(name));
         match self.handling {
             HandlingMethod::Error(kind) => Err(PosixError::new(
                 kind,
@@ -593,13 +745,14 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
 
     fn mknod(
         &self,
-        _req: &RequestInfo,
+        req: &RequestInfo,
         parent_id: TId,
         name: &OsStr,
         mode: u32,
         umask: u32,
         rdev: DeviceType,
     ) -> FuseResult<TId::Metadata> {
+        self.observe(req, "mknod", Some(&parent_id.display()), Some(name));
         match self.handling {
             HandlingMethod::Error(kind) => Err(
                 PosixError::new(kind, if cfg!(debug_assertions) {
@@ -628,10 +781,11 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
 
     fn open(
         &self,
-        _req: &RequestInfo,
+        req: &RequestInfo,
         file_id: TId,
         flags: OpenFlags,
     ) -> FuseResult<(OwnedFileHandle, FUSEOpenResponseFlags)> {
+        self.observe(req, "open", Some(&file_id.display()), None);
         match self.handling {
             HandlingMethod::Error(kind) => Err(PosixError::new(
                 kind,
@@ -651,10 +805,11 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
 
     fn opendir(
         &self,
-        _req: &RequestInfo,
-        _file_id: TId,
+        req: &RequestInfo,
+        file_id: TId,
         _flags: OpenFlags,
     ) -> FuseResult<(OwnedFileHandle, FUSEOpenResponseFlags)> {
+        self.observe(req, "opendir", Some(&file_id.display()), None);
         // Safe because in releasedir we don't use it
         Ok((
             unsafe { OwnedFileHandle::from_raw(0) },
@@ -662,16 +817,31 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
         ))
     }
 
+    fn poll(
+        &self,
+        req: &RequestInfo,
+        file_id: TId,
+        _file_handle: BorrowedFileHandle,
+        _poll_handle: PollHandle,
+        _events: u32,
+        _flags: u32,
+    ) -> FuseResult<u32> {
+        self.observe(req, "poll", Some(&file_id.display()), None);
+        // ENOSYS lets the kernel treat files as always ready, any other error would be reported to poll
+        Err(ErrorKind::FunctionNotImplemented.to_error("poll"))
+    }
+
     fn read(
         &self,
-        _req: &RequestInfo,
+        req: &RequestInfo,
         file_id: TId,
         file_handle: BorrowedFileHandle,
         seek: SeekFrom,
         size: u32,
-        flags: FUSEOpenFlags,
+        flags: OpenFlags,
         lock_owner: Option<u64>,
     ) -> FuseResult<Vec<u8>> {
+        self.observe(req, "read", Some(&file_id.display()), None);
         match self.handling {
             HandlingMethod::Error(kind) => Err(
                 PosixError::new(kind, if cfg!(debug_assertions) {
@@ -702,10 +872,11 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
 
     fn readdir(
         &self,
-        _req: &RequestInfo,
+        req: &RequestInfo,
         file_id: TId,
         file_handle: BorrowedFileHandle,
     ) -> FuseResult<Vec<(OsString, TId::MinimalMetadata)>> {
+        self.observe(req, "readdir", Some(&file_id.display()), None);
         match self.handling {
             HandlingMethod::Error(kind) => Err(PosixError::new(
                 kind,
@@ -729,10 +900,11 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
 
     fn readdirplus(
         &self,
-        _req: &RequestInfo,
+        req: &RequestInfo,
         file_id: TId,
         file_handle: BorrowedFileHandle,
     ) -> FuseResult<Vec<(OsString, TId::Metadata)>> {
+        self.observe(req, "readdirplus", Some(&file_id.display()), None);
         match self.handling {
             HandlingMethod::Error(kind) => Err(PosixError::new(
                 kind,
@@ -754,7 +926,8 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
         }
     }
 
-    fn readlink(&self, _req: &RequestInfo, file_id: TId) -> FuseResult<Vec<u8>> {
+    fn readlink(&self, req: &RequestInfo, file_id: TId) -> FuseResult<Vec<u8>> {
+        self.observe(req, "readlink", Some(&file_id.display()), None);
         match self.handling {
             HandlingMethod::Error(kind) => Err(PosixError::new(
                 kind,
@@ -772,13 +945,14 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
 
     fn release(
         &self,
-        _req: &RequestInfo,
+        req: &RequestInfo,
         file_id: TId,
         file_handle: OwnedFileHandle,
         flags: OpenFlags,
         lock_owner: Option<u64>,
         flush: bool,
     ) -> FuseResult<()> {
+        self.observe(req, "release", Some(&file_id.display()), None);
         match self.handling {
             HandlingMethod::Error(kind) => Err(
                 PosixError::new(kind, if cfg!(debug_assertions) {
@@ -807,15 +981,18 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
 
     fn releasedir(
         &self,
-        _req: &RequestInfo,
-        _file_id: TId,
+        req: &RequestInfo,
+        file_id: TId,
         _file_handle: OwnedFileHandle,
         _flags: OpenFlags,
     ) -> FuseResult<()> {
+        self.observe(req, "releasedir", Some(&file_id.display()), None);
         Ok(())
     }
 
-    fn removexattr(&self, _req: &RequestInfo, file_id: TId, name: &OsStr) -> FuseResult<()> {
+    #[cfg(feature = "xattr")]
+    fn removexattr(&self, req: &RequestInfo, file_id: TId, name: &OsStr) -> FuseResult<()> {
+        self.observe(req, "removexattr", Some(&file_id.display()), Some(name));
         match self.handling {
             HandlingMethod::Error(kind) => Err(PosixError::new(
                 kind,
@@ -839,13 +1016,14 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
 
     fn rename(
         &self,
-        _req: &RequestInfo,
+        req: &RequestInfo,
         parent_id: TId,
         name: &OsStr,
         newparent: TId,
         newname: &OsStr,
         flags: RenameFlags,
     ) -> FuseResult<()> {
+        self.observe(req, "rename", Some(&parent_id.display()), Some(name));
         match self.handling {
             HandlingMethod::Error(kind) => Err(
                 PosixError::new(kind, if cfg!(debug_assertions) {
@@ -872,7 +1050,8 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
         }
     }
 
-    fn rmdir(&self, _req: &RequestInfo, parent_id: TId, name: &OsStr) -> FuseResult<()> {
+    fn rmdir(&self, req: &RequestInfo, parent_id: TId, name: &OsStr) -> FuseResult<()> {
+        self.observe(req, "rmdir", Some(&parent_id.display()), Some(name));
         match self.handling {
             HandlingMethod::Error(kind) => Err(PosixError::new(
                 kind,
@@ -896,10 +1075,11 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
 
     fn setattr(
         &self,
-        _req: &RequestInfo,
+        req: &RequestInfo,
         file_id: TId,
         attrs: SetAttrRequest,
     ) -> FuseResult<FileAttribute> {
+        self.observe(req, "setattr", Some(&file_id.display()), None);
         match self.handling {
             HandlingMethod::Error(kind) => Err(PosixError::new(
                 kind,
@@ -921,15 +1101,17 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
         }
     }
 
+    #[cfg(feature = "locking")]
     fn setlk(
         &self,
-        _req: &RequestInfo,
+        req: &RequestInfo,
         file_id: TId,
         file_handle: BorrowedFileHandle,
         lock_owner: u64,
         lock_info: LockInfo,
         sleep: bool,
     ) -> FuseResult<()> {
+        self.observe(req, "setlk", Some(&file_id.display()), None);
         match self.handling {
             HandlingMethod::Error(kind) => Err(
                 PosixError::new(kind, if cfg!(debug_assertions) {
@@ -956,15 +1138,17 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
         }
     }
 
+    #[cfg(feature = "xattr")]
     fn setxattr(
         &self,
-        _req: &RequestInfo,
+        req: &RequestInfo,
         file_id: TId,
         name: &OsStr,
         _value: Vec<u8>,
         flags: FUSESetXAttrFlags,
         position: u32,
     ) -> FuseResult<()> {
+        self.observe(req, "setxattr", Some(&file_id.display()), Some(name));
         match self.handling {
             HandlingMethod::Error(kind) => Err(PosixError::new(
                 kind,
@@ -990,17 +1174,19 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
         }
     }
 
-    fn statfs(&self, _req: &RequestInfo, _file_id: TId) -> FuseResult<StatFs> {
+    fn statfs(&self, req: &RequestInfo, file_id: TId) -> FuseResult<StatFs> {
+        self.observe(req, "statfs", Some(&file_id.display()), None);
         Ok(StatFs::default())
     }
 
     fn symlink(
         &self,
-        _req: &RequestInfo,
+        req: &RequestInfo,
         parent_id: TId,
         link_name: &OsStr,
         target: &Path,
     ) -> FuseResult<TId::Metadata> {
+        self.observe(req, "symlink", Some(&parent_id.display()), Some(link_name));
         match self.handling {
             HandlingMethod::Error(kind) => Err(PosixError::new(
                 kind,
@@ -1024,7 +1210,8 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
         }
     }
 
-    fn unlink(&self, _req: &RequestInfo, parent_id: TId, name: &OsStr) -> FuseResult<()> {
+    fn unlink(&self, req: &RequestInfo, parent_id: TId, name: &OsStr) -> FuseResult<()> {
+        self.observe(req, "unlink", Some(&parent_id.display()), Some(name));
         match self.handling {
             HandlingMethod::Error(kind) => Err(PosixError::new(
                 kind,
@@ -1048,7 +1235,7 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
 
     fn write(
         &self,
-        _req: &RequestInfo,
+        req: &RequestInfo,
         file_id: TId,
         file_handle: BorrowedFileHandle,
         seek: SeekFrom,
@@ -1057,6 +1244,7 @@ impl<TId: FileIdType> FuseHandler<TId> for DefaultFuseHandler {
         flags: OpenFlags,
         lock_owner: Option<u64>,
     ) -> FuseResult<u32> {
+        self.observe(req, "write", Some(&file_id.display()), None);
         match self.handling {
             HandlingMethod::Error(kind) => Err(
                 PosixError::new(kind, if cfg!(debug_assertions) {
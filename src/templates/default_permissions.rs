@@ -0,0 +1,128 @@
+use crate::prelude::*;
+
+/// Wraps a handler to implement `access` with the POSIX permission checks.
+///
+/// The requested `AccessMask` is evaluated against the permission bits, owner and group returned
+/// by the `getattr` of the inner handler, for the uid and gid of the request. Access is refused
+/// with `ErrorKind::PermissionDeniedAccess` (`EACCES`), like the kernel would. Other operations are
+/// delegated to the inner handler.
+///
+/// This matters when mounting with `MountOption::AllowOther`, for handlers whose `access` always succeeds.
+/// The supplementary groups of the caller are not known to FUSE, only its primary group is checked.
+/// To also check permissions on `open` and the other operations, mount with `MountOption::DefaultPermissions`
+/// instead, the kernel then never calls `access`.
+///
+/// ```text
+/// mount(DefaultPermissions::new(my_handler), mountpoint, &[MountOption::AllowOther])
+/// ```
+pub struct DefaultPermissions<H> {
+    inner: H,
+}
+
+impl<H> DefaultPermissions<H> {
+    pub fn new(inner: H) -> Self {
+        Self { inner }
+    }
+
+    pub fn inner(&self) -> &H {
+        &self.inner
+    }
+}
+
+/// Checks the permission bits of `attr` for the caller `uid` and `gid`
+///
+/// Root is granted read and write access, and execute access if any execute bit is set or on directories.
+fn check_access(attr: &FileAttribute, uid: u32, gid: u32, mask: AccessMask) -> FuseResult<()> {
+    let requested = (mask & (AccessMask::CAN_READ | AccessMask::CAN_WRITE | AccessMask::CAN_EXEC))
+        .bits() as u16;
+    let granted = if uid == 0 {
+        let any_exec = attr.kind == FileKind::Directory || attr.perm & 0o111 != 0;
+        0o6 | if any_exec { 0o1 } else { 0 }
+    } else if uid == attr.uid {
+        (attr.perm >> 6) & 0o7
+    } else if gid == attr.gid {
+        (attr.perm >> 3) & 0o7
+    } else {
+        attr.perm & 0o7
+    };
+    if requested & !granted != 0 {
+        return Err(ErrorKind::PermissionDeniedAccess.to_error(format!(
+            "access {:?} denied to uid {} (mode {:o}, owner {}:{})",
+            mask, uid, attr.perm, attr.uid, attr.gid
+        )));
+    }
+    Ok(())
+}
+
+impl<T: FileIdType, H: FuseHandler<T>> FuseHandler<T> for DefaultPermissions<H> {
+    fn get_inner(&self) -> &dyn FuseHandler<T> {
+        &self.inner
+    }
+
+    fn access(&self, req: &RequestInfo, file_id: T, mask: AccessMask) -> FuseResult<()> {
+        // The existence check (F_OK) is done by the lookup preceding access
+        let attr = self.inner.getattr(req, file_id, None)?;
+        check_access(&attr, req.uid, req.gid, mask)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::{mem_fs::MemFs, DefaultFuseHandler};
+    use std::path::PathBuf;
+
+    fn request(uid: u32, gid: u32) -> RequestInfo {
+        RequestInfo {
+            uid,
+            gid,
+            ..RequestInfo::default()
+        }
+    }
+
+    #[test]
+    fn test_default_permissions() {
+        let inner = MemFs::<PathBuf>::new(DefaultFuseHandler::new());
+        inner.insert_file("file.txt", "content").unwrap();
+        inner.create_dir_all("dir").unwrap();
+        let fs = DefaultPermissions::new(inner);
+        let root = request(0, 0);
+        for (path, mode) in [("file.txt", 0o640), ("dir", 0o700)] {
+            fs.setattr(
+                &root,
+                PathBuf::from(path),
+                SetAttrRequest::new().mode(mode).uid(1000).gid(100),
+            )
+            .unwrap();
+        }
+        let read_write = AccessMask::CAN_READ | AccessMask::CAN_WRITE;
+        let file = || PathBuf::from("file.txt");
+        let dir = || PathBuf::from("dir");
+
+        // Owner, group and others
+        fs.access(&request(1000, 100), file(), read_write).unwrap();
+        fs.access(&request(1001, 100), file(), AccessMask::CAN_READ)
+            .unwrap();
+        let error = fs
+            .access(&request(1001, 100), file(), read_write)
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::PermissionDeniedAccess);
+        assert!(fs
+            .access(&request(1001, 101), file(), AccessMask::CAN_READ)
+            .is_err());
+        fs.access(&request(1001, 101), file(), AccessMask::EXISTS)
+            .unwrap();
+
+        // Root bypasses the permission bits, except execution of a file without any execute bit
+        fs.access(&root, file(), read_write).unwrap();
+        assert!(fs.access(&root, file(), AccessMask::CAN_EXEC).is_err());
+        fs.access(&root, dir(), AccessMask::CAN_EXEC).unwrap();
+
+        // Searching a directory requires its execute bit
+        fs.access(&request(1000, 100), dir(), AccessMask::CAN_EXEC)
+            .unwrap();
+        assert!(fs
+            .access(&request(1001, 100), dir(), AccessMask::CAN_EXEC)
+            .is_err());
+    }
+}
@@ -0,0 +1,676 @@
+use std::ffi::{OsStr, OsString};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::prelude::*;
+
+/// Operation of a `FuseHandler` whose error is being remapped, see [`ErrorMapHandler`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    Init,
+    PreUnmount,
+    DropCaches,
+    Access,
+    Bmap,
+    CopyFileRange,
+    Create,
+    Fallocate,
+    Flush,
+    Fsync,
+    Fsyncdir,
+    Getattr,
+    Getlk,
+    Getxattr,
+    Ioctl,
+    Link,
+    Listxattr,
+    ListxattrNames,
+    Lookup,
+    Lseek,
+    LseekRaw,
+    Mkdir,
+    Mknod,
+    Open,
+    Opendir,
+    Poll,
+    Read,
+    ReadInto,
+    Readdir,
+    ReaddirPartial,
+    ReaddirStream,
+    Readdirplus,
+    Readlink,
+    Release,
+    Releasedir,
+    Removexattr,
+    Rename,
+    Rmdir,
+    Setattr,
+    Setlk,
+    Setxattr,
+    Statfs,
+    Symlink,
+    Write,
+    Unlink,
+}
+
+/// Wraps a handler to remap the errors it returns before they reach the driver.
+///
+/// `map` receives the failed operation and its error, and returns the error replied to the kernel.
+/// This allows uniform policies, like hiding permission errors as not found to avoid leaking the
+/// existence of files, or turning `ENOSYS` into `ENOTSUP` for clients which only handle the latter.
+/// Errors yielded by the iterator of `readdir_stream` are remapped as well.
+///
+/// ```text
+/// let fs = ErrorMapHandler::new(my_handler, |_operation, error| match error.kind() {
+///     ErrorKind::PermissionDeniedAccess => ErrorKind::FileNotFound.to_error(error.msg),
+///     _ => error,
+/// });
+/// ```
+pub struct ErrorMapHandler<H, F> {
+    inner: H,
+    map: Arc<F>,
+}
+
+impl<H, F> ErrorMapHandler<H, F>
+where
+    F: Fn(Operation, PosixError) -> PosixError + Send + Sync + 'static,
+{
+    pub fn new(inner: H, map: F) -> Self {
+        Self {
+            inner,
+            map: Arc::new(map),
+        }
+    }
+
+    pub fn inner(&self) -> &H {
+        &self.inner
+    }
+}
+
+impl<T, H, F> FuseHandler<T> for ErrorMapHandler<H, F>
+where
+    T: FileIdType,
+    H: FuseHandler<T>,
+    F: Fn(Operation, PosixError) -> PosixError + Send + Sync + 'static,
+{
+    fn get_inner(&self) -> &dyn FuseHandler<T> {
+        &self.inner
+    }
+
+    fn init(&self, req: &RequestInfo, config: &mut KernelConfig) -> FuseResult<()> {
+        self.inner
+            .init(req, config)
+            .map_err(|error| (self.map)(Operation::Init, error))
+    }
+
+    fn pre_unmount(&self) -> FuseResult<()> {
+        self.inner
+            .pre_unmount()
+            .map_err(|error| (self.map)(Operation::PreUnmount, error))
+    }
+
+    fn drop_caches(&self, req: &RequestInfo) -> FuseResult<()> {
+        self.inner
+            .drop_caches(req)
+            .map_err(|error| (self.map)(Operation::DropCaches, error))
+    }
+
+    fn access(&self, req: &RequestInfo, file_id: T, mask: AccessMask) -> FuseResult<()> {
+        self.inner
+            .access(req, file_id, mask)
+            .map_err(|error| (self.map)(Operation::Access, error))
+    }
+
+    fn bmap(&self, req: &RequestInfo, file_id: T, blocksize: u32, idx: u64) -> FuseResult<u64> {
+        self.inner
+            .bmap(req, file_id, blocksize, idx)
+            .map_err(|error| (self.map)(Operation::Bmap, error))
+    }
+
+    fn copy_file_range(
+        &self,
+        req: &RequestInfo,
+        file_in: T,
+        file_handle_in: BorrowedFileHandle,
+        offset_in: i64,
+        file_out: T,
+        file_handle_out: BorrowedFileHandle,
+        offset_out: i64,
+        len: u64,
+        flags: u32,
+    ) -> FuseResult<u32> {
+        self.inner
+            .copy_file_range(
+                req,
+                file_in,
+                file_handle_in,
+                offset_in,
+                file_out,
+                file_handle_out,
+                offset_out,
+                len,
+                flags,
+            )
+            .map_err(|error| (self.map)(Operation::CopyFileRange, error))
+    }
+
+    fn create(
+        &self,
+        req: &RequestInfo,
+        parent_id: T,
+        name: &OsStr,
+        mode: u32,
+        umask: u32,
+        flags: OpenFlags,
+    ) -> FuseResult<(OwnedFileHandle, T::Metadata, FUSEOpenResponseFlags)> {
+        self.inner
+            .create(req, parent_id, name, mode, umask, flags)
+            .map_err(|error| (self.map)(Operation::Create, error))
+    }
+
+    fn fallocate(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        offset: i64,
+        length: i64,
+        mode: FallocateFlags,
+    ) -> FuseResult<()> {
+        self.inner
+            .fallocate(req, file_id, file_handle, offset, length, mode)
+            .map_err(|error| (self.map)(Operation::Fallocate, error))
+    }
+
+    fn flush(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        lock_owner: u64,
+    ) -> FuseResult<()> {
+        self.inner
+            .flush(req, file_id, file_handle, lock_owner)
+            .map_err(|error| (self.map)(Operation::Flush, error))
+    }
+
+    fn fsync(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        datasync: bool,
+    ) -> FuseResult<()> {
+        self.inner
+            .fsync(req, file_id, file_handle, datasync)
+            .map_err(|error| (self.map)(Operation::Fsync, error))
+    }
+
+    fn fsyncdir(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        datasync: bool,
+    ) -> FuseResult<()> {
+        self.inner
+            .fsyncdir(req, file_id, file_handle, datasync)
+            .map_err(|error| (self.map)(Operation::Fsyncdir, error))
+    }
+
+    fn getattr(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: Option<BorrowedFileHandle>,
+    ) -> FuseResult<FileAttribute> {
+        self.inner
+            .getattr(req, file_id, file_handle)
+            .map_err(|error| (self.map)(Operation::Getattr, error))
+    }
+
+    #[cfg(feature = "locking")]
+    fn getlk(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        lock_owner: u64,
+        lock_info: LockInfo,
+    ) -> FuseResult<LockInfo> {
+        self.inner
+            .getlk(req, file_id, file_handle, lock_owner, lock_info)
+            .map_err(|error| (self.map)(Operation::Getlk, error))
+    }
+
+    #[cfg(feature = "xattr")]
+    fn getxattr(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        name: &OsStr,
+        size: u32,
+    ) -> FuseResult<Vec<u8>> {
+        self.inner
+            .getxattr(req, file_id, name, size)
+            .map_err(|error| (self.map)(Operation::Getxattr, error))
+    }
+
+    #[cfg(feature = "ioctl")]
+    fn ioctl(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        flags: IOCtlFlags,
+        cmd: u32,
+        in_data: Vec<u8>,
+        out_size: u32,
+    ) -> FuseResult<(i32, Vec<u8>)> {
+        self.inner
+            .ioctl(req, file_id, file_handle, flags, cmd, in_data, out_size)
+            .map_err(|error| (self.map)(Operation::Ioctl, error))
+    }
+
+    fn link(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        newparent: T,
+        newname: &OsStr,
+    ) -> FuseResult<T::Metadata> {
+        self.inner
+            .link(req, file_id, newparent, newname)
+            .map_err(|error| (self.map)(Operation::Link, error))
+    }
+
+    #[cfg(feature = "xattr")]
+    fn listxattr(&self, req: &RequestInfo, file_id: T, size: u32) -> FuseResult<Vec<u8>> {
+        self.inner
+            .listxattr(req, file_id, size)
+            .map_err(|error| (self.map)(Operation::Listxattr, error))
+    }
+
+    #[cfg(feature = "xattr")]
+    fn listxattr_names(&self, req: &RequestInfo, file_id: T) -> FuseResult<Vec<OsString>> {
+        self.inner
+            .listxattr_names(req, file_id)
+            .map_err(|error| (self.map)(Operation::ListxattrNames, error))
+    }
+
+    fn lookup(&self, req: &RequestInfo, parent_id: T, name: &OsStr) -> FuseResult<T::Metadata> {
+        self.inner
+            .lookup(req, parent_id, name)
+            .map_err(|error| (self.map)(Operation::Lookup, error))
+    }
+
+    fn lseek(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+    ) -> FuseResult<i64> {
+        self.inner
+            .lseek(req, file_id, file_handle, seek)
+            .map_err(|error| (self.map)(Operation::Lseek, error))
+    }
+
+    fn lseek_raw(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        offset: i64,
+        whence: i32,
+    ) -> FuseResult<i64> {
+        self.inner
+            .lseek_raw(req, file_id, file_handle, offset, whence)
+            .map_err(|error| (self.map)(Operation::LseekRaw, error))
+    }
+
+    fn mkdir(
+        &self,
+        req: &RequestInfo,
+        parent_id: T,
+        name: &OsStr,
+        mode: u32,
+        umask: u32,
+    ) -> FuseResult<T::Metadata> {
+        self.inner
+            .mkdir(req, parent_id, name, mode, umask)
+            .map_err(|error| (self.map)(Operation::Mkdir, error))
+    }
+
+    fn mknod(
+        &self,
+        req: &RequestInfo,
+        parent_id: T,
+        name: &OsStr,
+        mode: u32,
+        umask: u32,
+        rdev: DeviceType,
+    ) -> FuseResult<T::Metadata> {
+        self.inner
+            .mknod(req, parent_id, name, mode, umask, rdev)
+            .map_err(|error| (self.map)(Operation::Mknod, error))
+    }
+
+    fn open(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        flags: OpenFlags,
+    ) -> FuseResult<(OwnedFileHandle, FUSEOpenResponseFlags)> {
+        self.inner
+            .open(req, file_id, flags)
+            .map_err(|error| (self.map)(Operation::Open, error))
+    }
+
+    fn opendir(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        flags: OpenFlags,
+    ) -> FuseResult<(OwnedFileHandle, FUSEOpenResponseFlags)> {
+        self.inner
+            .opendir(req, file_id, flags)
+            .map_err(|error| (self.map)(Operation::Opendir, error))
+    }
+
+    fn poll(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        poll_handle: PollHandle,
+        events: u32,
+        flags: u32,
+    ) -> FuseResult<u32> {
+        self.inner
+            .poll(req, file_id, file_handle, poll_handle, events, flags)
+            .map_err(|error| (self.map)(Operation::Poll, error))
+    }
+
+    fn read(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        size: u32,
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+    ) -> FuseResult<Vec<u8>> {
+        self.inner
+            .read(req, file_id, file_handle, seek, size, flags, lock_owner)
+            .map_err(|error| (self.map)(Operation::Read, error))
+    }
+
+    fn read_into(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        buf: &mut [u8],
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+    ) -> FuseResult<usize> {
+        self.inner
+            .read_into(req, file_id, file_handle, seek, buf, flags, lock_owner)
+            .map_err(|error| (self.map)(Operation::ReadInto, error))
+    }
+
+    fn readdir(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+    ) -> FuseResult<Vec<(OsString, T::MinimalMetadata)>> {
+        self.inner
+            .readdir(req, file_id, file_handle)
+            .map_err(|error| (self.map)(Operation::Readdir, error))
+    }
+
+    fn readdir_partial(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+    ) -> (Vec<(OsString, T::MinimalMetadata)>, Option<PosixError>) {
+        let (entries, error) = self.inner.readdir_partial(req, file_id, file_handle);
+        (
+            entries,
+            error.map(|error| (self.map)(Operation::ReaddirPartial, error)),
+        )
+    }
+
+    fn readdir_stream(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+    ) -> FuseResult<DirEntryStream<T>> {
+        let map = self.map.clone();
+        let entries = self
+            .inner
+            .readdir_stream(req, file_id, file_handle)
+            .map_err(|error| map(Operation::ReaddirStream, error))?;
+        Ok(Box::new(entries.map(move |entry| {
+            entry.map_err(|error| map(Operation::ReaddirStream, error))
+        })))
+    }
+
+    fn readdirplus(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+    ) -> FuseResult<Vec<(OsString, T::Metadata)>> {
+        self.inner
+            .readdirplus(req, file_id, file_handle)
+            .map_err(|error| (self.map)(Operation::Readdirplus, error))
+    }
+
+    fn readlink(&self, req: &RequestInfo, file_id: T) -> FuseResult<Vec<u8>> {
+        self.inner
+            .readlink(req, file_id)
+            .map_err(|error| (self.map)(Operation::Readlink, error))
+    }
+
+    fn release(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: OwnedFileHandle,
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+        flush: bool,
+    ) -> FuseResult<()> {
+        self.inner
+            .release(req, file_id, file_handle, flags, lock_owner, flush)
+            .map_err(|error| (self.map)(Operation::Release, error))
+    }
+
+    fn releasedir(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: OwnedFileHandle,
+        flags: OpenFlags,
+    ) -> FuseResult<()> {
+        self.inner
+            .releasedir(req, file_id, file_handle, flags)
+            .map_err(|error| (self.map)(Operation::Releasedir, error))
+    }
+
+    #[cfg(feature = "xattr")]
+    fn removexattr(&self, req: &RequestInfo, file_id: T, name: &OsStr) -> FuseResult<()> {
+        self.inner
+            .removexattr(req, file_id, name)
+            .map_err(|error| (self.map)(Operation::Removexattr, error))
+    }
+
+    fn rename(
+        &self,
+        req: &RequestInfo,
+        parent_id: T,
+        name: &OsStr,
+        newparent: T,
+        newname: &OsStr,
+        flags: RenameFlags,
+    ) -> FuseResult<()> {
+        self.inner
+            .rename(req, parent_id, name, newparent, newname, flags)
+            .map_err(|error| (self.map)(Operation::Rename, error))
+    }
+
+    fn rmdir(&self, req: &RequestInfo, parent_id: T, name: &OsStr) -> FuseResult<()> {
+        self.inner
+            .rmdir(req, parent_id, name)
+            .map_err(|error| (self.map)(Operation::Rmdir, error))
+    }
+
+    fn setattr(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        attrs: SetAttrRequest,
+    ) -> FuseResult<FileAttribute> {
+        self.inner
+            .setattr(req, file_id, attrs)
+            .map_err(|error| (self.map)(Operation::Setattr, error))
+    }
+
+    #[cfg(feature = "locking")]
+    fn setlk(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        lock_owner: u64,
+        lock_info: LockInfo,
+        sleep: bool,
+    ) -> FuseResult<()> {
+        self.inner
+            .setlk(req, file_id, file_handle, lock_owner, lock_info, sleep)
+            .map_err(|error| (self.map)(Operation::Setlk, error))
+    }
+
+    #[cfg(feature = "xattr")]
+    fn setxattr(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        name: &OsStr,
+        value: Vec<u8>,
+        flags: FUSESetXAttrFlags,
+        position: u32,
+    ) -> FuseResult<()> {
+        self.inner
+            .setxattr(req, file_id, name, value, flags, position)
+            .map_err(|error| (self.map)(Operation::Setxattr, error))
+    }
+
+    fn statfs(&self, req: &RequestInfo, file_id: T) -> FuseResult<StatFs> {
+        self.inner
+            .statfs(req, file_id)
+            .map_err(|error| (self.map)(Operation::Statfs, error))
+    }
+
+    fn symlink(
+        &self,
+        req: &RequestInfo,
+        parent_id: T,
+        link_name: &OsStr,
+        target: &Path,
+    ) -> FuseResult<T::Metadata> {
+        self.inner
+            .symlink(req, parent_id, link_name, target)
+            .map_err(|error| (self.map)(Operation::Symlink, error))
+    }
+
+    fn write(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        data: Vec<u8>,
+        write_flags: FUSEWriteFlags,
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+    ) -> FuseResult<u32> {
+        self.inner
+            .write(
+                req,
+                file_id,
+                file_handle,
+                seek,
+                data,
+                write_flags,
+                flags,
+                lock_owner,
+            )
+            .map_err(|error| (self.map)(Operation::Write, error))
+    }
+
+    fn unlink(&self, req: &RequestInfo, parent_id: T, name: &OsStr) -> FuseResult<()> {
+        self.inner
+            .unlink(req, parent_id, name)
+            .map_err(|error| (self.map)(Operation::Unlink, error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::DefaultFuseHandler;
+    use std::path::PathBuf;
+
+    struct ForbiddenFs {
+        inner: DefaultFuseHandler,
+    }
+
+    impl FuseHandler<PathBuf> for ForbiddenFs {
+        fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+            &self.inner
+        }
+
+        fn lookup(
+            &self,
+            _req: &RequestInfo,
+            _parent_id: PathBuf,
+            name: &OsStr,
+        ) -> FuseResult<FileAttribute> {
+            Err(ErrorKind::PermissionDeniedAccess.to_error(format!("{:?}: forbidden", name)))
+        }
+    }
+
+    #[test]
+    fn test_permission_errors_are_hidden() {
+        let fs = ErrorMapHandler::new(
+            ForbiddenFs {
+                inner: DefaultFuseHandler::new(),
+            },
+            |operation, error| match error.kind() {
+                ErrorKind::PermissionDeniedAccess => {
+                    assert_eq!(operation, Operation::Lookup);
+                    ErrorKind::FileNotFound.to_error(error.msg)
+                }
+                _ => error,
+            },
+        );
+        let req = RequestInfo::default();
+
+        let error = fs
+            .lookup(&req, PathBuf::new(), OsStr::new("secret"))
+            .unwrap_err();
+        assert_eq!(error.raw_error(), libc::ENOENT);
+        // Other errors are kept
+        let error = fs.readlink(&req, PathBuf::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::FunctionNotImplemented);
+    }
+}
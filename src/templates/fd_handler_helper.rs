@@ -17,24 +17,26 @@ Both helpers assume that file handles represent file descriptors on the filesyst
 
 Implements the following `FuseHandler<T>` methods:
 
-- `read`: Reads data from a file using the file descriptor.
+- `read`, `read_into`: Reads data from a file using the file descriptor.
 - `write`: Writes data to a file using the file descriptor.
 - `flush`: Flushes the file associated with the file descriptor.
 - `release`: Releases (closes) the file descriptor.
 - `fsync`: Synchronizes the file's in-core state with storage device.
 - `fallocate`: Manipulates the allocated disk space for the file.
 - `lseek`: Repositions the file offset of the file descriptor.
+- `lseek_raw`: Seeks for data or holes (`SEEK_DATA`, `SEEK_HOLE`) in the file descriptor.
 - `copy_file_range`: Copies a range of data from one file to another.
 
 ### `FdHandlerHelperReadOnly<T>`
 
 Implements a subset of `FuseHandler<T>` methods for read-only operations:
 
-- `read`: Reads data from a file using the file descriptor.
+- `read`, `read_into`: Reads data from a file using the file descriptor.
 - `flush`: Flushes the file associated with the file descriptor.
 - `release`: Releases (closes) the file descriptor.
 - `fsync`: Synchronizes the file's in-core state with storage device.
 - `lseek`: Repositions the file offset of the file descriptor.
+- `lseek_raw`: Seeks for data or holes (`SEEK_DATA`, `SEEK_HOLE`) in the file descriptor.
 
 ## Usage
 
@@ -104,6 +106,17 @@ macro_rules! fd_handler_readonly_methods {
             unix_fs::lseek(file_handle.as_borrowed_fd(), seek)
         }
 
+        fn lseek_raw(
+            &self,
+            _req: &RequestInfo,
+            _file_id: TId,
+            file_handle: BorrowedFileHandle,
+            offset: i64,
+            whence: i32,
+        ) -> FuseResult<i64> {
+            unix_fs::lseek_raw(file_handle.as_borrowed_fd(), offset, whence)
+        }
+
         fn read(
             &self,
             _req: &RequestInfo,
@@ -111,12 +124,25 @@ macro_rules! fd_handler_readonly_methods {
             file_handle: BorrowedFileHandle,
             seek: SeekFrom,
             size: u32,
-            _flags: FUSEOpenFlags,
+            _flags: OpenFlags,
             _lock_owner: Option<u64>,
         ) -> FuseResult<Vec<u8>> {
             unix_fs::read(file_handle.as_borrowed_fd(), seek, size as usize)
         }
 
+        fn read_into(
+            &self,
+            _req: &RequestInfo,
+            _file_id: TId,
+            file_handle: BorrowedFileHandle,
+            seek: SeekFrom,
+            buf: &mut [u8],
+            _flags: OpenFlags,
+            _lock_owner: Option<u64>,
+        ) -> FuseResult<usize> {
+            unix_fs::read_into(file_handle.as_borrowed_fd(), seek, buf)
+        }
+
         fn release(
             &self,
             _req: &RequestInfo,
@@ -0,0 +1,350 @@
+//! Read-only filesystem of generated files, see [`GenFs`].
+//!
+//! The tree is described by the paths of the registered files, kept sorted so that the entries below a
+//! directory are contiguous.
+
+use std::collections::BTreeMap;
+use std::ffi::{OsStr, OsString};
+use std::ops::Bound;
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::prelude::*;
+
+type Generator = Box<dyn Fn() -> Vec<u8> + Send + Sync>;
+
+/// A read-only filesystem of generated files, like `/proc`.
+///
+/// Each file is registered with a closure returning its content, which is called on every `getattr` and
+/// `read` of the file. Directories are implied by the paths of the files they contain.
+/// `lookup`, `getattr`, `open`, `read` and `readdir` are implemented, other operations are delegated to the
+/// inner handler.
+///
+/// Files are opened in direct I/O mode, so that reads always reach the closure instead of the page cache,
+/// and the size reported by `getattr` is the length of the content generated at that time. A file read in
+/// several chunks calls the closure for each chunk: the closure should return the same content for the
+/// duration of a read to avoid torn results.
+///
+/// ```rust, no_run
+/// use easy_fuser::prelude::*;
+/// use easy_fuser::templates::{DefaultFuseHandler, GenFs};
+/// use std::time::SystemTime;
+///
+/// let mut fs = GenFs::new(DefaultFuseHandler::new());
+/// fs.add("/status", || "running\n");
+/// fs.add("/clock/now", || format!("{:?}\n", SystemTime::now()));
+/// ```
+pub struct GenFs {
+    inner: Box<dyn FuseHandler<PathBuf>>,
+    files: BTreeMap<PathBuf, Generator>,
+    attr: FileAttribute,
+}
+
+/// Returns `path` relative to the root, as the file ids of the `PathBuf` resolver
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name),
+            _ => None,
+        })
+        .collect()
+}
+
+fn not_found() -> PosixError {
+    ErrorKind::FileNotFound.to_error("No such file or directory")
+}
+
+impl GenFs {
+    /// Creates an empty filesystem, delegating the operations it doesn't implement to `inner`
+    pub fn new<U: FuseHandler<PathBuf>>(inner: U) -> Self {
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        let now = SystemTime::now();
+        Self {
+            inner: Box::new(inner),
+            files: BTreeMap::new(),
+            attr: FileAttribute {
+                size: 0,
+                blocks: 0,
+                atime: now,
+                mtime: now,
+                ctime: now,
+                crtime: now,
+                kind: FileKind::Directory,
+                perm: 0o555,
+                nlink: 2,
+                uid,
+                gid,
+                rdev: 0,
+                blksize: 4096,
+                flags: 0,
+                ttl: None,
+                generation: None,
+            },
+        }
+    }
+
+    /// Registers the file at `path`, whose content is returned by `generator`
+    ///
+    /// The path is relative to the root, a leading `/` is ignored. Registering a path again replaces
+    /// its generator.
+    ///
+    /// # Panics
+    /// If `path` is the root, is below a registered file, or is the parent of a registered file.
+    pub fn add<P, F, C>(&mut self, path: P, generator: F) -> &mut Self
+    where
+        P: AsRef<Path>,
+        F: Fn() -> C + Send + Sync + 'static,
+        C: Into<Vec<u8>>,
+    {
+        let path = normalize(path.as_ref());
+        if path.as_os_str().is_empty() {
+            panic!("GenFs: the root is a directory and can't be a generated file");
+        }
+        if let Some(file) = path
+            .ancestors()
+            .skip(1)
+            .find(|a| self.files.contains_key(*a))
+        {
+            panic!(
+                "GenFs: {:?} can't be added below the generated file {:?}",
+                path, file
+            );
+        }
+        if self.is_dir(&path) {
+            panic!(
+                "GenFs: {:?} is already a directory of generated files",
+                path
+            );
+        }
+        self.files
+            .insert(path, Box::new(move || generator().into()));
+        self
+    }
+
+    /// Whether `path` is the root or a parent of a registered file
+    fn is_dir(&self, path: &Path) -> bool {
+        // Paths are ordered by components, the files below `path` directly follow it
+        path.as_os_str().is_empty()
+            || self
+                .files
+                .range::<Path, _>((Bound::Excluded(path), Bound::Unbounded))
+                .next()
+                .is_some_and(|(file, _)| file.starts_with(path))
+    }
+
+    fn attr(&self, path: &Path) -> FuseResult<FileAttribute> {
+        if let Some(generator) = self.files.get(path) {
+            let size = generator().len() as u64;
+            return Ok(FileAttribute {
+                size,
+                blocks: size.div_ceil(512),
+                kind: FileKind::RegularFile,
+                perm: 0o444,
+                nlink: 1,
+                ..self.attr.clone()
+            });
+        }
+        if self.is_dir(path) {
+            return Ok(self.attr.clone());
+        }
+        Err(not_found())
+    }
+}
+
+impl FuseHandler<PathBuf> for GenFs {
+    fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+        self.inner.as_ref()
+    }
+
+    fn getattr(
+        &self,
+        _req: &RequestInfo,
+        file_id: PathBuf,
+        _file_handle: Option<BorrowedFileHandle>,
+    ) -> FuseResult<FileAttribute> {
+        self.attr(&file_id)
+    }
+
+    fn lookup(
+        &self,
+        _req: &RequestInfo,
+        parent_id: PathBuf,
+        name: &OsStr,
+    ) -> FuseResult<FileAttribute> {
+        self.attr(&parent_id.join(name))
+    }
+
+    fn open(
+        &self,
+        _req: &RequestInfo,
+        file_id: PathBuf,
+        flags: OpenFlags,
+    ) -> FuseResult<(OwnedFileHandle, FUSEOpenResponseFlags)> {
+        if !self.files.contains_key(&file_id) {
+            return Err(match self.is_dir(&file_id) {
+                true => ErrorKind::IsADirectory.to_error("Is a directory"),
+                false => not_found(),
+            });
+        }
+        if flags.intersects(OpenFlags::WRITE_ONLY | OpenFlags::READ_WRITE) {
+            return Err(ErrorKind::ReadOnlyFileSystem.to_error("Generated files are read-only"));
+        }
+        Ok((
+            // Safe because file handles are not used
+            unsafe { OwnedFileHandle::from_raw(0) },
+            FUSEOpenResponseFlags::DIRECT_IO,
+        ))
+    }
+
+    fn read(
+        &self,
+        _req: &RequestInfo,
+        file_id: PathBuf,
+        _file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        size: u32,
+        _flags: OpenFlags,
+        _lock_owner: Option<u64>,
+    ) -> FuseResult<Vec<u8>> {
+        let generator = self.files.get(&file_id).ok_or_else(not_found)?;
+        let offset = match seek {
+            SeekFrom::Start(offset) => offset as usize,
+            _ => return Err(ErrorKind::InvalidArgument.to_error("Invalid offset")),
+        };
+        let content = generator();
+        let start = offset.min(content.len());
+        let end = offset.saturating_add(size as usize).min(content.len());
+        Ok(content[start..end].to_vec())
+    }
+
+    fn readdir(
+        &self,
+        _req: &RequestInfo,
+        file_id: PathBuf,
+        _file_handle: BorrowedFileHandle,
+    ) -> FuseResult<Vec<(OsString, FileKind)>> {
+        if !self.is_dir(&file_id) {
+            return Err(match self.files.contains_key(&file_id) {
+                true => ErrorKind::NotADirectory.to_error("Not a directory"),
+                false => not_found(),
+            });
+        }
+        let mut children = BTreeMap::new();
+        for file in self.files.keys() {
+            let Ok(relative) = file.strip_prefix(&file_id) else {
+                continue;
+            };
+            let mut components = relative.iter();
+            if let Some(name) = components.next() {
+                let kind = match components.next() {
+                    Some(_) => FileKind::Directory,
+                    None => FileKind::RegularFile,
+                };
+                children.insert(name.to_os_string(), kind);
+            }
+        }
+        let mut entries = vec![
+            (OsString::from("."), FileKind::Directory),
+            (OsString::from(".."), FileKind::Directory),
+        ];
+        entries.extend(children);
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::DefaultFuseHandler;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_gen_fs() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let counter_clone = counter.clone();
+        let mut fs = GenFs::new(DefaultFuseHandler::new());
+        fs.add("/status", || "running\n")
+            .add("stats/calls", move || {
+                format!("{}\n", counter_clone.fetch_add(1, Ordering::SeqCst))
+            });
+        let req = RequestInfo::default();
+
+        let entries = fs
+            .readdir(&req, PathBuf::new(), unsafe {
+                BorrowedFileHandle::from_raw(0)
+            })
+            .unwrap();
+        assert_eq!(
+            entries[2..],
+            [
+                (OsString::from("stats"), FileKind::Directory),
+                (OsString::from("status"), FileKind::RegularFile),
+            ]
+        );
+        let attr = fs
+            .lookup(&req, PathBuf::new(), OsStr::new("status"))
+            .unwrap();
+        assert_eq!((attr.kind, attr.size), (FileKind::RegularFile, 8));
+        let attr = fs.getattr(&req, PathBuf::from("stats"), None).unwrap();
+        assert_eq!(attr.kind, FileKind::Directory);
+        assert_eq!(
+            fs.lookup(&req, PathBuf::new(), OsStr::new("missing"))
+                .unwrap_err()
+                .kind(),
+            ErrorKind::FileNotFound
+        );
+
+        // The content is generated on each read, and sliced by offset
+        let read = |path: &str, offset: u64| {
+            let handle = unsafe { BorrowedFileHandle::from_raw(0) };
+            fs.read(
+                &req,
+                PathBuf::from(path),
+                handle,
+                SeekFrom::Start(offset),
+                4096,
+                OpenFlags::empty(),
+                None,
+            )
+            .unwrap()
+        };
+        assert_eq!(read("status", 4), b"ing\n");
+        assert_eq!(read("status", 100), b"");
+        assert_eq!(read("stats/calls", 0), b"0\n");
+        assert_eq!(read("stats/calls", 0), b"1\n");
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+
+        let error = fs
+            .open(&req, PathBuf::from("status"), OpenFlags::WRITE_ONLY)
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::ReadOnlyFileSystem);
+    }
+
+    #[test]
+    fn test_is_dir_skips_sibling_prefixes() {
+        let mut fs = GenFs::new(DefaultFuseHandler::new());
+        fs.add("a0/file", || "")
+            .add("a-b", || "")
+            .add("b/c/d", || "");
+        assert!(!fs.is_dir(Path::new("a")));
+        assert!(fs.is_dir(Path::new("a0")));
+        assert!(!fs.is_dir(Path::new("a-b")));
+        assert!(fs.is_dir(Path::new("b/c")));
+        assert!(!fs.is_dir(Path::new("b/c/d")));
+    }
+
+    #[test]
+    #[should_panic(expected = "below the generated file")]
+    fn test_add_below_file() {
+        let mut fs = GenFs::new(DefaultFuseHandler::new());
+        fs.add("a", || "").add("a/b", || "");
+    }
+
+    #[test]
+    #[should_panic(expected = "already a directory")]
+    fn test_add_over_directory() {
+        let mut fs = GenFs::new(DefaultFuseHandler::new());
+        fs.add("a/b", || "").add("/a", || "");
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use crate::types::*;
+
+/// Hands out unique file handles mapped to a per-open state, for filesystems without file descriptors.
+///
+/// Handles start at 1 and are never reused during the life of the table, so a stale handle can't
+/// reach the state of a later open. The table can be shared by the worker threads of the parallel mode.
+///
+/// The state is returned as an `Arc`, as it can't be borrowed from the table while other threads
+/// allocate or release handles. Interior mutability (a `Mutex` in `V`) allows to update it, for example
+/// to keep a read cursor.
+///
+/// ```
+/// # use easy_fuser::prelude::*;
+/// # use easy_fuser::templates::HandleTable;
+/// let table = HandleTable::new();
+/// let file_handle = table.allocate(String::from("state"));
+/// assert_eq!(table.get(file_handle.borrow()).as_deref(), Some(&String::from("state")));
+/// assert_eq!(table.release(file_handle).as_deref(), Some(&String::from("state")));
+/// ```
+pub struct HandleTable<V> {
+    entries: RwLock<HashMap<u64, Arc<V>>>,
+    next_handle: AtomicU64,
+}
+
+impl<V> HandleTable<V> {
+    pub fn new() -> Self {
+        Self {
+            entries: RwLock::new(HashMap::new()),
+            next_handle: AtomicU64::new(1),
+        }
+    }
+
+    /// Stores `value` under a new file handle, to be returned by `open`, `opendir` or `create`
+    pub fn allocate(&self, value: V) -> OwnedFileHandle {
+        let handle = self.next_handle.fetch_add(1, Ordering::Relaxed);
+        self.entries
+            .write()
+            .unwrap()
+            .insert(handle, Arc::new(value));
+        // Safe because the handle only refers to an entry of the table
+        unsafe { OwnedFileHandle::from_raw(handle) }
+    }
+
+    /// Returns the state of an allocated handle, or `None` if it is unknown or released
+    pub fn get(&self, file_handle: BorrowedFileHandle) -> Option<Arc<V>> {
+        self.entries
+            .read()
+            .unwrap()
+            .get(&file_handle.as_raw())
+            .cloned()
+    }
+
+    /// Removes the state of a handle, to be called from `release` or `releasedir`
+    ///
+    /// The state is still shared with the callers of [`get`](Self::get) holding it, if any.
+    pub fn release(&self, file_handle: OwnedFileHandle) -> Option<Arc<V>> {
+        self.entries.write().unwrap().remove(&file_handle.as_raw())
+    }
+
+    /// Number of handles currently allocated
+    pub fn len(&self) -> usize {
+        self.entries.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<V> Default for HandleTable<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handles_are_unique() {
+        let table = HandleTable::new();
+        let first = table.allocate(1);
+        let second = table.allocate(2);
+        assert_ne!(first.as_raw(), 0);
+        assert_ne!(first.as_raw(), second.as_raw());
+        assert_eq!(table.get(second.borrow()).as_deref(), Some(&2));
+
+        let raw = first.as_raw();
+        assert_eq!(table.release(first).as_deref(), Some(&1));
+        assert!(table
+            .get(unsafe { BorrowedFileHandle::from_raw(raw) })
+            .is_none());
+        assert_eq!(table.len(), 1);
+
+        // Released handles are not handed out again
+        let third = table.allocate(3);
+        assert_ne!(third.as_raw(), raw);
+    }
+}
@@ -0,0 +1,999 @@
+use std::ffi::{OsStr, OsString};
+use std::path::Path;
+
+use log::{log, Level};
+
+use crate::prelude::*;
+
+/// Wraps a handler to log every call it receives and its outcome.
+///
+/// Each operation is logged with the `log` crate at the chosen level, along with the id of the request
+/// and its arguments, then its result: `Ok`, or the `ErrorKind` and message of the error.
+/// The contents of buffers are not logged, only their length. This traces the behavior of a single
+/// handler, where the driver only logs the failed requests.
+///
+/// The wrapper is transparent: it can be slotted in front of any handler, including between
+/// composed templates, without other changes.
+///
+/// ```text
+/// mount(LoggingHandler::new(my_handler, Level::Debug), mountpoint, &[])
+/// ```
+pub struct LoggingHandler<H> {
+    inner: H,
+    level: Level,
+}
+
+impl<H> LoggingHandler<H> {
+    pub fn new(inner: H, level: Level) -> Self {
+        Self { inner, level }
+    }
+
+    pub fn inner(&self) -> &H {
+        &self.inner
+    }
+
+    fn trace<R>(&self, req: &RequestInfo, operation: &str, result: FuseResult<R>) -> FuseResult<R> {
+        match &result {
+            Ok(_) => log!(self.level, "[{}] {} -> Ok", req.id, operation),
+            Err(e) => log!(
+                self.level,
+                "[{}] {} -> Err({:?}): {}",
+                req.id,
+                operation,
+                e.kind(),
+                e.msg
+            ),
+        }
+        result
+    }
+}
+
+impl<T: FileIdType, H: FuseHandler<T>> FuseHandler<T> for LoggingHandler<H> {
+    fn get_inner(&self) -> &dyn FuseHandler<T> {
+        &self.inner
+    }
+
+    fn init(&self, req: &RequestInfo, config: &mut KernelConfig) -> FuseResult<()> {
+        log!(self.level, "[{}] init()", req.id);
+        let result = self.inner.init(req, config);
+        self.trace(req, "init", result)
+    }
+
+    fn destroy(&self) {
+        log!(self.level, "destroy()");
+        self.inner.destroy()
+    }
+
+    fn pre_unmount(&self) -> FuseResult<()> {
+        log!(self.level, "pre_unmount()");
+        let result = self.inner.pre_unmount();
+        match &result {
+            Ok(_) => log!(self.level, "pre_unmount -> Ok"),
+            Err(e) => log!(self.level, "pre_unmount -> Err({:?}): {}", e.kind(), e.msg),
+        }
+        result
+    }
+
+    fn drop_caches(&self, req: &RequestInfo) -> FuseResult<()> {
+        log!(self.level, "[{}] drop_caches()", req.id);
+        let result = self.inner.drop_caches(req);
+        self.trace(req, "drop_caches", result)
+    }
+
+    fn access(&self, req: &RequestInfo, file_id: T, mask: AccessMask) -> FuseResult<()> {
+        log!(
+            self.level,
+            "[{}] access(file_id: {}, mask: {:?})",
+            req.id,
+            file_id.display(),
+            mask
+        );
+        let result = self.inner.access(req, file_id, mask);
+        self.trace(req, "access", result)
+    }
+
+    fn batch_forget(&self, req: &RequestInfo, nodes: Vec<(T, u64)>) {
+        log!(
+            self.level,
+            "[{}] batch_forget(nodes.len: {})",
+            req.id,
+            nodes.len()
+        );
+        self.inner.batch_forget(req, nodes)
+    }
+
+    fn bmap(&self, req: &RequestInfo, file_id: T, blocksize: u32, idx: u64) -> FuseResult<u64> {
+        log!(
+            self.level,
+            "[{}] bmap(file_id: {}, blocksize: {:?}, idx: {:?})",
+            req.id,
+            file_id.display(),
+            blocksize,
+            idx
+        );
+        let result = self.inner.bmap(req, file_id, blocksize, idx);
+        self.trace(req, "bmap", result)
+    }
+
+    fn copy_file_range(
+        &self,
+        req: &RequestInfo,
+        file_in: T,
+        file_handle_in: BorrowedFileHandle,
+        offset_in: i64,
+        file_out: T,
+        file_handle_out: BorrowedFileHandle,
+        offset_out: i64,
+        len: u64,
+        flags: u32,
+    ) -> FuseResult<u32> {
+        log!(
+            self.level,
+            "[{}] copy_file_range(file_in: {}, file_handle_in: {:?}, offset_in: {:?}, file_out: {}, file_handle_out: {:?}, offset_out: {:?}, len: {:?}, flags: {:?})",
+            req.id,
+            file_in.display(),
+            file_handle_in,
+            offset_in,
+            file_out.display(),
+            file_handle_out,
+            offset_out,
+            len,
+            flags,
+        );
+        let result = self.inner.copy_file_range(
+            req,
+            file_in,
+            file_handle_in,
+            offset_in,
+            file_out,
+            file_handle_out,
+            offset_out,
+            len,
+            flags,
+        );
+        self.trace(req, "copy_file_range", result)
+    }
+
+    fn create(
+        &self,
+        req: &RequestInfo,
+        parent_id: T,
+        name: &OsStr,
+        mode: u32,
+        umask: u32,
+        flags: OpenFlags,
+    ) -> FuseResult<(OwnedFileHandle, T::Metadata, FUSEOpenResponseFlags)> {
+        log!(
+            self.level,
+            "[{}] create(parent_id: {}, name: {:?}, mode: {:?}, umask: {:?}, flags: {:?})",
+            req.id,
+            parent_id.display(),
+            name,
+            mode,
+            umask,
+            flags
+        );
+        let result = self.inner.create(req, parent_id, name, mode, umask, flags);
+        self.trace(req, "create", result)
+    }
+
+    fn fallocate(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        offset: i64,
+        length: i64,
+        mode: FallocateFlags,
+    ) -> FuseResult<()> {
+        log!(
+            self.level,
+            "[{}] fallocate(file_id: {}, file_handle: {:?}, offset: {:?}, length: {:?}, mode: {:?})",
+            req.id,
+            file_id.display(),
+            file_handle,
+            offset,
+            length,
+            mode,
+        );
+        let result = self
+            .inner
+            .fallocate(req, file_id, file_handle, offset, length, mode);
+        self.trace(req, "fallocate", result)
+    }
+
+    fn flush(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        lock_owner: u64,
+    ) -> FuseResult<()> {
+        log!(
+            self.level,
+            "[{}] flush(file_id: {}, file_handle: {:?}, lock_owner: {:?})",
+            req.id,
+            file_id.display(),
+            file_handle,
+            lock_owner
+        );
+        let result = self.inner.flush(req, file_id, file_handle, lock_owner);
+        self.trace(req, "flush", result)
+    }
+
+    fn forget(&self, req: &RequestInfo, file_id: T, nlookup: u64) {
+        log!(
+            self.level,
+            "[{}] forget(file_id: {}, nlookup: {:?})",
+            req.id,
+            file_id.display(),
+            nlookup
+        );
+        self.inner.forget(req, file_id, nlookup)
+    }
+
+    fn fsync(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        datasync: bool,
+    ) -> FuseResult<()> {
+        log!(
+            self.level,
+            "[{}] fsync(file_id: {}, file_handle: {:?}, datasync: {:?})",
+            req.id,
+            file_id.display(),
+            file_handle,
+            datasync
+        );
+        let result = self.inner.fsync(req, file_id, file_handle, datasync);
+        self.trace(req, "fsync", result)
+    }
+
+    fn fsyncdir(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        datasync: bool,
+    ) -> FuseResult<()> {
+        log!(
+            self.level,
+            "[{}] fsyncdir(file_id: {}, file_handle: {:?}, datasync: {:?})",
+            req.id,
+            file_id.display(),
+            file_handle,
+            datasync
+        );
+        let result = self.inner.fsyncdir(req, file_id, file_handle, datasync);
+        self.trace(req, "fsyncdir", result)
+    }
+
+    fn getattr(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: Option<BorrowedFileHandle>,
+    ) -> FuseResult<FileAttribute> {
+        log!(
+            self.level,
+            "[{}] getattr(file_id: {}, file_handle: {:?})",
+            req.id,
+            file_id.display(),
+            file_handle
+        );
+        let result = self.inner.getattr(req, file_id, file_handle);
+        self.trace(req, "getattr", result)
+    }
+
+    #[cfg(feature = "locking")]
+    fn getlk(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        lock_owner: u64,
+        lock_info: LockInfo,
+    ) -> FuseResult<LockInfo> {
+        log!(
+            self.level,
+            "[{}] getlk(file_id: {}, file_handle: {:?}, lock_owner: {:?}, lock_info: {:?})",
+            req.id,
+            file_id.display(),
+            file_handle,
+            lock_owner,
+            lock_info
+        );
+        let result = self
+            .inner
+            .getlk(req, file_id, file_handle, lock_owner, lock_info);
+        self.trace(req, "getlk", result)
+    }
+
+    #[cfg(feature = "xattr")]
+    fn getxattr(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        name: &OsStr,
+        size: u32,
+    ) -> FuseResult<Vec<u8>> {
+        log!(
+            self.level,
+            "[{}] getxattr(file_id: {}, name: {:?}, size: {:?})",
+            req.id,
+            file_id.display(),
+            name,
+            size
+        );
+        let result = self.inner.getxattr(req, file_id, name, size);
+        self.trace(req, "getxattr", result)
+    }
+
+    #[cfg(feature = "ioctl")]
+    fn ioctl(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        flags: IOCtlFlags,
+        cmd: u32,
+        in_data: Vec<u8>,
+        out_size: u32,
+    ) -> FuseResult<(i32, Vec<u8>)> {
+        log!(
+            self.level,
+            "[{}] ioctl(file_id: {}, file_handle: {:?}, flags: {:?}, cmd: {:?}, in_data.len: {}, out_size: {:?})",
+            req.id,
+            file_id.display(),
+            file_handle,
+            flags,
+            cmd,
+            in_data.len(),
+            out_size,
+        );
+        let result = self
+            .inner
+            .ioctl(req, file_id, file_handle, flags, cmd, in_data, out_size);
+        self.trace(req, "ioctl", result)
+    }
+
+    fn link(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        newparent: T,
+        newname: &OsStr,
+    ) -> FuseResult<T::Metadata> {
+        log!(
+            self.level,
+            "[{}] link(file_id: {}, newparent: {}, newname: {:?})",
+            req.id,
+            file_id.display(),
+            newparent.display(),
+            newname
+        );
+        let result = self.inner.link(req, file_id, newparent, newname);
+        self.trace(req, "link", result)
+    }
+
+    #[cfg(feature = "xattr")]
+    fn listxattr(&self, req: &RequestInfo, file_id: T, size: u32) -> FuseResult<Vec<u8>> {
+        log!(
+            self.level,
+            "[{}] listxattr(file_id: {}, size: {:?})",
+            req.id,
+            file_id.display(),
+            size
+        );
+        let result = self.inner.listxattr(req, file_id, size);
+        self.trace(req, "listxattr", result)
+    }
+
+    #[cfg(feature = "xattr")]
+    fn listxattr_names(&self, req: &RequestInfo, file_id: T) -> FuseResult<Vec<OsString>> {
+        log!(
+            self.level,
+            "[{}] listxattr_names(file_id: {})",
+            req.id,
+            file_id.display()
+        );
+        let result = self.inner.listxattr_names(req, file_id);
+        self.trace(req, "listxattr_names", result)
+    }
+
+    fn lookup(&self, req: &RequestInfo, parent_id: T, name: &OsStr) -> FuseResult<T::Metadata> {
+        log!(
+            self.level,
+            "[{}] lookup(parent_id: {}, name: {:?})",
+            req.id,
+            parent_id.display(),
+            name
+        );
+        let result = self.inner.lookup(req, parent_id, name);
+        self.trace(req, "lookup", result)
+    }
+
+    fn lseek(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+    ) -> FuseResult<i64> {
+        log!(
+            self.level,
+            "[{}] lseek(file_id: {}, file_handle: {:?}, seek: {:?})",
+            req.id,
+            file_id.display(),
+            file_handle,
+            seek
+        );
+        let result = self.inner.lseek(req, file_id, file_handle, seek);
+        self.trace(req, "lseek", result)
+    }
+
+    fn lseek_raw(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        offset: i64,
+        whence: i32,
+    ) -> FuseResult<i64> {
+        log!(
+            self.level,
+            "[{}] lseek_raw(file_id: {}, file_handle: {:?}, offset: {}, whence: {})",
+            req.id,
+            file_id.display(),
+            file_handle,
+            offset,
+            whence
+        );
+        let result = self
+            .inner
+            .lseek_raw(req, file_id, file_handle, offset, whence);
+        self.trace(req, "lseek_raw", result)
+    }
+
+    fn mkdir(
+        &self,
+        req: &RequestInfo,
+        parent_id: T,
+        name: &OsStr,
+        mode: u32,
+        umask: u32,
+    ) -> FuseResult<T::Metadata> {
+        log!(
+            self.level,
+            "[{}] mkdir(parent_id: {}, name: {:?}, mode: {:?}, umask: {:?})",
+            req.id,
+            parent_id.display(),
+            name,
+            mode,
+            umask
+        );
+        let result = self.inner.mkdir(req, parent_id, name, mode, umask);
+        self.trace(req, "mkdir", result)
+    }
+
+    fn mknod(
+        &self,
+        req: &RequestInfo,
+        parent_id: T,
+        name: &OsStr,
+        mode: u32,
+        umask: u32,
+        rdev: DeviceType,
+    ) -> FuseResult<T::Metadata> {
+        log!(
+            self.level,
+            "[{}] mknod(parent_id: {}, name: {:?}, mode: {:?}, umask: {:?}, rdev: {:?})",
+            req.id,
+            parent_id.display(),
+            name,
+            mode,
+            umask,
+            rdev
+        );
+        let result = self.inner.mknod(req, parent_id, name, mode, umask, rdev);
+        self.trace(req, "mknod", result)
+    }
+
+    fn open(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        flags: OpenFlags,
+    ) -> FuseResult<(OwnedFileHandle, FUSEOpenResponseFlags)> {
+        log!(
+            self.level,
+            "[{}] open(file_id: {}, flags: {:?})",
+            req.id,
+            file_id.display(),
+            flags
+        );
+        let result = self.inner.open(req, file_id, flags);
+        self.trace(req, "open", result)
+    }
+
+    fn opendir(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        flags: OpenFlags,
+    ) -> FuseResult<(OwnedFileHandle, FUSEOpenResponseFlags)> {
+        log!(
+            self.level,
+            "[{}] opendir(file_id: {}, flags: {:?})",
+            req.id,
+            file_id.display(),
+            flags
+        );
+        let result = self.inner.opendir(req, file_id, flags);
+        self.trace(req, "opendir", result)
+    }
+
+    fn poll(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        poll_handle: PollHandle,
+        events: u32,
+        flags: u32,
+    ) -> FuseResult<u32> {
+        log!(
+            self.level,
+            "[{}] poll(file_id: {}, file_handle: {:?}, events: {:?}, flags: {:?})",
+            req.id,
+            file_id.display(),
+            file_handle,
+            events,
+            flags
+        );
+        let result = self
+            .inner
+            .poll(req, file_id, file_handle, poll_handle, events, flags);
+        self.trace(req, "poll", result)
+    }
+
+    fn read(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        size: u32,
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+    ) -> FuseResult<Vec<u8>> {
+        log!(
+            self.level,
+            "[{}] read(file_id: {}, file_handle: {:?}, seek: {:?}, size: {:?}, flags: {:?}, lock_owner: {:?})",
+            req.id,
+            file_id.display(),
+            file_handle,
+            seek,
+            size,
+            flags,
+            lock_owner,
+        );
+        let result = self
+            .inner
+            .read(req, file_id, file_handle, seek, size, flags, lock_owner);
+        self.trace(req, "read", result)
+    }
+
+    fn read_into(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        buf: &mut [u8],
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+    ) -> FuseResult<usize> {
+        log!(
+            self.level,
+            "[{}] read_into(file_id: {}, file_handle: {:?}, seek: {:?}, size: {:?}, flags: {:?}, lock_owner: {:?})",
+            req.id,
+            file_id.display(),
+            file_handle,
+            seek,
+            buf.len(),
+            flags,
+            lock_owner,
+        );
+        let result = self
+            .inner
+            .read_into(req, file_id, file_handle, seek, buf, flags, lock_owner);
+        self.trace(req, "read_into", result)
+    }
+
+    fn readdir(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+    ) -> FuseResult<Vec<(OsString, T::MinimalMetadata)>> {
+        log!(
+            self.level,
+            "[{}] readdir(file_id: {}, file_handle: {:?})",
+            req.id,
+            file_id.display(),
+            file_handle
+        );
+        let result = self.inner.readdir(req, file_id, file_handle);
+        self.trace(req, "readdir", result)
+    }
+
+    fn readdir_partial(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+    ) -> (Vec<(OsString, T::MinimalMetadata)>, Option<PosixError>) {
+        log!(
+            self.level,
+            "[{}] readdir_partial(file_id: {}, file_handle: {:?})",
+            req.id,
+            file_id.display(),
+            file_handle
+        );
+        self.inner.readdir_partial(req, file_id, file_handle)
+    }
+
+    fn readdir_stream(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+    ) -> FuseResult<DirEntryStream<T>> {
+        log!(
+            self.level,
+            "[{}] readdir_stream(file_id: {}, file_handle: {:?})",
+            req.id,
+            file_id.display(),
+            file_handle
+        );
+        let result = self.inner.readdir_stream(req, file_id, file_handle);
+        self.trace(req, "readdir_stream", result)
+    }
+
+    fn readdirplus(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+    ) -> FuseResult<Vec<(OsString, T::Metadata)>> {
+        log!(
+            self.level,
+            "[{}] readdirplus(file_id: {}, file_handle: {:?})",
+            req.id,
+            file_id.display(),
+            file_handle
+        );
+        let result = self.inner.readdirplus(req, file_id, file_handle);
+        self.trace(req, "readdirplus", result)
+    }
+
+    fn readlink(&self, req: &RequestInfo, file_id: T) -> FuseResult<Vec<u8>> {
+        log!(
+            self.level,
+            "[{}] readlink(file_id: {})",
+            req.id,
+            file_id.display()
+        );
+        let result = self.inner.readlink(req, file_id);
+        self.trace(req, "readlink", result)
+    }
+
+    fn release(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: OwnedFileHandle,
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+        flush: bool,
+    ) -> FuseResult<()> {
+        log!(
+            self.level,
+            "[{}] release(file_id: {}, file_handle: {:?}, flags: {:?}, lock_owner: {:?}, flush: {:?})",
+            req.id,
+            file_id.display(),
+            file_handle,
+            flags,
+            lock_owner,
+            flush,
+        );
+        let result = self
+            .inner
+            .release(req, file_id, file_handle, flags, lock_owner, flush);
+        self.trace(req, "release", result)
+    }
+
+    fn releasedir(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: OwnedFileHandle,
+        flags: OpenFlags,
+    ) -> FuseResult<()> {
+        log!(
+            self.level,
+            "[{}] releasedir(file_id: {}, file_handle: {:?}, flags: {:?})",
+            req.id,
+            file_id.display(),
+            file_handle,
+            flags
+        );
+        let result = self.inner.releasedir(req, file_id, file_handle, flags);
+        self.trace(req, "releasedir", result)
+    }
+
+    #[cfg(feature = "xattr")]
+    fn removexattr(&self, req: &RequestInfo, file_id: T, name: &OsStr) -> FuseResult<()> {
+        log!(
+            self.level,
+            "[{}] removexattr(file_id: {}, name: {:?})",
+            req.id,
+            file_id.display(),
+            name
+        );
+        let result = self.inner.removexattr(req, file_id, name);
+        self.trace(req, "removexattr", result)
+    }
+
+    fn rename(
+        &self,
+        req: &RequestInfo,
+        parent_id: T,
+        name: &OsStr,
+        newparent: T,
+        newname: &OsStr,
+        flags: RenameFlags,
+    ) -> FuseResult<()> {
+        log!(
+            self.level,
+            "[{}] rename(parent_id: {}, name: {:?}, newparent: {}, newname: {:?}, flags: {:?})",
+            req.id,
+            parent_id.display(),
+            name,
+            newparent.display(),
+            newname,
+            flags
+        );
+        let result = self
+            .inner
+            .rename(req, parent_id, name, newparent, newname, flags);
+        self.trace(req, "rename", result)
+    }
+
+    fn rmdir(&self, req: &RequestInfo, parent_id: T, name: &OsStr) -> FuseResult<()> {
+        log!(
+            self.level,
+            "[{}] rmdir(parent_id: {}, name: {:?})",
+            req.id,
+            parent_id.display(),
+            name
+        );
+        let result = self.inner.rmdir(req, parent_id, name);
+        self.trace(req, "rmdir", result)
+    }
+
+    fn setattr(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        attrs: SetAttrRequest,
+    ) -> FuseResult<FileAttribute> {
+        log!(
+            self.level,
+            "[{}] setattr(file_id: {}, attrs: {:?})",
+            req.id,
+            file_id.display(),
+            attrs
+        );
+        let result = self.inner.setattr(req, file_id, attrs);
+        self.trace(req, "setattr", result)
+    }
+
+    #[cfg(feature = "locking")]
+    fn setlk(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        lock_owner: u64,
+        lock_info: LockInfo,
+        sleep: bool,
+    ) -> FuseResult<()> {
+        log!(
+            self.level,
+            "[{}] setlk(file_id: {}, file_handle: {:?}, lock_owner: {:?}, lock_info: {:?}, sleep: {:?})",
+            req.id,
+            file_id.display(),
+            file_handle,
+            lock_owner,
+            lock_info,
+            sleep,
+        );
+        let result = self
+            .inner
+            .setlk(req, file_id, file_handle, lock_owner, lock_info, sleep);
+        self.trace(req, "setlk", result)
+    }
+
+    #[cfg(feature = "xattr")]
+    fn setxattr(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        name: &OsStr,
+        value: Vec<u8>,
+        flags: FUSESetXAttrFlags,
+        position: u32,
+    ) -> FuseResult<()> {
+        log!(
+            self.level,
+            "[{}] setxattr(file_id: {}, name: {:?}, value.len: {}, flags: {:?}, position: {:?})",
+            req.id,
+            file_id.display(),
+            name,
+            value.len(),
+            flags,
+            position
+        );
+        let result = self
+            .inner
+            .setxattr(req, file_id, name, value, flags, position);
+        self.trace(req, "setxattr", result)
+    }
+
+    fn statfs(&self, req: &RequestInfo, file_id: T) -> FuseResult<StatFs> {
+        log!(
+            self.level,
+            "[{}] statfs(file_id: {})",
+            req.id,
+            file_id.display()
+        );
+        let result = self.inner.statfs(req, file_id);
+        self.trace(req, "statfs", result)
+    }
+
+    fn symlink(
+        &self,
+        req: &RequestInfo,
+        parent_id: T,
+        link_name: &OsStr,
+        target: &Path,
+    ) -> FuseResult<T::Metadata> {
+        log!(
+            self.level,
+            "[{}] symlink(parent_id: {}, link_name: {:?}, target: {:?})",
+            req.id,
+            parent_id.display(),
+            link_name,
+            target
+        );
+        let result = self.inner.symlink(req, parent_id, link_name, target);
+        self.trace(req, "symlink", result)
+    }
+
+    fn write(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        data: Vec<u8>,
+        write_flags: FUSEWriteFlags,
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+    ) -> FuseResult<u32> {
+        log!(
+            self.level,
+            "[{}] write(file_id: {}, file_handle: {:?}, seek: {:?}, data.len: {}, write_flags: {:?}, flags: {:?}, lock_owner: {:?})",
+            req.id,
+            file_id.display(),
+            file_handle,
+            seek,
+            data.len(),
+            write_flags,
+            flags,
+            lock_owner,
+        );
+        let result = self.inner.write(
+            req,
+            file_id,
+            file_handle,
+            seek,
+            data,
+            write_flags,
+            flags,
+            lock_owner,
+        );
+        self.trace(req, "write", result)
+    }
+
+    fn unlink(&self, req: &RequestInfo, parent_id: T, name: &OsStr) -> FuseResult<()> {
+        log!(
+            self.level,
+            "[{}] unlink(parent_id: {}, name: {:?})",
+            req.id,
+            parent_id.display(),
+            name
+        );
+        let result = self.inner.unlink(req, parent_id, name);
+        self.trace(req, "unlink", result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::{mem_fs::MemFs, DefaultFuseHandler};
+    use log::{Metadata, Record};
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+
+    struct CapturingLogger {
+        records: Mutex<Vec<(Level, String)>>,
+    }
+
+    impl log::Log for CapturingLogger {
+        fn enabled(&self, _metadata: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            self.records
+                .lock()
+                .unwrap()
+                .push((record.level(), record.args().to_string()));
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: CapturingLogger = CapturingLogger {
+        records: Mutex::new(Vec::new()),
+    };
+
+    #[test]
+    fn test_calls_are_logged() {
+        let _ = log::set_logger(&LOGGER);
+        log::set_max_level(log::LevelFilter::Trace);
+
+        let inner = MemFs::<PathBuf>::new(DefaultFuseHandler::new());
+        inner.insert_file("file.txt", "content").unwrap();
+        let fs = LoggingHandler::new(inner, Level::Debug);
+        let req = RequestInfo {
+            id: 42,
+            ..RequestInfo::default()
+        };
+        fs.lookup(&req, PathBuf::new(), OsStr::new("file.txt"))
+            .unwrap();
+        fs.lookup(&req, PathBuf::new(), OsStr::new("missing"))
+            .unwrap_err();
+
+        // Other tests may log concurrently
+        let records: Vec<_> = LOGGER
+            .records
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, message)| message.starts_with("[42] lookup"))
+            .cloned()
+            .collect();
+        assert_eq!(records.len(), 4);
+        assert!(records.iter().all(|(level, _)| *level == Level::Debug));
+        assert_eq!(records[0].1, "[42] lookup(parent_id: , name: \"file.txt\")");
+        assert_eq!(records[1].1, "[42] lookup -> Ok");
+        assert!(records[3].1.starts_with("[42] lookup -> Err(FileNotFound)"));
+    }
+}
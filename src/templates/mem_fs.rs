@@ -0,0 +1,785 @@
+/*!
+# MemFs
+
+A FUSE handler storing a whole filesystem tree in memory, to be used as a scratch filesystem or as a base for tests.
+
+## Overview
+
+`MemFs` keeps every file and directory in memory and loses its content when dropped. It implements:
+
+- Navigation and metadata: `lookup`, `getattr`, `setattr`, `readdir`, `statfs`
+- File content: `open`, `read`, `write`, `flush`, `fsync`, `release`
+- Tree modifications: `create`, `mkdir`, `unlink`, `rmdir`, `rename`
+
+Other operations are delegated to the inner handler, like `MirrorFs` does.
+
+It can be used with any file id type implementing [`MemFsId`] (`Inode`, `PathBuf` and `Vec<OsString>`).
+
+## Usage
+
+```rust, no_run
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mem_fs::MemFs, DefaultFuseHandler};
+use std::path::PathBuf;
+
+let fs = MemFs::<PathBuf>::new(DefaultFuseHandler::new());
+fs.create_dir_all("docs").unwrap();
+fs.insert_file("docs/README.md", "Hello").unwrap();
+```
+
+## Note
+Permissions are stored but not enforced by `MemFs`. Use the `MountOption::DefaultPermissions` option to let the kernel
+check them.
+
+Growing a file fails with `ErrorKind::NoSpaceLeftOnDevice` (ENOSPC) when its content can't be allocated, and
+`max_file_size` can be overridden in the inner handler to let the driver bound file sizes with EFBIG.
+*/
+
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::{OsStr, OsString};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::prelude::*;
+
+/// File id types which can be resolved to a `MemFs` node
+pub trait MemFsId: FileIdType {
+    /// Returns the inode of the node designated by the id, `lookup` resolving a child from its parent
+    fn resolve(&self, lookup: impl Fn(&Inode, &OsStr) -> Option<Inode>) -> Option<Inode>;
+
+    fn to_metadata(ino: Inode, attr: FileAttribute) -> Self::Metadata;
+
+    fn to_minimal_metadata(ino: Inode, kind: FileKind) -> Self::MinimalMetadata;
+}
+
+impl MemFsId for Inode {
+    fn resolve(&self, _lookup: impl Fn(&Inode, &OsStr) -> Option<Inode>) -> Option<Inode> {
+        Some(self.clone())
+    }
+
+    fn to_metadata(ino: Inode, attr: FileAttribute) -> Self::Metadata {
+        (ino, attr)
+    }
+
+    fn to_minimal_metadata(ino: Inode, kind: FileKind) -> Self::MinimalMetadata {
+        (ino, kind)
+    }
+}
+
+impl MemFsId for PathBuf {
+    fn resolve(&self, lookup: impl Fn(&Inode, &OsStr) -> Option<Inode>) -> Option<Inode> {
+        self.iter()
+            .try_fold(ROOT_INODE, |parent, name| lookup(&parent, name))
+    }
+
+    fn to_metadata(_ino: Inode, attr: FileAttribute) -> Self::Metadata {
+        attr
+    }
+
+    fn to_minimal_metadata(_ino: Inode, kind: FileKind) -> Self::MinimalMetadata {
+        kind
+    }
+}
+
+impl MemFsId for Vec<OsString> {
+    fn resolve(&self, lookup: impl Fn(&Inode, &OsStr) -> Option<Inode>) -> Option<Inode> {
+        // Components are stored from leaf to root
+        self.iter()
+            .rev()
+            .try_fold(ROOT_INODE, |parent, name| lookup(&parent, name))
+    }
+
+    fn to_metadata(_ino: Inode, attr: FileAttribute) -> Self::Metadata {
+        attr
+    }
+
+    fn to_minimal_metadata(_ino: Inode, kind: FileKind) -> Self::MinimalMetadata {
+        kind
+    }
+}
+
+struct Node {
+    parent: Inode,
+    attr: FileAttribute,
+    data: Vec<u8>,
+    children: BTreeMap<OsString, Inode>,
+}
+
+struct Tree {
+    nodes: HashMap<Inode, Node>,
+    next_inode: Inode,
+}
+
+fn new_attr(kind: FileKind, perm: u16, uid: u32, gid: u32) -> FileAttribute {
+    let now = SystemTime::now();
+    FileAttribute {
+        size: 0,
+        blocks: 0,
+        atime: now,
+        mtime: now,
+        ctime: now,
+        crtime: now,
+        kind,
+        perm,
+        nlink: if kind == FileKind::Directory { 2 } else { 1 },
+        uid,
+        gid,
+        rdev: 0,
+        blksize: 4096,
+        flags: 0,
+        ttl: None,
+        generation: None,
+    }
+}
+
+fn not_found() -> PosixError {
+    ErrorKind::FileNotFound.to_error("No such file or directory")
+}
+
+impl Tree {
+    fn new() -> Self {
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        let root = Node {
+            parent: ROOT_INODE,
+            attr: new_attr(FileKind::Directory, 0o755, uid, gid),
+            data: Vec::new(),
+            children: BTreeMap::new(),
+        };
+        Self {
+            nodes: HashMap::from([(ROOT_INODE, root)]),
+            next_inode: ROOT_INODE.add_one(),
+        }
+    }
+
+    fn resolve<T: MemFsId>(&self, id: &T) -> FuseResult<Inode> {
+        id.resolve(|parent, name| self.child(parent, name))
+            .ok_or_else(not_found)
+    }
+
+    fn child(&self, parent: &Inode, name: &OsStr) -> Option<Inode> {
+        self.nodes.get(parent)?.children.get(name).cloned()
+    }
+
+    fn node(&self, ino: &Inode) -> FuseResult<&Node> {
+        self.nodes.get(ino).ok_or_else(not_found)
+    }
+
+    fn node_mut(&mut self, ino: &Inode) -> FuseResult<&mut Node> {
+        self.nodes.get_mut(ino).ok_or_else(not_found)
+    }
+
+    fn dir_mut(&mut self, ino: &Inode) -> FuseResult<&mut Node> {
+        let node = self.node_mut(ino)?;
+        if node.attr.kind != FileKind::Directory {
+            return Err(ErrorKind::NotADirectory.to_error("Parent is not a directory"));
+        }
+        Ok(node)
+    }
+
+    fn touch_dir(&mut self, ino: &Inode, nlink_change: i32) {
+        if let Some(node) = self.nodes.get_mut(ino) {
+            let now = SystemTime::now();
+            node.attr.mtime = now;
+            node.attr.ctime = now;
+            node.attr.nlink = node.attr.nlink.saturating_add_signed(nlink_change);
+        }
+    }
+
+    /// Adds a new node, failing if the name is already taken
+    fn insert(&mut self, parent: &Inode, name: &OsStr, attr: FileAttribute) -> FuseResult<Inode> {
+        let ino = self.next_inode.clone();
+        let is_dir = attr.kind == FileKind::Directory;
+        let parent_node = self.dir_mut(parent)?;
+        if parent_node.children.contains_key(name) {
+            return Err(ErrorKind::FileExists.to_error("File exists"));
+        }
+        parent_node
+            .children
+            .insert(name.to_os_string(), ino.clone());
+        self.nodes.insert(
+            ino.clone(),
+            Node {
+                parent: parent.clone(),
+                attr,
+                data: Vec::new(),
+                children: BTreeMap::new(),
+            },
+        );
+        self.next_inode = ino.add_one();
+        self.touch_dir(parent, if is_dir { 1 } else { 0 });
+        Ok(ino)
+    }
+
+    /// Removes a node and its descendants
+    fn remove(&mut self, parent: &Inode, name: &OsStr) -> FuseResult<()> {
+        let ino = self
+            .dir_mut(parent)?
+            .children
+            .remove(name)
+            .ok_or_else(not_found)?;
+        let is_dir = self.node(&ino)?.attr.kind == FileKind::Directory;
+        let mut pending = vec![ino];
+        while let Some(ino) = pending.pop() {
+            if let Some(node) = self.nodes.remove(&ino) {
+                pending.extend(node.children.into_values());
+            }
+        }
+        self.touch_dir(parent, if is_dir { -1 } else { 0 });
+        Ok(())
+    }
+
+    /// Returns the inode of the directory at `path`, creating the missing ones
+    fn create_dir_all(&mut self, path: &Path) -> FuseResult<Inode> {
+        let mut current = ROOT_INODE;
+        for component in path.components() {
+            let name = match component {
+                Component::Normal(name) => name,
+                Component::RootDir | Component::CurDir => continue,
+                _ => return Err(ErrorKind::InvalidArgument.to_error("Unsupported path component")),
+            };
+            current = match self.child(&current, name) {
+                Some(ino) => ino,
+                None => {
+                    let owner = self.node(&current)?.attr.clone();
+                    let attr = new_attr(FileKind::Directory, 0o755, owner.uid, owner.gid);
+                    self.insert(&current, name, attr)?
+                }
+            };
+            if self.node(&current)?.attr.kind != FileKind::Directory {
+                return Err(ErrorKind::NotADirectory.to_error("Path component is not a directory"));
+            }
+        }
+        Ok(current)
+    }
+}
+
+/// Resizes the content of a file, failing instead of aborting if the memory can't be allocated
+fn resize_data(data: &mut Vec<u8>, size: u64) -> FuseResult<()> {
+    let size =
+        usize::try_from(size).map_err(|_| ErrorKind::FileTooLarge.to_error("File too large"))?;
+    if size > data.len() {
+        data.try_reserve_exact(size - data.len()).map_err(|_| {
+            ErrorKind::NoSpaceLeftOnDevice.to_error("Not enough memory for the file content")
+        })?;
+    }
+    data.resize(size, 0);
+    Ok(())
+}
+
+fn update_size(node: &mut Node) {
+    node.attr.size = node.data.len() as u64;
+    node.attr.blocks = node.attr.size.div_ceil(512);
+}
+
+/// Specific documentation is located in parent module documentation.
+pub struct MemFs<T: MemFsId> {
+    inner: Box<dyn FuseHandler<T>>,
+    tree: Mutex<Tree>,
+}
+
+impl<T: MemFsId> MemFs<T> {
+    /// Creates an empty filesystem, delegating the operations it doesn't implement to `inner`
+    pub fn new<U: FuseHandler<T>>(inner: U) -> Self {
+        Self {
+            inner: Box::new(inner),
+            tree: Mutex::new(Tree::new()),
+        }
+    }
+
+    /// Creates the directory at `path` (relative to the root) and all its missing parents
+    pub fn create_dir_all<P: AsRef<Path>>(&self, path: P) -> FuseResult<()> {
+        self.tree.lock().unwrap().create_dir_all(path.as_ref())?;
+        Ok(())
+    }
+
+    /// Creates or replaces the file at `path` (relative to the root) with `contents`
+    ///
+    /// Missing parent directories are created.
+    pub fn insert_file<P, C>(&self, path: P, contents: C) -> FuseResult<()>
+    where
+        P: AsRef<Path>,
+        C: Into<Vec<u8>>,
+    {
+        let path = path.as_ref();
+        let name = path
+            .file_name()
+            .ok_or_else(|| ErrorKind::InvalidArgument.to_error("Path has no file name"))?;
+        let mut tree = self.tree.lock().unwrap();
+        let parent = tree.create_dir_all(path.parent().unwrap_or(Path::new("")))?;
+        let ino = match tree.child(&parent, name) {
+            Some(ino) => ino,
+            None => {
+                let owner = tree.node(&parent)?.attr.clone();
+                let attr = new_attr(FileKind::RegularFile, 0o644, owner.uid, owner.gid);
+                tree.insert(&parent, name, attr)?
+            }
+        };
+        let node = tree.node_mut(&ino)?;
+        if node.attr.kind == FileKind::Directory {
+            return Err(ErrorKind::IsADirectory.to_error("Path is a directory"));
+        }
+        node.data = contents.into();
+        update_size(node);
+        node.attr.mtime = SystemTime::now();
+        node.attr.ctime = node.attr.mtime;
+        Ok(())
+    }
+}
+
+impl<T: MemFsId> FuseHandler<T> for MemFs<T> {
+    fn get_inner(&self) -> &dyn FuseHandler<T> {
+        self.inner.as_ref()
+    }
+
+    fn create(
+        &self,
+        req: &RequestInfo,
+        parent_id: T,
+        name: &OsStr,
+        mode: u32,
+        umask: u32,
+        _flags: OpenFlags,
+    ) -> FuseResult<(OwnedFileHandle, T::Metadata, FUSEOpenResponseFlags)> {
+        let mut tree = self.tree.lock().unwrap();
+        let parent = tree.resolve(&parent_id)?;
+        let perm = (mode & !umask & 0o7777) as u16;
+        let attr = new_attr(FileKind::RegularFile, perm, req.uid, req.gid);
+        let ino = tree.insert(&parent, name, attr.clone())?;
+        Ok((
+            // Safe because file handles are not used
+            unsafe { OwnedFileHandle::from_raw(0) },
+            T::to_metadata(ino, attr),
+            FUSEOpenResponseFlags::empty(),
+        ))
+    }
+
+    fn flush(
+        &self,
+        _req: &RequestInfo,
+        _file_id: T,
+        _file_handle: BorrowedFileHandle,
+        _lock_owner: u64,
+    ) -> FuseResult<()> {
+        Ok(())
+    }
+
+    fn fsync(
+        &self,
+        _req: &RequestInfo,
+        _file_id: T,
+        _file_handle: BorrowedFileHandle,
+        _datasync: bool,
+    ) -> FuseResult<()> {
+        Ok(())
+    }
+
+    fn getattr(
+        &self,
+        _req: &RequestInfo,
+        file_id: T,
+        _file_handle: Option<BorrowedFileHandle>,
+    ) -> FuseResult<FileAttribute> {
+        let tree = self.tree.lock().unwrap();
+        let ino = tree.resolve(&file_id)?;
+        Ok(tree.node(&ino)?.attr.clone())
+    }
+
+    fn lookup(&self, _req: &RequestInfo, parent_id: T, name: &OsStr) -> FuseResult<T::Metadata> {
+        let tree = self.tree.lock().unwrap();
+        let parent = tree.resolve(&parent_id)?;
+        let ino = tree.child(&parent, name).ok_or_else(not_found)?;
+        let attr = tree.node(&ino)?.attr.clone();
+        Ok(T::to_metadata(ino, attr))
+    }
+
+    fn mkdir(
+        &self,
+        req: &RequestInfo,
+        parent_id: T,
+        name: &OsStr,
+        mode: u32,
+        umask: u32,
+    ) -> FuseResult<T::Metadata> {
+        let mut tree = self.tree.lock().unwrap();
+        let parent = tree.resolve(&parent_id)?;
+        let perm = (mode & !umask & 0o7777) as u16;
+        let attr = new_attr(FileKind::Directory, perm, req.uid, req.gid);
+        let ino = tree.insert(&parent, name, attr.clone())?;
+        Ok(T::to_metadata(ino, attr))
+    }
+
+    fn open(
+        &self,
+        _req: &RequestInfo,
+        file_id: T,
+        _flags: OpenFlags,
+    ) -> FuseResult<(OwnedFileHandle, FUSEOpenResponseFlags)> {
+        let tree = self.tree.lock().unwrap();
+        tree.resolve(&file_id)?;
+        // Safe because file handles are not used
+        Ok((
+            unsafe { OwnedFileHandle::from_raw(0) },
+            FUSEOpenResponseFlags::empty(),
+        ))
+    }
+
+    fn read(
+        &self,
+        _req: &RequestInfo,
+        file_id: T,
+        _file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        size: u32,
+        _flags: OpenFlags,
+        _lock_owner: Option<u64>,
+    ) -> FuseResult<Vec<u8>> {
+        let tree = self.tree.lock().unwrap();
+        let node = tree.node(&tree.resolve(&file_id)?)?;
+        if node.attr.kind == FileKind::Directory {
+            return Err(ErrorKind::IsADirectory.to_error("Is a directory"));
+        }
+        let offset = match seek {
+            SeekFrom::Start(offset) => offset as usize,
+            _ => return Err(ErrorKind::InvalidArgument.to_error("Invalid offset")),
+        };
+        let start = offset.min(node.data.len());
+        let end = offset.saturating_add(size as usize).min(node.data.len());
+        Ok(node.data[start..end].to_vec())
+    }
+
+    fn readdir(
+        &self,
+        _req: &RequestInfo,
+        file_id: T,
+        _file_handle: BorrowedFileHandle,
+    ) -> FuseResult<Vec<(OsString, T::MinimalMetadata)>> {
+        let tree = self.tree.lock().unwrap();
+        let ino = tree.resolve(&file_id)?;
+        let node = tree.node(&ino)?;
+        if node.attr.kind != FileKind::Directory {
+            return Err(ErrorKind::NotADirectory.to_error("Not a directory"));
+        }
+        let mut entries = vec![
+            (
+                OsString::from("."),
+                T::to_minimal_metadata(ino, FileKind::Directory),
+            ),
+            (
+                OsString::from(".."),
+                T::to_minimal_metadata(node.parent.clone(), FileKind::Directory),
+            ),
+        ];
+        for (name, child) in &node.children {
+            let kind = tree.node(child)?.attr.kind;
+            entries.push((name.clone(), T::to_minimal_metadata(child.clone(), kind)));
+        }
+        Ok(entries)
+    }
+
+    fn release(
+        &self,
+        _req: &RequestInfo,
+        _file_id: T,
+        _file_handle: OwnedFileHandle,
+        _flags: OpenFlags,
+        _lock_owner: Option<u64>,
+        _flush: bool,
+    ) -> FuseResult<()> {
+        Ok(())
+    }
+
+    fn rename(
+        &self,
+        _req: &RequestInfo,
+        parent_id: T,
+        name: &OsStr,
+        newparent: T,
+        newname: &OsStr,
+        flags: RenameFlags,
+    ) -> FuseResult<()> {
+        let mut tree = self.tree.lock().unwrap();
+        let parent = tree.resolve(&parent_id)?;
+        let newparent = tree.resolve(&newparent)?;
+        let source = tree.child(&parent, name).ok_or_else(not_found)?;
+        tree.dir_mut(&newparent)?;
+        let destination = tree.child(&newparent, newname);
+
+        #[cfg(target_os = "linux")]
+        if flags.contains(RenameFlags::EXCHANGE) {
+            let destination = destination.ok_or_else(not_found)?;
+            tree.dir_mut(&parent)?
+                .children
+                .insert(name.to_os_string(), destination.clone());
+            tree.dir_mut(&newparent)?
+                .children
+                .insert(newname.to_os_string(), source.clone());
+            tree.node_mut(&source)?.parent = newparent.clone();
+            tree.node_mut(&destination)?.parent = parent.clone();
+            tree.touch_dir(&parent, 0);
+            tree.touch_dir(&newparent, 0);
+            return Ok(());
+        }
+
+        if destination.as_ref() == Some(&source) {
+            return Ok(());
+        }
+        let source_is_dir = tree.node(&source)?.attr.kind == FileKind::Directory;
+        if let Some(destination) = destination {
+            #[cfg(target_os = "linux")]
+            if flags.contains(RenameFlags::NOREPLACE) {
+                return Err(ErrorKind::FileExists.to_error("Destination already exists"));
+            }
+            let destination = tree.node(&destination)?;
+            match (source_is_dir, destination.attr.kind == FileKind::Directory) {
+                (true, false) => {
+                    return Err(ErrorKind::NotADirectory.to_error("Destination is not a directory"))
+                }
+                (false, true) => {
+                    return Err(ErrorKind::IsADirectory.to_error("Destination is a directory"))
+                }
+                (true, true) if !destination.children.is_empty() => {
+                    return Err(ErrorKind::DirectoryNotEmpty.to_error("Directory not empty"))
+                }
+                _ => {}
+            }
+            tree.remove(&newparent, newname)?;
+        }
+
+        tree.dir_mut(&parent)?.children.remove(name);
+        tree.dir_mut(&newparent)?
+            .children
+            .insert(newname.to_os_string(), source.clone());
+        let node = tree.node_mut(&source)?;
+        node.parent = newparent.clone();
+        node.attr.ctime = SystemTime::now();
+        let nlink_change = if source_is_dir { 1 } else { 0 };
+        tree.touch_dir(&parent, -nlink_change);
+        tree.touch_dir(&newparent, nlink_change);
+        Ok(())
+    }
+
+    fn rmdir(&self, _req: &RequestInfo, parent_id: T, name: &OsStr) -> FuseResult<()> {
+        let mut tree = self.tree.lock().unwrap();
+        let parent = tree.resolve(&parent_id)?;
+        let ino = tree.child(&parent, name).ok_or_else(not_found)?;
+        let node = tree.node(&ino)?;
+        if node.attr.kind != FileKind::Directory {
+            return Err(ErrorKind::NotADirectory.to_error("Not a directory"));
+        }
+        if !node.children.is_empty() {
+            return Err(ErrorKind::DirectoryNotEmpty.to_error("Directory not empty"));
+        }
+        tree.remove(&parent, name)
+    }
+
+    fn setattr(
+        &self,
+        _req: &RequestInfo,
+        file_id: T,
+        attrs: SetAttrRequest,
+    ) -> FuseResult<FileAttribute> {
+        let mut tree = self.tree.lock().unwrap();
+        let ino = tree.resolve(&file_id)?;
+        let node = tree.node_mut(&ino)?;
+        let now = SystemTime::now();
+        if let Some(mode) = attrs.mode {
+            node.attr.perm = (mode & 0o7777) as u16;
+        }
+        if let Some(uid) = attrs.uid {
+            node.attr.uid = uid;
+        }
+        if let Some(gid) = attrs.gid {
+            node.attr.gid = gid;
+        }
+        if let Some(size) = attrs.size {
+            if node.attr.kind == FileKind::Directory {
+                return Err(ErrorKind::IsADirectory.to_error("Is a directory"));
+            }
+            resize_data(&mut node.data, size)?;
+            update_size(node);
+            node.attr.mtime = now;
+        }
+        if let Some(atime) = attrs.atime {
+            node.attr.atime = match atime {
+                TimeOrNow::SpecificTime(time) => time,
+                TimeOrNow::Now => now,
+            };
+        }
+        if let Some(mtime) = attrs.mtime {
+            node.attr.mtime = match mtime {
+                TimeOrNow::SpecificTime(time) => time,
+                TimeOrNow::Now => now,
+            };
+        }
+        node.attr.ctime = attrs.ctime.unwrap_or(now);
+        if let Some(crtime) = attrs.crtime {
+            node.attr.crtime = crtime;
+        }
+        if let Some(flags) = attrs.flags {
+            node.attr.flags = flags;
+        }
+        Ok(node.attr.clone())
+    }
+
+    fn statfs(&self, _req: &RequestInfo, _file_id: T) -> FuseResult<StatFs> {
+        let tree = self.tree.lock().unwrap();
+        let used_blocks: u64 = tree.nodes.values().map(|node| node.attr.blocks).sum();
+        Ok(StatFs::builder()
+            .block_size(512)
+            .fragment_size(512)
+            .total_blocks(used_blocks)
+            .total_files(tree.nodes.len() as u64))
+    }
+
+    fn unlink(&self, _req: &RequestInfo, parent_id: T, name: &OsStr) -> FuseResult<()> {
+        let mut tree = self.tree.lock().unwrap();
+        let parent = tree.resolve(&parent_id)?;
+        let ino = tree.child(&parent, name).ok_or_else(not_found)?;
+        if tree.node(&ino)?.attr.kind == FileKind::Directory {
+            return Err(ErrorKind::IsADirectory.to_error("Is a directory"));
+        }
+        tree.remove(&parent, name)
+    }
+
+    fn write(
+        &self,
+        _req: &RequestInfo,
+        file_id: T,
+        _file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        data: Vec<u8>,
+        _write_flags: FUSEWriteFlags,
+        _flags: OpenFlags,
+        _lock_owner: Option<u64>,
+    ) -> FuseResult<u32> {
+        let mut tree = self.tree.lock().unwrap();
+        let ino = tree.resolve(&file_id)?;
+        let node = tree.node_mut(&ino)?;
+        if node.attr.kind == FileKind::Directory {
+            return Err(ErrorKind::IsADirectory.to_error("Is a directory"));
+        }
+        let offset = match seek {
+            SeekFrom::Start(offset) => offset,
+            _ => return Err(ErrorKind::InvalidArgument.to_error("Invalid offset")),
+        };
+        let end = offset
+            .checked_add(data.len() as u64)
+            .ok_or_else(|| ErrorKind::FileTooLarge.to_error("File too large"))?;
+        if end > node.data.len() as u64 {
+            resize_data(&mut node.data, end)?;
+        }
+        node.data[offset as usize..end as usize].copy_from_slice(&data);
+        update_size(node);
+        node.attr.mtime = SystemTime::now();
+        node.attr.ctime = node.attr.mtime;
+        Ok(data.len() as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::DefaultFuseHandler;
+
+    #[test]
+    fn test_seeded_tree() {
+        let fs = MemFs::<PathBuf>::new(DefaultFuseHandler::new());
+        fs.insert_file("a/b/file.txt", "content").unwrap();
+        let req = RequestInfo::default();
+
+        let attr = fs
+            .lookup(&req, PathBuf::from("a/b"), OsStr::new("file.txt"))
+            .unwrap();
+        assert_eq!(attr.kind, FileKind::RegularFile);
+        assert_eq!(attr.size, 7);
+
+        let handle = unsafe { OwnedFileHandle::from_raw(0) };
+        let data = fs
+            .read(
+                &req,
+                PathBuf::from("a/b/file.txt"),
+                handle.borrow(),
+                SeekFrom::Start(3),
+                100,
+                OpenFlags::empty(),
+                None,
+            )
+            .unwrap();
+        assert_eq!(data, b"tent");
+
+        let names: Vec<_> = fs
+            .readdir(&req, PathBuf::from("a"), handle.borrow())
+            .unwrap()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, vec![".", "..", "b"]);
+        assert!(fs.insert_file("a/b", "not a directory").is_err());
+    }
+
+    #[test]
+    fn test_rename_and_remove() {
+        let fs = MemFs::<Inode>::new(DefaultFuseHandler::new());
+        fs.create_dir_all("dir").unwrap();
+        fs.insert_file("file", "content").unwrap();
+        let req = RequestInfo::default();
+
+        let (dir, _) = fs.lookup(&req, ROOT_INODE, OsStr::new("dir")).unwrap();
+        fs.rename(
+            &req,
+            ROOT_INODE,
+            OsStr::new("file"),
+            dir.clone(),
+            OsStr::new("moved"),
+            RenameFlags::empty(),
+        )
+        .unwrap();
+        assert!(fs.lookup(&req, ROOT_INODE, OsStr::new("file")).is_err());
+        assert_eq!(
+            fs.rmdir(&req, ROOT_INODE, OsStr::new("dir"))
+                .unwrap_err()
+                .kind(),
+            ErrorKind::DirectoryNotEmpty
+        );
+
+        fs.unlink(&req, dir, OsStr::new("moved")).unwrap();
+        fs.rmdir(&req, ROOT_INODE, OsStr::new("dir")).unwrap();
+        assert_eq!(fs.getattr(&req, ROOT_INODE, None).unwrap().nlink, 2);
+    }
+
+    #[test]
+    fn test_oversized_file() {
+        let fs = MemFs::<PathBuf>::new(DefaultFuseHandler::new());
+        fs.insert_file("file", "content").unwrap();
+        let req = RequestInfo::default();
+        let handle = unsafe { BorrowedFileHandle::from_raw(0) };
+
+        let error = fs
+            .write(
+                &req,
+                PathBuf::from("file"),
+                handle,
+                SeekFrom::Start(u64::MAX),
+                b"data".to_vec(),
+                FUSEWriteFlags::empty(),
+                OpenFlags::empty(),
+                None,
+            )
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::FileTooLarge);
+        let error = fs
+            .setattr(
+                &req,
+                PathBuf::from("file"),
+                SetAttrRequest::new().size(u64::MAX / 2),
+            )
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NoSpaceLeftOnDevice);
+        // The file is left untouched
+        assert_eq!(
+            fs.getattr(&req, PathBuf::from("file"), None).unwrap().size,
+            7
+        );
+    }
+}
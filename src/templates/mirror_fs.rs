@@ -58,6 +58,38 @@ Specifically, operations such as lstat (used in lookup, getattr, and ls commands
 
 To avoid this issue, ensure that the mountpoint is not located within the mirrored repository.
 
+## Symlinks
+By default, symlinks of the source directory appear as symlinks in the mount, and their targets are resolved
+by the kernel relative to the mount. `follow_symlinks(true)` makes `lookup`, `getattr` and `readdir` report
+the attributes of the targets instead, so symlinks are transparently dereferenced and `readlink` fails with
+`EINVAL` as for any regular file. Symlink loops and dangling symlinks are reported as `ELOOP` and `ENOENT`.
+
+**Security**: the targets are then resolved by the source filesystem, outside of the mount. A symlink
+pointing outside of the source directory, for example to `/etc`, exposes its target through the mount with
+the permissions of the process serving the filesystem. Only enable it for trusted source directories.
+
+## Write Cache
+`MirrorFs::with_write_cache` creates a `MirrorFs` buffering the writes of each file handle up to a given size.
+Contiguous writes are coalesced and written to the source file on `flush`, `fsync` and `release`, or when a
+non-contiguous write arrives. Reads and attribute changes of a file first write out its buffers, and `getattr` reports
+the buffered size. Write errors may then be reported by the call writing the buffer out rather than by `write`.
+
+As running out of space would then only be reported once the application believes its data written,
+`MirrorFs::with_write_cache_policy` takes a `SpacePolicy` deciding, from the free space of the source filesystem,
+when writes must bypass the cache so that `ENOSPC` is returned by the `write` call itself.
+`MirrorFs::with_write_cache` uses `SpacePolicy::default()`.
+
+## Writeback Cache
+`MirrorFs::writeback_cache(true)` requests `FUSEInitFlags::WRITEBACK_CACHE` in `init`: the kernel then keeps written
+data in its page cache and sends larger writes later. As it reads through write-only handles to complete partially
+written pages, and appends to files by itself, files are opened with `OpenFlags::for_writeback_cache`. Opening a
+file write-only then requires the read permission on the source file.
+
+## Source Watch
+With the `inotify` feature on Linux, a `SourceWatcher` can follow changes made directly to the source directory
+and invalidate the kernel caches of the mount through the `Notifier` returned by `spawn_mount_with_notifier`,
+so out-of-band modifications show up without waiting for the TTLs to expire.
+
 ## Read-Only vs Read-Write
 - MirrorFsReadOnly: This variant only implements methods for reading and accessing file metadata. It does not allow any modifications to the mirrored filesystem.
 - MirrorFs: This variant implements all methods from MirrorFsReadOnly plus additional methods for modifying the filesystem, such as creating, deleting, and modifying files and directories.
@@ -70,8 +102,13 @@ If you intend to enforce read-only at the fuse level,
 prefer the usage of option `MountOption::RO` instead of `MirrorFsReadOnly`.
 */
 
+use std::collections::HashMap;
 use std::ffi::{OsStr, OsString};
+use std::fs::{self, File};
+use std::os::unix::fs::MetadataExt;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 use fd_handler_helper::*;
 
@@ -79,25 +116,29 @@ use crate::prelude::*;
 use crate::templates::*;
 use crate::unix_fs;
 
+#[cfg(all(feature = "inotify", target_os = "linux"))]
+mod source_watcher;
+#[cfg(all(feature = "inotify", target_os = "linux"))]
+pub use source_watcher::SourceWatcher;
+
 macro_rules! mirror_fs_readonly_methods {
     () => {
-        fn access(&self, _req: &RequestInfo, file_id: PathBuf, mask: AccessMask) -> FuseResult<()> {
-            let file_path = self.source_path.join(file_id);
-            unix_fs::access(&file_path, mask)
+        // The creation time is read with statx on Linux and stat on BSD-like systems, it falls back to
+        // the modification time if the source filesystem doesn't record it
+        fn available_attributes(&self) -> AttrMask {
+            AttrMask::all()
         }
 
-        fn getattr(
-            &self,
-            _req: &RequestInfo,
-            file_id: PathBuf,
-            _file_handle: Option<BorrowedFileHandle>,
-        ) -> FuseResult<FileAttribute> {
+        fn id_exists(&self, file_id: PathBuf) -> bool {
+            self.source_path.join(file_id).symlink_metadata().is_ok()
+        }
+
+        fn access(&self, _req: &RequestInfo, file_id: PathBuf, mask: AccessMask) -> FuseResult<()> {
             let file_path = self.source_path.join(file_id);
-            let fd = unix_fs::open(file_path.as_ref(), OpenFlags::empty())?;
-            let result = unix_fs::getattr(fd.as_fd());
-            result
+            unix_fs::access(&file_path, mask)
         }
 
+        #[cfg(feature = "xattr")]
         fn getxattr(
             &self,
             _req: &RequestInfo,
@@ -109,6 +150,7 @@ macro_rules! mirror_fs_readonly_methods {
             unix_fs::getxattr(&file_path, name, size)
         }
 
+        #[cfg(feature = "xattr")]
         fn listxattr(
             &self,
             _req: &RequestInfo,
@@ -126,7 +168,10 @@ macro_rules! mirror_fs_readonly_methods {
             name: &OsStr,
         ) -> FuseResult<FileAttribute> {
             let file_path = self.source_path.join(parent_id).join(name);
-            unix_fs::lookup(&file_path)
+            if self.follow_symlinks {
+                return unix_fs::stat(&file_path);
+            }
+            lookup_path(&file_path)
         }
 
         fn open(
@@ -136,7 +181,7 @@ macro_rules! mirror_fs_readonly_methods {
             flags: OpenFlags,
         ) -> FuseResult<(OwnedFileHandle, FUSEOpenResponseFlags)> {
             let file_path = self.source_path.join(file_id);
-            let fd = unix_fs::open(file_path.as_ref(), flags)?;
+            let fd = unix_fs::open(file_path.as_ref(), self.adjust_open_flags(flags))?;
             // Open by definition returns positive Fd or error
             let file_handle = OwnedFileHandle::from_owned_fd(fd).unwrap();
             Ok((file_handle, FUSEOpenResponseFlags::empty()))
@@ -154,12 +199,24 @@ macro_rules! mirror_fs_readonly_methods {
             result.push((OsString::from("."), FileKind::Directory));
             result.push((OsString::from(".."), FileKind::Directory));
             for (child_name, child_kind) in children {
+                let child_kind = match child_kind {
+                    // Dangling symlinks and loops are kept as is, their lookup reports the error
+                    FileKind::Symlink if self.follow_symlinks => {
+                        unix_fs::stat(&folder_path.join(&child_name))
+                            .map_or(child_kind, |attr| attr.kind)
+                    }
+                    _ => child_kind,
+                };
                 result.push((child_name, child_kind));
             }
             Ok(result)
         }
 
         fn readlink(&self, _req: &RequestInfo, file_id: PathBuf) -> FuseResult<Vec<u8>> {
+            if self.follow_symlinks {
+                return Err(ErrorKind::InvalidArgument
+                    .to_error(format!("{}: symlinks are followed", file_id.display())));
+            }
             let file_path = self.source_path.join(file_id);
             unix_fs::readlink(&file_path)
         }
@@ -183,12 +240,25 @@ macro_rules! mirror_fs_readwrite_methods {
             flags: OpenFlags,
         ) -> FuseResult<(OwnedFileHandle, FileAttribute, FUSEOpenResponseFlags)> {
             let file_path = self.source_path.join(parent_id).join(name);
-            let (fd, file_attr) = unix_fs::create(&file_path, mode, umask, flags)?;
+            let (fd, file_attr) =
+                unix_fs::create(&file_path, mode, umask, self.adjust_open_flags(flags))?;
             // Open by definition returns positive Fd or error
             let file_handle = OwnedFileHandle::from_owned_fd(fd).unwrap();
             Ok((file_handle, file_attr, FUSEOpenResponseFlags::empty()))
         }
 
+        fn link(
+            &self,
+            _req: &RequestInfo,
+            file_id: PathBuf,
+            newparent: PathBuf,
+            newname: &OsStr,
+        ) -> FuseResult<FileAttribute> {
+            let file_path = self.source_path.join(file_id);
+            let new_path = self.source_path.join(newparent).join(newname);
+            unix_fs::link(&file_path, &new_path)
+        }
+
         fn mkdir(
             &self,
             _req: &RequestInfo,
@@ -214,6 +284,7 @@ macro_rules! mirror_fs_readwrite_methods {
             unix_fs::mknod(&file_path, mode, umask, rdev)
         }
 
+        #[cfg(feature = "xattr")]
         fn removexattr(
             &self,
             _req: &RequestInfo,
@@ -243,16 +314,7 @@ macro_rules! mirror_fs_readwrite_methods {
             unix_fs::rmdir(&file_path)
         }
 
-        fn setattr(
-            &self,
-            _req: &RequestInfo,
-            file_id: PathBuf,
-            attrs: SetAttrRequest,
-        ) -> FuseResult<FileAttribute> {
-            let file_path = self.source_path.join(file_id);
-            unix_fs::setattr(&file_path, attrs)
-        }
-
+        #[cfg(feature = "xattr")]
         fn setxattr(
             &self,
             _req: &RequestInfo,
@@ -290,10 +352,321 @@ pub trait MirrorFsTrait: FuseHandler<PathBuf> {
     fn source_dir(&self) -> &Path;
 }
 
+/// Uses `statx` on Linux to report the real creation time of source files
+fn lookup_path(file_path: &Path) -> FuseResult<FileAttribute> {
+    #[cfg(target_os = "linux")]
+    return unix_fs::statx(file_path);
+    #[cfg(not(target_os = "linux"))]
+    unix_fs::lookup(file_path)
+}
+
+fn getattr_path(file_path: &Path, follow_symlinks: bool) -> FuseResult<FileAttribute> {
+    if follow_symlinks {
+        return unix_fs::stat(file_path);
+    }
+    #[cfg(target_os = "linux")]
+    return unix_fs::statx(file_path);
+    #[cfg(not(target_os = "linux"))]
+    {
+        let fd = unix_fs::open(file_path, OpenFlags::empty())?;
+        unix_fs::getattr(fd.as_fd())
+    }
+}
+
+/// Identifies a source file by its device and inode numbers, which don't change when it is renamed
+type FileKey = (u64, u64);
+
+fn path_key(file_path: &Path) -> FuseResult<FileKey> {
+    let metadata = fs::metadata(file_path)?;
+    Ok((metadata.dev(), metadata.ino()))
+}
+
+fn handle_key(file_handle: BorrowedFileHandle) -> FuseResult<FileKey> {
+    let metadata = File::from(file_handle.as_borrowed_fd().try_clone_to_owned()?).metadata()?;
+    Ok((metadata.dev(), metadata.ino()))
+}
+
+/// Writes of a file handle not yet written to the source file, as a single contiguous region
+struct PendingWrite {
+    file_key: FileKey,
+    offset: u64,
+    data: Vec<u8>,
+}
+
+impl PendingWrite {
+    fn end(&self) -> u64 {
+        self.offset + self.data.len() as u64
+    }
+}
+
+fn write_all(file_handle: BorrowedFileHandle, mut offset: u64, mut data: &[u8]) -> FuseResult<()> {
+    while !data.is_empty() {
+        let written = unix_fs::write(file_handle.as_borrowed_fd(), SeekFrom::Start(offset), data)?;
+        if written == 0 {
+            return Err(ErrorKind::InputOutputError.to_error("Short write to source file"));
+        }
+        offset += written as u64;
+        data = &data[written..];
+    }
+    Ok(())
+}
+
+/// Decides when the write cache of `MirrorFs` must write through because the source filesystem is running out of space.
+///
+/// Free space is the space available to non-root users, as reported by `statfs` on the source directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpacePolicy {
+    /// Always buffers writes. Running out of space is only reported when the buffers are written out.
+    Unchecked,
+    /// Probes the free space at most once every `probe_interval`, and writes through while
+    /// less than `low_space` bytes would remain free after buffering.
+    WriteThroughWhenLow {
+        low_space: u64,
+        probe_interval: Duration,
+    },
+    /// Probes the free space before each buffered write, and never buffers more than is available.
+    Strict,
+}
+
+impl Default for SpacePolicy {
+    /// Writes through when less than 64 MiB would remain free, probing at most once per second.
+    fn default() -> Self {
+        SpacePolicy::WriteThroughWhenLow {
+            low_space: 64 * 1024 * 1024,
+            probe_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Per file handle write buffers of `MirrorFs::with_write_cache`
+///
+/// Buffers are taken out of the map before being written out, so that the lock is never held during I/O.
+struct WriteCache {
+    capacity: usize,
+    source_path: PathBuf,
+    policy: SpacePolicy,
+    /// Last probed free space of the source filesystem, with the time of the probe
+    last_probe: Mutex<Option<(Instant, u64)>>,
+    buffers: Mutex<HashMap<u64, PendingWrite>>,
+}
+
+impl WriteCache {
+    fn new(capacity: usize, source_path: PathBuf, policy: SpacePolicy) -> Self {
+        Self {
+            capacity,
+            source_path,
+            policy,
+            last_probe: Mutex::new(None),
+            buffers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn available_space(&self, max_age: Duration) -> FuseResult<u64> {
+        if let Some((probed_at, available)) = *self.last_probe.lock().unwrap() {
+            if probed_at.elapsed() < max_age {
+                return Ok(available);
+            }
+        }
+        let stat = unix_fs::statfs(&self.source_path)?;
+        let available = stat.available_blocks * stat.block_size as u64;
+        *self.last_probe.lock().unwrap() = Some((Instant::now(), available));
+        Ok(available)
+    }
+
+    /// Whether `len` more bytes can be buffered on top of `buffered` bytes, according to the space policy
+    fn has_space_for(&self, buffered: u64, len: u64) -> FuseResult<bool> {
+        match self.policy {
+            SpacePolicy::Unchecked => Ok(true),
+            SpacePolicy::WriteThroughWhenLow {
+                low_space,
+                probe_interval,
+            } => Ok(self.available_space(probe_interval)? >= low_space + buffered + len),
+            SpacePolicy::Strict => Ok(self.available_space(Duration::ZERO)? >= buffered + len),
+        }
+    }
+
+    fn write(
+        &self,
+        file_handle: BorrowedFileHandle,
+        offset: u64,
+        data: Vec<u8>,
+    ) -> FuseResult<u32> {
+        let len = data.len();
+        let buffered: u64 = {
+            let buffers = self.buffers.lock().unwrap();
+            buffers.values().map(|p| p.data.len() as u64).sum()
+        };
+        let has_space = self.has_space_for(buffered, len as u64)?;
+        let previous = {
+            let mut buffers = self.buffers.lock().unwrap();
+            if let Some(pending) = buffers.get_mut(&file_handle.as_raw()) {
+                if has_space && pending.end() == offset && pending.data.len() + len <= self.capacity
+                {
+                    pending.data.extend_from_slice(&data);
+                    return Ok(len as u32);
+                }
+            }
+            buffers.remove(&file_handle.as_raw())
+        };
+        if let Some(pending) = &previous {
+            write_all(file_handle, pending.offset, &pending.data)?;
+        }
+        if !has_space || len >= self.capacity {
+            write_all(file_handle, offset, &data)?;
+        } else {
+            let file_key = match previous {
+                Some(pending) => pending.file_key,
+                None => handle_key(file_handle)?,
+            };
+            self.buffers.lock().unwrap().insert(
+                file_handle.as_raw(),
+                PendingWrite {
+                    file_key,
+                    offset,
+                    data,
+                },
+            );
+        }
+        Ok(len as u32)
+    }
+
+    /// Writes out the buffer of a file handle
+    fn flush_handle(&self, file_handle: BorrowedFileHandle) -> FuseResult<()> {
+        let pending = self.buffers.lock().unwrap().remove(&file_handle.as_raw());
+        match pending {
+            Some(pending) => write_all(file_handle, pending.offset, &pending.data),
+            None => Ok(()),
+        }
+    }
+
+    /// Writes out the buffers taken out of the map, reporting the first error
+    fn write_out(pending: Vec<(u64, PendingWrite)>) -> FuseResult<()> {
+        let mut result = Ok(());
+        for (fh, pending) in pending {
+            // Safe because buffers are written out before their file handle is released
+            let file_handle = unsafe { BorrowedFileHandle::from_raw(fh) };
+            let written = write_all(file_handle, pending.offset, &pending.data);
+            if result.is_ok() {
+                result = written;
+            }
+        }
+        result
+    }
+
+    /// Writes out the buffers of every file handle opened on the file at `file_path`, under any name
+    fn flush_path(&self, file_path: &Path) -> FuseResult<()> {
+        if self.buffers.lock().unwrap().is_empty() {
+            return Ok(());
+        }
+        // A file no longer reachable by its path is only written out through its own handles
+        let Ok(file_key) = path_key(file_path) else {
+            return Ok(());
+        };
+        let pending = {
+            let mut buffers = self.buffers.lock().unwrap();
+            let handles: Vec<u64> = buffers
+                .iter()
+                .filter(|(_, pending)| pending.file_key == file_key)
+                .map(|(fh, _)| *fh)
+                .collect();
+            handles
+                .into_iter()
+                .map(|fh| (fh, buffers.remove(&fh).unwrap()))
+                .collect()
+        };
+        Self::write_out(pending)
+    }
+
+    /// Writes out the buffers of every file handle
+    fn flush_all(&self) -> FuseResult<()> {
+        let pending = self.buffers.lock().unwrap().drain().collect();
+        Self::write_out(pending)
+    }
+
+    /// Returns the end of the furthest buffer of the file at `file_path`
+    fn buffered_end(&self, file_path: &Path) -> Option<u64> {
+        if self.buffers.lock().unwrap().is_empty() {
+            return None;
+        }
+        let file_key = path_key(file_path).ok()?;
+        self.buffers
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|pending| pending.file_key == file_key)
+            .map(PendingWrite::end)
+            .max()
+    }
+}
+
 /// Specific documentation is located in parent module documentation.
 pub struct MirrorFs {
     source_path: PathBuf,
     inner: Box<FdHandlerHelper<PathBuf>>,
+    write_cache: Option<WriteCache>,
+    follow_symlinks: bool,
+    writeback_cache: bool,
+}
+
+impl MirrorFs {
+    /// Dereferences the symlinks of the source directory, see the module documentation for its security implication
+    pub fn follow_symlinks(mut self, follow: bool) -> Self {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    /// Enables the kernel writeback cache, see the module documentation
+    pub fn writeback_cache(mut self, enabled: bool) -> Self {
+        self.writeback_cache = enabled;
+        self
+    }
+
+    fn adjust_open_flags(&self, flags: OpenFlags) -> OpenFlags {
+        if self.writeback_cache {
+            flags.for_writeback_cache()
+        } else {
+            flags
+        }
+    }
+
+    /// Creates a `MirrorFs` buffering up to `cache_bytes` of contiguous writes for each file handle
+    pub fn with_write_cache<U: FuseHandler<PathBuf>>(
+        source_path: PathBuf,
+        inner: U,
+        cache_bytes: usize,
+    ) -> Self {
+        Self::with_write_cache_policy(source_path, inner, cache_bytes, SpacePolicy::default())
+    }
+
+    /// Same as `with_write_cache`, with `policy` deciding when writes bypass the cache as space runs low
+    pub fn with_write_cache_policy<U: FuseHandler<PathBuf>>(
+        source_path: PathBuf,
+        inner: U,
+        cache_bytes: usize,
+        policy: SpacePolicy,
+    ) -> Self {
+        Self {
+            write_cache: Some(WriteCache::new(cache_bytes, source_path.clone(), policy)),
+            source_path,
+            inner: Box::new(FdHandlerHelper::new(inner)),
+            follow_symlinks: false,
+            writeback_cache: false,
+        }
+    }
+
+    fn flush_path(&self, file_id: &Path) -> FuseResult<()> {
+        match &self.write_cache {
+            Some(write_cache) => write_cache.flush_path(&self.source_path.join(file_id)),
+            None => Ok(()),
+        }
+    }
+
+    fn flush_handle(&self, file_handle: BorrowedFileHandle) -> FuseResult<()> {
+        match &self.write_cache {
+            Some(write_cache) => write_cache.flush_handle(file_handle),
+            None => Ok(()),
+        }
+    }
 }
 
 impl MirrorFsTrait for MirrorFs {
@@ -301,6 +674,9 @@ impl MirrorFsTrait for MirrorFs {
         Self {
             source_path,
             inner: Box::new(FdHandlerHelper::new(inner)),
+            write_cache: None,
+            follow_symlinks: false,
+            writeback_cache: false,
         }
     }
 
@@ -316,12 +692,230 @@ impl FuseHandler<PathBuf> for MirrorFs {
 
     mirror_fs_readonly_methods!();
     mirror_fs_readwrite_methods!();
+
+    fn init(&self, req: &RequestInfo, config: &mut KernelConfig) -> FuseResult<()> {
+        if self.writeback_cache {
+            // Left out if the kernel doesn't offer it, the mount then works without the cache
+            let _ = config.add_capabilities(FUSEInitFlags::WRITEBACK_CACHE.bits().into());
+        }
+        self.inner.init(req, config)
+    }
+
+    fn copy_file_range(
+        &self,
+        req: &RequestInfo,
+        file_in: PathBuf,
+        file_handle_in: BorrowedFileHandle,
+        offset_in: i64,
+        file_out: PathBuf,
+        file_handle_out: BorrowedFileHandle,
+        offset_out: i64,
+        len: u64,
+        flags: u32,
+    ) -> FuseResult<u32> {
+        self.flush_path(&file_in)?;
+        self.flush_path(&file_out)?;
+        self.inner.copy_file_range(
+            req,
+            file_in,
+            file_handle_in,
+            offset_in,
+            file_out,
+            file_handle_out,
+            offset_out,
+            len,
+            flags,
+        )
+    }
+
+    fn drop_caches(&self, req: &RequestInfo) -> FuseResult<()> {
+        if let Some(write_cache) = &self.write_cache {
+            write_cache.flush_all()?;
+        }
+        self.inner.drop_caches(req)
+    }
+
+    fn fallocate(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        offset: i64,
+        length: i64,
+        mode: FallocateFlags,
+    ) -> FuseResult<()> {
+        self.flush_path(&file_id)?;
+        self.inner
+            .fallocate(req, file_id, file_handle, offset, length, mode)
+    }
+
+    fn flush(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        lock_owner: u64,
+    ) -> FuseResult<()> {
+        self.flush_handle(file_handle)?;
+        self.inner.flush(req, file_id, file_handle, lock_owner)
+    }
+
+    fn fsync(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        datasync: bool,
+    ) -> FuseResult<()> {
+        self.flush_handle(file_handle)?;
+        self.inner.fsync(req, file_id, file_handle, datasync)
+    }
+
+    fn getattr(
+        &self,
+        _req: &RequestInfo,
+        file_id: PathBuf,
+        _file_handle: Option<BorrowedFileHandle>,
+    ) -> FuseResult<FileAttribute> {
+        let file_path = self.source_path.join(file_id);
+        let mut file_attr = getattr_path(&file_path, self.follow_symlinks)?;
+        // Buffers of every handle are accounted for, which includes the one of `file_handle` if provided
+        if let (Some(write_cache), FileKind::RegularFile) = (&self.write_cache, file_attr.kind) {
+            if let Some(end) = write_cache.buffered_end(&file_path) {
+                file_attr.size = file_attr.size.max(end);
+            }
+        }
+        Ok(file_attr)
+    }
+
+    fn lseek(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+    ) -> FuseResult<i64> {
+        self.flush_path(&file_id)?;
+        self.inner.lseek(req, file_id, file_handle, seek)
+    }
+
+    fn lseek_raw(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        offset: i64,
+        whence: i32,
+    ) -> FuseResult<i64> {
+        // Buffered writes may fill holes
+        self.flush_path(&file_id)?;
+        self.inner
+            .lseek_raw(req, file_id, file_handle, offset, whence)
+    }
+
+    fn read(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        size: u32,
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+    ) -> FuseResult<Vec<u8>> {
+        self.flush_path(&file_id)?;
+        self.inner
+            .read(req, file_id, file_handle, seek, size, flags, lock_owner)
+    }
+
+    fn read_into(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        buf: &mut [u8],
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+    ) -> FuseResult<usize> {
+        self.flush_path(&file_id)?;
+        self.inner
+            .read_into(req, file_id, file_handle, seek, buf, flags, lock_owner)
+    }
+
+    fn release(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: OwnedFileHandle,
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+        flush: bool,
+    ) -> FuseResult<()> {
+        let flushed = self.flush_handle(file_handle.borrow());
+        // The file handle is released even if its buffer couldn't be written out
+        self.inner
+            .release(req, file_id, file_handle, flags, lock_owner, flush)?;
+        flushed
+    }
+
+    fn setattr(
+        &self,
+        _req: &RequestInfo,
+        file_id: PathBuf,
+        attrs: SetAttrRequest,
+    ) -> FuseResult<FileAttribute> {
+        self.flush_path(&file_id)?;
+        let file_path = self.source_path.join(file_id);
+        unix_fs::setattr(&file_path, attrs)
+    }
+
+    fn write(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        data: Vec<u8>,
+        write_flags: FUSEWriteFlags,
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+    ) -> FuseResult<u32> {
+        match (&self.write_cache, seek) {
+            (Some(write_cache), SeekFrom::Start(offset)) => {
+                write_cache.write(file_handle, offset, data)
+            }
+            _ => self.inner.write(
+                req,
+                file_id,
+                file_handle,
+                seek,
+                data,
+                write_flags,
+                flags,
+                lock_owner,
+            ),
+        }
+    }
 }
 
 /// Specific documentation is located in parent module documentation.
 pub struct MirrorFsReadOnly {
     source_path: PathBuf,
     inner: Box<FdHandlerHelperReadOnly<PathBuf>>,
+    follow_symlinks: bool,
+}
+
+impl MirrorFsReadOnly {
+    /// Dereferences the symlinks of the source directory, see the module documentation for its security implication
+    pub fn follow_symlinks(mut self, follow: bool) -> Self {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    fn adjust_open_flags(&self, flags: OpenFlags) -> OpenFlags {
+        flags
+    }
 }
 
 impl MirrorFsTrait for MirrorFsReadOnly {
@@ -329,6 +923,7 @@ impl MirrorFsTrait for MirrorFsReadOnly {
         Self {
             source_path,
             inner: Box::new(FdHandlerHelperReadOnly::new(inner)),
+            follow_symlinks: false,
         }
     }
 
@@ -343,4 +938,111 @@ impl FuseHandler<PathBuf> for MirrorFsReadOnly {
     }
 
     mirror_fs_readonly_methods!();
+
+    fn getattr(
+        &self,
+        _req: &RequestInfo,
+        file_id: PathBuf,
+        _file_handle: Option<BorrowedFileHandle>,
+    ) -> FuseResult<FileAttribute> {
+        getattr_path(&self.source_path.join(file_id), self.follow_symlinks)
+    }
+
+    fn read_into(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        buf: &mut [u8],
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+    ) -> FuseResult<usize> {
+        self.inner
+            .read_into(req, file_id, file_handle, seek, buf, flags, lock_owner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn test_getattr_reports_creation_time() {
+        let source = tempfile::TempDir::new().unwrap();
+        std::fs::write(source.path().join("file"), "content").unwrap();
+        let fs = MirrorFs::new(source.path().to_path_buf(), DefaultFuseHandler::new());
+        let req = RequestInfo::default();
+
+        // As replied by the driver
+        let attr = fs
+            .getattr(&req, PathBuf::from("file"), None)
+            .unwrap()
+            .clear_unavailable(fs.available_attributes());
+        assert_ne!(attr.crtime, UNIX_EPOCH);
+    }
+
+    #[test]
+    fn test_write_cache_follows_renamed_file() {
+        let source = tempfile::TempDir::new().unwrap();
+        std::fs::write(source.path().join("old"), "").unwrap();
+        let fs = MirrorFs::with_write_cache(
+            source.path().to_path_buf(),
+            DefaultFuseHandler::new(),
+            4096,
+        );
+        let req = RequestInfo::default();
+
+        let (file_handle, _) = fs
+            .open(&req, PathBuf::from("old"), OpenFlags::READ_WRITE)
+            .unwrap();
+        let written = fs
+            .write(
+                &req,
+                PathBuf::from("old"),
+                file_handle.borrow(),
+                SeekFrom::Start(0),
+                b"buffered".to_vec(),
+                FUSEWriteFlags::empty(),
+                OpenFlags::READ_WRITE,
+                None,
+            )
+            .unwrap();
+        assert_eq!(written, 8);
+        assert_eq!(std::fs::read(source.path().join("old")).unwrap(), b"");
+
+        fs.rename(
+            &req,
+            PathBuf::new(),
+            OsStr::new("old"),
+            PathBuf::new(),
+            OsStr::new("new"),
+            RenameFlags::empty(),
+        )
+        .unwrap();
+        let attr = fs.getattr(&req, PathBuf::from("new"), None).unwrap();
+        assert_eq!(attr.size, 8);
+        let data = fs
+            .read(
+                &req,
+                PathBuf::from("new"),
+                file_handle.borrow(),
+                SeekFrom::Start(0),
+                4096,
+                OpenFlags::READ_WRITE,
+                None,
+            )
+            .unwrap();
+        assert_eq!(data, b"buffered");
+        fs.release(
+            &req,
+            PathBuf::from("new"),
+            file_handle,
+            OpenFlags::READ_WRITE,
+            None,
+            true,
+        )
+        .unwrap();
+    }
 }
@@ -0,0 +1,181 @@
+//! Keeps a mounted `MirrorFs` coherent with changes made directly to its source directory, using inotify.
+
+use std::collections::HashMap;
+use std::ffi::{CString, OsStr};
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+use log::warn;
+
+use crate::notifier::Notifier;
+use crate::prelude::*;
+use crate::templates::BackgroundTask;
+
+const WATCH_MASK: u32 = libc::IN_CREATE
+    | libc::IN_DELETE
+    | libc::IN_MODIFY
+    | libc::IN_ATTRIB
+    | libc::IN_MOVED_FROM
+    | libc::IN_MOVED_TO
+    | libc::IN_CLOSE_WRITE
+    | libc::IN_ONLYDIR;
+
+/// How long the watch thread waits for events before checking if it must stop, in milliseconds
+const POLL_TIMEOUT_MS: i32 = 100;
+
+/// Watches the source directory of a `MirrorFs` and invalidates the kernel caches of the files changed out-of-band.
+///
+/// Changes are reported promptly, without waiting for the attribute and entry TTLs to expire.
+/// Changes made through the mount are reported too, which only costs an extra lookup.
+///
+/// The watch thread stops when the `SourceWatcher` is stopped or dropped.
+///
+/// ```rust, ignore
+/// use easy_fuser::prelude::*;
+/// use easy_fuser::templates::{mirror_fs::*, DefaultFuseHandler};
+/// use std::path::PathBuf;
+///
+/// let source = PathBuf::from("/srv/data");
+/// let fs = MirrorFs::new(source.clone(), DefaultFuseHandler::new());
+/// let (session, notifier) = spawn_mount_with_notifier(fs, "/mnt/data", &[], 4).unwrap();
+/// let watcher = SourceWatcher::start(&source, notifier).unwrap();
+/// // ...
+/// watcher.stop();
+/// session.join();
+/// ```
+pub struct SourceWatcher {
+    task: BackgroundTask,
+}
+
+impl SourceWatcher {
+    /// Watches `source_path` and its subdirectories, sending invalidations through `notifier`
+    pub fn start(source_path: &Path, notifier: Notifier<PathBuf>) -> FuseResult<Self> {
+        let mut watches = Watches::new(source_path.to_path_buf())?;
+        watches.add_recursive(Path::new(""))?;
+        let task = BackgroundTask::new();
+        task.start(move |shutdown| watches.run(&notifier, shutdown))?;
+        Ok(Self { task })
+    }
+
+    /// Stops the watch thread
+    pub fn stop(&self) {
+        self.task.stop();
+    }
+}
+
+/// Inotify instance and the directory watched by each of its watch descriptors
+struct Watches {
+    fd: OwnedFd,
+    source_path: PathBuf,
+    dirs: HashMap<i32, PathBuf>,
+}
+
+impl Watches {
+    fn new(source_path: PathBuf) -> FuseResult<Self> {
+        let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(PosixError::last_error("inotify_init1 failed"));
+        }
+        Ok(Self {
+            fd: unsafe { OwnedFd::from_raw_fd(fd) },
+            source_path,
+            dirs: HashMap::new(),
+        })
+    }
+
+    /// Watches the directory at `relative_path` (relative to the source) and its subdirectories
+    fn add_recursive(&mut self, relative_path: &Path) -> FuseResult<()> {
+        let dir_path = self.source_path.join(relative_path);
+        let c_path = CString::new(dir_path.as_os_str().as_bytes())
+            .map_err(|_| ErrorKind::InvalidArgument.to_error("Path contains a nul byte"))?;
+        let wd =
+            unsafe { libc::inotify_add_watch(self.fd.as_raw_fd(), c_path.as_ptr(), WATCH_MASK) };
+        if wd < 0 {
+            return Err(PosixError::last_error(format!(
+                "{}: inotify_add_watch failed",
+                dir_path.display()
+            )));
+        }
+        self.dirs.insert(wd, relative_path.to_path_buf());
+        for entry in fs::read_dir(&dir_path)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                self.add_recursive(&relative_path.join(entry.file_name()))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn run(mut self, notifier: &Notifier<PathBuf>, shutdown: Receiver<()>) {
+        let mut buffer = vec![0u8; 64 * 1024];
+        while let Err(TryRecvError::Empty) = shutdown.try_recv() {
+            let mut pollfd = libc::pollfd {
+                fd: self.fd.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            if unsafe { libc::poll(&mut pollfd, 1, POLL_TIMEOUT_MS) } <= 0 {
+                continue;
+            }
+            let len = unsafe {
+                libc::read(
+                    self.fd.as_raw_fd(),
+                    buffer.as_mut_ptr() as *mut libc::c_void,
+                    buffer.len(),
+                )
+            };
+            if len <= 0 {
+                continue;
+            }
+            let changed = self.parse_events(&buffer[..len as usize]);
+            if let Err(e) = notifier.invalidate_batch(&changed) {
+                warn!("SourceWatcher: invalidation failed [{}]", e);
+            }
+        }
+    }
+
+    /// Returns the paths whose cached entries and attributes are outdated
+    fn parse_events(&mut self, events: &[u8]) -> Vec<PathBuf> {
+        let header_len = std::mem::size_of::<libc::inotify_event>();
+        let mut changed = Vec::new();
+        let mut offset = 0;
+        while offset + header_len <= events.len() {
+            let event = unsafe {
+                std::ptr::read_unaligned(events[offset..].as_ptr() as *const libc::inotify_event)
+            };
+            let name_start = offset + header_len;
+            let name_end = (name_start + event.len as usize).min(events.len());
+            let name = events[name_start..name_end]
+                .split(|byte| *byte == 0)
+                .next()
+                .unwrap_or_default();
+            offset = name_start + event.len as usize;
+
+            if event.mask & libc::IN_IGNORED != 0 {
+                // The watched directory was removed
+                self.dirs.remove(&event.wd);
+                continue;
+            }
+            let Some(dir) = self.dirs.get(&event.wd).cloned() else {
+                continue;
+            };
+            if name.is_empty() {
+                changed.push(dir);
+                continue;
+            }
+            let path = dir.join(OsStr::from_bytes(name));
+            if event.mask & libc::IN_ISDIR != 0
+                && event.mask & (libc::IN_CREATE | libc::IN_MOVED_TO) != 0
+            {
+                if let Err(e) = self.add_recursive(&path) {
+                    warn!("SourceWatcher: {} can't be watched [{}]", path.display(), e);
+                }
+            }
+            changed.push(dir);
+            changed.push(path);
+        }
+        changed
+    }
+}
@@ -0,0 +1,7 @@
+//! # Network backends
+//!
+//! Templates exposing the files of a remote server. They are compiled in with the feature of their protocol.
+//!
+//! - `sftp_fs` (feature `ssh`): An SFTP server, over a pool of SSH connections.
+
+pub mod sftp_fs;
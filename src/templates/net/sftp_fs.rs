@@ -0,0 +1,779 @@
+/*!
+# SftpFs
+
+A FUSE handler exposing the files of an SFTP server, in read-write mode.
+
+## Overview
+
+`SftpFs` implements `FuseHandler<PathBuf>` over SSH connections to the server. File identifiers are paths
+relative to `SftpConfig::remote_root`.
+
+- **Connection pool**: An SFTP session serializes its requests, so sharing a single one between the threads
+  of the `parallel` driver would serialize every operation. Connections are instead taken from a pool,
+  opened on demand and kept up to `SftpConfig::pool_size` when idle.
+- **Retry**: When a request fails because its connection is broken (server restart, network outage),
+  the connection is dropped. Requests which can safely run twice (`lstat`, `readdir`, `readlink`, and
+  opening an existing file without truncating it) are retried once on a new connection. Others fail, as the
+  server may have applied them before the connection broke. Open files are bound to the connection they were
+  opened on, and fail until they are opened again.
+- **Caching**: Attributes and directory listings are kept for `SftpConfig::cache_ttl`, as a single
+  `ls -l` otherwise costs one round trip per entry. Entries modified through the mount are invalidated,
+  changes made by other clients of the server are visible once the cache expires.
+
+SFTP (version 3, as served by OpenSSH) only reports the size, ownership, permissions, and access and
+modification times of files. Other attributes are reported as "not provided" (see `available_attributes`).
+
+## Usage
+
+```text
+let config = SftpConfig::new("example.com:22", "user", SftpAuth::Agent);
+let fs = SftpFs::connect(config, DefaultFuseHandler::new())?;
+mount(fs, mountpoint, &[], 4)
+```
+
+## Unimplemented Functions
+
+- link
+- xattr operations
+- setlk, getlk
+- bmap
+- ioctl
+*/
+
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::io::{Read, Seek, Write};
+use std::net::TcpStream;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use ssh2::{ErrorCode, FileStat, OpenType, Session, Sftp};
+
+use crate::prelude::*;
+
+/// Authentication method of the SSH connections.
+#[derive(Clone)]
+pub enum SftpAuth {
+    Password(String),
+    /// Key pair stored in files, the public key is derived from the private one if not given
+    PublicKey {
+        public_key: Option<PathBuf>,
+        private_key: PathBuf,
+        passphrase: Option<String>,
+    },
+    /// Keys of the running SSH agent
+    Agent,
+}
+
+/// Connection parameters of a `SftpFs`.
+#[derive(Clone)]
+pub struct SftpConfig {
+    /// Server address, as `host:port`
+    pub address: String,
+    pub username: String,
+    pub auth: SftpAuth,
+    /// Directory of the server exposed as the root of the filesystem. Defaults to the login directory.
+    pub remote_root: PathBuf,
+    /// Maximum number of idle connections kept open. Defaults to 4.
+    pub pool_size: usize,
+    /// Duration during which attributes and directory listings are cached. Defaults to 1 second.
+    pub cache_ttl: Duration,
+}
+
+impl SftpConfig {
+    pub fn new<A: ToString, U: ToString>(address: A, username: U, auth: SftpAuth) -> Self {
+        Self {
+            address: address.to_string(),
+            username: username.to_string(),
+            auth,
+            remote_root: PathBuf::from("."),
+            pool_size: 4,
+            cache_ttl: Duration::from_secs(1),
+        }
+    }
+}
+
+struct ConnectionPool {
+    config: SftpConfig,
+    idle: Mutex<Vec<Sftp>>,
+}
+
+impl ConnectionPool {
+    fn connect(&self) -> FuseResult<Sftp> {
+        let config = &self.config;
+        let tcp = TcpStream::connect(&config.address)?;
+        let mut session = Session::new().map_err(sftp_error)?;
+        session.set_tcp_stream(tcp);
+        session.handshake().map_err(sftp_error)?;
+        let authenticated = match &config.auth {
+            SftpAuth::Password(password) => session.userauth_password(&config.username, password),
+            SftpAuth::PublicKey {
+                public_key,
+                private_key,
+                passphrase,
+            } => session.userauth_pubkey_file(
+                &config.username,
+                public_key.as_deref(),
+                private_key,
+                passphrase.as_deref(),
+            ),
+            SftpAuth::Agent => session.userauth_agent(&config.username),
+        };
+        authenticated.map_err(|e| {
+            PosixError::new(
+                ErrorKind::PermissionDenied,
+                format!("{}: authentication failed: {}", config.address, e),
+            )
+        })?;
+        session.sftp().map_err(sftp_error)
+    }
+
+    fn release(&self, sftp: Sftp) {
+        let mut idle = self.idle.lock().unwrap();
+        if idle.len() < self.config.pool_size {
+            idle.push(sftp);
+        }
+    }
+
+    /// Runs `request` on a pooled connection, retrying once on a new connection if it is broken
+    ///
+    /// Only for idempotent requests, as the server may have applied the first attempt.
+    fn with_sftp<R, F>(&self, request: F) -> FuseResult<R>
+    where
+        F: Fn(&Sftp) -> Result<R, ssh2::Error>,
+    {
+        let sftp = self.acquire()?;
+        match request(&sftp) {
+            Err(e) if is_connection_error(&e) => {
+                drop(sftp);
+                self.run(self.connect()?, request)
+            }
+            result => {
+                self.release(sftp);
+                result.map_err(sftp_error)
+            }
+        }
+    }
+
+    /// Runs `request` on a pooled connection, without retrying if the connection is broken
+    fn with_sftp_once<R, F>(&self, request: F) -> FuseResult<R>
+    where
+        F: FnOnce(&Sftp) -> Result<R, ssh2::Error>,
+    {
+        self.run(self.acquire()?, request)
+    }
+
+    fn acquire(&self) -> FuseResult<Sftp> {
+        let pooled = self.idle.lock().unwrap().pop();
+        match pooled {
+            Some(sftp) => Ok(sftp),
+            None => self.connect(),
+        }
+    }
+
+    /// Runs `request` on `sftp`, which is returned to the pool unless it is broken
+    fn run<R, F>(&self, sftp: Sftp, request: F) -> FuseResult<R>
+    where
+        F: FnOnce(&Sftp) -> Result<R, ssh2::Error>,
+    {
+        let result = request(&sftp);
+        if !result.as_ref().is_err_and(is_connection_error) {
+            self.release(sftp);
+        }
+        result.map_err(sftp_error)
+    }
+}
+
+/// Values of remote paths which expire after a delay
+struct Cache<V> {
+    ttl: Duration,
+    entries: Mutex<HashMap<PathBuf, (Instant, V)>>,
+}
+
+impl<V: Clone> Cache<V> {
+    fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn get(&self, file_id: &Path) -> Option<V> {
+        let entries = self.entries.lock().unwrap();
+        match entries.get(file_id) {
+            Some((inserted, value)) if inserted.elapsed() < self.ttl => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    fn insert(&self, file_id: PathBuf, value: V) {
+        if !self.ttl.is_zero() {
+            self.entries
+                .lock()
+                .unwrap()
+                .insert(file_id, (Instant::now(), value));
+        }
+    }
+
+    fn remove(&self, file_id: &Path) {
+        self.entries.lock().unwrap().remove(file_id);
+    }
+
+    /// Removes `file_id` and its descendants
+    fn remove_tree(&self, file_id: &Path) {
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|path, _| !path.starts_with(file_id));
+    }
+}
+
+struct OpenFile {
+    file_id: PathBuf,
+    file: Mutex<ssh2::File>,
+}
+
+/// Specific documentation is located in parent module documentation.
+pub struct SftpFs {
+    pool: ConnectionPool,
+    attributes: Cache<FileAttribute>,
+    listings: Cache<Vec<(OsString, FileKind)>>,
+    open_files: Mutex<HashMap<u64, Arc<OpenFile>>>,
+    next_handle: AtomicU64,
+    inner: Box<dyn FuseHandler<PathBuf>>,
+}
+
+impl SftpFs {
+    /// Connects to the server, to fail early if it is unreachable or the credentials are wrong
+    pub fn connect<U: FuseHandler<PathBuf>>(config: SftpConfig, inner: U) -> FuseResult<Self> {
+        let cache_ttl = config.cache_ttl;
+        let pool = ConnectionPool {
+            config,
+            idle: Mutex::new(Vec::new()),
+        };
+        let sftp = pool.connect()?;
+        pool.release(sftp);
+        Ok(Self {
+            pool,
+            attributes: Cache::new(cache_ttl),
+            listings: Cache::new(cache_ttl),
+            open_files: Mutex::new(HashMap::new()),
+            next_handle: AtomicU64::new(1),
+            inner: Box::new(inner),
+        })
+    }
+
+    fn remote_path(&self, file_id: &Path) -> PathBuf {
+        self.pool.config.remote_root.join(file_id)
+    }
+
+    fn stat(&self, file_id: &Path) -> FuseResult<FileAttribute> {
+        if let Some(attr) = self.attributes.get(file_id) {
+            return Ok(attr);
+        }
+        let remote_path = self.remote_path(file_id);
+        let stat = self.pool.with_sftp(|sftp| sftp.lstat(&remote_path))?;
+        let attr = convert_file_stat(&stat);
+        self.attributes.insert(file_id.to_path_buf(), attr.clone());
+        Ok(attr)
+    }
+
+    /// Forgets the cached state of `file_id` and of its parent directory
+    fn invalidate(&self, file_id: &Path) {
+        self.attributes.remove_tree(file_id);
+        self.listings.remove_tree(file_id);
+        if let Some(parent) = file_id.parent() {
+            self.attributes.remove(parent);
+            self.listings.remove(parent);
+        }
+    }
+
+    fn register_file(&self, file_id: PathBuf, file: ssh2::File) -> OwnedFileHandle {
+        let handle = self.next_handle.fetch_add(1, Ordering::Relaxed);
+        self.open_files.lock().unwrap().insert(
+            handle,
+            Arc::new(OpenFile {
+                file_id,
+                file: Mutex::new(file),
+            }),
+        );
+        // Safe because handles are only used as keys of open_files
+        unsafe { OwnedFileHandle::from_raw(handle) }
+    }
+
+    fn get_file(&self, file_handle: &BorrowedFileHandle) -> FuseResult<Arc<OpenFile>> {
+        self.open_files
+            .lock()
+            .unwrap()
+            .get(&file_handle.as_raw())
+            .cloned()
+            .ok_or_else(|| {
+                ErrorKind::BadFileDescriptor.to_error(format!("{:?} is not open", file_handle))
+            })
+    }
+}
+
+/// Requests failing at the session level, as opposed to errors reported by the server
+fn is_connection_error(error: &ssh2::Error) -> bool {
+    matches!(error.code(), ErrorCode::Session(_))
+}
+
+/// Converts an SFTP status code to its errno equivalent
+fn sftp_error(error: ssh2::Error) -> PosixError {
+    let kind = match error.code() {
+        ErrorCode::SFTP(code) => match code {
+            2 | 10 => ErrorKind::FileNotFound,
+            3 => ErrorKind::PermissionDeniedAccess,
+            5 => ErrorKind::BadMessage,
+            6 => ErrorKind::NotConnected,
+            7 => ErrorKind::ConnectionReset,
+            8 => ErrorKind::NotSupported,
+            9 => ErrorKind::BadFileDescriptor,
+            11 => ErrorKind::FileExists,
+            12 => ErrorKind::ReadOnlyFileSystem,
+            14 => ErrorKind::NoSpaceLeftOnDevice,
+            15 => ErrorKind::QuotaExceeded,
+            16 => ErrorKind::PermissionDenied,
+            17 => ErrorKind::DeviceOrResourceBusy,
+            18 => ErrorKind::DirectoryNotEmpty,
+            19 => ErrorKind::NotADirectory,
+            20 => ErrorKind::InvalidArgument,
+            21 => ErrorKind::TooManySymbolicLinks,
+            _ => ErrorKind::InputOutputError,
+        },
+        ErrorCode::Session(_) => ErrorKind::InputOutputError,
+    };
+    kind.to_error(error)
+}
+
+fn to_system_time(secs: Option<u64>) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs.unwrap_or(0))
+}
+
+fn to_secs(time: TimeOrNow) -> u64 {
+    let time = match time {
+        TimeOrNow::SpecificTime(time) => time,
+        TimeOrNow::Now => SystemTime::now(),
+    };
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn convert_file_stat(stat: &FileStat) -> FileAttribute {
+    let mode = stat.perm.unwrap_or(0);
+    let kind = FileKind::from_mode(mode).unwrap_or(FileKind::RegularFile);
+    let size = stat.size.unwrap_or(0);
+    let mtime = to_system_time(stat.mtime);
+    FileAttribute {
+        size,
+        blocks: size.div_ceil(512),
+        atime: to_system_time(stat.atime),
+        mtime,
+        ctime: mtime,
+        crtime: mtime,
+        kind,
+        perm: (mode & 0o7777) as u16,
+        nlink: if kind == FileKind::Directory { 2 } else { 1 },
+        uid: stat.uid.unwrap_or(0),
+        gid: stat.gid.unwrap_or(0),
+        rdev: 0,
+        blksize: 0,
+        flags: 0,
+        ttl: None,
+        generation: None,
+    }
+}
+
+fn convert_open_flags(flags: OpenFlags) -> ssh2::OpenFlags {
+    let mut sftp_flags = match flags.bits() & libc::O_ACCMODE {
+        libc::O_WRONLY => ssh2::OpenFlags::WRITE,
+        libc::O_RDWR => ssh2::OpenFlags::READ | ssh2::OpenFlags::WRITE,
+        _ => ssh2::OpenFlags::READ,
+    };
+    if flags.contains(OpenFlags::APPEND_MODE) {
+        sftp_flags |= ssh2::OpenFlags::APPEND;
+    }
+    if flags.contains(OpenFlags::CREATE) {
+        sftp_flags |= ssh2::OpenFlags::CREATE;
+    }
+    if flags.contains(OpenFlags::CREATE_EXCLUSIVE) {
+        sftp_flags |= ssh2::OpenFlags::EXCLUSIVE;
+    }
+    if flags.contains(OpenFlags::TRUNCATE) {
+        sftp_flags |= ssh2::OpenFlags::TRUNCATE;
+    }
+    sftp_flags
+}
+
+impl FuseHandler<PathBuf> for SftpFs {
+    fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+        self.inner.as_ref()
+    }
+
+    fn available_attributes(&self) -> AttrMask {
+        AttrMask::ATIME | AttrMask::MTIME | AttrMask::BLOCKS
+    }
+
+    fn preferred_blksize(&self) -> u32 {
+        // Fewer and larger requests make up for the latency of the network
+        1 << 20
+    }
+
+    fn create(
+        &self,
+        _req: &RequestInfo,
+        parent_id: PathBuf,
+        name: &OsStr,
+        mode: u32,
+        umask: u32,
+        flags: OpenFlags,
+    ) -> FuseResult<(OwnedFileHandle, FileAttribute, FUSEOpenResponseFlags)> {
+        let file_id = parent_id.join(name);
+        let remote_path = self.remote_path(&file_id);
+        let sftp_flags = convert_open_flags(flags) | ssh2::OpenFlags::CREATE;
+        let sftp_flags = if sftp_flags.contains(ssh2::OpenFlags::WRITE) {
+            sftp_flags
+        } else {
+            sftp_flags | ssh2::OpenFlags::WRITE
+        };
+        let file = self.pool.with_sftp_once(|sftp| {
+            sftp.open_mode(
+                &remote_path,
+                sftp_flags,
+                (mode & !umask & 0o7777) as i32,
+                OpenType::File,
+            )
+        })?;
+        self.invalidate(&file_id);
+        let file_handle = self.register_file(file_id.clone(), file);
+        let attr = self.stat(&file_id)?;
+        Ok((file_handle, attr, FUSEOpenResponseFlags::empty()))
+    }
+
+    fn flush(
+        &self,
+        _req: &RequestInfo,
+        _file_id: PathBuf,
+        _file_handle: BorrowedFileHandle,
+        _lock_owner: u64,
+    ) -> FuseResult<()> {
+        // Writes are sent to the server as they come
+        Ok(())
+    }
+
+    fn fsync(
+        &self,
+        _req: &RequestInfo,
+        _file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        _datasync: bool,
+    ) -> FuseResult<()> {
+        let open_file = self.get_file(&file_handle)?;
+        let result = open_file.file.lock().unwrap().fsync();
+        match result {
+            Ok(()) => Ok(()),
+            // The fsync extension is not supported by every server
+            Err(e) if matches!(e.code(), ErrorCode::SFTP(8)) => Ok(()),
+            Err(e) => Err(sftp_error(e)),
+        }
+    }
+
+    fn getattr(
+        &self,
+        _req: &RequestInfo,
+        file_id: PathBuf,
+        _file_handle: Option<BorrowedFileHandle>,
+    ) -> FuseResult<FileAttribute> {
+        self.stat(&file_id)
+    }
+
+    fn lookup(
+        &self,
+        _req: &RequestInfo,
+        parent_id: PathBuf,
+        name: &OsStr,
+    ) -> FuseResult<FileAttribute> {
+        self.stat(&parent_id.join(name))
+    }
+
+    fn mkdir(
+        &self,
+        _req: &RequestInfo,
+        parent_id: PathBuf,
+        name: &OsStr,
+        mode: u32,
+        umask: u32,
+    ) -> FuseResult<FileAttribute> {
+        let file_id = parent_id.join(name);
+        let remote_path = self.remote_path(&file_id);
+        self.pool
+            .with_sftp_once(|sftp| sftp.mkdir(&remote_path, (mode & !umask & 0o7777) as i32))?;
+        self.invalidate(&file_id);
+        self.stat(&file_id)
+    }
+
+    fn open(
+        &self,
+        _req: &RequestInfo,
+        file_id: PathBuf,
+        flags: OpenFlags,
+    ) -> FuseResult<(OwnedFileHandle, FUSEOpenResponseFlags)> {
+        let remote_path = self.remote_path(&file_id);
+        let sftp_flags = convert_open_flags(flags);
+        let open = |sftp: &Sftp| sftp.open_mode(&remote_path, sftp_flags, 0, OpenType::File);
+        // Creating or truncating the file is a modification, which can't be retried
+        let modifies =
+            ssh2::OpenFlags::CREATE | ssh2::OpenFlags::EXCLUSIVE | ssh2::OpenFlags::TRUNCATE;
+        let file = match sftp_flags.intersects(modifies) {
+            true => self.pool.with_sftp_once(open)?,
+            false => self.pool.with_sftp(open)?,
+        };
+        if flags.contains(OpenFlags::TRUNCATE) {
+            self.invalidate(&file_id);
+        }
+        let file_handle = self.register_file(file_id, file);
+        Ok((file_handle, FUSEOpenResponseFlags::empty()))
+    }
+
+    fn read(
+        &self,
+        _req: &RequestInfo,
+        _file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        size: u32,
+        _flags: OpenFlags,
+        _lock_owner: Option<u64>,
+    ) -> FuseResult<Vec<u8>> {
+        let open_file = self.get_file(&file_handle)?;
+        let mut file = open_file.file.lock().unwrap();
+        file.seek(seek)?;
+        let mut data = Vec::with_capacity(size as usize);
+        (&mut *file).take(size as u64).read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    fn readdir(
+        &self,
+        _req: &RequestInfo,
+        file_id: PathBuf,
+        _file_handle: BorrowedFileHandle,
+    ) -> FuseResult<Vec<(OsString, FileKind)>> {
+        if let Some(children) = self.listings.get(&file_id) {
+            return Ok(children);
+        }
+        let remote_path = self.remote_path(&file_id);
+        let entries = self.pool.with_sftp(|sftp| sftp.readdir(&remote_path))?;
+        let mut children = vec![
+            (OsString::from("."), FileKind::Directory),
+            (OsString::from(".."), FileKind::Directory),
+        ];
+        for (entry_path, stat) in entries {
+            let Some(name) = entry_path.file_name() else {
+                continue;
+            };
+            // The listing carries the attributes, which spares a request for each entry
+            let attr = convert_file_stat(&stat);
+            children.push((name.to_os_string(), attr.kind));
+            self.attributes.insert(file_id.join(name), attr);
+        }
+        self.listings.insert(file_id, children.clone());
+        Ok(children)
+    }
+
+    fn readlink(&self, _req: &RequestInfo, file_id: PathBuf) -> FuseResult<Vec<u8>> {
+        let remote_path = self.remote_path(&file_id);
+        let target = self.pool.with_sftp(|sftp| sftp.readlink(&remote_path))?;
+        Ok(target.as_os_str().as_bytes().to_vec())
+    }
+
+    fn release(
+        &self,
+        _req: &RequestInfo,
+        _file_id: PathBuf,
+        file_handle: OwnedFileHandle,
+        _flags: OpenFlags,
+        _lock_owner: Option<u64>,
+        _flush: bool,
+    ) -> FuseResult<()> {
+        self.open_files
+            .lock()
+            .unwrap()
+            .remove(&file_handle.as_raw());
+        Ok(())
+    }
+
+    fn rename(
+        &self,
+        _req: &RequestInfo,
+        parent_id: PathBuf,
+        name: &OsStr,
+        newparent: PathBuf,
+        newname: &OsStr,
+        flags: RenameFlags,
+    ) -> FuseResult<()> {
+        if !flags.is_empty() {
+            return Err(ErrorKind::InvalidArgument.to_error("SFTP doesn't support rename flags"));
+        }
+        let file_id = parent_id.join(name);
+        let new_file_id = newparent.join(newname);
+        let remote_path = self.remote_path(&file_id);
+        let new_remote_path = self.remote_path(&new_file_id);
+        let sftp_flags =
+            ssh2::RenameFlags::OVERWRITE | ssh2::RenameFlags::ATOMIC | ssh2::RenameFlags::NATIVE;
+        self.pool
+            .with_sftp_once(|sftp| sftp.rename(&remote_path, &new_remote_path, Some(sftp_flags)))?;
+        self.invalidate(&file_id);
+        self.invalidate(&new_file_id);
+        Ok(())
+    }
+
+    fn rmdir(&self, _req: &RequestInfo, parent_id: PathBuf, name: &OsStr) -> FuseResult<()> {
+        let file_id = parent_id.join(name);
+        let remote_path = self.remote_path(&file_id);
+        self.pool.with_sftp_once(|sftp| sftp.rmdir(&remote_path))?;
+        self.invalidate(&file_id);
+        Ok(())
+    }
+
+    fn setattr(
+        &self,
+        _req: &RequestInfo,
+        file_id: PathBuf,
+        attrs: SetAttrRequest,
+    ) -> FuseResult<FileAttribute> {
+        let remote_path = self.remote_path(&file_id);
+        let stat = FileStat {
+            size: attrs.size,
+            uid: attrs.uid,
+            gid: attrs.gid,
+            perm: attrs.mode,
+            atime: attrs.atime.map(to_secs),
+            mtime: attrs.mtime.map(to_secs),
+        };
+        self.pool
+            .with_sftp_once(|sftp| sftp.setstat(&remote_path, stat))?;
+        self.invalidate(&file_id);
+        self.stat(&file_id)
+    }
+
+    fn symlink(
+        &self,
+        _req: &RequestInfo,
+        parent_id: PathBuf,
+        link_name: &OsStr,
+        target: &Path,
+    ) -> FuseResult<FileAttribute> {
+        let file_id = parent_id.join(link_name);
+        let remote_path = self.remote_path(&file_id);
+        // ssh2 creates the link at its second argument
+        self.pool
+            .with_sftp_once(|sftp| sftp.symlink(target, &remote_path))?;
+        self.invalidate(&file_id);
+        self.stat(&file_id)
+    }
+
+    fn unlink(&self, _req: &RequestInfo, parent_id: PathBuf, name: &OsStr) -> FuseResult<()> {
+        let file_id = parent_id.join(name);
+        let remote_path = self.remote_path(&file_id);
+        self.pool.with_sftp_once(|sftp| sftp.unlink(&remote_path))?;
+        self.invalidate(&file_id);
+        Ok(())
+    }
+
+    fn write(
+        &self,
+        _req: &RequestInfo,
+        _file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        data: Vec<u8>,
+        _write_flags: FUSEWriteFlags,
+        _flags: OpenFlags,
+        _lock_owner: Option<u64>,
+    ) -> FuseResult<u32> {
+        let open_file = self.get_file(&file_handle)?;
+        {
+            let mut file = open_file.file.lock().unwrap();
+            file.seek(seek)?;
+            file.write_all(&data)?;
+        }
+        self.attributes.remove(&open_file.file_id);
+        Ok(data.len() as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_file_stat() {
+        let stat = FileStat {
+            size: Some(1000),
+            uid: Some(1000),
+            gid: Some(100),
+            perm: Some(libc::S_IFDIR as u32 | 0o755),
+            atime: Some(10),
+            mtime: Some(20),
+        };
+        let attr = convert_file_stat(&stat);
+        assert_eq!(attr.kind, FileKind::Directory);
+        assert_eq!(attr.perm, 0o755);
+        assert_eq!((attr.uid, attr.gid), (1000, 100));
+        assert_eq!(attr.size, 1000);
+        assert_eq!(attr.blocks, 2);
+        assert_eq!(attr.mtime, UNIX_EPOCH + Duration::from_secs(20));
+
+        // Servers may omit any attribute
+        let stat = FileStat {
+            size: None,
+            uid: None,
+            gid: None,
+            perm: None,
+            atime: None,
+            mtime: None,
+        };
+        let attr = convert_file_stat(&stat);
+        assert_eq!(attr.kind, FileKind::RegularFile);
+        assert_eq!(attr.size, 0);
+    }
+
+    #[test]
+    fn test_convert_open_flags() {
+        assert_eq!(
+            convert_open_flags(OpenFlags::READ_ONLY),
+            ssh2::OpenFlags::READ
+        );
+        assert_eq!(
+            convert_open_flags(OpenFlags::WRITE_ONLY | OpenFlags::APPEND_MODE),
+            ssh2::OpenFlags::WRITE | ssh2::OpenFlags::APPEND
+        );
+        assert_eq!(
+            convert_open_flags(OpenFlags::READ_WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE),
+            ssh2::OpenFlags::READ
+                | ssh2::OpenFlags::WRITE
+                | ssh2::OpenFlags::CREATE
+                | ssh2::OpenFlags::TRUNCATE
+        );
+    }
+
+    #[test]
+    fn test_cache_expiry() {
+        let cache = Cache::new(Duration::from_millis(50));
+        cache.insert(PathBuf::from("dir/file"), 1);
+        cache.insert(PathBuf::from("other"), 2);
+        assert_eq!(cache.get(Path::new("dir/file")), Some(1));
+
+        cache.remove_tree(Path::new("dir"));
+        assert_eq!(cache.get(Path::new("dir/file")), None);
+        assert_eq!(cache.get(Path::new("other")), Some(2));
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(cache.get(Path::new("other")), None);
+    }
+}
@@ -0,0 +1,1086 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::{OsStr, OsString};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::prelude::*;
+use crate::templates::union_readdir::{merge_readdir, MergePolicy};
+
+/// Size of the reads used to copy a file from the lower layer to the upper layer
+const COPY_UP_CHUNK: u32 = 128 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Layer {
+    Upper,
+    Lower,
+}
+
+/// Entries of the lower layer hidden from the merged view
+#[derive(Default)]
+struct Whiteouts {
+    /// Deleted entries, hiding themselves and their descendants
+    removed: HashSet<PathBuf>,
+    /// Directories of the upper layer replacing a deleted directory, hiding its lower descendants
+    opaque: HashSet<PathBuf>,
+}
+
+impl Whiteouts {
+    fn hides(&self, path: &Path) -> bool {
+        path.ancestors().enumerate().any(|(depth, ancestor)| {
+            self.removed.contains(ancestor) || (depth > 0 && self.opaque.contains(ancestor))
+        })
+    }
+
+    /// Moves the whiteouts below `from` below `to`, as their directory was renamed
+    fn rebase(&mut self, from: &Path, to: &Path) {
+        for set in [&mut self.removed, &mut self.opaque] {
+            *set = set
+                .drain()
+                .map(|path| match path.strip_prefix(from) {
+                    Ok(relative) if !relative.as_os_str().is_empty() => to.join(relative),
+                    _ => path,
+                })
+                .collect();
+        }
+    }
+}
+
+/// Stacks a writable upper handler over a read-only lower handler, like the Linux overlay filesystem.
+///
+/// Lookups and reads are served by the upper layer first, then by the lower layer. Every modification
+/// is directed to the upper layer: a file of the lower layer is first copied up, with its parent directories,
+/// when it is opened for writing or has its attributes changed. The lower layer is never modified.
+///
+/// Deletions of lower entries are recorded as whiteouts hiding them from the merged view, and `readdir` merges
+/// the listings of both layers, deduplicated by name. Whiteouts are kept in memory only, so deleted lower
+/// entries reappear on the next mount.
+///
+/// Both layers identify files by path, for example `MirrorFsReadOnly` as the lower layer and `MirrorFs` as
+/// the upper layer. Renaming a directory of the lower layer fails with `EXDEV`, which tools like `mv` handle
+/// by copying the directory. Operations on an open file (`read`, `write`, `fallocate`, locks...) are dispatched
+/// to the layer which opened it. Operations not implemented here are delegated to the upper layer.
+///
+/// ```text
+/// let lower = MirrorFsReadOnly::new(image_dir, DefaultFuseHandler::new());
+/// let upper = MirrorFs::new(changes_dir, DefaultFuseHandler::new());
+/// mount(OverlayFs::new(lower, upper), mountpoint, &[])
+/// ```
+pub struct OverlayFs<L, U> {
+    lower: L,
+    upper: U,
+    whiteouts: Mutex<Whiteouts>,
+    /// Handles of open files, mapped to the layer and the handle of the layer
+    handles: Mutex<HashMap<u64, (Layer, u64)>>,
+    next_handle: AtomicU64,
+    /// Serializes the copies to the upper layer
+    copy_up_lock: Mutex<()>,
+}
+
+impl<L, U> OverlayFs<L, U>
+where
+    L: FuseHandler<PathBuf>,
+    U: FuseHandler<PathBuf>,
+{
+    pub fn new(lower: L, upper: U) -> Self {
+        Self {
+            lower,
+            upper,
+            whiteouts: Mutex::new(Whiteouts::default()),
+            handles: Mutex::new(HashMap::new()),
+            next_handle: AtomicU64::new(1),
+            copy_up_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn lower(&self) -> &L {
+        &self.lower
+    }
+
+    pub fn upper(&self) -> &U {
+        &self.upper
+    }
+
+    fn layer(&self, layer: Layer) -> &dyn FuseHandler<PathBuf> {
+        match layer {
+            Layer::Upper => &self.upper,
+            Layer::Lower => &self.lower,
+        }
+    }
+
+    fn register_handle(&self, layer: Layer, file_handle: OwnedFileHandle) -> OwnedFileHandle {
+        let handle = self.next_handle.fetch_add(1, Ordering::Relaxed);
+        self.handles
+            .lock()
+            .unwrap()
+            .insert(handle, (layer, file_handle.as_raw()));
+        unsafe { OwnedFileHandle::from_raw(handle) }
+    }
+
+    fn resolve_handle<'a>(
+        &self,
+        file_handle: BorrowedFileHandle<'a>,
+    ) -> FuseResult<(Layer, BorrowedFileHandle<'a>)> {
+        match self.handles.lock().unwrap().get(&file_handle.as_raw()) {
+            Some(&(layer, handle)) => Ok((layer, unsafe { BorrowedFileHandle::from_raw(handle) })),
+            None => Err(ErrorKind::BadFileDescriptor
+                .to_error(format!("Unknown file handle {}", file_handle.as_raw()))),
+        }
+    }
+
+    fn getattr_in(
+        &self,
+        req: &RequestInfo,
+        layer: Layer,
+        path: &Path,
+    ) -> FuseResult<Option<FileAttribute>> {
+        if layer == Layer::Lower && self.whiteouts.lock().unwrap().hides(path) {
+            return Ok(None);
+        }
+        match self.layer(layer).getattr(req, path.to_path_buf(), None) {
+            Ok(attr) => Ok(Some(attr)),
+            Err(e) if matches!(e.kind(), ErrorKind::FileNotFound | ErrorKind::NotADirectory) => {
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Finds the topmost layer containing `path`
+    fn locate(&self, req: &RequestInfo, path: &Path) -> FuseResult<(Layer, FileAttribute)> {
+        for layer in [Layer::Upper, Layer::Lower] {
+            if let Some(attr) = self.getattr_in(req, layer, path)? {
+                return Ok((layer, attr));
+            }
+        }
+        Err(ErrorKind::FileNotFound.to_error(format!("{}: not found", path.display())))
+    }
+
+    /// Copies `path` from the lower layer to the upper layer if it is not there yet
+    fn copy_up(&self, req: &RequestInfo, path: &Path) -> FuseResult<()> {
+        let _guard = self.copy_up_lock.lock().unwrap();
+        self.copy_up_locked(req, path)
+    }
+
+    fn copy_up_locked(&self, req: &RequestInfo, path: &Path) -> FuseResult<()> {
+        if self.getattr_in(req, Layer::Upper, path)?.is_some() {
+            return Ok(());
+        }
+        let attr = self.getattr_in(req, Layer::Lower, path)?.ok_or_else(|| {
+            ErrorKind::FileNotFound.to_error(format!("{}: not found", path.display()))
+        })?;
+        let (parent, name) = match (path.parent(), path.file_name()) {
+            (Some(parent), Some(name)) => (parent, name),
+            // The root always exists in the upper layer
+            _ => return Ok(()),
+        };
+        self.copy_up_locked(req, parent)?;
+        let perm = attr.perm as u32;
+        match attr.kind {
+            FileKind::Directory => {
+                self.upper.mkdir(req, parent.to_path_buf(), name, perm, 0)?;
+            }
+            FileKind::Symlink => {
+                let target = self.lower.readlink(req, path.to_path_buf())?;
+                self.upper.symlink(
+                    req,
+                    parent.to_path_buf(),
+                    name,
+                    Path::new(OsStr::from_bytes(&target)),
+                )?;
+            }
+            FileKind::RegularFile => self.copy_up_file(req, path, parent, name, perm)?,
+            kind => {
+                return Err(ErrorKind::NotSupported.to_error(format!(
+                    "{}: {:?} can't be copied to the upper layer",
+                    path.display(),
+                    kind
+                )))
+            }
+        }
+        // Keeps the modification time, so that tools comparing it don't see a change
+        let mut times = SetAttrRequest::new();
+        times.atime = Some(TimeOrNow::SpecificTime(attr.atime));
+        times.mtime = Some(TimeOrNow::SpecificTime(attr.mtime));
+        if attr.kind != FileKind::Symlink {
+            self.upper.setattr(req, path.to_path_buf(), times)?;
+        }
+        Ok(())
+    }
+
+    fn copy_up_file(
+        &self,
+        req: &RequestInfo,
+        path: &Path,
+        parent: &Path,
+        name: &OsStr,
+        perm: u32,
+    ) -> FuseResult<()> {
+        let (lower_handle, _) = self
+            .lower
+            .open(req, path.to_path_buf(), OpenFlags::READ_ONLY)?;
+        let (upper_handle, _, _) = match self.upper.create(
+            req,
+            parent.to_path_buf(),
+            name,
+            perm,
+            0,
+            OpenFlags::WRITE_ONLY,
+        ) {
+            Ok(created) => created,
+            Err(e) => {
+                let _ = self.lower.release(
+                    req,
+                    path.to_path_buf(),
+                    lower_handle,
+                    OpenFlags::READ_ONLY,
+                    None,
+                    false,
+                );
+                return Err(e);
+            }
+        };
+        let mut offset = 0;
+        let copied = loop {
+            let data = match self.lower.read(
+                req,
+                path.to_path_buf(),
+                lower_handle.borrow(),
+                SeekFrom::Start(offset),
+                COPY_UP_CHUNK,
+                OpenFlags::READ_ONLY,
+                None,
+            ) {
+                Ok(data) if data.is_empty() => break Ok(()),
+                Ok(data) => data,
+                Err(e) => break Err(e),
+            };
+            let len = data.len() as u64;
+            if let Err(e) = self.upper.write(
+                req,
+                path.to_path_buf(),
+                upper_handle.borrow(),
+                SeekFrom::Start(offset),
+                data,
+                FUSEWriteFlags::empty(),
+                OpenFlags::WRITE_ONLY,
+                None,
+            ) {
+                break Err(e);
+            }
+            offset += len;
+        };
+        let _ = self.lower.release(
+            req,
+            path.to_path_buf(),
+            lower_handle,
+            OpenFlags::READ_ONLY,
+            None,
+            false,
+        );
+        let released = self.upper.release(
+            req,
+            path.to_path_buf(),
+            upper_handle,
+            OpenFlags::WRITE_ONLY,
+            None,
+            true,
+        );
+        if copied.is_err() {
+            // A partial copy would hide the lower file
+            let _ = self.upper.unlink(req, parent.to_path_buf(), name);
+        }
+        copied.and(released)
+    }
+
+    /// Copies the parent directories of a new entry to the upper layer
+    fn prepare_new_entry(&self, req: &RequestInfo, path: &Path) -> FuseResult<()> {
+        match path.parent() {
+            Some(parent) => self.copy_up(req, parent),
+            None => Ok(()),
+        }
+    }
+
+    /// Removes the whiteout of a new entry, returning whether it replaced a deleted lower entry
+    fn commit_new_entry(&self, path: &Path) -> bool {
+        self.whiteouts.lock().unwrap().removed.remove(path)
+    }
+
+    fn in_lower(&self, req: &RequestInfo, path: &Path) -> FuseResult<bool> {
+        Ok(self.getattr_in(req, Layer::Lower, path)?.is_some())
+    }
+
+    fn list_layer(
+        &self,
+        req: &RequestInfo,
+        layer: Layer,
+        path: &Path,
+    ) -> FuseResult<Vec<(OsString, FileKind)>> {
+        let handler = self.layer(layer);
+        let flags = OpenFlags::READ_ONLY | OpenFlags::MUST_BE_DIRECTORY;
+        let (file_handle, _) = handler.opendir(req, path.to_path_buf(), flags)?;
+        let children = handler.readdir(req, path.to_path_buf(), file_handle.borrow());
+        handler.releasedir(req, path.to_path_buf(), file_handle, flags)?;
+        children
+    }
+
+    /// Merged listing of a directory, without whited-out entries
+    fn list_merged(&self, req: &RequestInfo, path: &Path) -> FuseResult<Vec<(OsString, FileKind)>> {
+        let mut sources = Vec::new();
+        if self.getattr_in(req, Layer::Upper, path)?.is_some() {
+            sources.push(self.list_layer(req, Layer::Upper, path)?);
+        }
+        let opaque = self.whiteouts.lock().unwrap().opaque.contains(path);
+        if !opaque && self.getattr_in(req, Layer::Lower, path)?.is_some() {
+            let mut children = self.list_layer(req, Layer::Lower, path)?;
+            let whiteouts = self.whiteouts.lock().unwrap();
+            children.retain(|(name, _)| !whiteouts.removed.contains(&path.join(name)));
+            sources.push(children);
+        }
+        merge_readdir(sources, MergePolicy::FirstWins)
+    }
+}
+
+impl<L, U> FuseHandler<PathBuf> for OverlayFs<L, U>
+where
+    L: FuseHandler<PathBuf>,
+    U: FuseHandler<PathBuf>,
+{
+    fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+        &self.upper
+    }
+
+    fn access(&self, req: &RequestInfo, file_id: PathBuf, mask: AccessMask) -> FuseResult<()> {
+        let (layer, _) = self.locate(req, &file_id)?;
+        self.layer(layer).access(req, file_id, mask)
+    }
+
+    fn copy_file_range(
+        &self,
+        req: &RequestInfo,
+        file_in: PathBuf,
+        file_handle_in: BorrowedFileHandle,
+        offset_in: i64,
+        file_out: PathBuf,
+        file_handle_out: BorrowedFileHandle,
+        offset_out: i64,
+        len: u64,
+        flags: u32,
+    ) -> FuseResult<u32> {
+        let (layer_in, file_handle_in) = self.resolve_handle(file_handle_in)?;
+        let (layer_out, file_handle_out) = self.resolve_handle(file_handle_out)?;
+        // The kernel falls back to reads and writes when the files are on different layers
+        if layer_in != layer_out {
+            return Err(ErrorKind::InvalidCrossDeviceLink
+                .to_error("Files of a copy_file_range are on different layers"));
+        }
+        self.layer(layer_in).copy_file_range(
+            req,
+            file_in,
+            file_handle_in,
+            offset_in,
+            file_out,
+            file_handle_out,
+            offset_out,
+            len,
+            flags,
+        )
+    }
+
+    fn create(
+        &self,
+        req: &RequestInfo,
+        parent_id: PathBuf,
+        name: &OsStr,
+        mode: u32,
+        umask: u32,
+        flags: OpenFlags,
+    ) -> FuseResult<(OwnedFileHandle, FileAttribute, FUSEOpenResponseFlags)> {
+        let file_path = parent_id.join(name);
+        self.prepare_new_entry(req, &file_path)?;
+        let (file_handle, attr, response_flags) = self
+            .upper
+            .create(req, parent_id, name, mode, umask, flags)?;
+        self.commit_new_entry(&file_path);
+        Ok((
+            self.register_handle(Layer::Upper, file_handle),
+            attr,
+            response_flags,
+        ))
+    }
+
+    fn fallocate(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        offset: i64,
+        length: i64,
+        mode: FallocateFlags,
+    ) -> FuseResult<()> {
+        let (layer, file_handle) = self.resolve_handle(file_handle)?;
+        self.layer(layer)
+            .fallocate(req, file_id, file_handle, offset, length, mode)
+    }
+
+    fn flush(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        lock_owner: u64,
+    ) -> FuseResult<()> {
+        let (layer, file_handle) = self.resolve_handle(file_handle)?;
+        self.layer(layer)
+            .flush(req, file_id, file_handle, lock_owner)
+    }
+
+    fn fsync(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        datasync: bool,
+    ) -> FuseResult<()> {
+        let (layer, file_handle) = self.resolve_handle(file_handle)?;
+        self.layer(layer).fsync(req, file_id, file_handle, datasync)
+    }
+
+    fn getattr(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: Option<BorrowedFileHandle>,
+    ) -> FuseResult<FileAttribute> {
+        match file_handle {
+            Some(file_handle) => {
+                let (layer, file_handle) = self.resolve_handle(file_handle)?;
+                self.layer(layer).getattr(req, file_id, Some(file_handle))
+            }
+            None => self.locate(req, &file_id).map(|(_, attr)| attr),
+        }
+    }
+
+    #[cfg(feature = "locking")]
+    fn getlk(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        lock_owner: u64,
+        lock_info: LockInfo,
+    ) -> FuseResult<LockInfo> {
+        let (layer, file_handle) = self.resolve_handle(file_handle)?;
+        self.layer(layer)
+            .getlk(req, file_id, file_handle, lock_owner, lock_info)
+    }
+
+    #[cfg(feature = "xattr")]
+    fn getxattr(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        name: &OsStr,
+        size: u32,
+    ) -> FuseResult<Vec<u8>> {
+        let (layer, _) = self.locate(req, &file_id)?;
+        self.layer(layer).getxattr(req, file_id, name, size)
+    }
+
+    #[cfg(feature = "ioctl")]
+    fn ioctl(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        flags: IOCtlFlags,
+        cmd: u32,
+        in_data: Vec<u8>,
+        out_size: u32,
+    ) -> FuseResult<(i32, Vec<u8>)> {
+        let (layer, file_handle) = self.resolve_handle(file_handle)?;
+        self.layer(layer)
+            .ioctl(req, file_id, file_handle, flags, cmd, in_data, out_size)
+    }
+
+    #[cfg(feature = "xattr")]
+    fn listxattr(&self, req: &RequestInfo, file_id: PathBuf, size: u32) -> FuseResult<Vec<u8>> {
+        let (layer, _) = self.locate(req, &file_id)?;
+        self.layer(layer).listxattr(req, file_id, size)
+    }
+
+    #[cfg(feature = "xattr")]
+    fn listxattr_names(&self, req: &RequestInfo, file_id: PathBuf) -> FuseResult<Vec<OsString>> {
+        let (layer, _) = self.locate(req, &file_id)?;
+        self.layer(layer).listxattr_names(req, file_id)
+    }
+
+    fn lookup(
+        &self,
+        req: &RequestInfo,
+        parent_id: PathBuf,
+        name: &OsStr,
+    ) -> FuseResult<FileAttribute> {
+        self.locate(req, &parent_id.join(name))
+            .map(|(_, attr)| attr)
+    }
+
+    fn lseek(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+    ) -> FuseResult<i64> {
+        let (layer, file_handle) = self.resolve_handle(file_handle)?;
+        self.layer(layer).lseek(req, file_id, file_handle, seek)
+    }
+
+    fn lseek_raw(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        offset: i64,
+        whence: i32,
+    ) -> FuseResult<i64> {
+        let (layer, file_handle) = self.resolve_handle(file_handle)?;
+        self.layer(layer)
+            .lseek_raw(req, file_id, file_handle, offset, whence)
+    }
+
+    fn mkdir(
+        &self,
+        req: &RequestInfo,
+        parent_id: PathBuf,
+        name: &OsStr,
+        mode: u32,
+        umask: u32,
+    ) -> FuseResult<FileAttribute> {
+        let file_path = parent_id.join(name);
+        self.prepare_new_entry(req, &file_path)?;
+        let attr = self.upper.mkdir(req, parent_id, name, mode, umask)?;
+        if self.commit_new_entry(&file_path) {
+            self.whiteouts.lock().unwrap().opaque.insert(file_path);
+        }
+        Ok(attr)
+    }
+
+    fn mknod(
+        &self,
+        req: &RequestInfo,
+        parent_id: PathBuf,
+        name: &OsStr,
+        mode: u32,
+        umask: u32,
+        rdev: DeviceType,
+    ) -> FuseResult<FileAttribute> {
+        let file_path = parent_id.join(name);
+        self.prepare_new_entry(req, &file_path)?;
+        let attr = self.upper.mknod(req, parent_id, name, mode, umask, rdev)?;
+        self.commit_new_entry(&file_path);
+        Ok(attr)
+    }
+
+    fn open(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        flags: OpenFlags,
+    ) -> FuseResult<(OwnedFileHandle, FUSEOpenResponseFlags)> {
+        let (mut layer, _) = self.locate(req, &file_id)?;
+        let writes =
+            flags.intersects(OpenFlags::WRITE_ONLY | OpenFlags::READ_WRITE | OpenFlags::TRUNCATE);
+        if layer == Layer::Lower && writes {
+            self.copy_up(req, &file_id)?;
+            layer = Layer::Upper;
+        }
+        let (file_handle, response_flags) = self.layer(layer).open(req, file_id, flags)?;
+        Ok((self.register_handle(layer, file_handle), response_flags))
+    }
+
+    fn opendir(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        _flags: OpenFlags,
+    ) -> FuseResult<(OwnedFileHandle, FUSEOpenResponseFlags)> {
+        let (_, attr) = self.locate(req, &file_id)?;
+        if attr.kind != FileKind::Directory {
+            return Err(ErrorKind::NotADirectory
+                .to_error(format!("{}: not a directory", file_id.display())));
+        }
+        // Layers are listed by readdir, the handle is only a token
+        let handle = self.next_handle.fetch_add(1, Ordering::Relaxed);
+        Ok((
+            unsafe { OwnedFileHandle::from_raw(handle) },
+            FUSEOpenResponseFlags::empty(),
+        ))
+    }
+
+    fn poll(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        poll_handle: PollHandle,
+        events: u32,
+        flags: u32,
+    ) -> FuseResult<u32> {
+        let (layer, file_handle) = self.resolve_handle(file_handle)?;
+        self.layer(layer)
+            .poll(req, file_id, file_handle, poll_handle, events, flags)
+    }
+
+    fn read(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        size: u32,
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+    ) -> FuseResult<Vec<u8>> {
+        let (layer, file_handle) = self.resolve_handle(file_handle)?;
+        self.layer(layer)
+            .read(req, file_id, file_handle, seek, size, flags, lock_owner)
+    }
+
+    fn read_into(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        buf: &mut [u8],
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+    ) -> FuseResult<usize> {
+        let (layer, file_handle) = self.resolve_handle(file_handle)?;
+        self.layer(layer)
+            .read_into(req, file_id, file_handle, seek, buf, flags, lock_owner)
+    }
+
+    fn readdir(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        _file_handle: BorrowedFileHandle,
+    ) -> FuseResult<Vec<(OsString, FileKind)>> {
+        self.list_merged(req, &file_id)
+    }
+
+    fn readlink(&self, req: &RequestInfo, file_id: PathBuf) -> FuseResult<Vec<u8>> {
+        let (layer, _) = self.locate(req, &file_id)?;
+        self.layer(layer).readlink(req, file_id)
+    }
+
+    fn release(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: OwnedFileHandle,
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+        flush: bool,
+    ) -> FuseResult<()> {
+        let (layer, handle) = self
+            .handles
+            .lock()
+            .unwrap()
+            .remove(&file_handle.as_raw())
+            .ok_or_else(|| {
+                ErrorKind::BadFileDescriptor
+                    .to_error(format!("Unknown file handle {}", file_handle.as_raw()))
+            })?;
+        self.layer(layer).release(
+            req,
+            file_id,
+            unsafe { OwnedFileHandle::from_raw(handle) },
+            flags,
+            lock_owner,
+            flush,
+        )
+    }
+
+    fn releasedir(
+        &self,
+        _req: &RequestInfo,
+        _file_id: PathBuf,
+        _file_handle: OwnedFileHandle,
+        _flags: OpenFlags,
+    ) -> FuseResult<()> {
+        Ok(())
+    }
+
+    fn rename(
+        &self,
+        req: &RequestInfo,
+        parent_id: PathBuf,
+        name: &OsStr,
+        newparent: PathBuf,
+        newname: &OsStr,
+        flags: RenameFlags,
+    ) -> FuseResult<()> {
+        let oldpath = parent_id.join(name);
+        let newpath = newparent.join(newname);
+        if flags.contains(RenameFlags::EXCHANGE) {
+            return Err(ErrorKind::InvalidArgument
+                .to_error("RENAME_EXCHANGE is not supported across layers"));
+        }
+        let (layer, attr) = self.locate(req, &oldpath)?;
+        let old_in_lower = self.in_lower(req, &oldpath)?;
+        if attr.kind == FileKind::Directory && old_in_lower {
+            return Err(ErrorKind::InvalidCrossDeviceLink.to_error(format!(
+                "{}: directories of the lower layer can't be renamed",
+                oldpath.display()
+            )));
+        }
+        if layer == Layer::Lower {
+            self.copy_up(req, &oldpath)?;
+        }
+        self.prepare_new_entry(req, &newpath)?;
+        self.upper
+            .rename(req, parent_id, name, newparent, newname, flags)?;
+        let replaces_lower = self.commit_new_entry(&newpath) || self.in_lower(req, &newpath)?;
+        let mut whiteouts = self.whiteouts.lock().unwrap();
+        if attr.kind == FileKind::Directory {
+            whiteouts.rebase(&oldpath, &newpath);
+            if replaces_lower {
+                whiteouts.opaque.insert(newpath);
+            }
+        }
+        if old_in_lower {
+            whiteouts.removed.insert(oldpath);
+        }
+        Ok(())
+    }
+
+    fn rmdir(&self, req: &RequestInfo, parent_id: PathBuf, name: &OsStr) -> FuseResult<()> {
+        let file_path = parent_id.join(name);
+        let (layer, _) = self.locate(req, &file_path)?;
+        let not_empty = self
+            .list_merged(req, &file_path)?
+            .iter()
+            .any(|(child, _)| child != "." && child != "..");
+        if not_empty {
+            return Err(ErrorKind::DirectoryNotEmpty
+                .to_error(format!("{}: directory not empty", file_path.display())));
+        }
+        let in_lower = self.in_lower(req, &file_path)?;
+        if layer == Layer::Upper {
+            self.upper.rmdir(req, parent_id, name)?;
+        }
+        let mut whiteouts = self.whiteouts.lock().unwrap();
+        whiteouts.opaque.remove(&file_path);
+        if in_lower {
+            whiteouts
+                .removed
+                .retain(|path| !path.starts_with(&file_path));
+            whiteouts.removed.insert(file_path);
+        }
+        Ok(())
+    }
+
+    fn setattr(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        mut attrs: SetAttrRequest,
+    ) -> FuseResult<FileAttribute> {
+        if let Some(file_handle) = attrs.file_handle {
+            let (layer, file_handle) = self.resolve_handle(file_handle)?;
+            if layer == Layer::Upper {
+                attrs.file_handle = Some(file_handle);
+                return self.upper.setattr(req, file_id, attrs);
+            }
+            // The lower handle can't be used to modify the copy
+            attrs.file_handle = None;
+        }
+        self.copy_up(req, &file_id)?;
+        self.upper.setattr(req, file_id, attrs)
+    }
+
+    #[cfg(feature = "locking")]
+    fn setlk(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        lock_owner: u64,
+        lock_info: LockInfo,
+        sleep: bool,
+    ) -> FuseResult<()> {
+        let (layer, file_handle) = self.resolve_handle(file_handle)?;
+        self.layer(layer)
+            .setlk(req, file_id, file_handle, lock_owner, lock_info, sleep)
+    }
+
+    fn statfs(&self, req: &RequestInfo, _file_id: PathBuf) -> FuseResult<StatFs> {
+        self.upper.statfs(req, PathBuf::new())
+    }
+
+    fn symlink(
+        &self,
+        req: &RequestInfo,
+        parent_id: PathBuf,
+        link_name: &OsStr,
+        target: &Path,
+    ) -> FuseResult<FileAttribute> {
+        let file_path = parent_id.join(link_name);
+        self.prepare_new_entry(req, &file_path)?;
+        let attr = self.upper.symlink(req, parent_id, link_name, target)?;
+        self.commit_new_entry(&file_path);
+        Ok(attr)
+    }
+
+    fn unlink(&self, req: &RequestInfo, parent_id: PathBuf, name: &OsStr) -> FuseResult<()> {
+        let file_path = parent_id.join(name);
+        let (layer, _) = self.locate(req, &file_path)?;
+        let in_lower = self.in_lower(req, &file_path)?;
+        if layer == Layer::Upper {
+            self.upper.unlink(req, parent_id, name)?;
+        }
+        if in_lower {
+            self.whiteouts.lock().unwrap().removed.insert(file_path);
+        }
+        Ok(())
+    }
+
+    fn write(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        data: Vec<u8>,
+        write_flags: FUSEWriteFlags,
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+    ) -> FuseResult<u32> {
+        let (layer, file_handle) = self.resolve_handle(file_handle)?;
+        self.layer(layer).write(
+            req,
+            file_id,
+            file_handle,
+            seek,
+            data,
+            write_flags,
+            flags,
+            lock_owner,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::{mem_fs::MemFs, DefaultFuseHandler};
+
+    type TestOverlay = OverlayFs<MemFs<PathBuf>, MemFs<PathBuf>>;
+
+    fn overlay() -> TestOverlay {
+        let lower = MemFs::<PathBuf>::new(DefaultFuseHandler::new());
+        lower.insert_file("dir/lower.txt", "lower").unwrap();
+        lower.insert_file("shared.txt", "from lower").unwrap();
+        let upper = MemFs::<PathBuf>::new(DefaultFuseHandler::new());
+        upper.insert_file("shared.txt", "from upper").unwrap();
+        OverlayFs::new(lower, upper)
+    }
+
+    fn read_all(fs: &TestOverlay, path: &str) -> Vec<u8> {
+        let req = RequestInfo::default();
+        let (file_handle, _) = fs
+            .open(&req, PathBuf::from(path), OpenFlags::READ_ONLY)
+            .unwrap();
+        let data = fs
+            .read(
+                &req,
+                PathBuf::from(path),
+                file_handle.borrow(),
+                SeekFrom::Start(0),
+                1024,
+                OpenFlags::READ_ONLY,
+                None,
+            )
+            .unwrap();
+        fs.release(
+            &req,
+            PathBuf::from(path),
+            file_handle,
+            OpenFlags::READ_ONLY,
+            None,
+            false,
+        )
+        .unwrap();
+        data
+    }
+
+    fn list(fs: &TestOverlay, path: &str) -> Vec<OsString> {
+        let req = RequestInfo::default();
+        let (file_handle, _) = fs
+            .opendir(&req, PathBuf::from(path), OpenFlags::READ_ONLY)
+            .unwrap();
+        let mut names: Vec<_> = fs
+            .readdir(&req, PathBuf::from(path), file_handle.borrow())
+            .unwrap()
+            .into_iter()
+            .map(|(name, _)| name)
+            .filter(|name| name != "." && name != "..")
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_layers_are_merged() {
+        let fs = overlay();
+        assert_eq!(read_all(&fs, "shared.txt"), b"from upper");
+        assert_eq!(read_all(&fs, "dir/lower.txt"), b"lower");
+        assert_eq!(list(&fs, ""), vec!["dir", "shared.txt"]);
+    }
+
+    #[test]
+    fn test_copy_up_on_write() {
+        let fs = overlay();
+        let req = RequestInfo::default();
+        let path = PathBuf::from("dir/lower.txt");
+        let (file_handle, _) = fs.open(&req, path.clone(), OpenFlags::WRITE_ONLY).unwrap();
+        fs.write(
+            &req,
+            path.clone(),
+            file_handle.borrow(),
+            SeekFrom::Start(0),
+            b"UPPER".to_vec(),
+            FUSEWriteFlags::empty(),
+            OpenFlags::WRITE_ONLY,
+            None,
+        )
+        .unwrap();
+        fs.release(
+            &req,
+            path.clone(),
+            file_handle,
+            OpenFlags::WRITE_ONLY,
+            None,
+            true,
+        )
+        .unwrap();
+
+        assert_eq!(read_all(&fs, "dir/lower.txt"), b"UPPER");
+        // The lower layer is left untouched
+        let attr = fs.upper().getattr(&req, path.clone(), None).unwrap();
+        assert_eq!(attr.size, 5);
+        let lower_handle = unsafe { OwnedFileHandle::from_raw(0) };
+        let lower_data = fs
+            .lower()
+            .read(
+                &req,
+                path,
+                lower_handle.borrow(),
+                SeekFrom::Start(0),
+                1024,
+                OpenFlags::READ_ONLY,
+                None,
+            )
+            .unwrap();
+        assert_eq!(lower_data, b"lower");
+    }
+
+    #[test]
+    fn test_whiteouts() {
+        let fs = overlay();
+        let req = RequestInfo::default();
+
+        fs.unlink(&req, PathBuf::from("dir"), OsStr::new("lower.txt"))
+            .unwrap();
+        let error = fs
+            .lookup(&req, PathBuf::from("dir"), OsStr::new("lower.txt"))
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::FileNotFound);
+        assert!(list(&fs, "dir").is_empty());
+
+        // Deleting the upper entry reveals nothing, the lower one is whited out as well
+        fs.unlink(&req, PathBuf::new(), OsStr::new("shared.txt"))
+            .unwrap();
+        assert!(fs
+            .lookup(&req, PathBuf::new(), OsStr::new("shared.txt"))
+            .is_err());
+
+        // A directory recreated over a deleted one doesn't show the lower entries
+        fs.rmdir(&req, PathBuf::new(), OsStr::new("dir")).unwrap();
+        fs.mkdir(&req, PathBuf::new(), OsStr::new("dir"), 0o755, 0)
+            .unwrap();
+        assert!(list(&fs, "dir").is_empty());
+        assert_eq!(list(&fs, ""), vec!["dir"]);
+    }
+
+    /// Upper layer recording the handles it receives in `fallocate`
+    struct FallocateLayer {
+        inner: MemFs<PathBuf>,
+        handles: Mutex<Vec<u64>>,
+    }
+
+    impl FuseHandler<PathBuf> for FallocateLayer {
+        fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+            &self.inner
+        }
+
+        fn fallocate(
+            &self,
+            _req: &RequestInfo,
+            _file_id: PathBuf,
+            file_handle: BorrowedFileHandle,
+            _offset: i64,
+            _length: i64,
+            _mode: FallocateFlags,
+        ) -> FuseResult<()> {
+            self.handles.lock().unwrap().push(file_handle.as_raw());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_fallocate_reaches_the_layer_handle() {
+        let upper = MemFs::<PathBuf>::new(DefaultFuseHandler::new());
+        upper.insert_file("upper.txt", "upper").unwrap();
+        let fs = OverlayFs::new(
+            MemFs::<PathBuf>::new(DefaultFuseHandler::new()),
+            FallocateLayer {
+                inner: upper,
+                handles: Mutex::new(Vec::new()),
+            },
+        );
+        let req = RequestInfo::default();
+
+        let (file_handle, _) = fs
+            .open(&req, PathBuf::from("upper.txt"), OpenFlags::READ_WRITE)
+            .unwrap();
+        let (_, layer_handle) = fs.resolve_handle(file_handle.borrow()).unwrap();
+        fs.fallocate(
+            &req,
+            PathBuf::from("upper.txt"),
+            file_handle.borrow(),
+            0,
+            4096,
+            FallocateFlags::empty(),
+        )
+        .unwrap();
+        assert_eq!(*fs.upper().handles.lock().unwrap(), [layer_handle.as_raw()]);
+
+        // Unknown handles are rejected instead of reaching a layer
+        let error = fs
+            .fallocate(
+                &req,
+                PathBuf::from("upper.txt"),
+                unsafe { BorrowedFileHandle::from_raw(u64::MAX) },
+                0,
+                4096,
+                FallocateFlags::empty(),
+            )
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::BadFileDescriptor);
+    }
+}
@@ -0,0 +1,219 @@
+use std::ffi::{OsStr, OsString};
+use std::path::Path;
+
+use crate::prelude::*;
+
+/// Wraps a handler to present every file as owned by a single user and group.
+///
+/// The `uid` and `gid` of all attributes returned by the inner handler are replaced, whatever the ownership
+/// stored by the backend. This avoids permission confusion on single-user mounts, for example
+/// when mirroring files owned by another user or stored on a backend without ownership.
+///
+/// Changing the ownership is rejected with `ErrorKind::PermissionDenied` (`EPERM`), unless it targets
+/// the presented owner, in which case it is a no-op. Other operations are delegated to the inner handler.
+///
+/// ```text
+/// let uid = unsafe { libc::getuid() };
+/// let gid = unsafe { libc::getgid() };
+/// mount(OwnershipHandler::new(my_handler, uid, gid), mountpoint, &[])
+/// ```
+pub struct OwnershipHandler<H> {
+    inner: H,
+    uid: u32,
+    gid: u32,
+}
+
+impl<H> OwnershipHandler<H> {
+    pub fn new(inner: H, uid: u32, gid: u32) -> Self {
+        Self { inner, uid, gid }
+    }
+
+    pub fn inner(&self) -> &H {
+        &self.inner
+    }
+
+    fn squash(&self, mut attr: FileAttribute) -> FileAttribute {
+        attr.uid = self.uid;
+        attr.gid = self.gid;
+        attr
+    }
+
+    fn squash_metadata<T: FileIdType>(&self, mut metadata: T::Metadata) -> T::Metadata {
+        let attr = T::metadata_attribute_mut(&mut metadata);
+        attr.uid = self.uid;
+        attr.gid = self.gid;
+        metadata
+    }
+}
+
+impl<T: FileIdType, H: FuseHandler<T>> FuseHandler<T> for OwnershipHandler<H> {
+    fn get_inner(&self) -> &dyn FuseHandler<T> {
+        &self.inner
+    }
+
+    fn create(
+        &self,
+        req: &RequestInfo,
+        parent_id: T,
+        name: &OsStr,
+        mode: u32,
+        umask: u32,
+        flags: OpenFlags,
+    ) -> FuseResult<(OwnedFileHandle, T::Metadata, FUSEOpenResponseFlags)> {
+        let (file_handle, metadata, response_flags) = self
+            .inner
+            .create(req, parent_id, name, mode, umask, flags)?;
+        Ok((
+            file_handle,
+            self.squash_metadata::<T>(metadata),
+            response_flags,
+        ))
+    }
+
+    fn getattr(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: Option<BorrowedFileHandle>,
+    ) -> FuseResult<FileAttribute> {
+        self.inner
+            .getattr(req, file_id, file_handle)
+            .map(|attr| self.squash(attr))
+    }
+
+    fn link(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        newparent: T,
+        newname: &OsStr,
+    ) -> FuseResult<T::Metadata> {
+        self.inner
+            .link(req, file_id, newparent, newname)
+            .map(|metadata| self.squash_metadata::<T>(metadata))
+    }
+
+    fn lookup(&self, req: &RequestInfo, parent_id: T, name: &OsStr) -> FuseResult<T::Metadata> {
+        self.inner
+            .lookup(req, parent_id, name)
+            .map(|metadata| self.squash_metadata::<T>(metadata))
+    }
+
+    fn mkdir(
+        &self,
+        req: &RequestInfo,
+        parent_id: T,
+        name: &OsStr,
+        mode: u32,
+        umask: u32,
+    ) -> FuseResult<T::Metadata> {
+        self.inner
+            .mkdir(req, parent_id, name, mode, umask)
+            .map(|metadata| self.squash_metadata::<T>(metadata))
+    }
+
+    fn mknod(
+        &self,
+        req: &RequestInfo,
+        parent_id: T,
+        name: &OsStr,
+        mode: u32,
+        umask: u32,
+        rdev: DeviceType,
+    ) -> FuseResult<T::Metadata> {
+        self.inner
+            .mknod(req, parent_id, name, mode, umask, rdev)
+            .map(|metadata| self.squash_metadata::<T>(metadata))
+    }
+
+    fn readdirplus(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+    ) -> FuseResult<Vec<(OsString, T::Metadata)>> {
+        let children = self.inner.readdirplus(req, file_id, file_handle)?;
+        Ok(children
+            .into_iter()
+            .map(|(name, metadata)| (name, self.squash_metadata::<T>(metadata)))
+            .collect())
+    }
+
+    fn setattr(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        mut attrs: SetAttrRequest,
+    ) -> FuseResult<FileAttribute> {
+        if attrs.uid.is_some_and(|uid| uid != self.uid)
+            || attrs.gid.is_some_and(|gid| gid != self.gid)
+        {
+            return Err(ErrorKind::PermissionDenied.to_error(format!(
+                "{}: ownership is fixed to {}:{}",
+                file_id.display(),
+                self.uid,
+                self.gid
+            )));
+        }
+        // The presented owner is not the one of the backend
+        attrs.uid = None;
+        attrs.gid = None;
+        self.inner
+            .setattr(req, file_id, attrs)
+            .map(|attr| self.squash(attr))
+    }
+
+    fn symlink(
+        &self,
+        req: &RequestInfo,
+        parent_id: T,
+        link_name: &OsStr,
+        target: &Path,
+    ) -> FuseResult<T::Metadata> {
+        self.inner
+            .symlink(req, parent_id, link_name, target)
+            .map(|metadata| self.squash_metadata::<T>(metadata))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::{mem_fs::MemFs, DefaultFuseHandler};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_ownership_is_fixed() {
+        let inner = MemFs::<PathBuf>::new(DefaultFuseHandler::new());
+        inner.insert_file("file.txt", "content").unwrap();
+        let fs = OwnershipHandler::new(inner, 1234, 5678);
+        let req = RequestInfo::default();
+        let root = PathBuf::new();
+
+        let attr = fs
+            .lookup(&req, root.clone(), OsStr::new("file.txt"))
+            .unwrap();
+        assert_eq!((attr.uid, attr.gid), (1234, 5678));
+        let attr = fs.getattr(&req, root.clone(), None).unwrap();
+        assert_eq!((attr.uid, attr.gid), (1234, 5678));
+        let attr = fs
+            .mkdir(&req, root.clone(), OsStr::new("dir"), 0o755, 0)
+            .unwrap();
+        assert_eq!((attr.uid, attr.gid), (1234, 5678));
+
+        let mut chown = SetAttrRequest::new();
+        chown.uid = Some(0);
+        let error = fs
+            .setattr(&req, PathBuf::from("file.txt"), chown)
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::PermissionDenied);
+
+        // Other changes, and chown to the presented owner, are allowed
+        let mut chmod = SetAttrRequest::new();
+        chmod.mode = Some(0o600);
+        chmod.uid = Some(1234);
+        let attr = fs.setattr(&req, PathBuf::from("file.txt"), chmod).unwrap();
+        assert_eq!(attr.perm, 0o600);
+        assert_eq!((attr.uid, attr.gid), (1234, 5678));
+    }
+}
@@ -0,0 +1,246 @@
+use std::ffi::OsStr;
+use std::path::Path;
+
+use crate::prelude::*;
+
+/// Wraps a handler to make it read-only.
+///
+/// Every operation modifying the filesystem fails with `ErrorKind::ReadOnlyFileSystem` (`EROFS`)
+/// without reaching the inner handler, as well as opening a file for writing or truncating it.
+/// Other operations are delegated to the inner handler.
+///
+/// This guards against a handler mutating its backend even when mounted with `MountOption::RO`:
+///
+/// ```text
+/// mount(ReadOnly::new(my_handler), mountpoint, &[MountOption::RO])
+/// ```
+pub struct ReadOnly<H> {
+    inner: H,
+}
+
+impl<H> ReadOnly<H> {
+    pub fn new(inner: H) -> Self {
+        Self { inner }
+    }
+
+    pub fn inner(&self) -> &H {
+        &self.inner
+    }
+}
+
+fn read_only_error() -> PosixError {
+    ErrorKind::ReadOnlyFileSystem.to_error("Read-only filesystem")
+}
+
+impl<T: FileIdType, H: FuseHandler<T>> FuseHandler<T> for ReadOnly<H> {
+    fn get_inner(&self) -> &dyn FuseHandler<T> {
+        &self.inner
+    }
+
+    fn copy_file_range(
+        &self,
+        _req: &RequestInfo,
+        _file_in: T,
+        _file_handle_in: BorrowedFileHandle,
+        _offset_in: i64,
+        _file_out: T,
+        _file_handle_out: BorrowedFileHandle,
+        _offset_out: i64,
+        _len: u64,
+        _flags: u32,
+    ) -> FuseResult<u32> {
+        Err(read_only_error())
+    }
+
+    fn create(
+        &self,
+        _req: &RequestInfo,
+        _parent_id: T,
+        _name: &OsStr,
+        _mode: u32,
+        _umask: u32,
+        _flags: OpenFlags,
+    ) -> FuseResult<(OwnedFileHandle, T::Metadata, FUSEOpenResponseFlags)> {
+        Err(read_only_error())
+    }
+
+    fn fallocate(
+        &self,
+        _req: &RequestInfo,
+        _file_id: T,
+        _file_handle: BorrowedFileHandle,
+        _offset: i64,
+        _length: i64,
+        _mode: FallocateFlags,
+    ) -> FuseResult<()> {
+        Err(read_only_error())
+    }
+
+    fn link(
+        &self,
+        _req: &RequestInfo,
+        _file_id: T,
+        _newparent: T,
+        _newname: &OsStr,
+    ) -> FuseResult<T::Metadata> {
+        Err(read_only_error())
+    }
+
+    fn mkdir(
+        &self,
+        _req: &RequestInfo,
+        _parent_id: T,
+        _name: &OsStr,
+        _mode: u32,
+        _umask: u32,
+    ) -> FuseResult<T::Metadata> {
+        Err(read_only_error())
+    }
+
+    fn mknod(
+        &self,
+        _req: &RequestInfo,
+        _parent_id: T,
+        _name: &OsStr,
+        _mode: u32,
+        _umask: u32,
+        _rdev: DeviceType,
+    ) -> FuseResult<T::Metadata> {
+        Err(read_only_error())
+    }
+
+    fn open(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        flags: OpenFlags,
+    ) -> FuseResult<(OwnedFileHandle, FUSEOpenResponseFlags)> {
+        if flags.intersects(OpenFlags::WRITE_ONLY | OpenFlags::READ_WRITE | OpenFlags::TRUNCATE) {
+            return Err(read_only_error());
+        }
+        self.inner.open(req, file_id, flags)
+    }
+
+    #[cfg(feature = "xattr")]
+    fn removexattr(&self, _req: &RequestInfo, _file_id: T, _name: &OsStr) -> FuseResult<()> {
+        Err(read_only_error())
+    }
+
+    fn rename(
+        &self,
+        _req: &RequestInfo,
+        _parent_id: T,
+        _name: &OsStr,
+        _newparent: T,
+        _newname: &OsStr,
+        _flags: RenameFlags,
+    ) -> FuseResult<()> {
+        Err(read_only_error())
+    }
+
+    fn rmdir(&self, _req: &RequestInfo, _parent_id: T, _name: &OsStr) -> FuseResult<()> {
+        Err(read_only_error())
+    }
+
+    fn setattr(
+        &self,
+        _req: &RequestInfo,
+        _file_id: T,
+        _attrs: SetAttrRequest,
+    ) -> FuseResult<FileAttribute> {
+        Err(read_only_error())
+    }
+
+    #[cfg(feature = "xattr")]
+    fn setxattr(
+        &self,
+        _req: &RequestInfo,
+        _file_id: T,
+        _name: &OsStr,
+        _value: Vec<u8>,
+        _flags: FUSESetXAttrFlags,
+        _position: u32,
+    ) -> FuseResult<()> {
+        Err(read_only_error())
+    }
+
+    fn symlink(
+        &self,
+        _req: &RequestInfo,
+        _parent_id: T,
+        _link_name: &OsStr,
+        _target: &Path,
+    ) -> FuseResult<T::Metadata> {
+        Err(read_only_error())
+    }
+
+    fn write(
+        &self,
+        _req: &RequestInfo,
+        _file_id: T,
+        _file_handle: BorrowedFileHandle,
+        _seek: SeekFrom,
+        _data: Vec<u8>,
+        _write_flags: FUSEWriteFlags,
+        _flags: OpenFlags,
+        _lock_owner: Option<u64>,
+    ) -> FuseResult<u32> {
+        Err(read_only_error())
+    }
+
+    fn unlink(&self, _req: &RequestInfo, _parent_id: T, _name: &OsStr) -> FuseResult<()> {
+        Err(read_only_error())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::{mem_fs::MemFs, DefaultFuseHandler};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_read_only_rejects_mutations() {
+        let inner = MemFs::<PathBuf>::new(DefaultFuseHandler::new());
+        inner.insert_file("file.txt", "content").unwrap();
+        let fs = ReadOnly::new(inner);
+        let req = RequestInfo::default();
+        let root = PathBuf::new();
+
+        let attr = fs
+            .lookup(&req, root.clone(), OsStr::new("file.txt"))
+            .unwrap();
+        assert_eq!(attr.size, 7);
+        let (handle, _) = fs
+            .open(&req, PathBuf::from("file.txt"), OpenFlags::READ_ONLY)
+            .unwrap();
+        let data = fs
+            .read(
+                &req,
+                PathBuf::from("file.txt"),
+                handle.borrow(),
+                SeekFrom::Start(0),
+                100,
+                OpenFlags::READ_ONLY,
+                None,
+            )
+            .unwrap();
+        assert_eq!(data, b"content");
+
+        let error = fs
+            .mkdir(&req, root.clone(), OsStr::new("dir"), 0o755, 0)
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::ReadOnlyFileSystem);
+        let error = fs
+            .unlink(&req, root.clone(), OsStr::new("file.txt"))
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::ReadOnlyFileSystem);
+        let error = fs
+            .open(&req, PathBuf::from("file.txt"), OpenFlags::READ_WRITE)
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::ReadOnlyFileSystem);
+
+        // The inner handler was left untouched
+        assert!(fs.inner().lookup(&req, root, OsStr::new("dir")).is_err());
+    }
+}
@@ -0,0 +1,475 @@
+/*!
+# SeekableCompressionHandler
+
+A FUSE handler serving the decompressed content of files compressed in independent blocks, with random access.
+
+## Overview
+
+Stream compression formats must be decompressed from the start to reach a given offset, which makes random reads
+into large files slow. Formats compressing a file as a sequence of independent blocks (like BGZF, seekable zstd or
+xz with several blocks) can instead be read from any block.
+
+`SeekableCompressionHandler` wraps an inner handler serving the compressed files (like `MirrorFsReadOnly`) and:
+
+- loads a [`BlockIndex`] of each file on `open`, mapping plaintext offsets to compressed blocks,
+- answers each `read` by reading and decompressing only the blocks overlapping the requested range,
+- keeps the last decompressed block, so that sequential reads within a block decompress it once.
+
+## Pluggable format
+
+The compression format and the way the index is obtained are provided by a [`SeekableCodec`]. The index may be
+built by scanning the block headers of the file, or loaded from a footer or a sidecar file.
+
+## Note
+Files are served with `FUSEOpenResponseFlags::DIRECT_IO`, as the size reported by the inner handler on `lookup`
+is the compressed one. `getattr` reports the plaintext size once the file has been opened.
+
+Writes are delegated to the inner handler and drop the index of the file, this handler being meant for read-mostly
+content.
+*/
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::prelude::*;
+
+/// Location of one independently compressed block of a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressedBlock {
+    /// Offset of the block in the decompressed content
+    pub plain_offset: u64,
+    /// Size of the block once decompressed
+    pub plain_len: u64,
+    /// Offset of the block in the compressed file
+    pub compressed_offset: u64,
+    /// Size of the block in the compressed file
+    pub compressed_len: u32,
+}
+
+/// Maps plaintext offsets of a file to its compressed blocks
+#[derive(Debug, Clone, Default)]
+pub struct BlockIndex {
+    blocks: Vec<CompressedBlock>,
+}
+
+impl BlockIndex {
+    /// Creates an index from the blocks of a file, in any order
+    pub fn new(mut blocks: Vec<CompressedBlock>) -> Self {
+        blocks.sort_by_key(|block| block.plain_offset);
+        Self { blocks }
+    }
+
+    pub fn blocks(&self) -> &[CompressedBlock] {
+        &self.blocks
+    }
+
+    /// Size of the decompressed content
+    pub fn plain_size(&self) -> u64 {
+        self.blocks
+            .last()
+            .map_or(0, |block| block.plain_offset + block.plain_len)
+    }
+
+    /// Returns the position of the block containing the plaintext `offset`
+    pub fn find(&self, offset: u64) -> Option<usize> {
+        let position = self
+            .blocks
+            .partition_point(|block| block.plain_offset <= offset);
+        let position = position.checked_sub(1)?;
+        let block = &self.blocks[position];
+        (offset < block.plain_offset + block.plain_len).then_some(position)
+    }
+}
+
+/// Compression format of the files served by a `SeekableCompressionHandler`
+pub trait SeekableCodec: Send + Sync + 'static {
+    /// Builds or loads the block index of a compressed file.
+    ///
+    /// `read_compressed(offset, size)` reads raw bytes of the compressed file, and may return less than `size`
+    /// bytes at the end of the file.
+    fn load_index(
+        &self,
+        read_compressed: &dyn Fn(u64, u32) -> FuseResult<Vec<u8>>,
+    ) -> FuseResult<BlockIndex>;
+
+    /// Decompresses a single block from its compressed bytes
+    fn decompress_block(&self, block: &CompressedBlock, compressed: &[u8]) -> FuseResult<Vec<u8>>;
+}
+
+/// Decompressed block of a file, with its position in the index of the file
+type CachedBlock<T> = (T, usize, Arc<Vec<u8>>);
+
+/// Specific documentation is located in module documentation.
+///
+/// The index of each file is kept until the file is written to, which requires file ids to be `Send`.
+pub struct SeekableCompressionHandler<T: FileIdType, C: SeekableCodec> {
+    inner: Box<dyn FuseHandler<T>>,
+    codec: C,
+    indexes: Mutex<HashMap<T, Arc<BlockIndex>>>,
+    /// Last decompressed block, with its file and its position in the index
+    last_block: Mutex<Option<CachedBlock<T>>>,
+}
+
+impl<T: FileIdType + Send, C: SeekableCodec> SeekableCompressionHandler<T, C> {
+    pub fn new<U: FuseHandler<T>>(inner: U, codec: C) -> Self {
+        Self {
+            inner: Box::new(inner),
+            codec,
+            indexes: Mutex::new(HashMap::new()),
+            last_block: Mutex::new(None),
+        }
+    }
+
+    /// Returns the index of a file, loading it through the inner handler if not known yet
+    fn index(
+        &self,
+        req: &RequestInfo,
+        file_id: &T,
+        file_handle: BorrowedFileHandle,
+        flags: OpenFlags,
+    ) -> FuseResult<Arc<BlockIndex>> {
+        if let Some(index) = self.indexes.lock().unwrap().get(file_id) {
+            return Ok(index.clone());
+        }
+        let read_compressed = |offset: u64, size: u32| {
+            self.inner.read(
+                req,
+                file_id.clone(),
+                file_handle,
+                SeekFrom::Start(offset),
+                size,
+                flags,
+                None,
+            )
+        };
+        let index = Arc::new(self.codec.load_index(&read_compressed)?);
+        self.indexes
+            .lock()
+            .unwrap()
+            .insert(file_id.clone(), index.clone());
+        Ok(index)
+    }
+
+    /// Returns the decompressed content of the block at `position` in the index
+    fn block(
+        &self,
+        req: &RequestInfo,
+        file_id: &T,
+        file_handle: BorrowedFileHandle,
+        flags: OpenFlags,
+        position: usize,
+        block: &CompressedBlock,
+    ) -> FuseResult<Arc<Vec<u8>>> {
+        if let Some((last_id, last_position, data)) = &*self.last_block.lock().unwrap() {
+            if last_id == file_id && *last_position == position {
+                return Ok(data.clone());
+            }
+        }
+        let compressed = self.inner.read(
+            req,
+            file_id.clone(),
+            file_handle,
+            SeekFrom::Start(block.compressed_offset),
+            block.compressed_len,
+            flags,
+            None,
+        )?;
+        if compressed.len() != block.compressed_len as usize {
+            return Err(ErrorKind::InputOutputError.to_error(format!(
+                "Compressed block at offset {} is truncated",
+                block.compressed_offset
+            )));
+        }
+        let data = self.codec.decompress_block(block, &compressed)?;
+        if data.len() as u64 != block.plain_len {
+            return Err(ErrorKind::InputOutputError.to_error(format!(
+                "Block at offset {} decompressed to {} bytes instead of {}",
+                block.plain_offset,
+                data.len(),
+                block.plain_len
+            )));
+        }
+        let data = Arc::new(data);
+        *self.last_block.lock().unwrap() = Some((file_id.clone(), position, data.clone()));
+        Ok(data)
+    }
+
+    fn forget_index(&self, file_id: &T) {
+        self.indexes.lock().unwrap().remove(file_id);
+        let mut last_block = self.last_block.lock().unwrap();
+        if matches!(&*last_block, Some((last_id, _, _)) if last_id == file_id) {
+            *last_block = None;
+        }
+    }
+}
+
+impl<T: FileIdType + Send, C: SeekableCodec> FuseHandler<T> for SeekableCompressionHandler<T, C> {
+    fn get_inner(&self) -> &dyn FuseHandler<T> {
+        self.inner.as_ref()
+    }
+
+    fn drop_caches(&self, req: &RequestInfo) -> FuseResult<()> {
+        self.indexes.lock().unwrap().clear();
+        *self.last_block.lock().unwrap() = None;
+        self.inner.drop_caches(req)
+    }
+
+    fn getattr(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: Option<BorrowedFileHandle>,
+    ) -> FuseResult<FileAttribute> {
+        let index = self.indexes.lock().unwrap().get(&file_id).cloned();
+        let mut file_attr = self.inner.getattr(req, file_id, file_handle)?;
+        if let Some(index) = index {
+            file_attr.size = index.plain_size();
+        }
+        Ok(file_attr)
+    }
+
+    fn open(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        flags: OpenFlags,
+    ) -> FuseResult<(OwnedFileHandle, FUSEOpenResponseFlags)> {
+        let (file_handle, response_flags) = self.inner.open(req, file_id.clone(), flags)?;
+        self.index(req, &file_id, file_handle.borrow(), flags)?;
+        Ok((
+            file_handle,
+            response_flags | FUSEOpenResponseFlags::DIRECT_IO,
+        ))
+    }
+
+    fn read(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        size: u32,
+        flags: OpenFlags,
+        _lock_owner: Option<u64>,
+    ) -> FuseResult<Vec<u8>> {
+        let offset = match seek {
+            SeekFrom::Start(offset) => offset,
+            _ => return Err(ErrorKind::InvalidArgument.to_error("Invalid offset")),
+        };
+        let index = self.index(req, &file_id, file_handle, flags)?;
+        let end = offset.saturating_add(size as u64).min(index.plain_size());
+        let mut result = Vec::with_capacity(end.saturating_sub(offset) as usize);
+        let mut position = offset;
+        while position < end {
+            let block_position = index.find(position).ok_or_else(|| {
+                ErrorKind::InputOutputError
+                    .to_error(format!("No compressed block contains offset {}", position))
+            })?;
+            let block = &index.blocks()[block_position];
+            let data = self.block(req, &file_id, file_handle, flags, block_position, block)?;
+            let start = (position - block.plain_offset) as usize;
+            let stop = (end - block.plain_offset).min(block.plain_len) as usize;
+            result.extend_from_slice(&data[start..stop]);
+            position = block.plain_offset + stop as u64;
+        }
+        Ok(result)
+    }
+
+    fn setattr(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        attrs: SetAttrRequest,
+    ) -> FuseResult<FileAttribute> {
+        self.forget_index(&file_id);
+        self.inner.setattr(req, file_id, attrs)
+    }
+
+    fn write(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        data: Vec<u8>,
+        write_flags: FUSEWriteFlags,
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+    ) -> FuseResult<u32> {
+        self.forget_index(&file_id);
+        self.inner.write(
+            req,
+            file_id,
+            file_handle,
+            seek,
+            data,
+            write_flags,
+            flags,
+            lock_owner,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::{mem_fs::MemFs, DefaultFuseHandler};
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const BLOCK_SIZE: u64 = 1024;
+
+    /// Toy format: a header of little endian u32 compressed block sizes terminated by 0, followed by
+    /// blocks of `BLOCK_SIZE` bytes run-length encoded as (count, byte) pairs
+    #[derive(Default)]
+    struct RleCodec {
+        decompressed: Arc<AtomicUsize>,
+    }
+
+    fn compress(data: &[u8]) -> Vec<u8> {
+        let blocks: Vec<Vec<u8>> = data
+            .chunks(BLOCK_SIZE as usize)
+            .map(|chunk| {
+                let mut block: Vec<u8> = Vec::new();
+                for &byte in chunk {
+                    match block.len() {
+                        len if len > 0 && block[len - 1] == byte && block[len - 2] < 255 => {
+                            block[len - 2] += 1
+                        }
+                        _ => block.extend_from_slice(&[1, byte]),
+                    }
+                }
+                block
+            })
+            .collect();
+        let mut result = Vec::new();
+        for block in blocks.iter() {
+            result.extend_from_slice(&(block.len() as u32).to_le_bytes());
+        }
+        result.extend_from_slice(&0u32.to_le_bytes());
+        result.extend(blocks.concat());
+        result
+    }
+
+    impl SeekableCodec for RleCodec {
+        fn load_index(
+            &self,
+            read_compressed: &dyn Fn(u64, u32) -> FuseResult<Vec<u8>>,
+        ) -> FuseResult<BlockIndex> {
+            let mut sizes = Vec::new();
+            loop {
+                let header = read_compressed(sizes.len() as u64 * 4, 4)?;
+                let size = u32::from_le_bytes(header.try_into().unwrap());
+                if size == 0 {
+                    break;
+                }
+                sizes.push(size);
+            }
+            let mut compressed_offset = (sizes.len() as u64 + 1) * 4;
+            let blocks = sizes
+                .into_iter()
+                .enumerate()
+                .map(|(i, compressed_len)| {
+                    let compressed = read_compressed(compressed_offset, compressed_len).unwrap();
+                    let plain_len = compressed.chunks(2).map(|pair| pair[0] as u64).sum();
+                    let block = CompressedBlock {
+                        plain_offset: i as u64 * BLOCK_SIZE,
+                        plain_len,
+                        compressed_offset,
+                        compressed_len,
+                    };
+                    compressed_offset += compressed_len as u64;
+                    block
+                })
+                .collect();
+            Ok(BlockIndex::new(blocks))
+        }
+
+        fn decompress_block(
+            &self,
+            _block: &CompressedBlock,
+            compressed: &[u8],
+        ) -> FuseResult<Vec<u8>> {
+            self.decompressed.fetch_add(1, Ordering::SeqCst);
+            Ok(compressed
+                .chunks(2)
+                .flat_map(|pair| std::iter::repeat_n(pair[1], pair[0] as usize))
+                .collect())
+        }
+    }
+
+    #[test]
+    fn test_block_index_find() {
+        let index = BlockIndex::new(vec![
+            CompressedBlock {
+                plain_offset: 100,
+                plain_len: 50,
+                compressed_offset: 10,
+                compressed_len: 5,
+            },
+            CompressedBlock {
+                plain_offset: 0,
+                plain_len: 100,
+                compressed_offset: 0,
+                compressed_len: 10,
+            },
+        ]);
+        assert_eq!(index.plain_size(), 150);
+        assert_eq!(index.find(0), Some(0));
+        assert_eq!(index.find(99), Some(0));
+        assert_eq!(index.find(100), Some(1));
+        assert_eq!(index.find(150), None);
+    }
+
+    #[test]
+    fn test_random_read_decompresses_one_block() {
+        let plain: Vec<u8> = (0..64 * BLOCK_SIZE).map(|i| (i / 100) as u8).collect();
+        let inner = MemFs::<PathBuf>::new(DefaultFuseHandler::new());
+        inner.insert_file("big.rle", compress(&plain)).unwrap();
+
+        let codec = RleCodec::default();
+        let decompressed = codec.decompressed.clone();
+        let fs = SeekableCompressionHandler::new(inner, codec);
+        let req = RequestInfo::default();
+        let file_id = PathBuf::from("big.rle");
+
+        let (handle, response_flags) = fs
+            .open(&req, file_id.clone(), OpenFlags::READ_ONLY)
+            .unwrap();
+        assert!(response_flags.contains(FUSEOpenResponseFlags::DIRECT_IO));
+        let attr = fs.getattr(&req, file_id.clone(), None).unwrap();
+        assert_eq!(attr.size, plain.len() as u64);
+
+        // In the middle of block 32
+        let offset = 32 * BLOCK_SIZE + 100;
+        let read = |offset: u64, size: u32| {
+            fs.read(
+                &req,
+                file_id.clone(),
+                handle.borrow(),
+                SeekFrom::Start(offset),
+                size,
+                OpenFlags::READ_ONLY,
+                None,
+            )
+            .unwrap()
+        };
+        assert_eq!(
+            read(offset, 200),
+            &plain[offset as usize..offset as usize + 200]
+        );
+        assert_eq!(decompressed.load(Ordering::SeqCst), 1);
+
+        // Within the same block, served from the last decompressed block
+        assert_eq!(
+            read(offset + 200, 100),
+            &plain[offset as usize + 200..offset as usize + 300]
+        );
+        assert_eq!(decompressed.load(Ordering::SeqCst), 1);
+
+        // Across two blocks, up to the end of the file
+        let offset = 63 * BLOCK_SIZE - 10;
+        assert_eq!(read(offset, 4096), &plain[offset as usize..]);
+        assert_eq!(decompressed.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,134 @@
+/*!
+# Union readdir
+
+Merges the directory listings of several backends into a single listing, the core primitive of overlay
+and union filesystems.
+
+Entries are deduplicated by name. When several sources contain the same name, the [`MergePolicy`] decides
+which entry is kept. The merged listing follows the order in which names first appear in the sources.
+
+## Example
+
+```rust
+use easy_fuser::prelude::*;
+use easy_fuser::templates::union_readdir::{merge_readdir, MergePolicy};
+use std::ffi::OsString;
+
+let upper = vec![(OsString::from("a"), FileKind::RegularFile)];
+let lower = vec![
+    (OsString::from("a"), FileKind::Directory),
+    (OsString::from("b"), FileKind::RegularFile),
+];
+let merged = merge_readdir(vec![upper, lower], MergePolicy::FirstWins).unwrap();
+assert_eq!(
+    merged,
+    vec![
+        (OsString::from("a"), FileKind::RegularFile),
+        (OsString::from("b"), FileKind::RegularFile),
+    ]
+);
+```
+*/
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+
+use crate::types::*;
+
+/// Decides which entry is kept when several sources list the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergePolicy {
+    /// Keep the entry of the first source listing the name, as an upper layer hiding lower ones
+    FirstWins,
+    /// Keep the entry of the last source listing the name
+    LastWins,
+    /// Fail with `ErrorKind::FileExists` (`EEXIST`) if a name is listed by more than one source
+    ErrorOnConflict,
+}
+
+/// Merges the listings of `sources`, deduplicating entries by name according to `policy`.
+///
+/// Sources are given in priority order for `MergePolicy::FirstWins`. The metadata type is generic,
+/// so it can be `TId::MinimalMetadata` of `readdir` or `TId::Metadata` of `readdirplus`.
+pub fn merge_readdir<M>(
+    sources: Vec<Vec<(OsString, M)>>,
+    policy: MergePolicy,
+) -> FuseResult<Vec<(OsString, M)>> {
+    let mut merged: Vec<(OsString, M)> = Vec::new();
+    let mut positions: HashMap<OsString, usize> = HashMap::new();
+    for source in sources {
+        for (name, metadata) in source {
+            match positions.get(&name) {
+                None => {
+                    positions.insert(name.clone(), merged.len());
+                    merged.push((name, metadata));
+                }
+                Some(&position) => match policy {
+                    MergePolicy::FirstWins => {}
+                    MergePolicy::LastWins => merged[position].1 = metadata,
+                    MergePolicy::ErrorOnConflict => {
+                        return Err(ErrorKind::FileExists
+                            .to_error(format!("{:?} is listed by several sources", name)));
+                    }
+                },
+            }
+        }
+    }
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, ino: u64, kind: FileKind) -> (OsString, (Inode, FileKind)) {
+        (OsString::from(name), (Inode::from(ino), kind))
+    }
+
+    fn sources() -> Vec<Vec<(OsString, (Inode, FileKind))>> {
+        vec![
+            vec![
+                entry("shared", 2, FileKind::RegularFile),
+                entry("upper", 3, FileKind::RegularFile),
+            ],
+            vec![
+                entry("lower", 4, FileKind::Directory),
+                entry("shared", 5, FileKind::Directory),
+            ],
+        ]
+    }
+
+    #[test]
+    fn test_merge_readdir() {
+        let merged = merge_readdir(sources(), MergePolicy::FirstWins).unwrap();
+        assert_eq!(
+            merged,
+            vec![
+                entry("shared", 2, FileKind::RegularFile),
+                entry("upper", 3, FileKind::RegularFile),
+                entry("lower", 4, FileKind::Directory),
+            ]
+        );
+
+        let merged = merge_readdir(sources(), MergePolicy::LastWins).unwrap();
+        assert_eq!(
+            merged,
+            vec![
+                entry("shared", 5, FileKind::Directory),
+                entry("upper", 3, FileKind::RegularFile),
+                entry("lower", 4, FileKind::Directory),
+            ]
+        );
+
+        let error = merge_readdir(sources(), MergePolicy::ErrorOnConflict).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::FileExists);
+
+        let disjoint = vec![sources().remove(0), Vec::new()];
+        assert_eq!(
+            merge_readdir(disjoint, MergePolicy::ErrorOnConflict)
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+}
@@ -0,0 +1,269 @@
+use std::ffi::OsStr;
+#[cfg(feature = "xattr")]
+use std::ffi::OsString;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::prelude::*;
+
+/// Wraps a handler to bound the duration of its read-only metadata operations.
+///
+/// Each bounded call runs on a fixed pool of threads while the calling worker waits for at most the timeout,
+/// then fails with `ErrorKind::TimedOut` (`ETIMEDOUT`). This keeps a handler blocked forever (for example
+/// on a dead socket) from exhausting the worker pool of the parallel driver.
+///
+/// Only operations which neither use a file handle nor modify the filesystem are bounded: `access`,
+/// `getattr` without a file handle, `getxattr`, `listxattr`, `lookup`, `readlink` and `statfs`.
+/// Other operations are delegated without a timeout: an abandoned `write` or `rename` may still complete
+/// after its failure was reported, and an abandoned call using a file handle could outlive its `release`.
+///
+/// ## Restrictions
+///
+/// - The inner handler must be `Send + Sync`, including with the `serial` feature, since calls move to
+///   another thread.
+/// - A call exceeding its timeout is not cancelled: it keeps its thread of the pool until the inner handler
+///   returns, and its result is discarded. Once every thread of the pool is held by such calls, bounded
+///   operations fail right away with `ErrorKind::ResourceUnavailableTryAgain` (`EAGAIN`).
+pub struct WithTimeout<H> {
+    inner: Arc<H>,
+    timeout: Duration,
+    jobs: Mutex<mpsc::Sender<Job>>,
+    /// Number of threads of the pool running or about to run an operation
+    busy: Arc<AtomicUsize>,
+    num_threads: usize,
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+impl<H> WithTimeout<H> {
+    /// Wraps `inner`, running its bounded operations on `num_threads` threads
+    ///
+    /// Panics if a thread of the pool can't be spawned.
+    pub fn new(inner: H, timeout: Duration, num_threads: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for _ in 0..num_threads {
+            let receiver = receiver.clone();
+            thread::Builder::new()
+                .name("easy_fuser-timeout".to_string())
+                .spawn(move || loop {
+                    // The lock is released before running the job
+                    let job = receiver.lock().unwrap().recv();
+                    match job {
+                        Ok(job) => job(),
+                        // The wrapper was dropped
+                        Err(_) => break,
+                    }
+                })
+                .expect("Failed to spawn operation thread");
+        }
+        Self {
+            inner: Arc::new(inner),
+            timeout,
+            jobs: Mutex::new(sender),
+            busy: Arc::new(AtomicUsize::new(0)),
+            num_threads,
+        }
+    }
+
+    pub fn inner(&self) -> &H {
+        &self.inner
+    }
+}
+
+impl<H: Send + Sync + 'static> WithTimeout<H> {
+    /// Runs `operation` on the pool, waiting for its result for at most the timeout
+    fn run<R, F>(&self, operation: F) -> FuseResult<R>
+    where
+        R: Send + 'static,
+        F: FnOnce(&H) -> FuseResult<R> + Send + 'static,
+    {
+        // Reserving a thread first guarantees that the job doesn't wait in the queue
+        if self.busy.fetch_add(1, Ordering::SeqCst) >= self.num_threads {
+            self.busy.fetch_sub(1, Ordering::SeqCst);
+            return Err(ErrorKind::ResourceUnavailableTryAgain
+                .to_error("Every operation thread is held by a timed out operation"));
+        }
+        let inner = self.inner.clone();
+        let busy = self.busy.clone();
+        let (sender, receiver) = mpsc::channel();
+        let job: Job = Box::new(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| operation(&inner)));
+            busy.fetch_sub(1, Ordering::SeqCst);
+            // Fails if the deadline expired, the result is then dropped
+            if let Ok(result) = result {
+                let _ = sender.send(result);
+            }
+        });
+        // The threads of the pool only stop once the sender is dropped
+        self.jobs.lock().unwrap().send(job).unwrap();
+        match receiver.recv_timeout(self.timeout) {
+            Ok(result) => result,
+            Err(RecvTimeoutError::Timeout) => Err(ErrorKind::TimedOut.to_error(format!(
+                "Operation did not complete within {:?}",
+                self.timeout
+            ))),
+            Err(RecvTimeoutError::Disconnected) => {
+                Err(ErrorKind::InputOutputError.to_error("Operation panicked"))
+            }
+        }
+    }
+}
+
+impl<T, H> FuseHandler<T> for WithTimeout<H>
+where
+    T: FileIdType + Send,
+    H: FuseHandler<T> + Send + Sync,
+{
+    fn get_inner(&self) -> &dyn FuseHandler<T> {
+        self.inner.as_ref()
+    }
+
+    fn access(&self, req: &RequestInfo, file_id: T, mask: AccessMask) -> FuseResult<()> {
+        let req = req.clone();
+        self.run(move |inner| inner.access(&req, file_id, mask))
+    }
+
+    fn getattr(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        file_handle: Option<BorrowedFileHandle>,
+    ) -> FuseResult<FileAttribute> {
+        // A file handle can't outlive its release, the call is never abandoned
+        if file_handle.is_some() {
+            return self.inner.getattr(req, file_id, file_handle);
+        }
+        let req = req.clone();
+        self.run(move |inner| inner.getattr(&req, file_id, None))
+    }
+
+    #[cfg(feature = "xattr")]
+    fn getxattr(
+        &self,
+        req: &RequestInfo,
+        file_id: T,
+        name: &OsStr,
+        size: u32,
+    ) -> FuseResult<Vec<u8>> {
+        let req = req.clone();
+        let name = name.to_os_string();
+        self.run(move |inner| inner.getxattr(&req, file_id, &name, size))
+    }
+
+    #[cfg(feature = "xattr")]
+    fn listxattr(&self, req: &RequestInfo, file_id: T, size: u32) -> FuseResult<Vec<u8>> {
+        let req = req.clone();
+        self.run(move |inner| inner.listxattr(&req, file_id, size))
+    }
+
+    #[cfg(feature = "xattr")]
+    fn listxattr_names(&self, req: &RequestInfo, file_id: T) -> FuseResult<Vec<OsString>> {
+        let req = req.clone();
+        self.run(move |inner| inner.listxattr_names(&req, file_id))
+    }
+
+    fn lookup(&self, req: &RequestInfo, parent_id: T, name: &OsStr) -> FuseResult<T::Metadata> {
+        let req = req.clone();
+        let name = name.to_os_string();
+        self.run(move |inner| inner.lookup(&req, parent_id, &name))
+    }
+
+    fn readlink(&self, req: &RequestInfo, file_id: T) -> FuseResult<Vec<u8>> {
+        let req = req.clone();
+        self.run(move |inner| inner.readlink(&req, file_id))
+    }
+
+    fn statfs(&self, req: &RequestInfo, file_id: T) -> FuseResult<StatFs> {
+        let req = req.clone();
+        self.run(move |inner| inner.statfs(&req, file_id))
+    }
+}
+
+// Handlers are only Send + Sync outside of the serial mode
+#[cfg(all(test, not(feature = "serial")))]
+mod tests {
+    use super::*;
+    use crate::templates::{mem_fs::MemFs, DefaultFuseHandler};
+    use std::path::PathBuf;
+
+    struct StuckLookupFs {
+        inner: MemFs<PathBuf>,
+    }
+
+    impl FuseHandler<PathBuf> for StuckLookupFs {
+        fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+            &self.inner
+        }
+
+        fn lookup(
+            &self,
+            req: &RequestInfo,
+            parent_id: PathBuf,
+            name: &OsStr,
+        ) -> FuseResult<FileAttribute> {
+            if name == "stuck" {
+                thread::sleep(Duration::from_secs(1));
+            }
+            self.inner.lookup(req, parent_id, name)
+        }
+    }
+
+    #[test]
+    fn test_with_timeout() {
+        let inner = MemFs::<PathBuf>::new(DefaultFuseHandler::new());
+        inner.insert_file("file.txt", "content").unwrap();
+        let fs = WithTimeout::new(StuckLookupFs { inner }, Duration::from_millis(100), 1);
+        let req = RequestInfo::default();
+
+        let attr = fs
+            .lookup(&req, PathBuf::new(), OsStr::new("file.txt"))
+            .unwrap();
+        assert_eq!(attr.size, 7);
+        let (handle, _) = fs
+            .open(&req, PathBuf::from("file.txt"), OpenFlags::READ_ONLY)
+            .unwrap();
+        let data = fs
+            .read(
+                &req,
+                PathBuf::from("file.txt"),
+                handle.borrow(),
+                SeekFrom::Start(0),
+                100,
+                OpenFlags::READ_ONLY,
+                None,
+            )
+            .unwrap();
+        assert_eq!(data, b"content");
+
+        let error = fs
+            .lookup(&req, PathBuf::new(), OsStr::new("stuck"))
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TimedOut);
+        // The abandoned lookup still holds the only thread of the pool
+        let error = fs
+            .lookup(&req, PathBuf::new(), OsStr::new("file.txt"))
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::ResourceUnavailableTryAgain);
+        // Reads are never bounded, they don't wait for the pool
+        let (handle, _) = fs
+            .open(&req, PathBuf::from("file.txt"), OpenFlags::READ_ONLY)
+            .unwrap();
+        let data = fs
+            .read(
+                &req,
+                PathBuf::from("file.txt"),
+                handle.borrow(),
+                SeekFrom::Start(0),
+                100,
+                OpenFlags::READ_ONLY,
+                None,
+            )
+            .unwrap();
+        assert_eq!(data, b"content");
+    }
+}
@@ -0,0 +1,86 @@
+//! Session loop running on a thread configured by the caller.
+//!
+//! [`spawn_mount_with_thread_config`](crate::spawn_mount_with_thread_config) works like `spawn_mount`,
+//! but lets the caller name the thread serving the session and size its stack. The name makes the mount
+//! identifiable in debuggers and `top -H`, a larger stack avoids overflows in handlers with deep recursion.
+//!
+//! In the `parallel` mode, the handler methods run on the worker threads of the driver, which keep the
+//! default name and stack size. The session thread receives the requests, and runs the handler itself
+//! in the `serial` mode.
+
+use std::io;
+use std::thread::{self, JoinHandle, Thread};
+
+use fuser::{Filesystem, Session, SessionUnmounter};
+
+/// Name and stack size of the thread serving a session
+///
+/// Unset fields keep the defaults of `std::thread`.
+#[derive(Debug, Clone, Default)]
+pub struct ThreadConfig {
+    /// Name of the thread. Linux truncates it to 15 bytes in `/proc/self/task/*/comm`.
+    pub name: Option<String>,
+    /// Stack size of the thread, in bytes
+    pub stack_size: Option<usize>,
+}
+
+/// A mounted filesystem whose session runs on a configured thread
+///
+/// Dropping it unmounts the filesystem and waits for the thread, like `BackgroundSession`.
+pub struct ThreadedSession {
+    guard: Option<JoinHandle<io::Result<()>>>,
+    unmounter: SessionUnmounter,
+}
+
+impl ThreadedSession {
+    pub(crate) fn spawn<FS>(mut session: Session<FS>, config: ThreadConfig) -> io::Result<Self>
+    where
+        FS: Filesystem + Send + 'static,
+    {
+        let unmounter = session.unmount_callable();
+        let mut builder = thread::Builder::new();
+        if let Some(name) = config.name {
+            builder = builder.name(name);
+        }
+        if let Some(stack_size) = config.stack_size {
+            builder = builder.stack_size(stack_size);
+        }
+        let guard = builder.spawn(move || session.run())?;
+        Ok(Self {
+            guard: Some(guard),
+            unmounter,
+        })
+    }
+
+    /// Thread serving the session
+    pub fn thread(&self) -> &Thread {
+        self.guard.as_ref().expect("Session not joined").thread()
+    }
+
+    /// Unmounts the filesystem and waits for the thread, returning the result of the session loop
+    pub fn join(mut self) -> io::Result<()> {
+        self.stop()
+    }
+
+    fn stop(&mut self) -> io::Result<()> {
+        let Some(guard) = self.guard.take() else {
+            return Ok(());
+        };
+        // The filesystem may have been unmounted externally
+        let unmounted = if guard.is_finished() {
+            Ok(())
+        } else {
+            self.unmounter.unmount()
+        };
+        let result = guard
+            .join()
+            .map_err(|_| io::Error::other("Session thread panicked"))?;
+        unmounted.and(result)
+    }
+}
+
+impl Drop for ThreadedSession {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
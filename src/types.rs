@@ -5,10 +5,12 @@
 //!
 //! # Modules
 //!
+//! - \[acl\]: Encodes and decodes POSIX ACLs stored in extended attributes.
 //! - \[arguments\]: Defines argument types and structures for FUSE operations.
 //! - \[errors\]: Contains error types and handling for FUSE operations.
 //! - \[file_descriptor\]: Provides types related to file descriptors.
 //! - \[file_id_type\]: Defines traits for file identification.
+//! - \[file_kind\]: Defines the `FileKind` type of filesystem objects.
 //! - \[flags\]: Contains flag definitions for various FUSE operations.
 //! - \[inode\]: Defines the `Inode` type for representing filesystem objects.
 //!
@@ -17,13 +19,17 @@
 //! This module re-exports key types from its submodules for easier access, as well as
 //! some types from the `fuser` crate that are commonly used in FUSE operations.
 
+pub mod acl;
 pub mod arguments;
 pub mod errors;
 pub mod file_handle;
 mod file_id_type;
+mod file_kind;
 pub mod flags;
 mod inode;
 
-pub use self::{arguments::*, errors::*, file_handle::*, file_id_type::*, flags::*, inode::*};
+pub use self::{
+    arguments::*, errors::*, file_handle::*, file_id_type::*, file_kind::*, flags::*, inode::*,
+};
 
-pub use fuser::{FileType as FileKind, KernelConfig, TimeOrNow};
+pub use fuser::{KernelConfig, PollHandle, TimeOrNow};
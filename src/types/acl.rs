@@ -0,0 +1,263 @@
+//! POSIX access control lists, as stored in extended attributes.
+//!
+//! `setfacl` and `getfacl` exchange ACLs with the filesystem through the `system.posix_acl_access` and
+//! `system.posix_acl_default` extended attributes, in the binary format of the Linux kernel. Handlers
+//! supporting ACLs can decode them in `setxattr` with [`Acl::from_xattr`], and encode them in `getxattr`
+//! with [`Acl::to_xattr`]. [`Acl::permissions`] implements the POSIX access check algorithm.
+//!
+//! The kernel only forwards these attributes when the handler requests `FUSEInitFlags::POSIX_ACL`
+//! in `FuseHandler::init`.
+//!
+//! # Example
+//! ```rust
+//! use easy_fuser::types::acl::{Acl, AclEntry, AclPerm, AclTag};
+//!
+//! // rw-r----- with read access granted to the user 1000
+//! let mut acl = Acl::from_mode(0o640);
+//! acl.entries.push(AclEntry::new(AclTag::User(1000), AclPerm::READ));
+//! acl.entries.push(AclEntry::new(AclTag::Mask, AclPerm::READ));
+//!
+//! let acl = Acl::from_xattr(&acl.to_xattr()).unwrap();
+//! assert_eq!(acl.permissions(0, 0, 1000, &[]), AclPerm::READ);
+//! ```
+
+use bitflags::bitflags;
+
+use super::errors::{ErrorKind, PosixError};
+
+/// Name of the extended attribute holding the access ACL of a file
+pub const ACL_ACCESS_XATTR: &str = "system.posix_acl_access";
+/// Name of the extended attribute holding the default ACL of a directory, inherited by its new entries
+pub const ACL_DEFAULT_XATTR: &str = "system.posix_acl_default";
+
+/// Version of the binary format
+const ACL_XATTR_VERSION: u32 = 2;
+/// Size of the header, holding the version
+const HEADER_SIZE: usize = 4;
+/// Size of an entry: tag (u16), permissions (u16) and id (u32)
+const ENTRY_SIZE: usize = 8;
+/// Id stored in the entries which don't designate a user or group
+const UNDEFINED_ID: u32 = u32::MAX;
+
+const TAG_USER_OBJ: u16 = 0x01;
+const TAG_USER: u16 = 0x02;
+const TAG_GROUP_OBJ: u16 = 0x04;
+const TAG_GROUP: u16 = 0x08;
+const TAG_MASK: u16 = 0x10;
+const TAG_OTHER: u16 = 0x20;
+
+bitflags! {
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    /// Permissions granted by an ACL entry.
+    pub struct AclPerm: u16 {
+        const READ = 0o4;
+        const WRITE = 0o2;
+        const EXECUTE = 0o1;
+    }
+}
+
+/// Subject of an ACL entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AclTag {
+    /// Owner of the file, mirrors the user permission bits
+    UserObj,
+    /// User with the given uid
+    User(u32),
+    /// Group of the file, mirrors the group permission bits when there is no mask
+    GroupObj,
+    /// Group with the given gid
+    Group(u32),
+    /// Upper bound of the permissions of named users and of groups, mirrors the group permission bits
+    Mask,
+    /// Everyone else, mirrors the other permission bits
+    Other,
+}
+
+impl AclTag {
+    /// Position in the order required by the kernel, entries of the same kind being sorted by id
+    fn sort_key(&self) -> (u16, u32) {
+        match *self {
+            AclTag::UserObj => (TAG_USER_OBJ, UNDEFINED_ID),
+            AclTag::User(uid) => (TAG_USER, uid),
+            AclTag::GroupObj => (TAG_GROUP_OBJ, UNDEFINED_ID),
+            AclTag::Group(gid) => (TAG_GROUP, gid),
+            AclTag::Mask => (TAG_MASK, UNDEFINED_ID),
+            AclTag::Other => (TAG_OTHER, UNDEFINED_ID),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AclEntry {
+    pub tag: AclTag,
+    pub perm: AclPerm,
+}
+
+impl AclEntry {
+    pub fn new(tag: AclTag, perm: AclPerm) -> Self {
+        Self { tag, perm }
+    }
+}
+
+/// Access control list of a file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Acl {
+    pub entries: Vec<AclEntry>,
+}
+
+impl Acl {
+    /// Returns the minimal ACL equivalent to the permission bits of `mode`
+    pub fn from_mode(mode: u32) -> Self {
+        let perm = |shift: u32| AclPerm::from_bits_truncate(((mode >> shift) & 0o7) as u16);
+        Self {
+            entries: vec![
+                AclEntry::new(AclTag::UserObj, perm(6)),
+                AclEntry::new(AclTag::GroupObj, perm(3)),
+                AclEntry::new(AclTag::Other, perm(0)),
+            ],
+        }
+    }
+
+    /// Decodes the value of an ACL extended attribute
+    ///
+    /// Fails with `InvalidArgument` if the data is not a valid ACL, as the kernel would.
+    pub fn from_xattr(data: &[u8]) -> Result<Self, PosixError> {
+        let invalid =
+            |reason: &str| PosixError::new(ErrorKind::InvalidArgument, reason.to_string());
+        if data.len() < HEADER_SIZE || !(data.len() - HEADER_SIZE).is_multiple_of(ENTRY_SIZE) {
+            return Err(invalid("ACL size is not a whole number of entries"));
+        }
+        let version = u32::from_le_bytes(data[..HEADER_SIZE].try_into().unwrap());
+        if version != ACL_XATTR_VERSION {
+            return Err(invalid("Unsupported ACL version"));
+        }
+        let entries = data[HEADER_SIZE..]
+            .chunks_exact(ENTRY_SIZE)
+            .map(|entry| {
+                let tag = u16::from_le_bytes([entry[0], entry[1]]);
+                let perm = u16::from_le_bytes([entry[2], entry[3]]);
+                let id = u32::from_le_bytes([entry[4], entry[5], entry[6], entry[7]]);
+                let tag = match tag {
+                    TAG_USER_OBJ => AclTag::UserObj,
+                    TAG_USER => AclTag::User(id),
+                    TAG_GROUP_OBJ => AclTag::GroupObj,
+                    TAG_GROUP => AclTag::Group(id),
+                    TAG_MASK => AclTag::Mask,
+                    TAG_OTHER => AclTag::Other,
+                    _ => return Err(invalid("Unknown ACL entry tag")),
+                };
+                let perm =
+                    AclPerm::from_bits(perm).ok_or_else(|| invalid("Invalid ACL permissions"))?;
+                Ok(AclEntry::new(tag, perm))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { entries })
+    }
+
+    /// Encodes the ACL as the value of an ACL extended attribute, with its entries in the kernel order
+    pub fn to_xattr(&self) -> Vec<u8> {
+        let mut entries = self.entries.clone();
+        entries.sort_by_key(|entry| entry.tag.sort_key());
+        let mut data = Vec::with_capacity(HEADER_SIZE + entries.len() * ENTRY_SIZE);
+        data.extend_from_slice(&ACL_XATTR_VERSION.to_le_bytes());
+        for entry in entries {
+            let (tag, id) = entry.tag.sort_key();
+            data.extend_from_slice(&tag.to_le_bytes());
+            data.extend_from_slice(&entry.perm.bits().to_le_bytes());
+            data.extend_from_slice(&id.to_le_bytes());
+        }
+        data
+    }
+
+    /// Returns the permissions granted to the user `uid`, member of the groups `gids`, on a file owned
+    /// by `owner` and `owner_group`
+    pub fn permissions(&self, owner: u32, owner_group: u32, uid: u32, gids: &[u32]) -> AclPerm {
+        let find = |tag: AclTag| self.entries.iter().find(|entry| entry.tag == tag);
+        let mask = find(AclTag::Mask).map_or(AclPerm::all(), |entry| entry.perm);
+
+        if uid == owner {
+            return find(AclTag::UserObj).map_or(AclPerm::empty(), |entry| entry.perm);
+        }
+        if let Some(entry) = find(AclTag::User(uid)) {
+            return entry.perm & mask;
+        }
+        // Any matching group entry grants its permissions
+        let is_member = |gid: u32| gids.contains(&gid);
+        let mut group_perm = None;
+        for entry in &self.entries {
+            let matches = match entry.tag {
+                AclTag::GroupObj => is_member(owner_group),
+                AclTag::Group(gid) => is_member(gid),
+                _ => false,
+            };
+            if matches {
+                group_perm = Some(group_perm.unwrap_or(AclPerm::empty()) | entry.perm);
+            }
+        }
+        if let Some(group_perm) = group_perm {
+            return group_perm & mask;
+        }
+        find(AclTag::Other).map_or(AclPerm::empty(), |entry| entry.perm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_acl_roundtrip() {
+        let mut acl = Acl::from_mode(0o640);
+        acl.entries
+            .push(AclEntry::new(AclTag::User(1000), AclPerm::READ));
+        acl.entries
+            .push(AclEntry::new(AclTag::Mask, AclPerm::READ | AclPerm::WRITE));
+
+        let data = acl.to_xattr();
+        assert_eq!(data.len(), HEADER_SIZE + 5 * ENTRY_SIZE);
+        assert_eq!(&data[..4], &2u32.to_le_bytes());
+        // Named users follow the owner
+        assert_eq!(&data[12..20], &[0x02, 0, 0o4, 0, 0xe8, 0x03, 0, 0]);
+
+        let decoded = Acl::from_xattr(&data).unwrap();
+        assert_eq!(decoded.entries.len(), 5);
+        assert_eq!(
+            decoded.entries[1],
+            AclEntry::new(AclTag::User(1000), AclPerm::READ)
+        );
+        assert_eq!(decoded.to_xattr(), data);
+
+        assert_eq!(
+            decoded.permissions(0, 0, 0, &[]),
+            AclPerm::READ | AclPerm::WRITE
+        );
+        assert_eq!(decoded.permissions(0, 0, 1000, &[]), AclPerm::READ);
+        assert_eq!(decoded.permissions(0, 0, 1001, &[0]), AclPerm::READ);
+        assert_eq!(decoded.permissions(0, 0, 1001, &[]), AclPerm::empty());
+    }
+
+    #[test]
+    fn test_acl_mask() {
+        let mut acl = Acl::from_mode(0o777);
+        acl.entries
+            .push(AclEntry::new(AclTag::Group(100), AclPerm::all()));
+        acl.entries.push(AclEntry::new(AclTag::Mask, AclPerm::READ));
+        assert_eq!(acl.permissions(0, 0, 1000, &[100]), AclPerm::READ);
+        // The mask doesn't apply to the owner and to others
+        assert_eq!(acl.permissions(1000, 0, 1000, &[]), AclPerm::all());
+        assert_eq!(acl.permissions(0, 0, 1000, &[]), AclPerm::all());
+    }
+
+    #[test]
+    fn test_invalid_acl() {
+        assert!(Acl::from_xattr(&[]).is_err());
+        assert!(Acl::from_xattr(&1u32.to_le_bytes()).is_err());
+        let mut data = Acl::from_mode(0o644).to_xattr();
+        data.push(0);
+        assert!(Acl::from_xattr(&data).is_err());
+        let mut data = Acl::from_mode(0o644).to_xattr();
+        data[4] = 0x40;
+        let error = Acl::from_xattr(&data).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidArgument);
+    }
+}
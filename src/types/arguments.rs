@@ -8,6 +8,7 @@
 //! - [`RequestInfo`]: Encapsulates essential information about a FUSE request.
 //! - [`FileAttribute`]: Represents file attributes for FUSE operations with optional caching parameters.
 //! - [`SetAttrRequest`]: Represents a request to set file attributes in a FUSE file system.
+//! - [`MountOptionsBuilder`]: Builds the mount options of a filesystem, including its name and subtype.
 //!
 //! # Functions
 //!
@@ -15,14 +16,17 @@
 //!
 //! This module also re-exports `SeekFrom` from the standard library for convenience.
 
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use fuser::FileAttr as FuseFileAttr;
-use fuser::{FileType, Request, TimeOrNow};
+use fuser::{MountOption, Request, TimeOrNow};
 use libc::mode_t;
 
+use super::AttrMask;
 use super::BorrowedFileHandle;
+use super::FileKind;
 use super::LockType;
+use super::{ErrorKind, PosixError};
 
 pub use std::io::SeekFrom;
 
@@ -65,19 +69,55 @@ pub enum DeviceType {
 }
 
 impl DeviceType {
+    /// Builds a device type from explicit major and minor numbers, as needed by `mknod`.
+    ///
+    /// The numbers are only kept for `FileKind::CharDevice` and `FileKind::BlockDevice`.
+    pub fn from_parts(major: u32, minor: u32, kind: FileKind) -> Self {
+        match kind {
+            FileKind::RegularFile => DeviceType::RegularFile,
+            FileKind::Directory => DeviceType::Directory,
+            FileKind::CharDevice => DeviceType::CharacterDevice { major, minor },
+            FileKind::BlockDevice => DeviceType::BlockDevice { major, minor },
+            FileKind::NamedPipe => DeviceType::NamedPipe,
+            FileKind::Socket => DeviceType::Socket,
+            FileKind::Symlink => DeviceType::Symlink,
+        }
+    }
+
+    /// Major number of a character or block device
+    pub fn major(&self) -> Option<u32> {
+        match self {
+            DeviceType::CharacterDevice { major, .. } | DeviceType::BlockDevice { major, .. } => {
+                Some(*major)
+            }
+            _ => None,
+        }
+    }
+
+    /// Minor number of a character or block device
+    pub fn minor(&self) -> Option<u32> {
+        match self {
+            DeviceType::CharacterDevice { minor, .. } | DeviceType::BlockDevice { minor, .. } => {
+                Some(*minor)
+            }
+            _ => None,
+        }
+    }
+
     pub fn from_rdev(rdev: mode_t) -> Self {
         use libc::*;
-        // Extract major and minor device numbers (assuming the device number format).
-        let major: u32 = (rdev >> 8).into(); // Major is the upper part of the 32-bit value (16 bit on macos)
-        let minor: u32 = (rdev & 0xFF).into(); // Minor is the lower 8 bits
-        match rdev {
-            x if x & S_IFREG != 0 => DeviceType::RegularFile,
-            x if x & S_IFDIR != 0 => DeviceType::Directory,
-            x if x & S_IFCHR != 0 => DeviceType::CharacterDevice { major, minor },
-            x if x & S_IFBLK != 0 => DeviceType::BlockDevice { major, minor },
-            x if x & S_IFIFO != 0 => DeviceType::NamedPipe,
-            x if x & S_IFSOCK != 0 => DeviceType::Socket,
-            x if x & S_IFLNK != 0 => DeviceType::Symlink,
+        // Extract major and minor device numbers (assuming the device number format), without the type bits.
+        let device = rdev & !S_IFMT;
+        let major: u32 = (device >> 8).into(); // Major is the upper part of the 32-bit value (16 bit on macos)
+        let minor: u32 = (device & 0xFF).into(); // Minor is the lower 8 bits
+        match rdev & S_IFMT {
+            S_IFREG => DeviceType::RegularFile,
+            S_IFDIR => DeviceType::Directory,
+            S_IFCHR => DeviceType::CharacterDevice { major, minor },
+            S_IFBLK => DeviceType::BlockDevice { major, minor },
+            S_IFIFO => DeviceType::NamedPipe,
+            S_IFSOCK => DeviceType::Socket,
+            S_IFLNK => DeviceType::Symlink,
             _ => DeviceType::Unknown,
         }
     }
@@ -150,6 +190,78 @@ impl StatFs {
             fragment_size: 4096,
         }
     }
+
+    /// Starts a `StatFs` with empty counts and the usual sizes, to be completed with chained setters.
+    ///
+    /// Block size and fragment size default to 4096 bytes and the maximum filename length to 255,
+    /// so the result never reports a zero block size, which would make tools like `df` divide by zero.
+    ///
+    /// ```
+    /// # use easy_fuser::types::StatFs;
+    /// let stats = StatFs::builder()
+    ///     .total_blocks(1024)
+    ///     .free_blocks(512)
+    ///     .available_blocks(512);
+    /// assert_eq!(stats.block_size, 4096);
+    /// ```
+    pub fn builder() -> Self {
+        StatFs {
+            total_blocks: 0,
+            free_blocks: 0,
+            available_blocks: 0,
+            total_files: 0,
+            free_files: 0,
+            block_size: 4096,
+            max_filename_length: 255,
+            fragment_size: 4096,
+        }
+    }
+
+    pub fn total_blocks(mut self, total_blocks: u64) -> Self {
+        self.total_blocks = total_blocks;
+        self
+    }
+
+    pub fn free_blocks(mut self, free_blocks: u64) -> Self {
+        self.free_blocks = free_blocks;
+        self
+    }
+
+    pub fn available_blocks(mut self, available_blocks: u64) -> Self {
+        self.available_blocks = available_blocks;
+        self
+    }
+
+    pub fn total_files(mut self, total_files: u64) -> Self {
+        self.total_files = total_files;
+        self
+    }
+
+    pub fn free_files(mut self, free_files: u64) -> Self {
+        self.free_files = free_files;
+        self
+    }
+
+    /// Sets the block size. A zero value is ignored.
+    pub fn block_size(mut self, block_size: u32) -> Self {
+        if block_size != 0 {
+            self.block_size = block_size;
+        }
+        self
+    }
+
+    pub fn max_filename_length(mut self, max_filename_length: u32) -> Self {
+        self.max_filename_length = max_filename_length;
+        self
+    }
+
+    /// Sets the fragment size. A zero value is ignored.
+    pub fn fragment_size(mut self, fragment_size: u32) -> Self {
+        if fragment_size != 0 {
+            self.fragment_size = fragment_size;
+        }
+        self
+    }
 }
 
 /// Encapsulates essential information about a FUSE request.
@@ -159,12 +271,17 @@ impl StatFs {
 /// - `uid`: User ID of the process that initiated the request
 /// - `gid`: Group ID of the process that initiated the request
 /// - `pid`: Process ID of the process that initiated the request
-#[derive(Debug, Clone)]
+/// - `deadline`: Soft deadline of the request, set from `FuseHandler::request_timeout` when it is received
+///
+/// The default value is a request from root with all fields zeroed and no deadline, convenient to call
+/// a handler directly, outside of a mount (eg: in tests).
+#[derive(Debug, Clone, Default)]
 pub struct RequestInfo {
     pub id: u64,
     pub uid: u32,
     pub gid: u32,
     pub pid: u32,
+    pub deadline: Option<Instant>,
 }
 impl<'a> From<&Request<'a>> for RequestInfo {
     fn from(req: &Request<'a>) -> Self {
@@ -173,6 +290,35 @@ impl<'a> From<&Request<'a>> for RequestInfo {
             uid: req.uid(),
             gid: req.gid(),
             pid: req.pid(),
+            deadline: None,
+        }
+    }
+}
+
+impl RequestInfo {
+    /// Sets the deadline `timeout` after now, or removes it if `None`
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.deadline = timeout.map(|timeout| Instant::now() + timeout);
+        self
+    }
+
+    /// Time left before the deadline, zero once it has passed, or `None` without deadline
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Fails with `ErrorKind::TimedOut` if the request can't complete before its deadline, taking `expected`
+    ///
+    /// Handlers knowing the latency of their backend can bail out early, rather than keep the caller
+    /// waiting for a reply which comes too late. The deadline is not enforced by the driver.
+    pub fn check_deadline(&self, expected: Duration) -> Result<(), PosixError> {
+        match self.remaining() {
+            Some(remaining) if remaining < expected => Err(ErrorKind::TimedOut.to_error(format!(
+                "Request {} can't complete in the {:?} left before its deadline",
+                self.id, remaining
+            ))),
+            _ => Ok(()),
         }
     }
 }
@@ -193,7 +339,7 @@ pub struct FileAttribute {
     /// Creation time
     pub crtime: SystemTime,
     /// File type (regular file, directory, etc.)
-    pub kind: FileType,
+    pub kind: FileKind,
     /// File permissions
     pub perm: u16,
     /// Number of hard links
@@ -205,10 +351,19 @@ pub struct FileAttribute {
     /// Device ID (if special file)
     pub rdev: u32,
     /// Preferred block size for file system I/O
+    ///
+    /// Reported as `st_blksize`, tools like `cp` size their buffers after it, so a large value
+    /// (eg: 1 MiB) improves throughput on network backends. Zero is replaced by
+    /// `FuseHandler::preferred_blksize`.
     pub blksize: u32,
-    /// File flags
+    /// File flags (BSD/macOS `st_flags`, eg: `UF_IMMUTABLE`, `UF_APPEND`, `SF_IMMUTABLE`)
+    ///
+    /// Linux doesn't report them through `stat`, `chattr` attributes require `ioctl(FS_IOC_GETFLAGS)`.
     pub flags: u32,
     /// Time-to-live for caching this attribute (None for default)
+    ///
+    /// Sub-second durations are kept, the kernel receives both the seconds and the nanoseconds.
+    /// It applies to the entry and to its attributes, including each entry returned by `readdirplus`.
     pub ttl: Option<Duration>,
     // File generation number (None for random)
     /// If set, it must follow these constraints:
@@ -220,6 +375,37 @@ pub struct FileAttribute {
 
 /// `FuseFileAttr`, `Option<ttl>`, `Option<generation>`
 impl FileAttribute {
+    /// Replaces the attributes missing from `available` by their "not provided" value
+    ///
+    /// Timestamps are set to `UNIX_EPOCH` and the block count to zero, instead of exposing values
+    /// made up by the handler (like a creation time copied from the modification time).
+    pub fn clear_unavailable(mut self, available: AttrMask) -> Self {
+        if !available.contains(AttrMask::ATIME) {
+            self.atime = UNIX_EPOCH;
+        }
+        if !available.contains(AttrMask::MTIME) {
+            self.mtime = UNIX_EPOCH;
+        }
+        if !available.contains(AttrMask::CTIME) {
+            self.ctime = UNIX_EPOCH;
+        }
+        if !available.contains(AttrMask::BTIME) {
+            self.crtime = UNIX_EPOCH;
+        }
+        if !available.contains(AttrMask::BLOCKS) {
+            self.blocks = 0;
+        }
+        self
+    }
+
+    /// Sets the block size to `blksize` if it was left to zero
+    pub fn with_default_blksize(mut self, blksize: u32) -> Self {
+        if self.blksize == 0 {
+            self.blksize = blksize;
+        }
+        self
+    }
+
     pub(crate) fn to_fuse(self, ino: u64) -> (FuseFileAttr, Option<Duration>, Option<u64>) {
         (
             FuseFileAttr {
@@ -230,7 +416,7 @@ impl FileAttribute {
                 mtime: self.mtime,
                 ctime: self.ctime,
                 crtime: self.crtime,
-                kind: self.kind,
+                kind: self.kind.into(),
                 perm: self.perm,
                 nlink: self.nlink,
                 uid: self.uid,
@@ -271,8 +457,8 @@ pub struct SetAttrRequest<'a> {
     pub chgtime: Option<SystemTime>,
     /// Backup time (for macOS)
     pub bkuptime: Option<SystemTime>,
-    /// File flags (unused in FUSE)
-    pub flags: Option<()>,
+    /// File flags, as set by `chflags` (BSD/macOS only)
+    pub flags: Option<u32>,
     /// File handle for the file being modified
     pub file_handle: Option<BorrowedFileHandle<'a>>,
 }
@@ -345,8 +531,7 @@ impl<'a> SetAttrRequest<'a> {
         self
     }
 
-    /// Unused by FUSE
-    pub fn flags(mut self, flags: ()) -> Self {
+    pub fn flags(mut self, flags: u32) -> Self {
         self.flags = Some(flags);
         self
     }
@@ -357,6 +542,59 @@ impl<'a> SetAttrRequest<'a> {
     }
 }
 
+/// Builds the `MountOption` list given to `mount` and `spawn_mount`.
+///
+/// The filesystem name and subtype are displayed by `mount` and `df -T`: a filesystem mounted with
+/// `.fs_name("data").subtype("myfs")` shows as `data on /mnt type fuse.myfs`, instead of a plain `fuse`.
+///
+/// ```
+/// # use easy_fuser::prelude::*;
+/// let options = MountOptionsBuilder::new()
+///     .fs_name("data")
+///     .subtype("myfs")
+///     .option(MountOption::RO)
+///     .build();
+/// assert!(options.contains(&MountOption::Subtype("myfs".to_string())));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MountOptionsBuilder {
+    options: Vec<MountOption>,
+}
+
+impl MountOptionsBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the name of the mounted filesystem, replacing any name set before
+    pub fn fs_name(mut self, name: &str) -> Self {
+        self.options
+            .retain(|option| !matches!(option, MountOption::FSName(_)));
+        self.options.push(MountOption::FSName(name.to_string()));
+        self
+    }
+
+    /// Sets the subtype of the mounted filesystem, shown as `fuse.<subtype>`, replacing any subtype set before
+    pub fn subtype(mut self, subtype: &str) -> Self {
+        self.options
+            .retain(|option| !matches!(option, MountOption::Subtype(_)));
+        self.options.push(MountOption::Subtype(subtype.to_string()));
+        self
+    }
+
+    /// Adds any other mount option
+    pub fn option(mut self, option: MountOption) -> Self {
+        if !self.options.contains(&option) {
+            self.options.push(option);
+        }
+        self
+    }
+
+    pub fn build(self) -> Vec<MountOption> {
+        self.options
+    }
+}
+
 /// Represents file locking information for FUSE operations.
 #[derive(Debug)]
 pub struct LockInfo {
@@ -369,3 +607,127 @@ pub struct LockInfo {
     /// Process ID of the lock owner
     pub pid: u32,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_device_type_from_parts() {
+        let device = DeviceType::from_parts(4, 2, FileKind::CharDevice);
+        assert_eq!((device.major(), device.minor()), (Some(4), Some(2)));
+
+        let device = DeviceType::from_rdev(device.to_rdev());
+        assert!(matches!(
+            device,
+            DeviceType::CharacterDevice { major: 4, minor: 2 }
+        ));
+        let device =
+            DeviceType::from_rdev(DeviceType::from_parts(8, 1, FileKind::BlockDevice).to_rdev());
+        assert!(matches!(
+            device,
+            DeviceType::BlockDevice { major: 8, minor: 1 }
+        ));
+
+        let pipe = DeviceType::from_parts(4, 2, FileKind::NamedPipe);
+        assert!(matches!(pipe, DeviceType::NamedPipe));
+        assert_eq!(pipe.major(), None);
+    }
+
+    #[test]
+    fn test_clear_unavailable_btime() {
+        let mtime = UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let attr = FileAttribute {
+            size: 10,
+            blocks: 1,
+            atime: mtime,
+            mtime,
+            ctime: mtime,
+            crtime: mtime,
+            kind: FileKind::RegularFile,
+            perm: 0o644,
+            nlink: 1,
+            uid: 0,
+            gid: 0,
+            rdev: 0,
+            blksize: 4096,
+            flags: 0,
+            ttl: None,
+            generation: None,
+        };
+
+        let cleared = attr
+            .clone()
+            .clear_unavailable(AttrMask::all() - AttrMask::BTIME);
+        assert_eq!(cleared.crtime, UNIX_EPOCH);
+        assert_eq!(cleared.mtime, mtime);
+        assert_eq!(cleared.blocks, 1);
+
+        assert_eq!(attr.clone().clear_unavailable(AttrMask::all()), attr);
+    }
+
+    #[test]
+    fn test_to_fuse_every_kind() {
+        for &kind in FileKind::ALL {
+            let attr = FileAttribute {
+                size: 0,
+                blocks: 0,
+                atime: UNIX_EPOCH,
+                mtime: UNIX_EPOCH,
+                ctime: UNIX_EPOCH,
+                crtime: UNIX_EPOCH,
+                kind,
+                perm: 0o644,
+                nlink: 1,
+                uid: 0,
+                gid: 0,
+                rdev: 0,
+                blksize: 4096,
+                flags: 0,
+                ttl: None,
+                generation: None,
+            };
+            let (fuse_attr, _, _) = attr.to_fuse(2);
+            assert_eq!(FileKind::from(fuse_attr.kind), kind);
+        }
+    }
+
+    #[test]
+    fn test_request_deadline() {
+        let req = RequestInfo {
+            id: 1,
+            uid: 0,
+            gid: 0,
+            pid: 0,
+            deadline: None,
+        };
+        assert_eq!(req.remaining(), None);
+        assert!(req.check_deadline(Duration::from_secs(3600)).is_ok());
+
+        let req = req.with_timeout(Some(Duration::from_secs(60)));
+        assert!(req.check_deadline(Duration::from_millis(10)).is_ok());
+        let error = req.check_deadline(Duration::from_secs(120)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TimedOut);
+
+        let req = req.with_timeout(Some(Duration::ZERO));
+        assert_eq!(req.remaining(), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_mount_options_builder_subtype() {
+        let options = MountOptionsBuilder::new()
+            .subtype("other")
+            .fs_name("data")
+            .subtype("myfs")
+            .option(MountOption::RO)
+            .build();
+        assert_eq!(
+            options,
+            vec![
+                MountOption::FSName("data".to_string()),
+                MountOption::Subtype("myfs".to_string()),
+                MountOption::RO,
+            ]
+        );
+    }
+}
@@ -8,6 +8,7 @@
 //! - [`PosixError`]: Represents a POSIX error with an error code and message.
 //! - [`ErrorKind`]: Represents various kinds of POSIX errors.
 //! - [`FuseResult`]: A type alias for `Result<T, PosixError>`.
+//! - [`FuseResultExt`]: Adds context to the message of the error of a `FuseResult`.
 //!
 //! # Functions
 //!
@@ -20,6 +21,7 @@ use crate::unix_fs::get_errno;
 use std::any::Any;
 
 use std::fmt::{Debug, Display};
+use std::time::Duration;
 
 pub type FuseResult<T> = Result<T, PosixError>;
 
@@ -28,6 +30,7 @@ pub type FuseResult<T> = Result<T, PosixError>;
 pub struct PosixError {
     code: i32,
     pub msg: String,
+    negative_ttl: Option<Duration>,
 }
 
 impl PosixError {
@@ -44,6 +47,7 @@ impl PosixError {
         Self {
             code: code.into(),
             msg: msg.to_string(),
+            negative_ttl: None,
         }
     }
 
@@ -62,9 +66,32 @@ impl PosixError {
         Self {
             code: get_errno(),
             msg: msg.to_string(),
+            negative_ttl: None,
         }
     }
 
+    /// Creates a `FileNotFound` error stating that the entry is known to be absent for `ttl`.
+    ///
+    /// When returned by `lookup`, the driver replies with a negative entry that the kernel caches,
+    /// so further lookups of the same name don't reach the filesystem until `ttl` expires.
+    /// It should only be used when the name can't appear in the meantime (eg: read-only or finalized directories).
+    /// Other operations handle it as a plain `FileNotFound` error.
+    pub fn not_found_cached<U>(ttl: Duration, msg: U) -> Self
+    where
+        U: ToString,
+    {
+        Self {
+            code: libc::ENOENT,
+            msg: msg.to_string(),
+            negative_ttl: Some(ttl),
+        }
+    }
+
+    /// Duration during which the kernel may cache the absence of the entry, see `not_found_cached`.
+    pub fn negative_ttl(&self) -> Option<Duration> {
+        self.negative_ttl
+    }
+
     pub fn kind(&self) -> ErrorKind {
         ErrorKind::from(self.code)
     }
@@ -72,6 +99,14 @@ impl PosixError {
     pub fn raw_error(&self) -> i32 {
         self.code
     }
+
+    /// Returns the errno the error was built with, like `std::io::Error::raw_os_error`.
+    ///
+    /// The code is kept as is up to the reply to the kernel, even when several errno share the same
+    /// `ErrorKind` or when it is unknown to `ErrorKind`. Returns `None` if the code is not a valid errno.
+    pub fn raw_os_error(&self) -> Option<i32> {
+        (self.code > 0).then_some(self.code)
+    }
 }
 
 impl<E> From<E> for PosixError
@@ -110,11 +145,59 @@ impl Display for PosixError {
     }
 }
 
+/// Extension of `FuseResult` to attach context to errors propagated through layered handlers.
+///
+/// Like `anyhow::Context`, the context is prepended to the message of the error, separated by `": "`.
+/// The errno, and thus the `ErrorKind` replied to the kernel, are left unchanged. The closure only
+/// runs when the result is an error.
+///
+/// # Example
+/// ```rust
+/// use easy_fuser::prelude::*;
+///
+/// fn read_config() -> FuseResult<Vec<u8>> {
+///     Err(ErrorKind::FileNotFound.to_error("no such file"))
+/// }
+///
+/// let error = read_config()
+///     .context(|| format!("reading {}", "/etc/app.conf"))
+///     .unwrap_err();
+/// assert_eq!(error.kind(), ErrorKind::FileNotFound);
+/// assert_eq!(error.msg, "reading /etc/app.conf: no such file");
+/// ```
+pub trait FuseResultExt<T> {
+    /// Prepends the message returned by `f` to the message of the error
+    fn context<C, F>(self, f: F) -> FuseResult<T>
+    where
+        C: Display,
+        F: FnOnce() -> C;
+}
+
+impl<T> FuseResultExt<T> for FuseResult<T> {
+    #[inline]
+    fn context<C, F>(self, f: F) -> FuseResult<T>
+    where
+        C: Display,
+        F: FnOnce() -> C,
+    {
+        self.map_err(|mut error| {
+            error.msg = match error.msg.as_str() {
+                "" => f().to_string(),
+                msg => format!("{}: {}", f(), msg),
+            };
+            error
+        })
+    }
+}
+
 /// Represents various kinds of POSIX errors.
 ///
 /// This enum is not exhaustive and may be extended in the future to include
 /// additional error kinds as needed. The `Unknown` variant is used for
 /// error codes that are not explicitly listed.
+///
+/// Every kind is available on all supported platforms. Kinds whose errno is not defined by a platform
+/// are converted to `EIO` there, and are never produced from an error code.
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum ErrorKind {
     PermissionDenied,
@@ -196,6 +279,12 @@ pub enum ErrorKind {
     MultihopAttempted,
     LinkHasBeenSevered,
     NoMessage,
+    /// Extended attribute not found, `ENODATA` on Linux and `ENOATTR` elsewhere
+    ///
+    /// To be returned by `getxattr` and `removexattr` for a missing name.
+    AttributeNotFound,
+    OperationCanceled,
+    ProtocolError,
     Unknown(i32),
 }
 
@@ -288,9 +377,18 @@ impl From<i32> for ErrorKind {
             libc::EILSEQ => Self::IllegalByteSequence,
             libc::EBADMSG => Self::BadMessage,
             libc::EIDRM => Self::IdentifierRemoved,
+            // OpenBSD doesn't define EMULTIHOP nor ENOLINK
+            #[cfg(not(target_os = "openbsd"))]
             libc::EMULTIHOP => Self::MultihopAttempted,
+            #[cfg(not(target_os = "openbsd"))]
             libc::ENOLINK => Self::LinkHasBeenSevered,
             libc::ENOMSG => Self::NoMessage,
+            #[cfg(target_os = "linux")]
+            libc::ENODATA => Self::AttributeNotFound,
+            #[cfg(not(target_os = "linux"))]
+            libc::ENOATTR => Self::AttributeNotFound,
+            libc::ECANCELED => Self::OperationCanceled,
+            libc::EPROTO => Self::ProtocolError,
             _ => Self::Unknown(code),
         }
     }
@@ -375,9 +473,19 @@ impl From<ErrorKind> for i32 {
             ErrorKind::IllegalByteSequence => libc::EILSEQ,
             ErrorKind::BadMessage => libc::EBADMSG,
             ErrorKind::IdentifierRemoved => libc::EIDRM,
+            #[cfg(not(target_os = "openbsd"))]
             ErrorKind::MultihopAttempted => libc::EMULTIHOP,
+            #[cfg(not(target_os = "openbsd"))]
             ErrorKind::LinkHasBeenSevered => libc::ENOLINK,
+            #[cfg(target_os = "openbsd")]
+            ErrorKind::MultihopAttempted | ErrorKind::LinkHasBeenSevered => libc::EIO,
             ErrorKind::NoMessage => libc::ENOMSG,
+            #[cfg(target_os = "linux")]
+            ErrorKind::AttributeNotFound => libc::ENODATA,
+            #[cfg(not(target_os = "linux"))]
+            ErrorKind::AttributeNotFound => libc::ENOATTR,
+            ErrorKind::OperationCanceled => libc::ECANCELED,
+            ErrorKind::ProtocolError => libc::EPROTO,
             ErrorKind::Unknown(code) => code, // Unknown variant retains its i32 value
         }
     }
@@ -387,10 +495,9 @@ impl From<ErrorKind> for i32 {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_error_kind_roundtrip() {
-        // List of all ErrorKind variants except Unknown
-        let error_kinds = vec![
+    /// List of all ErrorKind variants except Unknown
+    fn all_error_kinds() -> Vec<ErrorKind> {
+        vec![
             ErrorKind::PermissionDenied,
             ErrorKind::FileNotFound,
             ErrorKind::NoSuchProcess,
@@ -470,7 +577,27 @@ mod tests {
             ErrorKind::MultihopAttempted,
             ErrorKind::LinkHasBeenSevered,
             ErrorKind::NoMessage,
-        ];
+            ErrorKind::AttributeNotFound,
+            ErrorKind::OperationCanceled,
+            ErrorKind::ProtocolError,
+        ]
+    }
+
+    /// Kinds without a dedicated errno on the current platform
+    fn fallback_error_kinds() -> Vec<ErrorKind> {
+        if cfg!(target_os = "openbsd") {
+            vec![ErrorKind::MultihopAttempted, ErrorKind::LinkHasBeenSevered]
+        } else {
+            vec![]
+        }
+    }
+
+    #[test]
+    fn test_error_kind_roundtrip() {
+        let fallbacks = fallback_error_kinds();
+        let error_kinds = all_error_kinds()
+            .into_iter()
+            .filter(|kind| !fallbacks.contains(kind));
 
         for kind in error_kinds {
             let code: i32 = kind.into(); // Convert ErrorKind -> i32
@@ -483,4 +610,84 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_error_kind_valid_errno() {
+        for kind in all_error_kinds() {
+            let code: i32 = kind.into();
+            assert!(code > 0, "Invalid code {} for ErrorKind::{:?}", code, kind);
+            // The platform knows how to describe every errno it defines
+            let description = std::io::Error::from_raw_os_error(code).to_string();
+            assert!(
+                !description.starts_with("Unknown error"),
+                "ErrorKind::{:?} maps to code {} unknown to the platform",
+                kind,
+                code
+            );
+        }
+        for kind in fallback_error_kinds() {
+            assert_eq!(i32::from(kind), libc::EIO);
+        }
+    }
+
+    #[test]
+    fn test_filesystem_error_kinds_errno() {
+        // Errors filesystems commonly return, with the same errno on Linux and macOS
+        let expected = [
+            (ErrorKind::DirectoryNotEmpty, libc::ENOTEMPTY),
+            (ErrorKind::TooManySymbolicLinks, libc::ELOOP),
+            (ErrorKind::FileNameTooLong, libc::ENAMETOOLONG),
+            (ErrorKind::QuotaExceeded, libc::EDQUOT),
+            (ErrorKind::StaleFileHandle, libc::ESTALE),
+            (ErrorKind::InvalidCrossDeviceLink, libc::EXDEV),
+        ];
+        for (kind, errno) in expected {
+            assert_eq!(i32::from(kind), errno, "ErrorKind::{:?}", kind);
+            assert_eq!(ErrorKind::from(errno), kind);
+            let error = kind.to_error("filesystem error");
+            assert_eq!(error.raw_os_error(), Some(errno));
+            assert_eq!(error.kind(), kind);
+        }
+    }
+
+    #[test]
+    fn test_context() {
+        let result: FuseResult<()> = Err(PosixError::new(libc::EDQUOT, "write failed"));
+        let error = result
+            .context(|| "mirror/file.txt")
+            .context(|| format!("layer {}", 1))
+            .unwrap_err();
+        assert_eq!(error.msg, "layer 1: mirror/file.txt: write failed");
+        assert_eq!(error.raw_os_error(), Some(libc::EDQUOT));
+        assert_eq!(error.kind(), ErrorKind::QuotaExceeded);
+
+        let error = PosixError::not_found_cached(Duration::from_secs(1), "");
+        let error = Err::<(), _>(error).context(|| "lookup").unwrap_err();
+        assert_eq!(error.msg, "lookup");
+        assert_eq!(error.negative_ttl(), Some(Duration::from_secs(1)));
+
+        // The closure is not evaluated on success
+        let result: FuseResult<u32> = Ok(1);
+        assert_eq!(result.context(|| -> &str { unreachable!() }), Ok(1));
+    }
+
+    #[test]
+    fn test_raw_os_error_is_preserved() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let error = crate::unix_fs::readdir(file.path()).unwrap_err();
+        assert_eq!(error.raw_os_error(), Some(libc::ENOTDIR));
+        assert_eq!(error.kind(), ErrorKind::NotADirectory);
+
+        let io_error = std::fs::read_dir(file.path()).unwrap_err();
+        assert_eq!(
+            PosixError::from(io_error).raw_os_error(),
+            Some(libc::ENOTDIR)
+        );
+
+        // Codes unknown to ErrorKind are kept
+        let error = PosixError::new(4242, "custom");
+        assert_eq!(error.kind(), ErrorKind::Unknown(4242));
+        assert_eq!(error.raw_os_error(), Some(4242));
+        assert_eq!(PosixError::new(0, "").raw_os_error(), None);
+    }
 }
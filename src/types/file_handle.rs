@@ -19,6 +19,16 @@
 //! Because FileHandle doesn't necessarly represent a concrete resource, no RAII is done when OwnedFileHandle is drop.
 //! It is the role of the user to manipulate the resource by converting to and from OwnedFd.
 //!
+//! # Ownership of file descriptors
+//! Handlers backed by file descriptors don't need raw values:
+//! - `unix_fs::open` returns an `OwnedFd`, closed when dropped.
+//! - `OwnedFileHandle::from_owned_fd` hands it over to the kernel in `open` or `create` without closing it.
+//! - `BorrowedFileHandle::as_borrowed_fd` gives access to it in `read`, `write`, etc.
+//! - `OwnedFileHandle::into_owned_fd` takes it back in `release`, where dropping it closes it.
+//!
+//! A handler keeping its own copy beyond `release` (a cache of open files) duplicates it with
+//! `OwnedFd::try_clone` or `BorrowedFd::try_clone_to_owned`, which call `dup`.
+//!
 //! # Examples
 //! ```rust
 //! use std::os::fd::OwnedFd;
@@ -13,11 +13,11 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use fuser::FileType as FileKind;
-
 use crate::core::InodeResolvable;
 
 use super::arguments::FileAttribute;
+use super::errors::FuseResult;
+use super::file_kind::FileKind;
 use super::inode::*;
 
 /// Represents the type used to identify files in the file system.
@@ -48,7 +48,7 @@ pub trait FileIdType:
     ///
     /// For PathBuf-based: FileAttribute
     /// - User only needs to provide FileAttribute; Inode is managed internally.
-    type Metadata;
+    type Metadata: Send + 'static;
 
     /// Minimal metadata type for the file system.
     ///
@@ -57,7 +57,7 @@ pub trait FileIdType:
     ///
     /// For PathBuf-based: FileKind
     /// - User only needs to provide FileKind; Inode is managed internally.
-    type MinimalMetadata;
+    type MinimalMetadata: Send + 'static;
     #[doc(hidden)]
     type _Id;
 
@@ -77,8 +77,14 @@ pub trait FileIdType:
     fn extract_metadata(metadata: Self::Metadata) -> (Self::_Id, FileAttribute);
     #[doc(hidden)]
     fn extract_minimal_metadata(minimal_metadata: Self::MinimalMetadata) -> (Self::_Id, FileKind);
+    #[doc(hidden)]
+    fn metadata_attribute_mut(metadata: &mut Self::Metadata) -> &mut FileAttribute;
 }
 
+/// Entries of a directory as returned by `FuseHandler::readdir_stream`, with their minimal metadata
+pub type DirEntryStream<TId> =
+    Box<dyn Iterator<Item = FuseResult<(OsString, <TId as FileIdType>::MinimalMetadata)>> + Send>;
+
 impl FileIdType for Inode {
     type _Id = Inode;
     type Metadata = (Inode, FileAttribute);
@@ -99,6 +105,10 @@ impl FileIdType for Inode {
     fn extract_minimal_metadata(minimal_metadata: Self::MinimalMetadata) -> (Self::_Id, FileKind) {
         minimal_metadata
     }
+
+    fn metadata_attribute_mut(metadata: &mut Self::Metadata) -> &mut FileAttribute {
+        &mut metadata.1
+    }
 }
 
 impl FileIdType for PathBuf {
@@ -121,6 +131,10 @@ impl FileIdType for PathBuf {
     fn extract_minimal_metadata(minimal_metadata: Self::MinimalMetadata) -> (Self::_Id, FileKind) {
         ((), minimal_metadata)
     }
+
+    fn metadata_attribute_mut(metadata: &mut Self::Metadata) -> &mut FileAttribute {
+        metadata
+    }
 }
 
 impl FileIdType for Vec<OsString> {
@@ -147,4 +161,8 @@ impl FileIdType for Vec<OsString> {
     fn extract_minimal_metadata(minimal_metadata: Self::MinimalMetadata) -> (Self::_Id, FileKind) {
         ((), minimal_metadata)
     }
+
+    fn metadata_attribute_mut(metadata: &mut Self::Metadata) -> &mut FileAttribute {
+        metadata
+    }
 }
@@ -0,0 +1,147 @@
+//! File type of filesystem objects.
+//!
+//! `FileKind` mirrors `fuser::FileType`, conversions in both directions are provided
+//! for users interoperating with raw `fuser` types.
+//!
+//! Breaking change in 0.5.0: `FileKind` used to be a re-export of `fuser::FileType`. Its variants are
+//! unchanged, but code passing it to `fuser` (or the reverse) must now convert it with `.into()`.
+
+use fuser::FileType;
+
+/// Type of a file (regular file, directory, symlink...)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileKind {
+    /// Named pipe (S_IFIFO)
+    NamedPipe,
+    /// Character device (S_IFCHR)
+    CharDevice,
+    /// Block device (S_IFBLK)
+    BlockDevice,
+    /// Directory (S_IFDIR)
+    Directory,
+    /// Regular file (S_IFREG)
+    RegularFile,
+    /// Symbolic link (S_IFLNK)
+    Symlink,
+    /// Unix domain socket (S_IFSOCK)
+    Socket,
+}
+
+impl FileKind {
+    /// Every kind, for exhaustive checks of the conversions.
+    pub const ALL: &'static [FileKind] = &[
+        FileKind::NamedPipe,
+        FileKind::CharDevice,
+        FileKind::BlockDevice,
+        FileKind::Directory,
+        FileKind::RegularFile,
+        FileKind::Symlink,
+        FileKind::Socket,
+    ];
+
+    /// Returns the kind encoded in the type bits (`S_IFMT`) of a mode, as received in `mknod` or `create`.
+    ///
+    /// Returns `None` if the type bits don't match any known kind.
+    pub fn from_mode(mode: u32) -> Option<Self> {
+        use libc::*;
+        let file_type = (mode & S_IFMT as u32) as mode_t;
+        Some(match file_type {
+            S_IFREG => FileKind::RegularFile,
+            S_IFDIR => FileKind::Directory,
+            S_IFCHR => FileKind::CharDevice,
+            S_IFBLK => FileKind::BlockDevice,
+            S_IFIFO => FileKind::NamedPipe,
+            S_IFLNK => FileKind::Symlink,
+            S_IFSOCK => FileKind::Socket,
+            _ => return None, // Unsupported or unknown file type
+        })
+    }
+
+    /// Returns the type bits (`S_IFMT`) of this kind, to be combined with the permission bits of a mode.
+    pub fn to_mode_bits(&self) -> u32 {
+        use libc::*;
+        let file_type: mode_t = match self {
+            FileKind::NamedPipe => S_IFIFO,
+            FileKind::CharDevice => S_IFCHR,
+            FileKind::BlockDevice => S_IFBLK,
+            FileKind::Directory => S_IFDIR,
+            FileKind::RegularFile => S_IFREG,
+            FileKind::Symlink => S_IFLNK,
+            FileKind::Socket => S_IFSOCK,
+        };
+        file_type as u32
+    }
+}
+
+/// `fuser::FileType` has no whiteout variant, so both conversions are lossless.
+impl From<FileKind> for FileType {
+    fn from(kind: FileKind) -> Self {
+        match kind {
+            FileKind::NamedPipe => FileType::NamedPipe,
+            FileKind::CharDevice => FileType::CharDevice,
+            FileKind::BlockDevice => FileType::BlockDevice,
+            FileKind::Directory => FileType::Directory,
+            FileKind::RegularFile => FileType::RegularFile,
+            FileKind::Symlink => FileType::Symlink,
+            FileKind::Socket => FileType::Socket,
+        }
+    }
+}
+
+impl From<FileType> for FileKind {
+    fn from(file_type: FileType) -> Self {
+        match file_type {
+            FileType::NamedPipe => FileKind::NamedPipe,
+            FileType::CharDevice => FileKind::CharDevice,
+            FileType::BlockDevice => FileKind::BlockDevice,
+            FileType::Directory => FileKind::Directory,
+            FileType::RegularFile => FileKind::RegularFile,
+            FileType::Symlink => FileKind::Symlink,
+            FileType::Socket => FileKind::Socket,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_kinds_are_listed() {
+        // Fails to compile when a variant is added, until it is given the next index and added to ALL
+        let index = |kind: FileKind| match kind {
+            FileKind::NamedPipe => 0,
+            FileKind::CharDevice => 1,
+            FileKind::BlockDevice => 2,
+            FileKind::Directory => 3,
+            FileKind::RegularFile => 4,
+            FileKind::Symlink => 5,
+            FileKind::Socket => 6,
+        };
+        let mut indexes: Vec<_> = FileKind::ALL.iter().map(|&kind| index(kind)).collect();
+        indexes.sort();
+        assert_eq!(indexes, (0..7).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_file_kind_roundtrip() {
+        for &kind in FileKind::ALL {
+            let file_type = FileType::from(kind);
+            assert_eq!(FileKind::from(file_type), kind);
+        }
+        assert_eq!(FileType::from(FileKind::Directory), FileType::Directory);
+    }
+
+    #[test]
+    fn test_file_kind_mode() {
+        for &kind in FileKind::ALL {
+            assert_eq!(FileKind::from_mode(kind.to_mode_bits() | 0o644), Some(kind));
+        }
+        assert_eq!(
+            FileKind::from_mode(libc::S_IFDIR as u32 | 0o755),
+            Some(FileKind::Directory)
+        );
+        // Permission bits alone carry no type
+        assert_eq!(FileKind::from_mode(0o644), None);
+    }
+}
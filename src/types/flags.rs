@@ -10,8 +10,14 @@ use bitflags::bitflags;
 bitflags! {
     #[derive(Debug, Copy, Clone)]
     /// Flags used to check file accessibility.
+    ///
+    /// The permission flags take the values of `R_OK`, `W_OK` and `X_OK`, which are the same on
+    /// Linux and macOS, and match the mask sent by the kernel.
     pub struct AccessMask: i32 {
         /// Check if the file exists.
+        ///
+        /// It is the empty mask (`F_OK` is 0): use `mask.is_empty()` rather than `mask.contains`,
+        /// which is always true, to tell an existence check from a permission check.
         const EXISTS = libc::F_OK;
         /// Check if the file is readable.
         const CAN_READ = libc::R_OK;
@@ -23,6 +29,27 @@ bitflags! {
     }
 }
 
+bitflags! {
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    /// Attributes a filesystem is able to provide, modeled after the `statx` mask.
+    ///
+    /// Only attributes which have a meaningful "not provided" value are listed:
+    /// timestamps are reported as `UNIX_EPOCH` and block counts as zero when absent.
+    pub struct AttrMask: u32 {
+        /// Last access time
+        const ATIME = 1 << 0;
+        /// Last modification time
+        const MTIME = 1 << 1;
+        /// Last status change time
+        const CTIME = 1 << 2;
+        /// Creation (birth) time
+        const BTIME = 1 << 3;
+        /// Number of allocated blocks
+        const BLOCKS = 1 << 4;
+        const _ = !0;
+    }
+}
+
 bitflags! {
     #[derive(Debug, Copy, Clone)]
     /// Flags used in fallocate calls.
@@ -98,6 +125,66 @@ bitflags! {
     }
 }
 
+bitflags! {
+    #[derive(Debug, Copy, Clone)]
+    /// Capabilities negotiated with the kernel in `init`, requested with `KernelConfig::add_capabilities`.
+    pub struct FUSEInitFlags: u32 {
+        /// Asynchronous read requests.
+        const ASYNC_READ = 1 << 0;
+        /// Remote POSIX locks.
+        const POSIX_LOCKS = 1 << 1;
+        /// File handles are passed in `getattr`, `setattr`...
+        const FILE_OPS = 1 << 2;
+        /// `O_TRUNC` is handled in `open`.
+        const ATOMIC_O_TRUNC = 1 << 3;
+        /// Lookups of `.` and `..` are handled.
+        const EXPORT_SUPPORT = 1 << 4;
+        /// Writes larger than a page.
+        const BIG_WRITES = 1 << 5;
+        /// Don't apply the umask to the mode of created files.
+        const DONT_MASK = 1 << 6;
+        /// Splice when writing to the device.
+        const SPLICE_WRITE = 1 << 7;
+        /// Move pages when splicing to the device.
+        const SPLICE_MOVE = 1 << 8;
+        /// Splice when reading from the device.
+        const SPLICE_READ = 1 << 9;
+        /// Remote BSD locks (`flock`).
+        const FLOCK_LOCKS = 1 << 10;
+        /// `ioctl` on directories.
+        const HAS_IOCTL_DIR = 1 << 11;
+        /// Invalidate cached data when the modification time or size change.
+        const AUTO_INVAL_DATA = 1 << 12;
+        /// `readdirplus` is handled.
+        const DO_READDIRPLUS = 1 << 13;
+        /// The kernel chooses between `readdir` and `readdirplus`.
+        const READDIRPLUS_AUTO = 1 << 14;
+        /// Asynchronous direct I/O.
+        const ASYNC_DIO = 1 << 15;
+        /// Buffer writes in the page cache, see `FuseHandler::init`.
+        const WRITEBACK_CACHE = 1 << 16;
+        /// A failing `open` with `ENOSYS` is cached as success.
+        const NO_OPEN_SUPPORT = 1 << 17;
+        /// Concurrent lookups and readdirs in the same directory.
+        const PARALLEL_DIROPS = 1 << 18;
+        /// The filesystem clears the suid and sgid bits on writes.
+        const HANDLE_KILLPRIV = 1 << 19;
+        /// POSIX ACLs are handled by the filesystem.
+        const POSIX_ACL = 1 << 20;
+        /// Reading the device after an abort returns `ECONNABORTED`.
+        const ABORT_ERROR = 1 << 21;
+        /// The maximum number of pages per request is configurable.
+        const MAX_PAGES = 1 << 22;
+        /// Cache the targets of symbolic links.
+        const CACHE_SYMLINKS = 1 << 23;
+        /// A failing `opendir` with `ENOSYS` is cached as success.
+        const NO_OPENDIR_SUPPORT = 1 << 24;
+        /// Cached data is only invalidated by notifications.
+        const EXPLICIT_INVAL_DATA = 1 << 25;
+        const _ = !0;
+    }
+}
+
 bitflags! {
     #[derive(Debug, Copy, Clone)]
     pub struct FUSEIoctlFlags: u32 {
@@ -215,20 +302,57 @@ bitflags! {
         /// Create an unnamed temporary file (Linux only).
         #[cfg(target_os = "linux")]
         const TEMPORARY_FILE = libc::O_TMPFILE;
+        /// Obtain a descriptor usable only for metadata operations (Linux only).
+        #[cfg(target_os = "linux")]
+        const PATH_ONLY = libc::O_PATH;
         const _ = !0;
     }
 }
 
+impl OpenFlags {
+    /// Returns true for an `O_PATH` open.
+    ///
+    /// Such an open succeeds without read or write permission, and the descriptor only allows metadata
+    /// operations like `fstat`: reading or writing it fails with `EBADF`. Handlers should not attempt
+    /// IO on it. Always false outside of Linux.
+    pub fn is_path(&self) -> bool {
+        #[cfg(target_os = "linux")]
+        return self.contains(OpenFlags::PATH_ONLY);
+        #[cfg(not(target_os = "linux"))]
+        return false;
+    }
+
+    /// Adapts the flags of an open to the writeback cache (`FUSEInitFlags::WRITEBACK_CACHE`).
+    ///
+    /// The kernel then reads through write-only handles to fill the pages partially written, and handles
+    /// `O_APPEND` itself by writing at the end of file. Write-only opens become read-write and `O_APPEND` is dropped.
+    pub fn for_writeback_cache(self) -> Self {
+        let flags = self - OpenFlags::APPEND_MODE;
+        if flags.bits() & libc::O_ACCMODE == libc::O_WRONLY {
+            return (flags - OpenFlags::WRITE_ONLY) | OpenFlags::READ_WRITE;
+        }
+        flags
+    }
+}
+
 bitflags! {
     #[derive(Debug, Copy, Clone)]
     /// Flags used in rename operations.
     pub struct RenameFlags: u32 {
-        /// Atomically exchange the old and new pathnames. (Linux only)
+        /// Atomically exchange the old and new pathnames. (Not supported on BSD)
         #[cfg(target_os = "linux")]
         const EXCHANGE = libc::RENAME_EXCHANGE;
-        /// Don't overwrite the destination file if it exists. (Linux only)
+        #[cfg(target_os = "macos")]
+        const EXCHANGE = libc::RENAME_SWAP;
+        #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+        const EXCHANGE = 1 << 1;
+        /// Don't overwrite the destination file if it exists. (Emulated on BSD)
         #[cfg(target_os = "linux")]
         const NOREPLACE = libc::RENAME_NOREPLACE;
+        #[cfg(target_os = "macos")]
+        const NOREPLACE = libc::RENAME_EXCL;
+        #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+        const NOREPLACE = 1 << 0;
         const _ = !0;
     }
 }
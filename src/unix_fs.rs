@@ -36,7 +36,7 @@ use std::os::unix::ffi::OsStrExt;
 use std::os::unix::fs::*;
 
 use crate::types::*;
-use libc::{c_char, c_void, timespec};
+use libc::{c_char, timespec};
 
 // Modify to #[cfg_attr(windows, path = "windows/mod.rs")]
 #[cfg(target_os = "linux")]
@@ -63,7 +63,7 @@ pub(crate) mod macos_fs;
 use macos_fs as unix_impl;
 
 pub(crate) use unix_impl::get_errno;
-pub use unix_impl::{copy_file_range, statfs};
+pub use unix_impl::statfs;
 
 /// Converts a `std::fs::FileType` to the corresponding `FileKind` expected by fuse_api.
 ///
@@ -95,13 +95,15 @@ pub fn convert_filetype(f: fs::FileType) -> FileKind {
 /// # Important
 /// For symlinks, use `fs::symlink_metadata` instead of regular `fs::metadata`.
 pub fn convert_fileattribute(metadata: fs::Metadata) -> FileAttribute {
+    let mtime = timestamp_to_system_time(metadata.mtime(), metadata.mtime_nsec());
     FileAttribute {
         size: metadata.size(),
         blocks: metadata.blocks(),
-        atime: SystemTime::UNIX_EPOCH + Duration::new(metadata.atime() as u64, 0),
-        mtime: SystemTime::UNIX_EPOCH + Duration::new(metadata.mtime() as u64, 0),
-        ctime: SystemTime::UNIX_EPOCH + Duration::new(metadata.ctime() as u64, 0),
-        crtime: SystemTime::UNIX_EPOCH + Duration::new(metadata.mtime() as u64, 0),
+        atime: timestamp_to_system_time(metadata.atime(), metadata.atime_nsec()),
+        mtime,
+        ctime: timestamp_to_system_time(metadata.ctime(), metadata.ctime_nsec()),
+        // Falls back to mtime where the creation time is not reported
+        crtime: metadata.created().unwrap_or(mtime),
         kind: convert_filetype(metadata.file_type()),
         perm: (metadata.mode() & 0o777) as u16,
         nlink: metadata.nlink() as u32,
@@ -109,21 +111,35 @@ pub fn convert_fileattribute(metadata: fs::Metadata) -> FileAttribute {
         gid: metadata.gid(),
         rdev: metadata.rdev() as u32,
         blksize: metadata.blksize() as u32,
-        flags: 0, // macOS only; placeholder here
+        flags: unix_impl::metadata_flags(&metadata),
         ttl: None,
         generation: None,
     }
 }
 
+impl FileAttribute {
+    /// Converts a `libc::stat` structure, as filled by `stat`, `fstat` or `fstatat`, to a `FileAttribute`.
+    ///
+    /// This is the raw-syscall counterpart of `convert_fileattribute`.
+    /// Returns `None` if the file type in `st_mode` is unknown.
+    pub fn from_stat(statbuf: &libc::stat) -> Option<FileAttribute> {
+        convert_stat_struct(*statbuf)
+    }
+}
+
 fn convert_stat_struct(statbuf: libc::stat) -> Option<FileAttribute> {
     // Convert timestamp values to SystemTime
-    let atime = SystemTime::UNIX_EPOCH + Duration::new(statbuf.st_atime as u64, 0);
-    let mtime = SystemTime::UNIX_EPOCH + Duration::new(statbuf.st_mtime as u64, 0);
-    let ctime = SystemTime::UNIX_EPOCH + Duration::new(statbuf.st_ctime as u64, 0);
+    let atime = timestamp_to_system_time(statbuf.st_atime, statbuf.st_atime_nsec);
+    let mtime = timestamp_to_system_time(statbuf.st_mtime, statbuf.st_mtime_nsec);
+    let ctime = timestamp_to_system_time(statbuf.st_ctime, statbuf.st_ctime_nsec);
+    // Birth time is only reported by some BSD-like systems, otherwise falls back to mtime
+    let crtime = unix_impl::stat_birthtime(&statbuf)
+        .map(|(secs, nsecs)| timestamp_to_system_time(secs, nsecs))
+        .unwrap_or(mtime);
     // Extract permissions (lower 9 bits of st_mode)
     let perm = (statbuf.st_mode & (libc::S_IRWXU | libc::S_IRWXG | libc::S_IRWXO)) as u16;
-    // Flags are not directly supported in `stat`, use a placeholder for now
-    let flags = 0; // Update this if your system provides a way to get file flags
+    // File flags (chflags) are only reported by BSD-like systems
+    let flags = unix_impl::stat_flags(&statbuf);
 
     Some(FileAttribute {
         size: statbuf.st_size as u64,
@@ -131,12 +147,12 @@ fn convert_stat_struct(statbuf: libc::stat) -> Option<FileAttribute> {
         atime,
         mtime,
         ctime,
-        crtime: mtime,
-        kind: stat_to_kind(statbuf)?,
+        crtime,
+        kind: FileKind::from_mode(unix_impl::stat_mode(&statbuf))?,
         perm: perm,
         nlink: statbuf.st_nlink as u32,
-        uid: statbuf.st_uid as u32,
-        gid: statbuf.st_gid as u32,
+        uid: statbuf.st_uid,
+        gid: statbuf.st_gid,
         rdev: statbuf.st_rdev as u32,
         blksize: statbuf.st_blksize as u32,
         flags: flags,
@@ -145,18 +161,16 @@ fn convert_stat_struct(statbuf: libc::stat) -> Option<FileAttribute> {
     })
 }
 
-fn stat_to_kind(statbuf: libc::stat) -> Option<FileKind> {
-    use libc::*;
-    Some(match statbuf.st_mode & S_IFMT {
-        S_IFREG => FileKind::RegularFile,
-        S_IFDIR => FileKind::Directory,
-        S_IFCHR => FileKind::CharDevice,
-        S_IFBLK => FileKind::BlockDevice,
-        S_IFIFO => FileKind::NamedPipe,
-        S_IFLNK => FileKind::Symlink,
-        S_IFSOCK => FileKind::Socket,
-        _ => return None, // Unsupported or unknown file type
-    })
+/// Builds a `SystemTime` from seconds and nanoseconds since the epoch, as found in `stat`.
+///
+/// Timestamps before the epoch have negative seconds, with nanoseconds still counting forward.
+fn timestamp_to_system_time(secs: i64, nsecs: i64) -> SystemTime {
+    let nanos = Duration::from_nanos(nsecs as u64);
+    if secs >= 0 {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs as u64) + nanos
+    } else {
+        SystemTime::UNIX_EPOCH - Duration::from_secs(secs.unsigned_abs()) + nanos
+    }
 }
 
 fn system_time_to_timespec(time: SystemTime) -> Result<timespec, PosixError> {
@@ -204,6 +218,95 @@ pub fn lookup(path: &Path) -> Result<FileAttribute, PosixError> {
     ))?)
 }
 
+/// Retrieves file attributes for a given path, following symlinks.
+///
+/// Unlike `lookup`, the attributes are the ones of the symlink target. Symlink loops result
+/// in `ErrorKind::TooManySymbolicLinks` (`ELOOP`).
+pub fn stat(path: &Path) -> Result<FileAttribute, PosixError> {
+    let c_path = cstring_from_path(path)?;
+    let mut statbuf: libc::stat = unsafe { std::mem::zeroed() };
+    let result = unsafe { libc::stat(c_path.as_ptr(), &mut statbuf) };
+    if result == -1 {
+        return Err(PosixError::last_error(format!(
+            "{}: stat failed",
+            path.display()
+        )));
+    }
+    convert_stat_struct(statbuf).ok_or(PosixError::new(
+        ErrorKind::InvalidArgument,
+        format!(
+            "{}: statbuf conversion failed {:?}",
+            path.display(),
+            statbuf
+        ),
+    ))
+}
+
+/// Retrieves file attributes for a given path, with its real creation time.
+///
+/// Like `lookup`, symlinks are not followed. `statx` is the only way to get the birth time of a file on Linux:
+/// `crtime` is set to `mtime` when the filesystem doesn't report it, and this falls back to `lookup`
+/// when the kernel doesn't support `statx`.
+#[cfg(target_os = "linux")]
+pub fn statx(path: &Path) -> Result<FileAttribute, PosixError> {
+    let c_path = cstring_from_path(path)?;
+    let mut statxbuf: libc::statx = unsafe { std::mem::zeroed() };
+    let result = unsafe {
+        libc::statx(
+            libc::AT_FDCWD,
+            c_path.as_ptr(),
+            libc::AT_SYMLINK_NOFOLLOW,
+            libc::STATX_BASIC_STATS | libc::STATX_BTIME,
+            &mut statxbuf,
+        )
+    };
+    if result == -1 {
+        if get_errno() == libc::ENOSYS {
+            return lookup(path);
+        }
+        return Err(PosixError::last_error(format!(
+            "{}: statx failed",
+            path.display()
+        )));
+    }
+    convert_statx_struct(&statxbuf).ok_or(PosixError::new(
+        ErrorKind::InvalidArgument,
+        format!("{}: statx conversion failed {:?}", path.display(), statxbuf),
+    ))
+}
+
+#[cfg(target_os = "linux")]
+fn convert_statx_struct(statxbuf: &libc::statx) -> Option<FileAttribute> {
+    let to_system_time =
+        |ts: libc::statx_timestamp| timestamp_to_system_time(ts.tv_sec, ts.tv_nsec as i64);
+    let mtime = to_system_time(statxbuf.stx_mtime);
+    let crtime = if statxbuf.stx_mask & libc::STATX_BTIME != 0 {
+        to_system_time(statxbuf.stx_btime)
+    } else {
+        mtime
+    };
+    let mode = statxbuf.stx_mode as libc::mode_t;
+
+    Some(FileAttribute {
+        size: statxbuf.stx_size,
+        blocks: statxbuf.stx_blocks,
+        atime: to_system_time(statxbuf.stx_atime),
+        mtime,
+        ctime: to_system_time(statxbuf.stx_ctime),
+        crtime,
+        kind: FileKind::from_mode(statxbuf.stx_mode as u32)?,
+        perm: (mode & (libc::S_IRWXU | libc::S_IRWXG | libc::S_IRWXO)) as u16,
+        nlink: statxbuf.stx_nlink,
+        uid: statxbuf.stx_uid,
+        gid: statxbuf.stx_gid,
+        rdev: libc::makedev(statxbuf.stx_rdev_major, statxbuf.stx_rdev_minor) as u32,
+        blksize: statxbuf.stx_blksize,
+        flags: 0,
+        ttl: None,
+        generation: None,
+    })
+}
+
 /// Retrieves file attributes for a given file descriptor.
 ///
 /// This function is equivalent to the FUSE `getattr` operation.
@@ -331,25 +434,45 @@ pub fn setattr(path: &Path, attrs: SetAttrRequest) -> Result<FileAttribute, Posi
         }
     }
 
+    // Set file flags last, as an immutable flag would prevent the other changes.
+    // Linux kernels never send them, they are ignored there.
+    #[cfg(any(target_os = "macos", target_os = "freebsd"))]
+    if let Some(flags) = attrs.flags {
+        let result = unsafe { libc::chflags(c_path.as_ptr(), flags as _) };
+        if result == -1 {
+            return Err(PosixError::last_error(format!(
+                "{}: chflags failed in setattr",
+                path.display()
+            )));
+        }
+    }
+
     lookup(path)
 }
 
 /// Reads the target of a symbolic link.
 ///
 /// This function is equivalent to the FUSE `readlink` operation.
+/// The target is returned as stored, whatever its length and encoding.
 pub fn readlink(path: &Path) -> Result<Vec<u8>, PosixError> {
     let c_path = cstring_from_path(path)?;
     let mut buf = vec![0u8; 1024]; // Initial buffer size
-    let ret =
-        unsafe { libc::readlink(c_path.as_ptr(), buf.as_mut_ptr() as *mut c_char, buf.len()) };
-    if ret == -1 {
-        return Err(PosixError::last_error(format!(
-            "{}: readlink",
-            path.display()
-        )));
+    loop {
+        let ret =
+            unsafe { libc::readlink(c_path.as_ptr(), buf.as_mut_ptr() as *mut c_char, buf.len()) };
+        if ret == -1 {
+            return Err(PosixError::last_error(format!(
+                "{}: readlink",
+                path.display()
+            )));
+        }
+        // A target filling the buffer may have been truncated
+        if (ret as usize) < buf.len() {
+            buf.truncate(ret as usize);
+            return Ok(buf);
+        }
+        buf.resize(buf.len() * 2, 0);
     }
-    buf.truncate(ret as usize);
-    Ok(buf)
 }
 
 /// Creates a new file node (device special file or named pipe) at the specified path.
@@ -444,11 +567,169 @@ pub fn symlink(path: &Path, target: &Path) -> Result<FileAttribute, PosixError>
     lookup(path)
 }
 
+/// Creates a hard link at `newpath` to the file at `oldpath`.
+///
+/// This function is equivalent to the FUSE `link` operation and uses the system's linkat call.
+/// A symbolic link at `oldpath` is linked itself, not its target. Returns the attributes of the new entry.
+pub fn link(oldpath: &Path, newpath: &Path) -> Result<FileAttribute, PosixError> {
+    let old_cstr = cstring_from_path(oldpath)?;
+    let new_cstr = cstring_from_path(newpath)?;
+
+    let result = unsafe {
+        libc::linkat(
+            libc::AT_FDCWD,
+            old_cstr.as_ptr(),
+            libc::AT_FDCWD,
+            new_cstr.as_ptr(),
+            0, // Without AT_SYMLINK_FOLLOW
+        )
+    };
+    if result == -1 {
+        return Err(PosixError::last_error(format!(
+            "{}: link failed (target: {})",
+            newpath.display(),
+            oldpath.display()
+        )));
+    }
+
+    lookup(newpath)
+}
+
+/// Opens the directory at `path`, to be passed to the `*_at` functions.
+///
+/// The descriptor keeps designating the same directory if it is renamed. Operations relative to it don't
+/// resolve the whole path again, and can't be redirected elsewhere by a concurrent rename of a parent.
+/// Handlers can keep the descriptors of the directories they use often.
+///
+/// On Linux, the directory is opened with `O_PATH`, which requires no read permission on it.
+pub fn open_dir(path: &Path) -> Result<OwnedFd, PosixError> {
+    let c_path = cstring_from_path(path)?;
+    #[cfg(target_os = "linux")]
+    let access = libc::O_PATH;
+    #[cfg(not(target_os = "linux"))]
+    let access = libc::O_RDONLY;
+    let fd = unsafe {
+        libc::open(
+            c_path.as_ptr(),
+            access | libc::O_DIRECTORY | libc::O_CLOEXEC,
+        )
+    };
+    if fd == -1 {
+        return Err(PosixError::last_error(format!(
+            "{}: open_dir failed",
+            path.display()
+        )));
+    }
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+/// Converts the name of an entry of a directory, rejecting `.`, `..`, empty names and names containing `/`,
+/// which would designate another file than an entry of the directory.
+fn cstring_from_name(name: &OsStr) -> Result<CString, PosixError> {
+    if name.is_empty() || name == "." || name == ".." || name.as_bytes().contains(&b'/') {
+        return Err(PosixError::new(
+            ErrorKind::InvalidArgument,
+            format!("{:?}: not a directory entry name", name),
+        ));
+    }
+    cstring_from_path(Path::new(name))
+}
+
+/// Retrieves the attributes of the entry `name` of the directory `dirfd`, without following symlinks.
+///
+/// This function is equivalent to [`lookup`], relative to a descriptor from [`open_dir`].
+/// `name` must be a single component: `.`, `..`, empty names and names containing `/` fail with
+/// `ErrorKind::InvalidArgument`, as for the other `*_at` functions.
+pub fn lookup_at(dirfd: BorrowedFd, name: &OsStr) -> Result<FileAttribute, PosixError> {
+    let c_name = cstring_from_name(name)?;
+    let mut statbuf: libc::stat = unsafe { std::mem::zeroed() };
+    let result = unsafe {
+        libc::fstatat(
+            dirfd.as_raw_fd(),
+            c_name.as_ptr(),
+            &mut statbuf,
+            libc::AT_SYMLINK_NOFOLLOW,
+        )
+    };
+    if result == -1 {
+        return Err(PosixError::last_error(format!(
+            "{:?}/{}: fstatat failed in lookup_at",
+            dirfd,
+            Path::new(name).display()
+        )));
+    }
+    convert_stat_struct(statbuf).ok_or(PosixError::new(
+        ErrorKind::InvalidArgument,
+        format!(
+            "{:?}/{}: statbuf conversion failed {:?}",
+            dirfd,
+            Path::new(name).display(),
+            statbuf
+        ),
+    ))
+}
+
+/// Creates the directory `name` in the directory `dirfd` with the given mode and umask.
+///
+/// This function is equivalent to [`mkdir`], relative to a descriptor from [`open_dir`].
+pub fn mkdir_at(
+    dirfd: BorrowedFd,
+    name: &OsStr,
+    mode: u32,
+    umask: u32,
+) -> Result<FileAttribute, PosixError> {
+    let c_name = cstring_from_name(name)?;
+    let final_mode = mode & !umask;
+    let result = unsafe {
+        libc::mkdirat(
+            dirfd.as_raw_fd(),
+            c_name.as_ptr(),
+            final_mode.try_into().unwrap(),
+        )
+    };
+    if result == -1 {
+        return Err(PosixError::last_error(format!(
+            "{:?}/{}: mkdirat failed",
+            dirfd,
+            Path::new(name).display()
+        )));
+    }
+    lookup_at(dirfd, name)
+}
+
+/// Removes the file `name` of the directory `dirfd`.
+///
+/// This function is equivalent to [`unlink`], relative to a descriptor from [`open_dir`].
+pub fn unlink_at(dirfd: BorrowedFd, name: &OsStr) -> Result<(), PosixError> {
+    unlinkat(dirfd, name, 0)
+}
+
+/// Removes the empty directory `name` of the directory `dirfd`.
+///
+/// This function is equivalent to [`rmdir`], relative to a descriptor from [`open_dir`].
+pub fn rmdir_at(dirfd: BorrowedFd, name: &OsStr) -> Result<(), PosixError> {
+    unlinkat(dirfd, name, libc::AT_REMOVEDIR)
+}
+
+fn unlinkat(dirfd: BorrowedFd, name: &OsStr, flags: i32) -> Result<(), PosixError> {
+    let c_name = cstring_from_name(name)?;
+    let result = unsafe { libc::unlinkat(dirfd.as_raw_fd(), c_name.as_ptr(), flags) };
+    if result == -1 {
+        return Err(PosixError::last_error(format!(
+            "{:?}/{}: unlinkat failed",
+            dirfd,
+            Path::new(name).display()
+        )));
+    }
+    Ok(())
+}
+
 /// Renames a file or directory from the old path to the new path.
 ///
 /// This function is equivalent to the FUSE `rename` operation.
 /// It allows for specifying additional flags to control the rename operation.
-/// However, those flags will be ignored on other platforms than linux.
+/// On BSD, `RenameFlags::NOREPLACE` is emulated by checking the new path first, which is not atomic,
+/// and other flags fail with `ErrorKind::NotSupported`.
 pub fn rename(oldpath: &Path, newpath: &Path, flags: RenameFlags) -> Result<(), PosixError> {
     let old_cstr = cstring_from_path(oldpath)?;
     let new_cstr = cstring_from_path(newpath)?;
@@ -477,6 +758,9 @@ pub fn rename(oldpath: &Path, newpath: &Path, flags: RenameFlags) -> Result<(),
 /// which may not necessarily be equivalent to the FUSE file handle.
 ///
 /// Although this function returns a Fd, it is guaranted to be positive and valid.
+///
+/// With `O_PATH` (see [`OpenFlags::is_path`]), the descriptor can be passed to [`getattr`], but [`read`]
+/// and [`write`] fail with `ErrorKind::BadFileDescriptor`.
 pub fn open(path: &Path, flags: OpenFlags) -> Result<OwnedFd, PosixError> {
     let c_path = cstring_from_path(path)?;
     let fd = unsafe { libc::open(c_path.as_ptr(), flags.bits()) };
@@ -498,8 +782,20 @@ pub fn open(path: &Path, flags: OpenFlags) -> Result<OwnedFd, PosixError> {
 /// For `SeekFrom::Current` or `SeekFrom::End`, it first updates the file's current position,
 /// then reads from there. In all cases, the file's position after the read operation
 /// remains where it was before the read, regardless of how much data was read.
+///
+/// Short reads of the underlying file are retried, so that less than `size` bytes are only returned at EOF.
 pub fn read(fd: BorrowedFd, seek: SeekFrom, size: usize) -> Result<Vec<u8>, PosixError> {
-    let mut buffer = vec![0; size as usize];
+    let mut buffer = vec![0; size];
+    let total_read = read_into(fd, seek, &mut buffer)?;
+    buffer.truncate(total_read);
+    Ok(buffer)
+}
+
+/// Reads data from a file descriptor at a specified offset into `buffer`, returning the number of bytes read.
+///
+/// Works like [`read`], filling the buffer of the caller instead of allocating one.
+pub fn read_into(fd: BorrowedFd, seek: SeekFrom, buffer: &mut [u8]) -> Result<usize, PosixError> {
+    let size = buffer.len();
     let offset: libc::off_t = match seek {
         SeekFrom::Start(offset) => offset.try_into().map_err(|_| {
             PosixError::new(
@@ -526,19 +822,28 @@ pub fn read(fd: BorrowedFd, seek: SeekFrom, size: usize) -> Result<Vec<u8>, Posi
             })?
         }
     };
-    let bytes_read = unsafe {
-        libc::pread(
-            fd.as_raw_fd(),
-            buffer.as_mut_ptr() as *mut libc::c_void,
-            size,
-            offset,
-        )
-    };
-    if bytes_read == -1 {
-        return Err(PosixError::last_error(format!("{:?}: read failed", fd)));
+    let mut total_read = 0;
+    while total_read < size {
+        let bytes_read = unsafe {
+            libc::pread(
+                fd.as_raw_fd(),
+                buffer[total_read..].as_mut_ptr() as *mut libc::c_void,
+                size - total_read,
+                offset + total_read as libc::off_t,
+            )
+        };
+        if bytes_read == -1 {
+            if get_errno() == libc::EINTR {
+                continue;
+            }
+            return Err(PosixError::last_error(format!("{:?}: read failed", fd)));
+        }
+        if bytes_read == 0 {
+            break;
+        }
+        total_read += bytes_read as usize;
     }
-    buffer.truncate(bytes_read as usize);
-    Ok(buffer)
+    Ok(total_read)
 }
 
 /// Writes data to a file descriptor at a specified offset.
@@ -550,8 +855,11 @@ pub fn read(fd: BorrowedFd, seek: SeekFrom, size: usize) -> Result<Vec<u8>, Posi
 /// For `SeekFrom::Current` or `SeekFrom::End`, it first updates the file's current position,
 /// then reads from there. In all cases, the file's position after the read operation
 /// remains where it was before the read, regardless of how much data was read.
+///
+/// Short writes are continued until all the data is written. If a signal interrupts the write after
+/// part of the data was written, the number of bytes written so far is returned instead of `EINTR`,
+/// so the caller knows where to resume.
 pub fn write(fd: BorrowedFd, seek: SeekFrom, data: &[u8]) -> Result<usize, PosixError> {
-    let bytes_to_write = data.len() as usize;
     let offset: libc::off_t = match seek {
         SeekFrom::Start(offset) => offset.try_into().map_err(|_| {
             PosixError::new(
@@ -578,19 +886,106 @@ pub fn write(fd: BorrowedFd, seek: SeekFrom, data: &[u8]) -> Result<usize, Posix
             })?
         }
     };
-    let bytes_written = unsafe {
-        libc::pwrite(
-            fd.as_raw_fd(),
-            data.as_ptr() as *const libc::c_void,
-            bytes_to_write,
-            offset,
-        )
-    };
-    if bytes_written == -1 {
-        return Err(PosixError::last_error(format!("{:?}: write failed", fd)));
+    let mut total_written = 0;
+    while total_written < data.len() {
+        let remaining = &data[total_written..];
+        let bytes_written = unsafe {
+            libc::pwrite(
+                fd.as_raw_fd(),
+                remaining.as_ptr() as *const libc::c_void,
+                remaining.len(),
+                offset + total_written as libc::off_t,
+            )
+        };
+        if bytes_written == -1 {
+            let error = PosixError::last_error(format!("{:?}: write failed", fd));
+            if error.kind() == ErrorKind::InterruptedSystemCall && total_written > 0 {
+                break;
+            }
+            return Err(error);
+        }
+        if bytes_written == 0 {
+            break;
+        }
+        total_written += bytes_written as usize;
     }
 
-    Ok(bytes_written as usize)
+    Ok(total_written)
+}
+
+/// Copies a range of data from one file to another.
+///
+/// This function is equivalent to the FUSE `copy_file_range` operation.
+///
+/// It copies `len` bytes from the file descriptor `fd_in` starting at offset `offset_in`
+/// to the file descriptor `fd_out` starting at offset `offset_out`. The function returns
+/// the number of bytes actually copied, which may be less than requested.
+///
+/// Note: If the accelerated copy is unavailable (`ENOSYS`, as on BSD, or `EXDEV` across filesystems
+/// on older kernels), the data is copied with `pread`/`pwrite` instead.
+pub fn copy_file_range(
+    fd_in: BorrowedFd,
+    offset_in: i64,
+    fd_out: BorrowedFd,
+    offset_out: i64,
+    len: u64,
+) -> Result<u32, PosixError> {
+    match unix_impl::copy_file_range(fd_in, offset_in, fd_out, offset_out, len) {
+        Err(e) if e.raw_error() == libc::ENOSYS || e.raw_error() == libc::EXDEV => {
+            copy_file_range_fallback(fd_in, offset_in, fd_out, offset_out, len)
+        }
+        result => result,
+    }
+}
+
+/// Copies up to `len` bytes between the descriptors with `pread`/`pwrite`, stopping at the end of `fd_in`
+fn copy_file_range_fallback(
+    fd_in: BorrowedFd,
+    offset_in: i64,
+    fd_out: BorrowedFd,
+    offset_out: i64,
+    len: u64,
+) -> Result<u32, PosixError> {
+    const CHUNK_SIZE: u64 = 128 * 1024;
+    let (offset_in, offset_out) = match (u64::try_from(offset_in), u64::try_from(offset_out)) {
+        (Ok(offset_in), Ok(offset_out)) => (offset_in, offset_out),
+        _ => {
+            return Err(PosixError::new(
+                ErrorKind::InvalidArgument,
+                "copy_file_range: negative offset".to_string(),
+            ))
+        }
+    };
+    // The copied length is reported as u32
+    let len = len.min(u32::MAX as u64);
+    let mut copied: u64 = 0;
+    while copied < len {
+        let chunk = (len - copied).min(CHUNK_SIZE) as usize;
+        let data = match read(fd_in, SeekFrom::Start(offset_in + copied), chunk) {
+            Ok(data) => data,
+            Err(_) if copied > 0 => break,
+            Err(e) => return Err(e),
+        };
+        if data.is_empty() {
+            break;
+        }
+        let mut written = 0;
+        while written < data.len() {
+            let position = offset_out + copied + written as u64;
+            match write(fd_out, SeekFrom::Start(position), &data[written..]) {
+                Ok(0) => break,
+                Ok(bytes_written) => written += bytes_written,
+                // Report the bytes already copied, the error will surface on the next call
+                Err(_) if copied + (written as u64) > 0 => break,
+                Err(e) => return Err(e),
+            }
+        }
+        copied += written as u64;
+        if written < data.len() {
+            break;
+        }
+    }
+    Ok(copied as u32)
 }
 
 /// Flushes any buffered data to the file system for the given file descriptor.
@@ -749,7 +1144,7 @@ pub fn setxattr(
         unix_impl::setxattr(
             c_path.as_ptr(),
             c_name.as_ptr(),
-            value.as_ptr() as *const c_void,
+            value.as_ptr() as *const libc::c_void,
             value.len(),
             position,
             flags.bits(),
@@ -802,7 +1197,7 @@ pub fn getxattr(path: &Path, name: &OsStr, size: u32) -> Result<Vec<u8>, PosixEr
         unix_impl::getxattr(
             c_path.as_ptr(),
             c_name.as_ptr(),
-            buf.as_mut_ptr() as *mut c_void,
+            buf.as_mut_ptr() as *mut libc::c_void,
             buf.len(),
         )
     };
@@ -893,15 +1288,22 @@ pub fn removexattr(path: &Path, name: &OsStr) -> Result<(), PosixError> {
     Ok(())
 }
 
+/// Mode passed to `access`, without the bits unknown to it which would make it fail with `EINVAL`
+///
+/// An empty mask is `F_OK`, which only checks the existence of the file.
+fn access_mode(mask: AccessMask) -> i32 {
+    (mask & (AccessMask::CAN_READ | AccessMask::CAN_WRITE | AccessMask::CAN_EXEC)).bits()
+}
+
 /// Checks file accessibility based on the process's real user and group IDs.
 ///
 /// This function is equivalent to the FUSE `access` operation.
 ///
 /// It verifies whether the calling process can access the file specified by the path
-/// according to the given access mask.
+/// according to the given access mask. With `AccessMask::EXISTS`, only the existence of the file is checked.
 pub fn access(path: &Path, mask: AccessMask) -> Result<(), PosixError> {
     let c_path = cstring_from_path(path)?;
-    let ret = unsafe { libc::access(c_path.as_ptr(), mask.bits()) };
+    let ret = unsafe { libc::access(c_path.as_ptr(), access_mode(mask)) };
     if ret == -1 {
         return Err(PosixError::last_error(format!(
             "{}: access failed. Mask {:?}",
@@ -912,6 +1314,35 @@ pub fn access(path: &Path, mask: AccessMask) -> Result<(), PosixError> {
     Ok(())
 }
 
+/// Checks the accessibility of an open file.
+///
+/// This is the file descriptor counterpart of `access`, which avoids resolving the path again.
+/// It relies on `faccessat2` with `AT_EMPTY_PATH` (Linux 5.8), and falls back to the `/proc/self/fd`
+/// entry of the descriptor on older kernels.
+#[cfg(target_os = "linux")]
+pub fn faccess(fd: BorrowedFd, mask: AccessMask) -> Result<(), PosixError> {
+    let empty_path = c"".as_ptr();
+    let mut ret = unsafe {
+        libc::faccessat(
+            fd.as_raw_fd(),
+            empty_path,
+            access_mode(mask),
+            libc::AT_EMPTY_PATH,
+        )
+    };
+    if ret == -1 && matches!(get_errno(), libc::ENOSYS | libc::EINVAL) {
+        let c_path = cstring_from_path(Path::new(&format!("/proc/self/fd/{}", fd.as_raw_fd())))?;
+        ret = unsafe { libc::access(c_path.as_ptr(), access_mode(mask)) };
+    }
+    if ret == -1 {
+        return Err(PosixError::last_error(format!(
+            "{:?}: faccess failed. Mask {:?}",
+            fd, mask
+        )));
+    }
+    Ok(())
+}
+
 /// Creates and opens a new file with specified permissions and flags.
 ///
 /// This function is equivalent to the FUSE `create` operation.
@@ -920,6 +1351,12 @@ pub fn access(path: &Path, mask: AccessMask) -> Result<(), PosixError> {
 /// which may not necessarily be equivalent to the FUSE file handle, along with its attributes.
 /// An error is returned if the file already exists and the [OpenFlags::CREATE_EXCLUSIVE] flag is set.
 ///
+/// On Linux, if [OpenFlags::TEMPORARY_FILE] is set, an unnamed temporary file is created in the parent
+/// directory of `path` instead, and `path` itself is never created. The file has no directory entry
+/// until it is given a name with `linkat`, and is deleted when its last descriptor is closed.
+/// Note that the kernel doesn't forward `O_TMPFILE` to FUSE filesystems with the protocol version
+/// used by easy_fuser, so this only matters when the flags come from another source.
+///
 /// Although this function returns a Fd, it is guaranted to be positive and valid.
 pub fn create(
     path: &Path,
@@ -938,6 +1375,23 @@ pub fn create(
         open_flags
     };
 
+    // O_TMPFILE opens the parent directory and can't be combined with O_CREAT
+    #[cfg(target_os = "linux")]
+    if flags.contains(OpenFlags::TEMPORARY_FILE) {
+        let parent = path.parent().unwrap_or(path);
+        let c_parent = cstring_from_path(parent)?;
+        let fd = unsafe { libc::open(c_parent.as_ptr(), open_flags, final_mode) };
+        if fd == -1 {
+            return Err(PosixError::last_error(format!(
+                "{}: create of temporary file failed",
+                parent.display()
+            )));
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+        let file_attr = getattr(fd.as_fd())?;
+        return Ok((fd, file_attr));
+    }
+
     // Open the file with O_CREAT (create if it does not exist)
     let fd = unsafe {
         libc::open(
@@ -1002,6 +1456,22 @@ pub fn lseek(fd: BorrowedFd, seek: SeekFrom) -> Result<i64, PosixError> {
     Ok(result)
 }
 
+/// Repositions the file offset with a raw `whence`, including `SEEK_DATA` and `SEEK_HOLE` (Linux only).
+///
+/// The error of the syscall is kept as is. In particular, seeking for data at or past the last data
+/// region fails with `ErrorKind::NoSuchDeviceOrAddress` (`ENXIO`), which sparse-aware tools like
+/// `cp --sparse` rely on to stop.
+pub fn lseek_raw(fd: BorrowedFd, offset: i64, whence: i32) -> Result<i64, PosixError> {
+    let result = unsafe { libc::lseek(fd.as_raw_fd(), offset as libc::off_t, whence) };
+    if result == -1 {
+        return Err(PosixError::last_error(format!(
+            "{:?}: lseek failed. Offset: {:?}, whence: {:?}",
+            fd, offset, whence
+        )));
+    }
+    Ok(result)
+}
+
 #[cfg(test)]
 mod tests {
     /*
@@ -1051,6 +1521,26 @@ mod tests {
         drop(tmpfile);
     }
 
+    #[test]
+    fn test_file_attribute_from_stat() {
+        let tmpfile = NamedTempFile::new().unwrap();
+        fs::write(tmpfile.path(), "blah").unwrap();
+        let c_path = cstring_from_path(tmpfile.path()).unwrap();
+        let mut statbuf: libc::stat = unsafe { std::mem::zeroed() };
+        assert_eq!(unsafe { libc::stat(c_path.as_ptr(), &mut statbuf) }, 0);
+
+        let attr = FileAttribute::from_stat(&statbuf).unwrap();
+        let expected = convert_fileattribute(fs::metadata(tmpfile.path()).unwrap());
+        assert_eq!(attr.kind, FileKind::RegularFile);
+        assert_eq!(attr.size, expected.size);
+        assert_eq!(attr.perm, expected.perm);
+        assert_eq!(attr.mtime, expected.mtime);
+
+        // Unknown file type
+        statbuf.st_mode = 0;
+        assert!(FileAttribute::from_stat(&statbuf).is_none());
+    }
+
     #[test]
     fn test_system_time_to_timespec() {
         let system_time = SystemTime::now();
@@ -1060,6 +1550,42 @@ mod tests {
         assert!(timespec.tv_nsec >= 0);
     }
 
+    #[test]
+    fn test_nanosecond_timestamps() {
+        let tmpfile = NamedTempFile::new().unwrap();
+        let mtime = SystemTime::UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789);
+        tmpfile.as_file().set_modified(mtime).unwrap();
+
+        let attr = lookup(tmpfile.path()).unwrap();
+        assert_eq!(attr.mtime, mtime);
+        let attr = convert_fileattribute(fs::metadata(tmpfile.path()).unwrap());
+        assert_eq!(attr.mtime, mtime);
+
+        // Before the epoch, nanoseconds still count forward from the seconds
+        let old_time = timestamp_to_system_time(-2, 500_000_000);
+        assert_eq!(
+            SystemTime::UNIX_EPOCH.duration_since(old_time).unwrap(),
+            Duration::from_millis(1500)
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_statx() {
+        let tmpfile = NamedTempFile::new().unwrap();
+        fs::write(tmpfile.path(), "blah").unwrap();
+        let attr = statx(tmpfile.path()).unwrap();
+        let expected = lookup(tmpfile.path()).unwrap();
+        assert_eq!(attr.kind, FileKind::RegularFile);
+        assert_eq!(attr.size, expected.size);
+        assert_eq!(attr.mtime, expected.mtime);
+        assert_eq!(attr.rdev, expected.rdev);
+
+        // std also relies on statx for the creation time
+        let metadata = fs::metadata(tmpfile.path()).unwrap();
+        assert_eq!(attr.crtime, metadata.created().unwrap_or(attr.mtime));
+    }
+
     #[test]
     fn test_cstring_from_path() {
         let path = PathBuf::from("test_cstring");
@@ -1080,6 +1606,36 @@ mod tests {
         drop(tmpfile);
     }
 
+    #[cfg(any(target_os = "macos", target_os = "freebsd"))]
+    #[test]
+    fn test_file_flags() {
+        let tmpfile = NamedTempFile::new().unwrap();
+        let c_path = cstring_from_path(tmpfile.path()).unwrap();
+        let result = unsafe { libc::chflags(c_path.as_ptr(), libc::UF_NODUMP as _) };
+        assert_eq!(result, 0);
+
+        let attr = lookup(tmpfile.path()).unwrap();
+        assert_ne!(attr.flags & libc::UF_NODUMP as u32, 0);
+        let attr = convert_fileattribute(fs::symlink_metadata(tmpfile.path()).unwrap());
+        assert_ne!(attr.flags & libc::UF_NODUMP as u32, 0);
+        drop(tmpfile);
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "freebsd"))]
+    #[test]
+    fn test_setattr_file_flags() {
+        let tmpfile = NamedTempFile::new().unwrap();
+        let mut attrs = SetAttrRequest::new();
+        attrs.flags = Some(libc::UF_NODUMP as u32);
+        let attr = setattr(tmpfile.path(), attrs).unwrap();
+        assert_ne!(attr.flags & libc::UF_NODUMP as u32, 0);
+
+        let mut attrs = SetAttrRequest::new();
+        attrs.flags = Some(0);
+        let attr = setattr(tmpfile.path(), attrs).unwrap();
+        assert_eq!(attr.flags & libc::UF_NODUMP as u32, 0);
+    }
+
     #[test]
     fn test_readlink() {
         let tmpdir = TempDir::new().unwrap();
@@ -1098,6 +1654,23 @@ mod tests {
         assert_eq!(Path::new(OsStr::from_bytes(&link_target)), target_path);
     }
 
+    #[test]
+    fn test_symlink_readlink_roundtrip() {
+        let tmpdir = TempDir::new().unwrap();
+        let targets: [&[u8]; 3] = [
+            b"target with spaces",
+            b"non-utf8-\xff\xfe-target",
+            &[b'a'; 4000],
+        ];
+
+        for (index, target) in targets.into_iter().enumerate() {
+            let symlink_path = tmpdir.path().join(format!("symlink{}", index));
+            symlink(&symlink_path, Path::new(OsStr::from_bytes(target))).unwrap();
+            assert_eq!(readlink(&symlink_path).unwrap(), target);
+        }
+        drop(tmpdir);
+    }
+
     #[test]
     fn test_mkdir_and_rmdir() {
         let tmpdir = TempDir::new().unwrap();
@@ -1153,6 +1726,23 @@ mod tests {
         fs::remove_file(&dest_path).unwrap();
     }
 
+    #[test]
+    fn test_rename_noreplace() {
+        let tmpdir = TempDir::new().unwrap();
+        let src_path = tmpdir.path().join("src");
+        let dest_path = tmpdir.path().join("dest");
+        fs::write(&src_path, "source").unwrap();
+        fs::write(&dest_path, "destination").unwrap();
+
+        let error = rename(&src_path, &dest_path, RenameFlags::NOREPLACE).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::FileExists);
+        assert_eq!(fs::read_to_string(&dest_path).unwrap(), "destination");
+
+        fs::remove_file(&dest_path).unwrap();
+        rename(&src_path, &dest_path, RenameFlags::NOREPLACE).unwrap();
+        assert_eq!(fs::read_to_string(&dest_path).unwrap(), "source");
+    }
+
     #[test]
     fn test_open() {
         let tmpfile = NamedTempFile::new().unwrap();
@@ -1181,6 +1771,22 @@ mod tests {
         drop(tmpfile);
     }
 
+    #[test]
+    fn test_read_into() {
+        let tmpfile = NamedTempFile::new().unwrap();
+        fs::write(tmpfile.path(), b"Hello, world!").unwrap();
+
+        let fd = open(tmpfile.path(), OpenFlags::READ_ONLY).unwrap();
+        let mut buffer = [0; 8];
+        let bytes_read = read_into(fd.as_fd(), SeekFrom::Start(7), &mut buffer).unwrap();
+        assert_eq!(bytes_read, 6);
+        assert_eq!(&buffer[..bytes_read], b"world!");
+
+        let bytes_read = read_into(fd.as_fd(), SeekFrom::End(-13), &mut buffer).unwrap();
+        assert_eq!(&buffer[..bytes_read], b"Hello, w");
+        drop(tmpfile);
+    }
+
     #[test]
     fn test_write() {
         let tmpfile = NamedTempFile::new().unwrap();
@@ -1279,4 +1885,201 @@ mod tests {
 
         drop(tmpfile);
     }
+
+    #[test]
+    fn test_copy_file_range() {
+        let source = NamedTempFile::new().unwrap();
+        let destination = NamedTempFile::new().unwrap();
+        fs::write(source.path(), "Hello, World!").unwrap();
+        fs::write(destination.path(), "0123456789").unwrap();
+        let fd_in = File::open(source.path()).unwrap();
+        let fd_out = fs::OpenOptions::new()
+            .write(true)
+            .open(destination.path())
+            .unwrap();
+
+        let copied = copy_file_range(fd_in.as_fd(), 7, fd_out.as_fd(), 2, 5).unwrap();
+        assert_eq!(copied, 5);
+        assert_eq!(fs::read(destination.path()).unwrap(), b"01World789");
+
+        // Stops at the end of the source
+        let copied = copy_file_range_fallback(fd_in.as_fd(), 0, fd_out.as_fd(), 8, 100).unwrap();
+        assert_eq!(copied, 13);
+        assert_eq!(
+            fs::read(destination.path()).unwrap(),
+            b"01World7Hello, World!"
+        );
+
+        let result = copy_file_range_fallback(fd_in.as_fd(), -1, fd_out.as_fd(), 0, 1);
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidArgument);
+    }
+
+    #[test]
+    fn test_access_exists() {
+        let tmpfile = NamedTempFile::new().unwrap();
+        fs::set_permissions(tmpfile.path(), fs::Permissions::from_mode(0o000)).unwrap();
+
+        // F_OK only checks the existence, whatever the permissions
+        access(tmpfile.path(), AccessMask::EXISTS).unwrap();
+        let error = access(tmpfile.path(), AccessMask::CAN_EXEC).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::PermissionDeniedAccess);
+        // Root bypasses the read permission
+        if unsafe { libc::getuid() } != 0 {
+            let error = access(tmpfile.path(), AccessMask::CAN_READ).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::PermissionDeniedAccess);
+        }
+        // Bits unknown to access are ignored rather than failing with EINVAL
+        access(tmpfile.path(), AccessMask::from_bits_retain(0x100)).unwrap();
+
+        let missing = tmpfile.path().with_extension("missing");
+        let error = access(&missing, AccessMask::EXISTS).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::FileNotFound);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_faccess() {
+        let tmpfile = NamedTempFile::new().unwrap();
+        fs::set_permissions(tmpfile.path(), fs::Permissions::from_mode(0o644)).unwrap();
+        let fd = open(tmpfile.path(), OpenFlags::READ_ONLY).unwrap();
+
+        faccess(fd.as_fd(), AccessMask::CAN_READ).unwrap();
+        // Even root can't execute a file without any execution bit
+        let error = faccess(fd.as_fd(), AccessMask::CAN_EXEC).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::PermissionDeniedAccess);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_create_temporary_file() {
+        let tmpdir = TempDir::new().unwrap();
+        let path = tmpdir.path().join("unnamed");
+        let (fd, attr) = create(
+            &path,
+            0o600,
+            0,
+            OpenFlags::TEMPORARY_FILE | OpenFlags::READ_WRITE,
+        )
+        .unwrap();
+        assert_eq!(attr.kind, FileKind::RegularFile);
+        assert_eq!(attr.size, 0);
+        assert!(!path.exists());
+        assert_eq!(fs::read_dir(tmpdir.path()).unwrap().count(), 0);
+
+        assert_eq!(write(fd.as_fd(), SeekFrom::Start(0), b"data").unwrap(), 4);
+        assert_eq!(getattr(fd.as_fd()).unwrap().size, 4);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_seek_data_past_last_data() {
+        let tmpfile = NamedTempFile::new().unwrap();
+        let file = tmpfile.as_file();
+        file.write_all_at(b"data", 0).unwrap();
+        // Ends with a hole
+        file.set_len(1 << 20).unwrap();
+
+        assert_eq!(lseek_raw(file.as_fd(), 0, libc::SEEK_DATA).unwrap(), 0);
+        for offset in [1 << 20, (1 << 20) + 10] {
+            let error = lseek_raw(file.as_fd(), offset, libc::SEEK_DATA).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::NoSuchDeviceOrAddress);
+        }
+    }
+
+    #[test]
+    fn test_operations_at_dirfd() {
+        let tmpdir = TempDir::new().unwrap();
+        let parent = tmpdir.path().join("parent");
+        fs::create_dir(&parent).unwrap();
+        let dirfd = open_dir(&parent).unwrap();
+
+        // The descriptor follows the directory when it is renamed
+        let moved = tmpdir.path().join("moved");
+        fs::rename(&parent, &moved).unwrap();
+
+        let attr = mkdir_at(dirfd.as_fd(), OsStr::new("child"), 0o755, 0o022).unwrap();
+        assert_eq!(attr.kind, FileKind::Directory);
+        assert_eq!(attr.perm, 0o755);
+        assert!(moved.join("child").is_dir());
+
+        fs::write(moved.join("file"), "content").unwrap();
+        let attr = lookup_at(dirfd.as_fd(), OsStr::new("file")).unwrap();
+        assert_eq!((attr.kind, attr.size), (FileKind::RegularFile, 7));
+
+        unlink_at(dirfd.as_fd(), OsStr::new("file")).unwrap();
+        let error = lookup_at(dirfd.as_fd(), OsStr::new("file")).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::FileNotFound);
+        rmdir_at(dirfd.as_fd(), OsStr::new("child")).unwrap();
+        assert!(!moved.join("child").exists());
+    }
+
+    #[test]
+    fn test_operations_at_reject_non_entry_names() {
+        let tmpdir = TempDir::new().unwrap();
+        let parent = tmpdir.path().join("parent");
+        fs::create_dir_all(parent.join("sub")).unwrap();
+        fs::write(tmpdir.path().join("outside"), "content").unwrap();
+        let dirfd = open_dir(&parent).unwrap();
+
+        for name in ["", ".", "..", "../outside", "sub/", "sub/file", "/tmp"] {
+            let name = OsStr::new(name);
+            let errors = [
+                lookup_at(dirfd.as_fd(), name).unwrap_err(),
+                mkdir_at(dirfd.as_fd(), name, 0o755, 0).unwrap_err(),
+                unlink_at(dirfd.as_fd(), name).unwrap_err(),
+                rmdir_at(dirfd.as_fd(), name).unwrap_err(),
+            ];
+            for error in errors {
+                assert_eq!(error.kind(), ErrorKind::InvalidArgument, "{:?}", name);
+            }
+        }
+        assert!(tmpdir.path().join("outside").exists());
+        assert!(parent.join("sub").is_dir());
+    }
+
+    #[test]
+    fn test_link() {
+        let tmpdir = TempDir::new().unwrap();
+        let original = tmpdir.path().join("original");
+        let linked = tmpdir.path().join("linked");
+        fs::write(&original, "content").unwrap();
+
+        let attr = link(&original, &linked).unwrap();
+        assert_eq!(attr.nlink, 2);
+        assert_eq!(lookup(&original).unwrap().nlink, 2);
+        assert_eq!(fs::read_to_string(&linked).unwrap(), "content");
+
+        let error = link(&original, &linked).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::FileExists);
+    }
+
+    #[test]
+    fn test_getxattr_missing_name() {
+        let tmpfile = NamedTempFile::new().unwrap();
+        let error = getxattr(tmpfile.path(), OsStr::new("user.missing"), 64).unwrap_err();
+        // Filesystems without user extended attributes answer ENOTSUP instead
+        assert!(
+            matches!(
+                error.kind(),
+                ErrorKind::AttributeNotFound | ErrorKind::NotSupported
+            ),
+            "{}",
+            error
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_open_path_only() {
+        let tmpfile = NamedTempFile::new().unwrap();
+        fs::write(tmpfile.path(), "content").unwrap();
+        let flags = OpenFlags::PATH_ONLY | OpenFlags::READ_ONLY;
+        assert!(flags.is_path());
+        assert!(!OpenFlags::READ_ONLY.is_path());
+
+        let fd = open(tmpfile.path(), flags).unwrap();
+        assert_eq!(getattr(fd.as_fd()).unwrap().size, 7);
+        let error = read(fd.as_fd(), SeekFrom::Start(0), 7).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::BadFileDescriptor);
+    }
 }
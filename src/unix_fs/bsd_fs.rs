@@ -6,6 +6,33 @@ use std::ffi::c_void;
 use libc::{self, c_char, c_int, size_t, ssize_t};
 
 use super::{cstring_from_path, StatFs};
+use crate::types::RenameFlags;
+
+// RENAME_NOREPLACE is emulated by checking that newpath doesn't exist first, which can race with its
+// creation by another process. Other flags fail with ENOTSUP instead of being ignored.
+pub(super) unsafe fn renameat2(
+    olddirfd: c_int,
+    oldpath: *const libc::c_char,
+    newdirfd: c_int,
+    newpath: *const libc::c_char,
+    flags: libc::c_uint,
+) -> c_int {
+    if flags & !RenameFlags::NOREPLACE.bits() != 0 {
+        set_errno(libc::ENOTSUP);
+        return -1;
+    }
+    if flags != 0 {
+        let mut statbuf: libc::stat = std::mem::zeroed();
+        if libc::fstatat(newdirfd, newpath, &mut statbuf, libc::AT_SYMLINK_NOFOLLOW) == 0 {
+            set_errno(libc::EEXIST);
+            return -1;
+        }
+        if get_errno() != libc::ENOENT {
+            return -1;
+        }
+    }
+    libc::renameat(olddirfd, oldpath, newdirfd, newpath)
+}
 
 pub(super) unsafe fn fallocate(fd: c_int, _mode: c_int, offset: off_t, len: off_t) -> c_int {
     libc::posix_fallocate(fd, offset, len)
@@ -1,4 +1,4 @@
-use libc::{self, c_char, c_int, c_uint};
+use libc::{self, c_int};
 use std::os::fd::*;
 
 use crate::{ErrorKind, PosixError};
@@ -11,21 +11,47 @@ pub(super) fn set_errno(errno: i32) {
     unsafe { *libc::__error() = errno };
 }
 
-// Flags are ignored
-pub(super) unsafe fn renameat2(
-    olddirfd: c_int,
-    oldpath: *const c_char,
-    newdirfd: c_int,
-    newpath: *const c_char,
-    _flags: c_uint,
-) -> c_int {
-    libc::renameat(olddirfd, oldpath, newdirfd, newpath)
-}
-
 pub(super) unsafe fn fdatasync(fd: c_int) -> c_int {
     libc::fsync(fd)
 }
 
+// mode_t is 16 bits wide on BSD-like systems
+pub(super) fn stat_mode(statbuf: &libc::stat) -> u32 {
+    u32::from(statbuf.st_mode)
+}
+
+pub(super) fn stat_flags(statbuf: &libc::stat) -> u32 {
+    statbuf.st_flags as u32
+}
+
+pub(super) fn stat_birthtime(statbuf: &libc::stat) -> Option<(i64, i64)> {
+    #[cfg(any(target_os = "macos", target_os = "freebsd"))]
+    return Some((
+        statbuf.st_birthtime as i64,
+        statbuf.st_birthtime_nsec as i64,
+    ));
+    #[cfg(target_os = "netbsd")]
+    return Some((statbuf.st_birthtime as i64, statbuf.st_birthtimensec as i64));
+    #[cfg(target_os = "openbsd")]
+    {
+        let _ = statbuf;
+        None
+    }
+}
+
+pub(super) fn metadata_flags(metadata: &std::fs::Metadata) -> u32 {
+    #[cfg(target_os = "freebsd")]
+    use std::os::freebsd::fs::MetadataExt;
+    #[cfg(target_os = "macos")]
+    use std::os::macos::fs::MetadataExt;
+    #[cfg(target_os = "netbsd")]
+    use std::os::netbsd::fs::MetadataExt;
+    #[cfg(target_os = "openbsd")]
+    use std::os::openbsd::fs::MetadataExt;
+
+    metadata.st_flags()
+}
+
 /// Copies a range of data from one file to another.
 ///
 /// This function is equivalent to the FUSE `copy_file_range` operation.
@@ -34,7 +60,7 @@ pub(super) unsafe fn fdatasync(fd: c_int) -> c_int {
 /// to the file descriptor `fd_out` starting at offset `offset_out`. The function returns
 /// the number of bytes actually copied, which may be less than requested.
 ///
-/// Note: This function is not available on all platforms, like BSD, in that case, it will return not implemented.
+/// Note: There is no such syscall on BSD-like platforms, so this always returns not implemented.
 pub fn copy_file_range(
     _fd_in: BorrowedFd,
     _offset_in: i64,
@@ -31,6 +31,24 @@ pub(super) unsafe fn fdatasync(fd: c_int) -> c_int {
     libc::fdatasync(fd)
 }
 
+pub(super) fn stat_mode(statbuf: &libc::stat) -> u32 {
+    statbuf.st_mode
+}
+
+// File flags (chattr) are not part of stat on Linux, they require ioctl(FS_IOC_GETFLAGS)
+pub(super) fn stat_flags(_statbuf: &libc::stat) -> u32 {
+    0
+}
+
+/// Linux `stat` doesn't report the birth time, only `statx` does
+pub(super) fn stat_birthtime(_statbuf: &libc::stat) -> Option<(i64, i64)> {
+    None
+}
+
+pub(super) fn metadata_flags(_metadata: &std::fs::Metadata) -> u32 {
+    0
+}
+
 pub(super) unsafe fn fallocate(fd: c_int, mode: c_int, offset: off_t, len: off_t) -> c_int {
     libc::fallocate(fd, mode, offset, len)
 }
@@ -98,8 +116,6 @@ pub fn statfs(path: &Path) -> Result<StatFs, PosixError> {
 /// It copies `len` bytes from the file descriptor `fd_in` starting at offset `offset_in`
 /// to the file descriptor `fd_out` starting at offset `offset_out`. The function returns
 /// the number of bytes actually copied, which may be less than requested.
-///
-/// Note: This function is not available on all platforms, like BSD, in that case, it will return not implemented.
 pub fn copy_file_range(
     fd_in: BorrowedFd,
     offset_in: i64,
@@ -107,12 +123,14 @@ pub fn copy_file_range(
     offset_out: i64,
     len: u64,
 ) -> Result<u32, PosixError> {
+    let mut offset_in = offset_in as libc::off_t;
+    let mut offset_out = offset_out as libc::off_t;
     let result = unsafe {
         libc::copy_file_range(
             fd_in.as_raw_fd(),
-            offset_in as *mut libc::off_t,
+            &mut offset_in,
             fd_out.as_raw_fd(),
-            offset_out as *mut libc::off_t,
+            &mut offset_out,
             len as usize,
             0, // placeholder
         )
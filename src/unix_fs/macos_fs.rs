@@ -9,6 +9,17 @@ use libc::{self, c_char, c_int, size_t, ssize_t};
 use libc::{fcntl, fstore_t, ftruncate, off_t, ENOTSUP, F_ALLOCATECONTIG, F_PREALLOCATE};
 use std::path::Path;
 
+// RenameFlags are defined with the values of renameatx_np
+pub(super) unsafe fn renameat2(
+    olddirfd: c_int,
+    oldpath: *const libc::c_char,
+    newdirfd: c_int,
+    newpath: *const libc::c_char,
+    flags: libc::c_uint,
+) -> c_int {
+    libc::renameatx_np(olddirfd, oldpath, newdirfd, newpath, flags)
+}
+
 // Define FALLOC_FL_KEEP_SIZE constant
 const FALLOC_FL_KEEP_SIZE: c_int = 1;
 
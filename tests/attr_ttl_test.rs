@@ -0,0 +1,100 @@
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mirror_fs::*, DefaultFuseHandler};
+
+use std::ffi::OsStr;
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+
+const VOLATILE_TTL: Duration = Duration::from_millis(100);
+
+struct VolatileAttrFs {
+    inner: MirrorFsReadOnly,
+    requests: Arc<AtomicUsize>,
+}
+
+impl FuseHandler<PathBuf> for VolatileAttrFs {
+    fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+        &self.inner
+    }
+
+    fn lookup(
+        &self,
+        req: &RequestInfo,
+        parent_id: PathBuf,
+        name: &OsStr,
+    ) -> FuseResult<FileAttribute> {
+        let mut attr = self.inner.lookup(req, parent_id, name)?;
+        if name == "volatile" {
+            attr.ttl = Some(VOLATILE_TTL);
+        }
+        Ok(attr)
+    }
+
+    fn getattr(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: Option<BorrowedFileHandle>,
+    ) -> FuseResult<FileAttribute> {
+        let is_volatile = file_id.file_name() == Some(OsStr::new("volatile"));
+        let mut attr = self.inner.getattr(req, file_id, file_handle)?;
+        if is_volatile {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            attr.ttl = Some(VOLATILE_TTL);
+        }
+        Ok(attr)
+    }
+}
+
+#[test]
+fn test_sub_second_attr_ttl() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+    std::fs::write(source_dir.path().join("volatile"), b"data").unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+    let requests = Arc::new(AtomicUsize::new(0));
+
+    let mntpoint_clone = mntpoint.clone();
+    let requests_clone = requests.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = VolatileAttrFs {
+            inner: MirrorFsReadOnly::new(source_path, DefaultFuseHandler::new()),
+            requests: requests_clone,
+        };
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        // Through the file descriptor, only getattr requests refresh the attributes, not lookups
+        let volatile = File::open(mntpoint.join("volatile")).unwrap();
+        std::thread::sleep(VOLATILE_TTL * 3);
+
+        // Within the TTL, the attributes are served by the kernel cache
+        for _ in 0..5 {
+            assert_eq!(volatile.metadata().unwrap().len(), 4);
+        }
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        // A TTL truncated to whole seconds would either disable the cache or keep it for a second
+        std::thread::sleep(VOLATILE_TTL * 3);
+        assert_eq!(volatile.metadata().unwrap().len(), 4);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
@@ -0,0 +1,98 @@
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mirror_fs::*, DefaultFuseHandler};
+
+use std::ffi::OsStr;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tempfile::TempDir;
+
+const PREFERRED_BLKSIZE: u32 = 1 << 20;
+
+/// Leaves the block size of `unset.txt` to zero
+struct LargeBlocksFs {
+    inner: MirrorFs,
+}
+
+impl LargeBlocksFs {
+    fn unset_blksize(&self, file_id: &Path, mut attr: FileAttribute) -> FileAttribute {
+        if file_id.file_name() == Some(OsStr::new("unset.txt")) {
+            attr.blksize = 0;
+        }
+        attr
+    }
+}
+
+impl FuseHandler<PathBuf> for LargeBlocksFs {
+    fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+        &self.inner
+    }
+
+    fn preferred_blksize(&self) -> u32 {
+        PREFERRED_BLKSIZE
+    }
+
+    fn lookup(
+        &self,
+        req: &RequestInfo,
+        parent_id: PathBuf,
+        name: &OsStr,
+    ) -> FuseResult<FileAttribute> {
+        let file_id = parent_id.join(name);
+        let attr = self.inner.lookup(req, parent_id, name)?;
+        Ok(self.unset_blksize(&file_id, attr))
+    }
+
+    fn getattr(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: Option<BorrowedFileHandle>,
+    ) -> FuseResult<FileAttribute> {
+        let attr = self.inner.getattr(req, file_id.clone(), file_handle)?;
+        Ok(self.unset_blksize(&file_id, attr))
+    }
+}
+
+#[test]
+fn test_preferred_blksize() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+    fs::write(source_path.join("unset.txt"), "content").unwrap();
+    fs::write(source_path.join("explicit.txt"), "content").unwrap();
+    let backend_blksize = fs::metadata(source_path.join("explicit.txt"))
+        .unwrap()
+        .blksize();
+
+    let mntpoint_clone = mntpoint.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = LargeBlocksFs {
+            inner: MirrorFs::new(source_path, DefaultFuseHandler::new()),
+        };
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        let metadata = fs::metadata(mntpoint.join("unset.txt")).unwrap();
+        assert_eq!(metadata.blksize(), PREFERRED_BLKSIZE as u64);
+
+        // An explicit block size is kept
+        let metadata = fs::metadata(mntpoint.join("explicit.txt")).unwrap();
+        assert_eq!(metadata.blksize(), backend_blksize);
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
@@ -0,0 +1,85 @@
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mirror_fs::*, DefaultFuseHandler};
+
+use std::path::PathBuf;
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// Latency of the backend for the reads of "slow"
+const SLOW_READ_LATENCY: Duration = Duration::from_secs(5);
+
+struct SlowBackendFs {
+    inner: MirrorFsReadOnly,
+}
+
+impl FuseHandler<PathBuf> for SlowBackendFs {
+    fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+        &self.inner
+    }
+
+    fn request_timeout(&self) -> Option<Duration> {
+        Some(Duration::from_secs(1))
+    }
+
+    fn read(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        size: u32,
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+    ) -> FuseResult<Vec<u8>> {
+        assert!(req.deadline.is_some());
+        if file_id.ends_with("slow") {
+            // Bail out instead of blocking the caller past the deadline
+            req.check_deadline(SLOW_READ_LATENCY)?;
+            std::thread::sleep(SLOW_READ_LATENCY);
+        }
+        self.inner
+            .read(req, file_id, file_handle, seek, size, flags, lock_owner)
+    }
+}
+
+#[test]
+fn test_read_past_deadline() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+    std::fs::write(source_dir.path().join("slow"), "slow content").unwrap();
+    std::fs::write(source_dir.path().join("fast"), "fast content").unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+
+    let mntpoint_clone = mntpoint.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = SlowBackendFs {
+            inner: MirrorFsReadOnly::new(source_path, DefaultFuseHandler::new()),
+        };
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        let start = std::time::Instant::now();
+        let error = std::fs::read(mntpoint.join("slow")).unwrap_err();
+        assert_eq!(error.raw_os_error(), Some(libc::ETIMEDOUT));
+        assert!(start.elapsed() < SLOW_READ_LATENCY);
+
+        assert_eq!(
+            std::fs::read_to_string(mntpoint.join("fast")).unwrap(),
+            "fast content"
+        );
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
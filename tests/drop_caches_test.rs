@@ -0,0 +1,70 @@
+// DROP_CACHES_IOCTL is answered by the driver's ioctl operation
+#![cfg(feature = "ioctl")]
+
+use easy_fuser::notifier::drop_caches;
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mirror_fs::*, DefaultFuseHandler};
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use tempfile::TempDir;
+
+struct LongTtlFs {
+    inner: MirrorFsReadOnly,
+}
+
+impl FuseHandler<PathBuf> for LongTtlFs {
+    fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+        &self.inner
+    }
+
+    // Long enough that only a cache drop can refresh the attributes during the test
+    fn get_default_ttl(&self) -> Duration {
+        Duration::from_secs(3600)
+    }
+}
+
+#[test]
+fn test_drop_caches_ioctl() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+    fs::write(source_path.join("file.txt"), "old").unwrap();
+
+    let mntpoint_clone = mntpoint.clone();
+    let source_path_clone = source_path.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = LongTtlFs {
+            inner: MirrorFsReadOnly::new(source_path_clone, DefaultFuseHandler::new()),
+        };
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        let file_path = mntpoint.join("file.txt");
+        assert_eq!(fs::metadata(&file_path).unwrap().len(), 3);
+
+        // Modified behind the mount, the cached attributes are still served
+        fs::write(source_path.join("file.txt"), "newer").unwrap();
+        assert_eq!(fs::metadata(&file_path).unwrap().len(), 3);
+
+        drop_caches(&mntpoint).unwrap();
+        std::thread::sleep(Duration::from_millis(50)); // Wait for the kernel invalidations
+        assert_eq!(fs::metadata(&file_path).unwrap().len(), 5);
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "newer");
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
@@ -0,0 +1,83 @@
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mirror_fs::*, DefaultFuseHandler};
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+
+struct FlushCounterFs {
+    inner: MirrorFs,
+    flushes: Arc<AtomicUsize>,
+}
+
+impl FuseHandler<PathBuf> for FlushCounterFs {
+    fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+        &self.inner
+    }
+
+    fn skip_clean_flushes(&self) -> bool {
+        true
+    }
+
+    fn flush(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        lock_owner: u64,
+    ) -> FuseResult<()> {
+        self.flushes.fetch_add(1, Ordering::SeqCst);
+        self.inner.flush(req, file_id, file_handle, lock_owner)
+    }
+}
+
+#[test]
+fn test_flush_once_with_dup_descriptors() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+    let flushes = Arc::new(AtomicUsize::new(0));
+
+    let mntpoint_clone = mntpoint.clone();
+    let flushes_clone = flushes.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = FlushCounterFs {
+            inner: MirrorFs::new(source_path, DefaultFuseHandler::new()),
+            flushes: flushes_clone,
+        };
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .open(mntpoint.join("dup.txt"))
+            .unwrap();
+        let duplicate = file.try_clone().unwrap();
+        file.write_all(b"written once").unwrap();
+
+        // Closing both descriptors sends two flush requests
+        drop(duplicate);
+        drop(file);
+        assert_eq!(flushes.load(Ordering::SeqCst), 1);
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
@@ -0,0 +1,65 @@
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mirror_fs::*, DefaultFuseHandler};
+
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::time::Duration;
+use tempfile::TempDir;
+
+#[test]
+fn test_follow_symlinks() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+
+    fs::write(source_path.join("target.txt"), "content").unwrap();
+    fs::create_dir(source_path.join("target_dir")).unwrap();
+    symlink("target.txt", source_path.join("link.txt")).unwrap();
+    symlink("target_dir", source_path.join("link_dir")).unwrap();
+    symlink("loop_b", source_path.join("loop_a")).unwrap();
+    symlink("loop_a", source_path.join("loop_b")).unwrap();
+
+    let mntpoint_clone = mntpoint.clone();
+    let source_path_clone = source_path.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = MirrorFs::new(source_path_clone, DefaultFuseHandler::new()).follow_symlinks(true);
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        // Symlinks are presented as their targets
+        let metadata = fs::symlink_metadata(mntpoint.join("link.txt")).unwrap();
+        assert!(metadata.is_file());
+        assert_eq!(metadata.len(), 7);
+        assert_eq!(fs::read(mntpoint.join("link.txt")).unwrap(), b"content");
+        assert!(fs::symlink_metadata(mntpoint.join("link_dir"))
+            .unwrap()
+            .is_dir());
+
+        let error = fs::read_link(mntpoint.join("link.txt")).unwrap_err();
+        assert_eq!(error.raw_os_error(), Some(libc::EINVAL));
+
+        let error = fs::symlink_metadata(mntpoint.join("loop_a")).unwrap_err();
+        assert_eq!(error.raw_os_error(), Some(libc::ELOOP));
+
+        let entry = fs::read_dir(&mntpoint)
+            .unwrap()
+            .map(|entry| entry.unwrap())
+            .find(|entry| entry.file_name() == "link_dir")
+            .unwrap();
+        assert!(entry.file_type().unwrap().is_dir());
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
@@ -94,6 +94,12 @@ fn test_mirror_fs_operations() {
 
         let truncated_len = fs::metadata(&new_file).unwrap().len();
         assert_eq!(truncated_len, truncate_len);
+
+        // Hard link a file
+        let linked_file = mntpoint.join("linked.txt");
+        fs::hard_link(&new_file, &linked_file).unwrap();
+        assert_eq!(fs::metadata(&linked_file).unwrap().nlink(), 2);
+        assert_eq!(fs::metadata(&new_file).unwrap().nlink(), 2);
     }
 
     eprintln!("Unmounting filesystem...");
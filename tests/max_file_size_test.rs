@@ -0,0 +1,77 @@
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mirror_fs::*, DefaultFuseHandler};
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+use tempfile::TempDir;
+
+struct LimitedFs {
+    inner: MirrorFs,
+}
+
+impl FuseHandler<PathBuf> for LimitedFs {
+    fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+        &self.inner
+    }
+
+    fn max_file_size(&self) -> Option<u64> {
+        Some(10)
+    }
+}
+
+#[test]
+fn test_max_file_size() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+
+    let mntpoint_clone = mntpoint.clone();
+    let source_path_clone = source_path.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = LimitedFs {
+            inner: MirrorFs::new(source_path_clone, DefaultFuseHandler::new()),
+        };
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        let file_path = mntpoint.join("file.txt");
+        let mut file = OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .open(&file_path)
+            .unwrap();
+
+        // Up to the limit
+        file.write_all(b"0123456789").unwrap();
+        let error = file.write_all(b"overflow").unwrap_err();
+        assert_eq!(error.raw_os_error(), Some(libc::EFBIG));
+        drop(file);
+        assert_eq!(
+            fs::read(source_path.join("file.txt")).unwrap(),
+            b"0123456789"
+        );
+
+        let file = OpenOptions::new().write(true).open(&file_path).unwrap();
+        let error = file.set_len(100).unwrap_err();
+        assert_eq!(error.raw_os_error(), Some(libc::EFBIG));
+        file.set_len(5).unwrap();
+        assert_eq!(fs::metadata(&file_path).unwrap().len(), 5);
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
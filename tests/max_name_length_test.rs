@@ -0,0 +1,67 @@
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mirror_fs::*, DefaultFuseHandler};
+
+use std::ffi::CString;
+use std::fs::{self, File};
+use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+use std::time::Duration;
+use tempfile::TempDir;
+
+struct ShortNamesFs {
+    inner: MirrorFs,
+}
+
+impl FuseHandler<PathBuf> for ShortNamesFs {
+    fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+        &self.inner
+    }
+
+    fn max_name_length(&self) -> Option<u32> {
+        Some(100)
+    }
+}
+
+#[test]
+fn test_max_name_length() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+
+    let mntpoint_clone = mntpoint.clone();
+    let source_path_clone = source_path.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = ShortNamesFs {
+            inner: MirrorFs::new(source_path_clone, DefaultFuseHandler::new()),
+        };
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        // The advertised limit is the enforced one
+        let path = CString::new(mntpoint.as_os_str().as_bytes()).unwrap();
+        let mut statvfs: libc::statvfs = unsafe { std::mem::zeroed() };
+        assert_eq!(unsafe { libc::statvfs(path.as_ptr(), &mut statvfs) }, 0);
+        assert_eq!(statvfs.f_namemax, 100);
+
+        File::create(mntpoint.join("a".repeat(100))).unwrap();
+        let error = File::create(mntpoint.join("a".repeat(101))).unwrap_err();
+        assert_eq!(error.raw_os_error(), Some(libc::ENAMETOOLONG));
+        let error = fs::create_dir(mntpoint.join("b".repeat(101))).unwrap_err();
+        assert_eq!(error.raw_os_error(), Some(libc::ENAMETOOLONG));
+        assert!(!source_path.join("a".repeat(101)).exists());
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
@@ -0,0 +1,89 @@
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mem_fs::MemFs, DefaultFuseHandler};
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+
+const MAX_WRITE: u32 = 8192;
+
+/// Records the size of the largest write request
+struct SmallWritesFs {
+    inner: MemFs<PathBuf>,
+    largest_write: Arc<AtomicUsize>,
+}
+
+impl FuseHandler<PathBuf> for SmallWritesFs {
+    fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+        &self.inner
+    }
+
+    fn max_write(&self) -> u32 {
+        MAX_WRITE
+    }
+
+    fn write(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        data: Vec<u8>,
+        write_flags: FUSEWriteFlags,
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+    ) -> FuseResult<u32> {
+        self.largest_write.fetch_max(data.len(), Ordering::SeqCst);
+        self.inner.write(
+            req,
+            file_id,
+            file_handle,
+            seek,
+            data,
+            write_flags,
+            flags,
+            lock_owner,
+        )
+    }
+}
+
+#[test]
+fn test_max_write() {
+    let mount_dir = TempDir::new().unwrap();
+    let mntpoint = mount_dir.path().to_path_buf();
+    let largest_write = Arc::new(AtomicUsize::new(0));
+
+    let mntpoint_clone = mntpoint.clone();
+    let largest_write_clone = largest_write.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = SmallWritesFs {
+            inner: MemFs::new(DefaultFuseHandler::new()),
+            largest_write: largest_write_clone,
+        };
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        let content: Vec<u8> = (0..64 * 1024).map(|i| (i % 251) as u8).collect();
+        std::fs::write(mntpoint.join("file.bin"), &content).unwrap();
+        assert_eq!(std::fs::read(mntpoint.join("file.bin")).unwrap(), content);
+
+        // The kernel splits the write in requests of at most max_write bytes
+        let largest_write = largest_write.load(Ordering::SeqCst);
+        assert!(largest_write > 0);
+        assert!(largest_write <= MAX_WRITE as usize);
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
@@ -0,0 +1,144 @@
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mirror_fs::*, DefaultFuseHandler};
+
+use std::ffi::OsStr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+
+struct CountingLookupFs {
+    inner: MirrorFsReadOnly,
+    lookups: Arc<AtomicUsize>,
+}
+
+impl FuseHandler<PathBuf> for CountingLookupFs {
+    fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+        &self.inner
+    }
+
+    fn lookup(
+        &self,
+        req: &RequestInfo,
+        parent_id: PathBuf,
+        name: &OsStr,
+    ) -> FuseResult<FileAttribute> {
+        if name == "missing" {
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+            return Err(PosixError::not_found_cached(
+                Duration::from_secs(60),
+                "missing is never created",
+            ));
+        }
+        self.inner.lookup(req, parent_id, name)
+    }
+}
+
+#[test]
+fn test_cached_negative_lookup() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+    let lookups = Arc::new(AtomicUsize::new(0));
+
+    let mntpoint_clone = mntpoint.clone();
+    let lookups_clone = lookups.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = CountingLookupFs {
+            inner: MirrorFsReadOnly::new(source_path, DefaultFuseHandler::new()),
+            lookups: lookups_clone,
+        };
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        let missing = mntpoint.join("missing");
+        for _ in 0..5 {
+            let error = std::fs::metadata(&missing).unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+        }
+        assert_eq!(lookups.load(Ordering::SeqCst), 1);
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
+
+struct NegativeTtlFs {
+    inner: MirrorFsReadOnly,
+    lookups: Arc<AtomicUsize>,
+}
+
+impl FuseHandler<PathBuf> for NegativeTtlFs {
+    fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+        &self.inner
+    }
+
+    fn negative_lookup_ttl(&self) -> Option<Duration> {
+        Some(Duration::from_secs(60))
+    }
+
+    fn lookup(
+        &self,
+        req: &RequestInfo,
+        parent_id: PathBuf,
+        name: &OsStr,
+    ) -> FuseResult<FileAttribute> {
+        if name == "absent" {
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+        }
+        self.inner.lookup(req, parent_id, name)
+    }
+}
+
+#[test]
+fn test_negative_lookup_ttl() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+    let lookups = Arc::new(AtomicUsize::new(0));
+
+    let mntpoint_clone = mntpoint.clone();
+    let lookups_clone = lookups.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = NegativeTtlFs {
+            inner: MirrorFsReadOnly::new(source_path, DefaultFuseHandler::new()),
+            lookups: lookups_clone,
+        };
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        // The plain FileNotFound of the handler is cached
+        let absent = mntpoint.join("absent");
+        for _ in 0..5 {
+            let error = std::fs::metadata(&absent).unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+        }
+        assert_eq!(lookups.load(Ordering::SeqCst), 1);
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
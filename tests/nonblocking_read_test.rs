@@ -0,0 +1,98 @@
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mirror_fs::*, DefaultFuseHandler};
+
+use std::fs::{File, OpenOptions};
+use std::io::Read;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// Serves `stream` as an always empty stream, like a FIFO nobody writes to
+struct EmptyStreamFs {
+    inner: MirrorFsReadOnly,
+}
+
+impl FuseHandler<PathBuf> for EmptyStreamFs {
+    fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+        &self.inner
+    }
+
+    fn open(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        flags: OpenFlags,
+    ) -> FuseResult<(OwnedFileHandle, FUSEOpenResponseFlags)> {
+        let (file_handle, response_flags) = self.inner.open(req, file_id, flags)?;
+        Ok((
+            file_handle,
+            response_flags | FUSEOpenResponseFlags::DIRECT_IO,
+        ))
+    }
+
+    fn read(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        size: u32,
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+    ) -> FuseResult<Vec<u8>> {
+        if file_id.ends_with("stream") && flags.contains(OpenFlags::NON_BLOCKING_MODE) {
+            return Err(PosixError::new(
+                ErrorKind::ResourceUnavailableTryAgain,
+                "No data available yet",
+            ));
+        }
+        self.inner
+            .read(req, file_id, file_handle, seek, size, flags, lock_owner)
+    }
+}
+
+#[test]
+fn test_nonblocking_read_returns_eagain() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+    File::create(source_path.join("stream")).unwrap();
+
+    let mntpoint_clone = mntpoint.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = EmptyStreamFs {
+            inner: MirrorFsReadOnly::new(source_path, DefaultFuseHandler::new()),
+        };
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(mntpoint.join("stream"))
+            .unwrap();
+        let mut buffer = [0u8; 16];
+        let error = file.read(&mut buffer).unwrap_err();
+        assert_eq!(error.raw_os_error(), Some(libc::EAGAIN));
+        assert_eq!(error.kind(), std::io::ErrorKind::WouldBlock);
+
+        // Without O_NONBLOCK, the read goes through to the underlying empty file
+        let mut file = File::open(mntpoint.join("stream")).unwrap();
+        assert_eq!(file.read(&mut buffer).unwrap(), 0);
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
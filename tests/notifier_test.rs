@@ -0,0 +1,142 @@
+// MirrorFs doesn't implement Send in serial mode, which spawn_mount_with_notifier requires
+#![cfg(not(feature = "serial"))]
+
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mirror_fs::*, DefaultFuseHandler};
+
+use std::ffi::OsStr;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+
+struct CountingLookupFs {
+    inner: MirrorFsReadOnly,
+    lookups: Arc<AtomicUsize>,
+}
+
+impl FuseHandler<PathBuf> for CountingLookupFs {
+    fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+        &self.inner
+    }
+
+    // Long enough that only an invalidation can trigger a new lookup during the test
+    fn get_default_ttl(&self) -> Duration {
+        Duration::from_secs(3600)
+    }
+
+    fn lookup(
+        &self,
+        req: &RequestInfo,
+        parent_id: PathBuf,
+        name: &OsStr,
+    ) -> FuseResult<FileAttribute> {
+        self.lookups.fetch_add(1, Ordering::SeqCst);
+        self.inner.lookup(req, parent_id, name)
+    }
+}
+
+#[test]
+fn test_invalidate_batch_triggers_new_lookups() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+    let names = ["first", "second", "third"];
+    for name in names {
+        File::create(source_path.join(name)).unwrap();
+    }
+
+    let lookups = Arc::new(AtomicUsize::new(0));
+    let fs = CountingLookupFs {
+        inner: MirrorFsReadOnly::new(source_path, DefaultFuseHandler::new()),
+        lookups: lookups.clone(),
+    };
+    let (session, notifier) = spawn_mount_with_notifier(fs, &mntpoint, &[], 4).unwrap();
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        for name in names {
+            std::fs::metadata(mntpoint.join(name)).unwrap();
+        }
+        assert_eq!(lookups.load(Ordering::SeqCst), names.len());
+
+        // Entries are cached by the kernel
+        for name in names {
+            std::fs::metadata(mntpoint.join(name)).unwrap();
+        }
+        assert_eq!(lookups.load(Ordering::SeqCst), names.len());
+
+        // Only paths looked up by the kernel have an inode
+        assert!(notifier.resolve_path(Path::new("first")).is_some());
+        assert_eq!(notifier.resolve_path(Path::new("never_looked_up")), None);
+
+        // Duplicates and unknown ids are accepted
+        notifier
+            .invalidate_batch(&[
+                PathBuf::from("first"),
+                PathBuf::from("second"),
+                PathBuf::from("first"),
+                PathBuf::from("never_looked_up"),
+            ])
+            .unwrap();
+        for name in names {
+            std::fs::metadata(mntpoint.join(name)).unwrap();
+        }
+        assert_eq!(lookups.load(Ordering::SeqCst), names.len() + 2);
+    }
+
+    session.join();
+}
+
+#[test]
+fn test_invalidate_attr_and_entry() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+    std::fs::write(source_path.join("file.txt"), "old").unwrap();
+
+    let lookups = Arc::new(AtomicUsize::new(0));
+    let fs = CountingLookupFs {
+        inner: MirrorFsReadOnly::new(source_path.clone(), DefaultFuseHandler::new()),
+        lookups: lookups.clone(),
+    };
+    let (session, notifier) = spawn_mount_with_notifier(fs, &mntpoint, &[], 4).unwrap();
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        let file_path = mntpoint.join("file.txt");
+        assert_eq!(std::fs::metadata(&file_path).unwrap().len(), 3);
+
+        // Modified behind the mount, the cached attributes are still served
+        std::fs::write(source_path.join("file.txt"), "newer").unwrap();
+        assert_eq!(std::fs::metadata(&file_path).unwrap().len(), 3);
+
+        notifier
+            .invalidate_attr(&PathBuf::from("file.txt"))
+            .unwrap();
+        assert_eq!(std::fs::metadata(&file_path).unwrap().len(), 5);
+        assert_eq!(lookups.load(Ordering::SeqCst), 1);
+
+        notifier
+            .invalidate_entry(&PathBuf::new(), OsStr::new("file.txt"))
+            .unwrap();
+        std::fs::metadata(&file_path).unwrap();
+        assert_eq!(lookups.load(Ordering::SeqCst), 2);
+
+        // Unknown ids are accepted
+        notifier
+            .invalidate_attr(&PathBuf::from("never_looked_up"))
+            .unwrap();
+        notifier
+            .invalidate_entry(&PathBuf::from("never_looked_up"), OsStr::new("child"))
+            .unwrap();
+    }
+
+    session.join();
+}
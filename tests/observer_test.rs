@@ -0,0 +1,43 @@
+use easy_fuser::prelude::*;
+use easy_fuser::templates::DefaultFuseHandler;
+
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::Duration;
+use tempfile::TempDir;
+
+#[test]
+fn test_observer_records_lookup() {
+    let mount_dir = TempDir::new().unwrap();
+    let mntpoint = mount_dir.path().to_path_buf();
+    let (sender, receiver) = mpsc::channel();
+
+    let mntpoint_clone = mntpoint.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = DefaultFuseHandler::new().with_observer(sender);
+        #[cfg(feature = "serial")]
+        mount::<PathBuf, _, _>(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount::<PathBuf, _, _>(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        // Lookup is not implemented, but still reaches the handler
+        assert!(std::fs::metadata(mntpoint.join("file")).is_err());
+
+        let lookup = std::iter::from_fn(|| receiver.recv_timeout(Duration::from_secs(5)).ok())
+            .find(|operation| operation.operation == "lookup")
+            .unwrap();
+        assert_eq!(lookup.file_id.as_deref(), Some(""));
+        assert_eq!(lookup.name, Some(OsString::from("file")));
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
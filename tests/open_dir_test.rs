@@ -0,0 +1,95 @@
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mirror_fs::*, DefaultFuseHandler};
+
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// Records the file ids received by `open` and `opendir`
+struct OpenRecorderFs {
+    inner: MirrorFsReadOnly,
+    opened: Arc<Mutex<Vec<(&'static str, PathBuf)>>>,
+}
+
+impl FuseHandler<PathBuf> for OpenRecorderFs {
+    fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+        &self.inner
+    }
+
+    fn open(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        flags: OpenFlags,
+    ) -> FuseResult<(OwnedFileHandle, FUSEOpenResponseFlags)> {
+        self.opened.lock().unwrap().push(("open", file_id.clone()));
+        self.inner.open(req, file_id, flags)
+    }
+
+    fn opendir(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        flags: OpenFlags,
+    ) -> FuseResult<(OwnedFileHandle, FUSEOpenResponseFlags)> {
+        self.opened
+            .lock()
+            .unwrap()
+            .push(("opendir", file_id.clone()));
+        self.inner.opendir(req, file_id, flags)
+    }
+}
+
+#[test]
+fn test_open_directory() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+    std::fs::create_dir(source_dir.path().join("dir")).unwrap();
+    std::fs::write(source_dir.path().join("dir/file"), b"data").unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+    let opened = Arc::new(Mutex::new(Vec::new()));
+
+    let mntpoint_clone = mntpoint.clone();
+    let opened_clone = opened.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = OpenRecorderFs {
+            inner: MirrorFsReadOnly::new(source_path, DefaultFuseHandler::new()),
+            opened: opened_clone,
+        };
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        // open(2) on a directory, as opposed to opendir(3)
+        let mut dir = std::fs::File::open(mntpoint.join("dir")).unwrap();
+        assert!(dir.metadata().unwrap().is_dir());
+        let error = dir.read(&mut [0; 16]).unwrap_err();
+        assert_eq!(error.raw_os_error(), Some(libc::EISDIR));
+        drop(dir);
+
+        // The kernel routes it to opendir, open only receives the files
+        std::fs::read(mntpoint.join("dir/file")).unwrap();
+        assert_eq!(
+            *opened.lock().unwrap(),
+            vec![
+                ("opendir", PathBuf::from("dir")),
+                ("open", PathBuf::from("dir/file")),
+            ]
+        );
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
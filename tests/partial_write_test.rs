@@ -0,0 +1,111 @@
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mirror_fs::*, DefaultFuseHandler};
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// Interrupts the first write halfway through, as a signal hitting the backend would
+struct InterruptedWriteFs {
+    inner: MirrorFs,
+    interrupted: AtomicBool,
+}
+
+impl FuseHandler<PathBuf> for InterruptedWriteFs {
+    fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+        &self.inner
+    }
+
+    fn open(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        flags: OpenFlags,
+    ) -> FuseResult<(OwnedFileHandle, FUSEOpenResponseFlags)> {
+        let (file_handle, response_flags) = self.inner.open(req, file_id, flags)?;
+        Ok((
+            file_handle,
+            response_flags | FUSEOpenResponseFlags::DIRECT_IO,
+        ))
+    }
+
+    fn write(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        mut data: Vec<u8>,
+        write_flags: FUSEWriteFlags,
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+    ) -> FuseResult<u32> {
+        if !self.interrupted.swap(true, Ordering::SeqCst) {
+            data.truncate(data.len() / 2);
+        }
+        self.inner.write(
+            req,
+            file_id,
+            file_handle,
+            seek,
+            data,
+            write_flags,
+            flags,
+            lock_owner,
+        )
+    }
+}
+
+#[test]
+fn test_interrupted_write_reports_progress() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+    std::fs::write(source_dir.path().join("file"), b"").unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+
+    let mntpoint_clone = mntpoint.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = InterruptedWriteFs {
+            inner: MirrorFs::new(source_path, DefaultFuseHandler::new()),
+            interrupted: AtomicBool::new(false),
+        };
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        let data: Vec<u8> = (0..64).collect();
+        let mut file = OpenOptions::new()
+            .write(true)
+            .open(mntpoint.join("file"))
+            .unwrap();
+
+        // The application sees a short write matching the persisted bytes
+        let written = file.write(&data).unwrap();
+        assert_eq!(written, 32);
+        assert_eq!(
+            std::fs::read(source_dir.path().join("file")).unwrap(),
+            &data[..written]
+        );
+
+        // And resumes from there
+        file.write_all(&data[written..]).unwrap();
+        drop(file);
+        assert_eq!(std::fs::read(source_dir.path().join("file")).unwrap(), data);
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
@@ -0,0 +1,76 @@
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mirror_fs::*, DefaultFuseHandler};
+
+use std::fs::{self, File};
+use std::os::fd::AsRawFd;
+use std::path::PathBuf;
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// Reports files as readable but never writable
+struct ReadablePollFs {
+    inner: MirrorFs,
+}
+
+impl FuseHandler<PathBuf> for ReadablePollFs {
+    fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+        &self.inner
+    }
+
+    fn poll(
+        &self,
+        _req: &RequestInfo,
+        _file_id: PathBuf,
+        _file_handle: BorrowedFileHandle,
+        _poll_handle: PollHandle,
+        events: u32,
+        _flags: u32,
+    ) -> FuseResult<u32> {
+        Ok(events & libc::POLLIN as u32)
+    }
+}
+
+#[test]
+fn test_poll() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+    fs::write(source_path.join("events"), "").unwrap();
+
+    let mntpoint_clone = mntpoint.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = ReadablePollFs {
+            inner: MirrorFs::new(source_path, DefaultFuseHandler::new()),
+        };
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .open(mntpoint.join("events"))
+            .unwrap();
+        let mut pollfd = libc::pollfd {
+            fd: file.as_raw_fd(),
+            events: libc::POLLIN | libc::POLLOUT,
+            revents: 0,
+        };
+        let ready = unsafe { libc::poll(&mut pollfd, 1, 1000) };
+        assert_eq!(ready, 1);
+        assert_eq!(pollfd.revents, libc::POLLIN);
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
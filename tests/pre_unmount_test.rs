@@ -0,0 +1,63 @@
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mirror_fs::*, DefaultFuseHandler};
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tempfile::TempDir;
+
+struct RecordingFs {
+    inner: MirrorFs,
+    calls: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl FuseHandler<PathBuf> for RecordingFs {
+    fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+        &self.inner
+    }
+
+    fn pre_unmount(&self) -> FuseResult<()> {
+        self.calls.lock().unwrap().push("pre_unmount");
+        Err(ErrorKind::InputOutputError.to_error("flush failed"))
+    }
+
+    fn destroy(&self) {
+        self.calls.lock().unwrap().push("destroy");
+    }
+}
+
+#[test]
+fn test_pre_unmount_runs_before_destroy() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+    let calls = Arc::new(Mutex::new(Vec::new()));
+
+    let mntpoint_clone = mntpoint.clone();
+    let calls_clone = calls.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = RecordingFs {
+            inner: MirrorFs::new(source_path, DefaultFuseHandler::new()),
+            calls: calls_clone,
+        };
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    assert!(calls.lock().unwrap().is_empty());
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+
+    // A failing pre_unmount doesn't prevent destroy
+    assert_eq!(*calls.lock().unwrap(), vec!["pre_unmount", "destroy"]);
+}
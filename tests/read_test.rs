@@ -0,0 +1,110 @@
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mirror_fs::*, DefaultFuseHandler};
+
+use std::fs::{self, File};
+use std::io::{Read, Seek};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// Serves the files of the source directory by chunks of 1000 bytes, like a network backend
+struct ChunkedFs {
+    inner: MirrorFsReadOnly,
+    /// Reads returning less than requested before the end of the file, which the kernel takes for the end
+    short_reads: Arc<AtomicUsize>,
+}
+
+impl FuseHandler<PathBuf> for ChunkedFs {
+    fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+        &self.inner
+    }
+
+    fn read(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+        seek: SeekFrom,
+        size: u32,
+        flags: OpenFlags,
+        lock_owner: Option<u64>,
+    ) -> FuseResult<Vec<u8>> {
+        let SeekFrom::Start(offset) = seek else {
+            return Err(ErrorKind::InvalidArgument.to_error("Only absolute offsets are supported"));
+        };
+        // Chunks are gathered until the requested size or the end of the file is reached
+        let mut data = Vec::with_capacity(size as usize);
+        while data.len() < size as usize {
+            let chunk_size = (size as usize - data.len()).min(1000);
+            let chunk = self.inner.read(
+                req,
+                file_id.clone(),
+                file_handle,
+                SeekFrom::Start(offset + data.len() as u64),
+                chunk_size as u32,
+                flags,
+                lock_owner,
+            )?;
+            if chunk.is_empty() {
+                break;
+            }
+            data.extend_from_slice(&chunk);
+        }
+        if data.len() < size as usize {
+            let attr = self.inner.getattr(req, file_id, Some(file_handle))?;
+            if offset + (data.len() as u64) < attr.size {
+                self.short_reads.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+        Ok(data)
+    }
+}
+
+#[test]
+fn test_read_multi_chunk_file() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+    // Spans several kernel reads, and doesn't end on a chunk or page boundary
+    let content: Vec<u8> = (0..1_000_003u32).map(|i| (i % 251) as u8).collect();
+    fs::write(source_path.join("file.bin"), &content).unwrap();
+
+    let short_reads = Arc::new(AtomicUsize::new(0));
+
+    let mntpoint_clone = mntpoint.clone();
+    let short_reads_clone = short_reads.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = ChunkedFs {
+            inner: MirrorFsReadOnly::new(source_path, DefaultFuseHandler::new()),
+            short_reads: short_reads_clone,
+        };
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        assert_eq!(fs::read(mntpoint.join("file.bin")).unwrap(), content);
+
+        // A read in the middle of the file returns the requested size
+        let mut file = File::open(mntpoint.join("file.bin")).unwrap();
+        let mut buffer = vec![0; 300_000];
+        file.seek(std::io::SeekFrom::Start(500_000)).unwrap();
+        file.read_exact(&mut buffer).unwrap();
+        assert_eq!(buffer, &content[500_000..800_000]);
+        assert_eq!(short_reads.load(Ordering::SeqCst), 0);
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
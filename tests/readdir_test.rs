@@ -0,0 +1,267 @@
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mirror_fs::*, DefaultFuseHandler};
+
+use std::ffi::{CStr, CString, OsString};
+use std::fs::{self, File};
+use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use tempfile::TempDir;
+
+#[test]
+fn test_readdir_after_interrupted_listing_and_failed_lookup() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+
+    // Enough entries to need several readdir requests
+    let subdir = source_path.join("big_dir");
+    fs::create_dir(&subdir).unwrap();
+    let file_count = 500;
+    for i in 0..file_count {
+        File::create(subdir.join(format!("a_rather_long_file_name_{:04}", i))).unwrap();
+    }
+
+    let mntpoint_clone = mntpoint.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = MirrorFs::new(source_path.clone(), DefaultFuseHandler::new());
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        let mounted_dir = mntpoint.join("big_dir");
+
+        // Open the directory and stop reading before the end
+        let mut entries = fs::read_dir(&mounted_dir).unwrap();
+        assert!(entries.next().is_some());
+
+        // Failed lookup, like a `cd` into a missing directory
+        assert!(fs::metadata(mounted_dir.join("missing")).is_err());
+        drop(entries);
+
+        // A new listing must start fresh and be complete
+        let names: Vec<_> = fs::read_dir(&mounted_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(names.len(), file_count);
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
+
+#[test]
+fn test_seekdir_to_recorded_offset() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+
+    let subdir = source_path.join("dir");
+    fs::create_dir(&subdir).unwrap();
+    for i in 0..10 {
+        File::create(subdir.join(format!("file_{}", i))).unwrap();
+    }
+
+    let mntpoint_clone = mntpoint.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = MirrorFs::new(source_path.clone(), DefaultFuseHandler::new());
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        let path = CString::new(mntpoint.join("dir").as_os_str().as_bytes()).unwrap();
+        unsafe {
+            let dir = libc::opendir(path.as_ptr());
+            assert!(!dir.is_null());
+
+            let read_name = |dir: *mut libc::DIR| {
+                let entry = libc::readdir(dir);
+                if entry.is_null() {
+                    None
+                } else {
+                    Some(CStr::from_ptr((*entry).d_name.as_ptr()).to_owned())
+                }
+            };
+
+            // Read the whole listing, recording the position before each entry
+            let mut listing = Vec::new();
+            loop {
+                let position = libc::telldir(dir);
+                match read_name(dir) {
+                    Some(name) => listing.push((position, name)),
+                    None => break,
+                }
+            }
+            // 10 files, "." and ".."
+            assert_eq!(listing.len(), 12);
+
+            // Seeking back to a recorded position returns the same entry
+            for (position, name) in listing.iter().rev() {
+                libc::seekdir(dir, *position);
+                assert_eq!(read_name(dir).as_ref(), Some(name));
+            }
+
+            libc::closedir(dir);
+        }
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
+
+// Runs in every concurrency mode, as the serial driver shares the directory reply handling
+#[test]
+fn test_readdir_reports_names_and_kinds() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+
+    fs::create_dir(source_path.join("subdir")).unwrap();
+    File::create(source_path.join("file.txt")).unwrap();
+    std::os::unix::fs::symlink("file.txt", source_path.join("link")).unwrap();
+
+    let mntpoint_clone = mntpoint.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = MirrorFs::new(source_path.clone(), DefaultFuseHandler::new());
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        let mut entries: Vec<_> = fs::read_dir(&mntpoint)
+            .unwrap()
+            .map(|entry| {
+                let entry = entry.unwrap();
+                let file_type = entry.file_type().unwrap();
+                (
+                    entry.file_name().into_string().unwrap(),
+                    file_type.is_dir(),
+                    file_type.is_symlink(),
+                )
+            })
+            .collect();
+        entries.sort();
+        assert_eq!(
+            entries,
+            vec![
+                ("file.txt".to_string(), false, false),
+                ("link".to_string(), false, true),
+                ("subdir".to_string(), true, false),
+            ]
+        );
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
+
+/// Lists the first entries of each directory, then fails as if the connection was lost
+struct PartialListingFs {
+    inner: MirrorFs,
+}
+
+impl FuseHandler<PathBuf> for PartialListingFs {
+    fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+        &self.inner
+    }
+
+    fn readdir_partial(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: BorrowedFileHandle,
+    ) -> (Vec<(OsString, FileKind)>, Option<PosixError>) {
+        let mut entries = match self.inner.readdir(req, file_id, file_handle) {
+            Ok(entries) => entries,
+            Err(e) => return (Vec::new(), Some(e)),
+        };
+        entries.retain(|(name, _)| name.as_bytes().starts_with(b"kept"));
+        (
+            entries,
+            Some(ErrorKind::ConnectionReset.to_error("listing interrupted")),
+        )
+    }
+}
+
+#[test]
+fn test_readdir_partial_listing() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+
+    fs::create_dir(source_path.join("dir")).unwrap();
+    File::create(source_path.join("dir/kept_1")).unwrap();
+    File::create(source_path.join("dir/kept_2")).unwrap();
+    File::create(source_path.join("dir/lost")).unwrap();
+    fs::create_dir(source_path.join("empty")).unwrap();
+
+    let mntpoint_clone = mntpoint.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = PartialListingFs {
+            inner: MirrorFs::new(source_path.clone(), DefaultFuseHandler::new()),
+        };
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        // The gathered entries are listed without error
+        let mut names: Vec<_> = fs::read_dir(mntpoint.join("dir"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["kept_1", "kept_2"]);
+
+        // Without any entry, the error is returned
+        let error = fs::read_dir(mntpoint.join("empty"))
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(error.raw_os_error(), Some(libc::ECONNRESET));
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
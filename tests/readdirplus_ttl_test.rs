@@ -0,0 +1,173 @@
+use easy_fuser::prelude::*;
+use easy_fuser::templates::DefaultFuseHandler;
+
+use std::ffi::{OsStr, OsString};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+use tempfile::TempDir;
+
+/// Name, inode and ttl of the entries of the root directory
+const ENTRIES: [(&str, u64, Duration); 2] = [
+    ("stable", 2, Duration::from_secs(60)),
+    ("volatile", 3, Duration::ZERO),
+];
+
+/// Root directory listing a file cached for long and a file never cached
+struct MixedTtlFs {
+    inner: DefaultFuseHandler,
+    /// Number of lookup and getattr requests received for each entry
+    requests: Arc<[AtomicUsize; 2]>,
+}
+
+impl MixedTtlFs {
+    fn attr(kind: FileKind, ttl: Option<Duration>) -> FileAttribute {
+        FileAttribute {
+            size: 0,
+            blocks: 0,
+            atime: UNIX_EPOCH,
+            mtime: UNIX_EPOCH,
+            ctime: UNIX_EPOCH,
+            crtime: UNIX_EPOCH,
+            kind,
+            perm: 0o755,
+            nlink: 1,
+            uid: 0,
+            gid: 0,
+            rdev: 0,
+            flags: 0,
+            blksize: 4096,
+            ttl,
+            generation: None,
+        }
+    }
+
+    fn entry(&self, index: usize) -> (Inode, FileAttribute) {
+        let (_, ino, ttl) = ENTRIES[index];
+        (
+            Inode::from(ino),
+            Self::attr(FileKind::RegularFile, Some(ttl)),
+        )
+    }
+}
+
+impl FuseHandler<Inode> for MixedTtlFs {
+    fn get_inner(&self) -> &dyn FuseHandler<Inode> {
+        &self.inner
+    }
+
+    fn init(&self, req: &RequestInfo, config: &mut KernelConfig) -> FuseResult<()> {
+        // Older kernels fall back to readdir and lookup, the ttl of each entry still applies
+        let _ = config.add_capabilities(FUSEInitFlags::DO_READDIRPLUS.bits().into());
+        self.get_inner().init(req, config)
+    }
+
+    fn getattr(
+        &self,
+        _req: &RequestInfo,
+        file_id: Inode,
+        _file_handle: Option<BorrowedFileHandle>,
+    ) -> FuseResult<FileAttribute> {
+        if file_id == ROOT_INODE {
+            return Ok(Self::attr(FileKind::Directory, None));
+        }
+        let index = ENTRIES
+            .iter()
+            .position(|(_, ino, _)| Inode::from(*ino) == file_id)
+            .ok_or_else(|| ErrorKind::FileNotFound.to_error("No such inode"))?;
+        self.requests[index].fetch_add(1, Ordering::SeqCst);
+        Ok(self.entry(index).1)
+    }
+
+    fn lookup(
+        &self,
+        _req: &RequestInfo,
+        parent_id: Inode,
+        name: &OsStr,
+    ) -> FuseResult<(Inode, FileAttribute)> {
+        let index = ENTRIES
+            .iter()
+            .position(|(known, _, _)| name == *known)
+            .filter(|_| parent_id == ROOT_INODE)
+            .ok_or_else(|| ErrorKind::FileNotFound.to_error("No such entry"))?;
+        self.requests[index].fetch_add(1, Ordering::SeqCst);
+        Ok(self.entry(index))
+    }
+
+    fn readdir(
+        &self,
+        _req: &RequestInfo,
+        file_id: Inode,
+        _file_handle: BorrowedFileHandle,
+    ) -> FuseResult<Vec<(OsString, (Inode, FileKind))>> {
+        let mut entries = vec![
+            (OsString::from("."), (file_id.clone(), FileKind::Directory)),
+            (OsString::from(".."), (file_id, FileKind::Directory)),
+        ];
+        for (name, ino, _) in ENTRIES {
+            entries.push((
+                OsString::from(name),
+                (Inode::from(ino), FileKind::RegularFile),
+            ));
+        }
+        Ok(entries)
+    }
+
+    fn readdirplus(
+        &self,
+        _req: &RequestInfo,
+        _file_id: Inode,
+        _file_handle: BorrowedFileHandle,
+    ) -> FuseResult<Vec<(OsString, (Inode, FileAttribute))>> {
+        Ok((0..ENTRIES.len())
+            .map(|index| (OsString::from(ENTRIES[index].0), self.entry(index)))
+            .collect())
+    }
+}
+
+#[test]
+fn test_readdirplus_per_entry_ttl() {
+    let mount_dir = TempDir::new().unwrap();
+    let mntpoint = mount_dir.path().to_path_buf();
+    let requests = Arc::new([AtomicUsize::new(0), AtomicUsize::new(0)]);
+
+    let mntpoint_clone = mntpoint.clone();
+    let requests_clone = requests.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = MixedTtlFs {
+            inner: DefaultFuseHandler::new(),
+            requests: requests_clone,
+        };
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        let mut names: Vec<_> = std::fs::read_dir(&mntpoint)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["stable", "volatile"]);
+
+        for _ in 0..3 {
+            for (name, _, _) in ENTRIES {
+                assert!(std::fs::metadata(mntpoint.join(name)).unwrap().is_file());
+            }
+        }
+        // The stable entry is served from the cache, at most one lookup when readdirplus is unavailable
+        assert!(requests[0].load(Ordering::SeqCst) <= 1);
+        // The volatile entry reaches the filesystem on every access
+        assert!(requests[1].load(Ordering::SeqCst) >= 3);
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
@@ -0,0 +1,51 @@
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mirror_fs::*, DefaultFuseHandler};
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use tempfile::TempDir;
+
+// Part of the test matrix of every concurrency mode, including serial
+#[test]
+fn test_readlink() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+    fs::write(source_path.join("target.txt"), "content").unwrap();
+    std::os::unix::fs::symlink("target.txt", source_path.join("link")).unwrap();
+
+    let mntpoint_clone = mntpoint.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = MirrorFsReadOnly::new(source_path, DefaultFuseHandler::new());
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        assert_eq!(
+            fs::read_link(mntpoint.join("link")).unwrap(),
+            PathBuf::from("target.txt")
+        );
+        assert_eq!(
+            fs::read_to_string(mntpoint.join("link")).unwrap(),
+            "content"
+        );
+
+        // Only symbolic links can be read as such
+        let error = fs::read_link(mntpoint.join("target.txt")).unwrap_err();
+        assert_eq!(error.raw_os_error(), Some(libc::EINVAL));
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
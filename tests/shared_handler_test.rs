@@ -0,0 +1,34 @@
+// MemFs doesn't implement Send in serial mode, which spawn_mount requires
+#![cfg(not(feature = "serial"))]
+
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mem_fs::MemFs, DefaultFuseHandler};
+
+use std::ffi::OsStr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+
+#[test]
+fn test_shared_handler_inspection() {
+    let mount_dir = TempDir::new().unwrap();
+    let mntpoint = mount_dir.path().to_path_buf();
+
+    let fs = Arc::new(MemFs::<PathBuf>::new(DefaultFuseHandler::new()));
+    let session = spawn_mount(fs.clone(), &mntpoint, &[], 4).unwrap();
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        std::fs::write(mntpoint.join("file.txt"), "content").unwrap();
+
+        // The handler kept by the caller is the one serving the mount
+        let req = RequestInfo::default();
+        let attr = fs
+            .lookup(&req, PathBuf::new(), OsStr::new("file.txt"))
+            .unwrap();
+        assert_eq!(attr.size, 7);
+    }
+
+    session.join();
+}
@@ -0,0 +1,59 @@
+#![cfg(all(feature = "inotify", target_os = "linux", not(feature = "serial")))]
+
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mirror_fs::*, DefaultFuseHandler};
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// MirrorFs with attributes cached for much longer than the test duration
+struct LongTtlMirrorFs {
+    inner: MirrorFs,
+}
+
+impl FuseHandler<PathBuf> for LongTtlMirrorFs {
+    fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+        &self.inner
+    }
+
+    fn get_default_ttl(&self) -> Duration {
+        Duration::from_secs(3600)
+    }
+}
+
+#[test]
+fn test_source_changes_are_visible_before_ttl() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+    fs::create_dir(source_path.join("dir")).unwrap();
+    fs::write(source_path.join("dir/file.txt"), b"short").unwrap();
+
+    let fs = LongTtlMirrorFs {
+        inner: MirrorFs::new(source_path.clone(), DefaultFuseHandler::new()),
+    };
+    let (session, notifier) = spawn_mount_with_notifier(fs, &mntpoint, &[], 4).unwrap();
+    let watcher = SourceWatcher::start(&source_path, notifier).unwrap();
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        let mounted_file = mntpoint.join("dir/file.txt");
+        assert_eq!(fs::metadata(&mounted_file).unwrap().len(), 5);
+        assert!(fs::metadata(mntpoint.join("dir/new.txt")).is_err());
+
+        // Modify the source behind the mount's back
+        fs::write(source_path.join("dir/file.txt"), b"a longer content").unwrap();
+        fs::write(source_path.join("dir/new.txt"), b"new").unwrap();
+        std::thread::sleep(Duration::from_millis(300));
+
+        assert_eq!(fs::metadata(&mounted_file).unwrap().len(), 16);
+        assert_eq!(fs::read(mntpoint.join("dir/new.txt")).unwrap(), b"new");
+    }
+
+    watcher.stop();
+    session.join();
+}
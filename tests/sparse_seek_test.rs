@@ -0,0 +1,65 @@
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mirror_fs::*, DefaultFuseHandler};
+
+use std::fs::File;
+use std::io::{Seek, SeekFrom, Write};
+use std::os::fd::AsRawFd;
+use std::time::Duration;
+use tempfile::TempDir;
+
+const HOLE_SIZE: u64 = 1024 * 1024;
+
+#[test]
+fn test_seek_data_skips_hole() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+
+    // A hole followed by a single data block
+    let mut sparse = File::create(source_dir.path().join("sparse")).unwrap();
+    sparse.seek(SeekFrom::Start(HOLE_SIZE)).unwrap();
+    sparse.write_all(&[1; 4096]).unwrap();
+    // Skip when the backing filesystem does not track holes and reports the whole file as data
+    if unsafe { libc::lseek(sparse.as_raw_fd(), 0, libc::SEEK_DATA) } != HOLE_SIZE as i64 {
+        eprintln!("skipping test_seek_data_skips_hole: no hole support in the source filesystem");
+        return;
+    }
+    drop(sparse);
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+
+    let mntpoint_clone = mntpoint.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = MirrorFs::new(source_path, DefaultFuseHandler::new());
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        let file = File::open(mntpoint.join("sparse")).unwrap();
+        let fd = file.as_raw_fd();
+        assert_eq!(
+            unsafe { libc::lseek(fd, 0, libc::SEEK_DATA) },
+            HOLE_SIZE as i64
+        );
+        assert_eq!(unsafe { libc::lseek(fd, 0, libc::SEEK_HOLE) }, 0);
+
+        // Past the last data region
+        let past_end = unsafe { libc::lseek(fd, HOLE_SIZE as i64 + 4096, libc::SEEK_DATA) };
+        assert_eq!(past_end, -1);
+        assert_eq!(
+            std::io::Error::last_os_error().raw_os_error(),
+            Some(libc::ENXIO)
+        );
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
@@ -0,0 +1,78 @@
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mirror_fs::*, DefaultFuseHandler};
+
+use std::ffi::CString;
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// Mirror whose `upper` subtree is reported as a separate backend
+struct SubtreeStatsFs {
+    inner: MirrorFs,
+}
+
+impl FuseHandler<PathBuf> for SubtreeStatsFs {
+    fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+        &self.inner
+    }
+
+    fn statfs(&self, _req: &RequestInfo, file_id: PathBuf) -> FuseResult<StatFs> {
+        let total_blocks = if file_id.starts_with("upper") {
+            1000
+        } else {
+            2000
+        };
+        Ok(StatFs::builder()
+            .total_blocks(total_blocks)
+            .free_blocks(total_blocks / 2)
+            .available_blocks(total_blocks / 2))
+    }
+}
+
+fn total_blocks(path: &Path) -> libc::fsblkcnt_t {
+    let path = CString::new(path.as_os_str().as_bytes()).unwrap();
+    let mut statvfs: libc::statvfs = unsafe { std::mem::zeroed() };
+    assert_eq!(unsafe { libc::statvfs(path.as_ptr(), &mut statvfs) }, 0);
+    statvfs.f_blocks
+}
+
+#[test]
+fn test_statfs_per_subtree() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+    fs::create_dir_all(source_path.join("upper/dir")).unwrap();
+    fs::create_dir(source_path.join("lower")).unwrap();
+
+    let mntpoint_clone = mntpoint.clone();
+    let source_path_clone = source_path.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = SubtreeStatsFs {
+            inner: MirrorFs::new(source_path_clone, DefaultFuseHandler::new()),
+        };
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        // The handler receives the id of the file statfs was called on
+        assert_eq!(total_blocks(&mntpoint.join("upper")), 1000);
+        assert_eq!(total_blocks(&mntpoint.join("upper/dir")), 1000);
+        assert_eq!(total_blocks(&mntpoint.join("lower")), 2000);
+        assert_eq!(total_blocks(&mntpoint), 2000);
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
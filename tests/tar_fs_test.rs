@@ -0,0 +1,81 @@
+#![cfg(feature = "tar")]
+
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{archive::TarFs, DefaultFuseHandler};
+
+use std::fs::File;
+use std::path::Path;
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn build_archive(path: &Path) {
+    let mut builder = tar::Builder::new(File::create(path).unwrap());
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Directory);
+    header.set_mode(0o755);
+    header.set_size(0);
+    builder
+        .append_data(&mut header, "docs/", std::io::empty())
+        .unwrap();
+
+    let content = b"Nested content";
+    let mut header = tar::Header::new_gnu();
+    header.set_mode(0o644);
+    header.set_size(content.len() as u64);
+    builder
+        .append_data(&mut header, "docs/guide/intro.txt", &content[..])
+        .unwrap();
+
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Symlink);
+    header.set_size(0);
+    builder
+        .append_link(&mut header, "intro", "docs/guide/intro.txt")
+        .unwrap();
+    builder.finish().unwrap();
+}
+
+#[test]
+fn test_mount_tar_archive() {
+    let mount_dir = TempDir::new().unwrap();
+    let archive_dir = TempDir::new().unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let archive_path = archive_dir.path().join("archive.tar");
+    build_archive(&archive_path);
+
+    let mntpoint_clone = mntpoint.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = TarFs::open(&archive_path, DefaultFuseHandler::new()).unwrap();
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[MountOption::RO]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[MountOption::RO], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        let nested = mntpoint.join("docs/guide/intro.txt");
+        assert_eq!(std::fs::read_to_string(&nested).unwrap(), "Nested content");
+
+        let mut names: Vec<_> = std::fs::read_dir(mntpoint.join("docs"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["guide"]);
+
+        assert_eq!(
+            std::fs::read_link(mntpoint.join("intro")).unwrap(),
+            Path::new("docs/guide/intro.txt")
+        );
+        assert!(std::fs::write(&nested, "modified").is_err());
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
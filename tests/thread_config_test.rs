@@ -0,0 +1,114 @@
+// MemFs doesn't implement Send in serial mode, which spawn_mount_with_thread_config requires
+#![cfg(all(not(feature = "serial"), target_os = "linux"))]
+
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mem_fs::MemFs, DefaultFuseHandler};
+
+use std::ffi::OsStr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn thread_names() -> Vec<String> {
+    std::fs::read_dir("/proc/self/task")
+        .unwrap()
+        .filter_map(|task| std::fs::read_to_string(task.unwrap().path().join("comm")).ok())
+        .map(|comm| comm.trim_end().to_string())
+        .collect()
+}
+
+/// Records the name of the thread running `lookup` or `getattr`
+struct ThreadRecordingFs {
+    inner: MemFs<PathBuf>,
+    handler_thread: Arc<Mutex<Option<String>>>,
+}
+
+impl ThreadRecordingFs {
+    fn record_thread(&self) {
+        *self.handler_thread.lock().unwrap() = std::thread::current().name().map(String::from);
+    }
+}
+
+impl FuseHandler<PathBuf> for ThreadRecordingFs {
+    fn get_inner(&self) -> &dyn FuseHandler<PathBuf> {
+        &self.inner
+    }
+
+    fn lookup(
+        &self,
+        req: &RequestInfo,
+        parent_id: PathBuf,
+        name: &OsStr,
+    ) -> FuseResult<FileAttribute> {
+        self.record_thread();
+        self.inner.lookup(req, parent_id, name)
+    }
+
+    fn getattr(
+        &self,
+        req: &RequestInfo,
+        file_id: PathBuf,
+        file_handle: Option<BorrowedFileHandle>,
+    ) -> FuseResult<FileAttribute> {
+        self.record_thread();
+        self.inner.getattr(req, file_id, file_handle)
+    }
+}
+
+#[test]
+fn test_session_thread_name() {
+    let mount_dir = TempDir::new().unwrap();
+    let mntpoint = mount_dir.path().to_path_buf();
+
+    let fs = MemFs::<PathBuf>::new(DefaultFuseHandler::new());
+    fs.insert_file("file.txt", "content").unwrap();
+    let thread_config = ThreadConfig {
+        name: Some("easy-fuser-test".to_string()),
+        stack_size: Some(8 * 1024 * 1024),
+    };
+    let session = spawn_mount_with_thread_config(fs, &mntpoint, &[], 4, thread_config).unwrap();
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        assert_eq!(session.thread().name(), Some("easy-fuser-test"));
+        assert!(thread_names().iter().any(|name| name == "easy-fuser-test"));
+        assert_eq!(
+            std::fs::read_to_string(mntpoint.join("file.txt")).unwrap(),
+            "content"
+        );
+    }
+
+    session.join().unwrap();
+    assert!(!thread_names().iter().any(|name| name == "easy-fuser-test"));
+}
+
+#[test]
+fn test_single_thread_runs_inline() {
+    let mount_dir = TempDir::new().unwrap();
+    let mntpoint = mount_dir.path().to_path_buf();
+
+    let handler_thread = Arc::new(Mutex::new(None));
+    let fs = ThreadRecordingFs {
+        inner: MemFs::new(DefaultFuseHandler::new()),
+        handler_thread: handler_thread.clone(),
+    };
+    fs.inner.insert_file("file.txt", "content").unwrap();
+    let thread_config = ThreadConfig {
+        name: Some("easy-fuser-one".to_string()),
+        stack_size: None,
+    };
+    let session = spawn_mount_with_thread_config(fs, &mntpoint, &[], 1, thread_config).unwrap();
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        std::fs::metadata(mntpoint.join("file.txt")).unwrap();
+        // Without a thread pool, the handler runs on the session thread
+        assert_eq!(
+            handler_thread.lock().unwrap().as_deref(),
+            Some("easy-fuser-one")
+        );
+    }
+
+    session.join().unwrap();
+}
@@ -0,0 +1,147 @@
+use easy_fuser::prelude::*;
+use easy_fuser::templates::DefaultFuseHandler;
+
+use std::ffi::{OsStr, OsString};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+use tempfile::TempDir;
+
+const NAMES: [&str; 2] = ["a", "b"];
+
+/// Root directory whose files get a new inode on each lookup or readdir
+struct VolatileFs {
+    inner: DefaultFuseHandler,
+    next_ino: AtomicU64,
+    lookups: Arc<AtomicUsize>,
+}
+
+impl VolatileFs {
+    fn new_inode(&self) -> Inode {
+        Inode::from(self.next_ino.fetch_add(1, Ordering::SeqCst))
+    }
+
+    fn attr(kind: FileKind) -> FileAttribute {
+        FileAttribute {
+            size: 4,
+            blocks: 1,
+            atime: UNIX_EPOCH,
+            mtime: UNIX_EPOCH,
+            ctime: UNIX_EPOCH,
+            crtime: UNIX_EPOCH,
+            kind,
+            perm: 0o755,
+            nlink: 1,
+            uid: 0,
+            gid: 0,
+            rdev: 0,
+            flags: 0,
+            blksize: 4096,
+            // Ignored for volatile inodes
+            ttl: Some(Duration::from_secs(60)),
+            generation: None,
+        }
+    }
+}
+
+impl FuseHandler<Inode> for VolatileFs {
+    fn get_inner(&self) -> &dyn FuseHandler<Inode> {
+        &self.inner
+    }
+
+    fn volatile_inodes(&self) -> bool {
+        true
+    }
+
+    fn getattr(
+        &self,
+        _req: &RequestInfo,
+        file_id: Inode,
+        _file_handle: Option<BorrowedFileHandle>,
+    ) -> FuseResult<FileAttribute> {
+        if file_id == ROOT_INODE {
+            return Ok(Self::attr(FileKind::Directory));
+        }
+        Ok(Self::attr(FileKind::RegularFile))
+    }
+
+    fn lookup(
+        &self,
+        _req: &RequestInfo,
+        parent_id: Inode,
+        name: &OsStr,
+    ) -> FuseResult<(Inode, FileAttribute)> {
+        if parent_id != ROOT_INODE || !NAMES.iter().any(|known| name == *known) {
+            return Err(ErrorKind::FileNotFound.to_error("No such entry"));
+        }
+        self.lookups.fetch_add(1, Ordering::SeqCst);
+        Ok((self.new_inode(), Self::attr(FileKind::RegularFile)))
+    }
+
+    fn readdir(
+        &self,
+        _req: &RequestInfo,
+        file_id: Inode,
+        _file_handle: BorrowedFileHandle,
+    ) -> FuseResult<Vec<(OsString, (Inode, FileKind))>> {
+        let mut entries = vec![
+            (OsString::from("."), (file_id.clone(), FileKind::Directory)),
+            (OsString::from(".."), (file_id, FileKind::Directory)),
+        ];
+        for name in NAMES {
+            entries.push((
+                OsString::from(name),
+                (self.new_inode(), FileKind::RegularFile),
+            ));
+        }
+        Ok(entries)
+    }
+}
+
+#[test]
+fn test_volatile_inodes_navigation() {
+    let mount_dir = TempDir::new().unwrap();
+    let mntpoint = mount_dir.path().to_path_buf();
+    let lookups = Arc::new(AtomicUsize::new(0));
+
+    let mntpoint_clone = mntpoint.clone();
+    let lookups_clone = lookups.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = VolatileFs {
+            inner: DefaultFuseHandler::new(),
+            next_ino: AtomicU64::new(2),
+            lookups: lookups_clone,
+        };
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        for round in 0..3 {
+            let mut names: Vec<_> = std::fs::read_dir(&mntpoint)
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name())
+                .collect();
+            names.sort();
+            assert_eq!(names, NAMES);
+
+            for name in NAMES {
+                let metadata = std::fs::metadata(mntpoint.join(name)).unwrap();
+                assert!(metadata.is_file());
+                assert_eq!(metadata.len(), 4);
+            }
+            // Nothing is cached despite the TTL of the attributes, every access looks the entry up again
+            assert!(lookups.load(Ordering::SeqCst) >= (round + 1) * NAMES.len());
+        }
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
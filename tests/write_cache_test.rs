@@ -0,0 +1,173 @@
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mirror_fs::*, DefaultFuseHandler};
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::time::Duration;
+use tempfile::TempDir;
+
+#[test]
+fn test_write_cache_coalesces_writes() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+
+    let mntpoint_clone = mntpoint.clone();
+    let source_path_clone = source_path.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = MirrorFs::with_write_cache(source_path_clone, DefaultFuseHandler::new(), 4096);
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .open(mntpoint.join("log.txt"))
+            .unwrap();
+        for i in 0..100 {
+            file.write_all(format!("line {:03}\n", i).as_bytes())
+                .unwrap();
+        }
+
+        // Writes are still buffered, but the mount reports the written size
+        assert_eq!(fs::metadata(source_path.join("log.txt")).unwrap().len(), 0);
+        assert_eq!(fs::metadata(mntpoint.join("log.txt")).unwrap().len(), 900);
+
+        // Reading through the mount sees the buffered data
+        let content = fs::read_to_string(mntpoint.join("log.txt")).unwrap();
+        assert!(content.starts_with("line 000\nline 001\n"));
+
+        file.write_all(b"last line\n").unwrap();
+        drop(file);
+        let content = fs::read_to_string(source_path.join("log.txt")).unwrap();
+        assert_eq!(content.len(), 910);
+        assert!(content.ends_with("line 099\nlast line\n"));
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
+
+#[test]
+fn test_write_cache_getattr_through_handle() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+    fs::write(source_path.join("data.bin"), b"0123456789").unwrap();
+
+    let mntpoint_clone = mntpoint.clone();
+    let source_path_clone = source_path.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = MirrorFs::with_write_cache(source_path_clone, DefaultFuseHandler::new(), 4096);
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(mntpoint.join("data.bin"))
+            .unwrap();
+        file.write_all(b"abcdef").unwrap();
+
+        // fstat goes through the open handle, whose pending write grows the file
+        assert_eq!(file.metadata().unwrap().len(), 16);
+        assert_eq!(
+            fs::metadata(source_path.join("data.bin")).unwrap().len(),
+            10
+        );
+
+        drop(file);
+        assert_eq!(
+            fs::read(source_path.join("data.bin")).unwrap(),
+            b"0123456789abcdef"
+        );
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
+
+#[test]
+fn test_write_cache_reports_enospc_on_write() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+
+    // A small backend to fill up, which requires the permission to mount a tmpfs
+    let mounted = std::process::Command::new("mount")
+        .args(["-t", "tmpfs", "-o", "size=1m", "tmpfs"])
+        .arg(&source_path)
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false);
+    if !mounted {
+        eprintln!("Skipping test: unable to mount a tmpfs");
+        return;
+    }
+
+    let mntpoint_clone = mntpoint.clone();
+    let source_path_clone = source_path.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = MirrorFs::with_write_cache_policy(
+            source_path_clone,
+            DefaultFuseHandler::new(),
+            4 * 1024 * 1024,
+            SpacePolicy::Strict,
+        );
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .open(mntpoint.join("big.bin"))
+            .unwrap();
+        let chunk = vec![0xAB; 64 * 1024];
+        let error = (0..32)
+            .find_map(|_| file.write_all(&chunk).err())
+            .expect("Writing 2 MiB to a 1 MiB backend should fail");
+        assert_eq!(error.raw_os_error(), Some(libc::ENOSPC));
+        drop(file);
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+    std::process::Command::new("umount")
+        .arg(&source_path)
+        .status()
+        .unwrap();
+}
@@ -0,0 +1,59 @@
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mirror_fs::*, DefaultFuseHandler};
+
+use std::fs::{self, OpenOptions};
+use std::io::{Seek, SeekFrom, Write};
+use std::time::Duration;
+use tempfile::TempDir;
+
+#[test]
+fn test_writeback_cache_write_only() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+    fs::write(source_path.join("file.bin"), vec![b'a'; 8192]).unwrap();
+
+    let mntpoint_clone = mntpoint.clone();
+    let source_path_clone = source_path.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = MirrorFs::new(source_path_clone, DefaultFuseHandler::new()).writeback_cache(true);
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        // A write smaller than a page makes the kernel read the rest of the page through the write-only handle
+        let mut file = OpenOptions::new()
+            .write(true)
+            .open(mntpoint.join("file.bin"))
+            .unwrap();
+        file.seek(SeekFrom::Start(100)).unwrap();
+        file.write_all(b"bbbb").unwrap();
+        drop(file);
+
+        // Appends are placed at the end of file by the kernel
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(mntpoint.join("file.bin"))
+            .unwrap();
+        file.write_all(b"tail").unwrap();
+        drop(file);
+
+        let mut expected = vec![b'a'; 8192];
+        expected[100..104].copy_from_slice(b"bbbb");
+        expected.extend_from_slice(b"tail");
+        assert_eq!(fs::read(source_path.join("file.bin")).unwrap(), expected);
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}
@@ -0,0 +1,64 @@
+// Without the xattr feature, the driver leaves extended attributes to fuser, which answers ENOSYS
+#![cfg(all(not(feature = "xattr"), target_os = "linux"))]
+
+use easy_fuser::prelude::*;
+use easy_fuser::templates::{mirror_fs::*, DefaultFuseHandler};
+
+use std::ffi::CString;
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+use std::time::Duration;
+use tempfile::TempDir;
+
+#[test]
+fn test_xattr_disabled() {
+    let mount_dir = TempDir::new().unwrap();
+    let source_dir = TempDir::new().unwrap();
+
+    let mntpoint = mount_dir.path().to_path_buf();
+    let source_path = source_dir.path().to_path_buf();
+    fs::write(source_path.join("file.txt"), "content").unwrap();
+
+    let mntpoint_clone = mntpoint.clone();
+    let handle = std::thread::spawn(move || {
+        let fs = MirrorFsReadOnly::new(source_path, DefaultFuseHandler::new());
+        #[cfg(feature = "serial")]
+        mount(fs, &mntpoint_clone, &[]).unwrap();
+        #[cfg(not(feature = "serial"))]
+        mount(fs, &mntpoint_clone, &[], 4).unwrap();
+    });
+    std::thread::sleep(Duration::from_millis(50)); // Wait for the mount to finish
+
+    {
+        let file_path = CString::new(mntpoint.join("file.txt").as_os_str().as_bytes()).unwrap();
+        let name = CString::new("user.test").unwrap();
+        let mut buffer = [0u8; 16];
+        let result = unsafe {
+            libc::getxattr(
+                file_path.as_ptr(),
+                name.as_ptr(),
+                buffer.as_mut_ptr() as *mut libc::c_void,
+                buffer.len(),
+            )
+        };
+        assert_eq!(result, -1);
+        // The kernel reports the ENOSYS of the filesystem as unsupported
+        assert_eq!(
+            std::io::Error::last_os_error().raw_os_error(),
+            Some(libc::EOPNOTSUPP)
+        );
+
+        // Other operations are unaffected
+        assert_eq!(
+            fs::read_to_string(mntpoint.join("file.txt")).unwrap(),
+            "content"
+        );
+    }
+
+    std::process::Command::new("fusermount")
+        .arg("-u")
+        .arg(&mntpoint)
+        .status()
+        .unwrap();
+    handle.join().unwrap();
+}